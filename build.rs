@@ -1,4 +1,4 @@
-/**
+/*
 * filename : build
 * author : HAMA
 * date: 2025. 4. 30.
@@ -27,7 +27,7 @@ fn main() {
 
 fn get_git_hash() -> String {
   let output = Command::new("git")
-    .args(&["rev-parse", "--short", "HEAD"])
+    .args(["rev-parse", "--short", "HEAD"])
     .output();
   
   match output {
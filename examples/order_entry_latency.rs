@@ -0,0 +1,123 @@
+/*
+* filename : order_entry_latency
+* author : HAMA
+* date: 2026. 10. 18.
+* description: REST(JSON over HTTP) 주문 입력과 바이너리 TCP 주문 입력의 왕복 지연 시간 비교
+**/
+
+use std::error::Error;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use serde_json::json;
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, Mutex};
+
+use xTraderz::auth::AccountRegistry;
+use xTraderz::binary::client::BinaryClient;
+use xTraderz::binary::gateway::BinaryGateway;
+use xTraderz::instrument::{Instrument, InstrumentRegistry};
+use xTraderz::risk::RiskManager;
+use xTraderz::throttle::Throttle;
+use xTraderz::models::{OrderType, Price, Qty, Side};
+use xTraderz::util::binary_codec::BinaryMessage;
+use xTraderz::{order_manager, sequencer};
+use xTraderz::order_manager::OrderApi;
+use xTraderz::sequencer::SequencerOutputs;
+
+const ORDERS: usize = 5000;
+const WARMUP: usize = 500;
+
+/// 주문 입력 지연 시간 비교 예제
+/// 같은 프로세스에서 매칭 엔진, REST API, 바이너리 게이트웨이를 띄우고
+/// 주문 요청부터 접수 응답까지의 왕복 시간을 경로별로 측정합니다.
+/// 정확한 수치를 위해 `cargo run --release --example order_entry_latency`로 실행하세요.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+  let accounts = Arc::new(AccountRegistry::new());
+  accounts.insert("bench", "bench-key");
+  let instruments = Arc::new(InstrumentRegistry::new());
+  instruments.insert(Instrument::new("BENCH"));
+  
+  // 매칭 엔진 파이프라인
+  let (order_tx, order_rx) = mpsc::channel(10000);
+  let (exec_tx, mut exec_rx) = mpsc::channel(10000);
+  let (md_tx, mut md_rx) = mpsc::channel(10000);
+  tokio::spawn(sequencer::run(order_rx, SequencerOutputs::new(exec_tx, md_tx), instruments.clone()));
+  tokio::spawn(async move { while md_rx.recv().await.is_some() {} });
+  tokio::spawn(async move { while exec_rx.recv().await.is_some() {} });
+  
+  // REST API
+  let exec_store = Arc::new(Mutex::new(Vec::new()));
+  let routes = order_manager::routes(OrderApi::new(order_tx.clone(), exec_store, instruments.clone(), accounts.clone()));
+  let (rest_addr, server) = warp::serve(routes).bind_ephemeral(([127, 0, 0, 1], 0));
+  tokio::spawn(server);
+  
+  // 바이너리 게이트웨이
+  let gateway = Arc::new(BinaryGateway::new(accounts, instruments, order_tx, Arc::new(RiskManager::new()), Arc::new(Throttle::default())));
+  let listener = TcpListener::bind("127.0.0.1:0").await?;
+  let binary_addr = listener.local_addr()?;
+  tokio::spawn(gateway.serve(listener));
+  
+  // 1. 인코딩/디코딩 비용 (네트워크 제외)
+  let message = BinaryMessage::NewOrder {
+    client_order_id: "c1".to_string(),
+    symbol: "BENCH".to_string(),
+    side: Side::Buy,
+    order_type: OrderType::Limit,
+    price: Price(100),
+    quantity: Qty(1),
+  };
+  let body = json!({ "symbol": "BENCH", "side": "Buy", "order_type": "Limit", "price": "100", "quantity": "1" });
+  let started = Instant::now();
+  for _ in 0..ORDERS {
+    let text = serde_json::to_string(&body)?;
+    let _: serde_json::Value = serde_json::from_str(&text)?;
+  }
+  let json_codec = started.elapsed() / ORDERS as u32;
+  let started = Instant::now();
+  for _ in 0..ORDERS {
+    let mut frame = message.encode()?;
+    BinaryMessage::decode(&mut frame)?;
+  }
+  let binary_codec = started.elapsed() / ORDERS as u32;
+  println!("주문 1건 인코딩+디코딩: JSON {:?} ({}바이트), 바이너리 {:?} ({}바이트)",
+           json_codec, body.to_string().len(), binary_codec, message.encode()?.len());
+  
+  // 2. REST 왕복 (POST /v1/order -> 201 Created)
+  let client = reqwest::Client::new();
+  let url = format!("http://{}/v1/order", rest_addr);
+  let mut rest = Vec::with_capacity(ORDERS);
+  for i in 0..WARMUP + ORDERS {
+    let started = Instant::now();
+    let response = client.post(&url).header("x-api-key", "bench-key").json(&body).send().await?;
+    response.bytes().await?;
+    if i >= WARMUP {
+      rest.push(started.elapsed());
+    }
+  }
+  
+  // 3. 바이너리 왕복 (NewOrder -> ExecutionReport New)
+  let mut binary_client = BinaryClient::connect(binary_addr, "bench-key").await?;
+  let mut binary = Vec::with_capacity(ORDERS);
+  for i in 0..WARMUP + ORDERS {
+    let started = Instant::now();
+    binary_client.new_order(&format!("c{}", i), "BENCH", Side::Buy, OrderType::Limit, Price(100), Qty(1)).await?;
+    binary_client.recv().await?;
+    if i >= WARMUP {
+      binary.push(started.elapsed());
+    }
+  }
+  
+  println!("주문 {}건 왕복 지연 시간:", ORDERS);
+  print_summary("REST (JSON/HTTP)", &mut rest);
+  print_summary("바이너리 (TCP)", &mut binary);
+  Ok(())
+}
+
+fn print_summary(name: &str, samples: &mut [Duration]) {
+  samples.sort();
+  let mean = samples.iter().sum::<Duration>() / samples.len() as u32;
+  let percentile = |p: f64| samples[((samples.len() - 1) as f64 * p) as usize];
+  println!("  {:<18} 평균 {:>10?}  p50 {:>10?}  p99 {:>10?}  최대 {:>10?}",
+           name, mean, percentile(0.5), percentile(0.99), samples[samples.len() - 1]);
+}
\ No newline at end of file
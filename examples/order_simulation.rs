@@ -1,12 +1,11 @@
-/**
+/*
 * filename : order_simulation
 * author : HAMA
 * date: 2025. 4. 30.
 * description: 
 **/
 
-use futures::{SinkExt, StreamExt};
-use reqwest;
+use futures::StreamExt;
 use serde_json::{json, Value};
 use std::error::Error;
 use std::time::Duration;
@@ -26,16 +25,17 @@ async fn main() -> Result<(), Box<dyn Error>> {
   let server_base = "http://127.0.0.1:3030";
   let ws_base = "ws://127.0.0.1:3030";
   
-  // HTTP 클라이언트 생성
+  // HTTP 클라이언트 생성 (주문 계정은 ACCOUNTS_CONFIG에 등록된 API 키로 인증)
   let client = reqwest::Client::new();
+  let api_key = std::env::var("API_KEY").unwrap_or_else(|_| "demo-key".to_string());
   
   println!("주문 시뮬레이션 시작");
   println!("--------------------");
   
-  // 체결 수신용 WebSocket 연결
-  let executions_url = Url::parse(&format!("{}/ws/executions", ws_base))?;
+  // 체결 수신용 WebSocket 연결 (익명 공개 체결 채널)
+  let executions_url = Url::parse(&format!("{}/ws/trades", ws_base))?;
   let (executions_ws, _) = connect_async(executions_url).await?;
-  let (mut exec_write, mut exec_read) = executions_ws.split();
+  let (_, mut exec_read) = executions_ws.split();
   
   println!("체결 스트림에 연결됨");
   
@@ -50,12 +50,13 @@ async fn main() -> Result<(), Box<dyn Error>> {
           Err(_) => continue,
         };
         
+        let trade = &json["data"];
         execution_count += 1;
         println!("\n체결 #{}: {} - 가격: {}, 수량: {}",
                  execution_count,
-                 json["symbol"],
-                 format_price(json["price"].as_u64().unwrap_or(0)),
-                 json["quantity"]);
+                 trade["symbol"],
+                 trade["price"],
+                 trade["quantity"]);
       }
     }
   });
@@ -69,32 +70,25 @@ async fn main() -> Result<(), Box<dyn Error>> {
       interval.tick().await;
       
       // 오더북 상태 조회
-      match client_clone.get(&format!("{}/api/v1/orderbook/BTC-KRW", server_base))
+      match client_clone.get(format!("{}/api/v1/orderbook/BTC-KRW", server_base))
         .send()
         .await {
         Ok(resp) if resp.status().is_success() => {
           if let Ok(orderbook) = resp.json::<Value>().await {
-            let bids = orderbook["bids"].as_array().unwrap_or(&vec![]);
-            let asks = orderbook["asks"].as_array().unwrap_or(&vec![]);
+            let empty = Vec::new();
+            let bids = orderbook["bids"].as_array().unwrap_or(&empty);
+            let asks = orderbook["asks"].as_array().unwrap_or(&empty);
             
-            // 최고 매수가와 최저 매도가 추출
-            let top_bid = if !bids.is_empty() {
-              bids[0]["price"].as_u64().unwrap_or(0)
-            } else {
-              0
-            };
+            // 최고 매수가와 최저 매도가 추출 (가격은 10진수 문자열)
+            let top_bid = bids.first().and_then(|level| level["price"].as_str());
+            let top_ask = asks.first().and_then(|level| level["price"].as_str());
             
-            let top_ask = if !asks.is_empty() {
-              asks[0]["price"].as_u64().unwrap_or(0)
-            } else {
-              0
-            };
-            
-            if top_bid > 0 && top_ask > 0 {
+            if let (Some(top_bid), Some(top_ask)) = (top_bid, top_ask) {
+              let spread = top_ask.parse::<f64>().unwrap_or(0.0) - top_bid.parse::<f64>().unwrap_or(0.0);
               println!("\n현재 시장 상태:");
               println!("최고 매수가: {}", format_price(top_bid));
               println!("최저 매도가: {}", format_price(top_ask));
-              println!("스프레드: {}", format_price(top_ask.saturating_sub(top_bid)));
+              println!("스프레드: {}", format_price(&spread.to_string()));
               println!("매수 단계: {}, 매도 단계: {}", bids.len(), asks.len());
             }
           }
@@ -113,17 +107,17 @@ async fn main() -> Result<(), Box<dyn Error>> {
       interval.tick().await;
       
       // 시장 통계 조회
-      match client_clone.get(&format!("{}/api/v1/statistics/BTC-KRW", server_base))
+      match client_clone.get(format!("{}/api/v1/statistics/BTC-KRW", server_base))
         .send()
         .await {
         Ok(resp) if resp.status().is_success() => {
           if let Ok(stats) = resp.json::<Value>().await {
             println!("\n24시간 시장 통계:");
-            println!("마지막 가격: {}", format_price(stats["last_price"].as_u64().unwrap_or(0)));
-            println!("24시간 고가: {}", format_price(stats["high_price_24h"].as_u64().unwrap_or(0)));
-            println!("24시간 저가: {}", format_price(stats["low_price_24h"].as_u64().unwrap_or(0)));
-            println!("24시간 거래량: {}", stats["volume_24h"]);
-            println!("24시간 변동률: {}%", stats["price_change_24h"]);
+            println!("마지막 가격: {}", format_price(stats["last_price"].as_str().unwrap_or("0")));
+            println!("24시간 고가: {}", format_price(stats["high_price_24h"].as_str().unwrap_or("0")));
+            println!("24시간 저가: {}", format_price(stats["low_price_24h"].as_str().unwrap_or("0")));
+            println!("24시간 거래량: {}", stats["volume_24h"].as_str().unwrap_or("0"));
+            println!("24시간 변동률: {}bp", stats["price_change_bps_24h"]);
           }
         },
         _ => {}
@@ -140,7 +134,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
       interval.tick().await;
       
       // 1분봉 데이터 조회
-      match client_clone.get(&format!("{}/api/v1/klines/BTC-KRW/1m?limit=1", server_base))
+      match client_clone.get(format!("{}/api/v1/klines/BTC-KRW/1m?limit=1", server_base))
         .send()
         .await {
         Ok(resp) if resp.status().is_success() => {
@@ -149,11 +143,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
               let candle = &candles[0];
               println!("\n최근 1분봉:");
               println!("시간: {}", candle["open_time"]);
-              println!("시가: {}", format_price(candle["open"].as_u64().unwrap_or(0)));
-              println!("고가: {}", format_price(candle["high"].as_u64().unwrap_or(0)));
-              println!("저가: {}", format_price(candle["low"].as_u64().unwrap_or(0)));
-              println!("종가: {}", format_price(candle["close"].as_u64().unwrap_or(0)));
-              println!("거래량: {}", candle["volume"]);
+              println!("시가: {}", format_price(candle["open"].as_str().unwrap_or("0")));
+              println!("고가: {}", format_price(candle["high"].as_str().unwrap_or("0")));
+              println!("저가: {}", format_price(candle["low"].as_str().unwrap_or("0")));
+              println!("종가: {}", format_price(candle["close"].as_str().unwrap_or("0")));
+              println!("거래량: {}", candle["volume"].as_str().unwrap_or("0"));
               println!("거래 횟수: {}", candle["trade_count"]);
             }
           }
@@ -171,6 +165,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
   
   let mut rng = thread_rng();
   let mut order_count = 0;
+  let mut last_order_id: Option<String> = None;
   let mut interval = time::interval(order_interval);
   let start_time = std::time::Instant::now();
   
@@ -199,7 +194,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
         });
     
     // 주문 제출
-    let resp = client.post(&format!("{}/v1/order", server_base))
+    let resp = client.post(format!("{}/v1/order", server_base))
+      .header("x-api-key", &api_key)
       .json(&order)
       .send()
       .await?;
@@ -207,26 +203,31 @@ async fn main() -> Result<(), Box<dyn Error>> {
     if resp.status().is_success() {
       let order_data: Value = resp.json().await?;
       order_count += 1;
+      last_order_id = order_data["order_id"].as_str().map(String::from);
       
       println!("주문 #{} 생성: {} {} BTC @ {} KRW",
                order_count,
                if side == "Buy" { "매수" } else { "매도" },
                quantity,
-               format_price(price));
+               format_price(&price.to_string()));
     } else {
       eprintln!("주문 생성 실패: {}", resp.status());
     }
     
     // 무작위로 기존 주문 취소 (25% 확률)
-    if rng.gen::<f64>() < 0.25 && order_count > 0 {
-      // 취소할 주문 ID 가져오기
-      let order_id = order_data["order_id"].as_str().unwrap_or("");
+    if rng.gen::<f64>() < 0.25 {
+      // 마지막으로 생성한 주문 취소
+      let order_id = match last_order_id.take() {
+        Some(order_id) => order_id,
+        None => continue,
+      };
       
       let cancel_req = json!({
                 "order_id": order_id
             });
       
-      let resp = client.post(&format!("{}/v1/order/cancel", server_base))
+      let resp = client.post(format!("{}/v1/order/cancel", server_base))
+        .header("x-api-key", &api_key)
         .json(&cancel_req)
         .send()
         .await?;
@@ -243,7 +244,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
   tokio::time::sleep(Duration::from_secs(2)).await;
   
   // 체결 내역 조회
-  let resp = client.get(&format!("{}/api/v1/executions/BTC-KRW?limit=10", server_base))
+  let resp = client.get(format!("{}/api/v1/executions/BTC-KRW?limit=10", server_base))
     .send()
     .await?;
   
@@ -256,8 +257,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
       println!("#{}: 시간: {}, 가격: {}, 수량: {}, 방향: {}",
                i + 1,
                exec["timestamp"],
-               format_price(exec["price"].as_u64().unwrap_or(0)),
-               exec["volume"],
+               format_price(exec["price"].as_str().unwrap_or("0")),
+               exec["volume"].as_str().unwrap_or("0"),
                exec["side"]);
     }
   }
@@ -272,18 +273,22 @@ async fn main() -> Result<(), Box<dyn Error>> {
   Ok(())
 }
 
-/// 가격 포맷팅 헬퍼 함수
-fn format_price(price: u64) -> String {
-  let price_str = price.to_string();
+/// 가격 포맷팅 헬퍼 함수 (10진수 문자열의 정수 부분에 천 단위 구분자 추가)
+fn format_price(price: &str) -> String {
+  let (int_part, frac_part) = price.split_once('.').map_or((price, None), |(int_part, frac_part)| (int_part, Some(frac_part)));
   let mut formatted = String::new();
-  let len = price_str.len();
+  let len = int_part.len();
   
-  for (i, c) in price_str.chars().enumerate() {
-    if i > 0 && (len - i) % 3 == 0 {
+  for (i, c) in int_part.chars().enumerate() {
+    if i > 0 && (len - i).is_multiple_of(3) {
       formatted.push(',');
     }
     formatted.push(c);
   }
+  if let Some(frac_part) = frac_part {
+    formatted.push('.');
+    formatted.push_str(frac_part);
+  }
   
   format!("{} KRW", formatted)
 }
\ No newline at end of file
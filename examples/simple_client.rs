@@ -1,17 +1,16 @@
-/**
+/*
 * filename : simple_client
 * author : HAMA
 * date: 2025. 4. 30.
 * description: 
 **/
 
-use futures::{SinkExt, StreamExt};
+use futures::StreamExt;
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
 use url::Url;
 use serde_json::{json, Value};
 use std::time::Duration;
 use std::error::Error;
-use reqwest;
 
 /// 간단한 클라이언트 예제
 /// 이 예제는 주문 매칭 엔진 서버에 REST API를 통해 주문을 제출하고
@@ -23,16 +22,17 @@ async fn main() -> Result<(), Box<dyn Error>> {
   let server_base = "http://127.0.0.1:3030";
   let ws_base = "ws://127.0.0.1:3030";
   
-  // HTTP 클라이언트 생성
+  // HTTP 클라이언트 생성 (주문 계정은 ACCOUNTS_CONFIG에 등록된 API 키로 인증)
   let client = reqwest::Client::new();
+  let api_key = std::env::var("API_KEY").unwrap_or_else(|_| "demo-key".to_string());
   
   println!("주문 매칭 엔진 클라이언트 예제");
   println!("------------------------------");
   
-  // 체결 수신용 WebSocket 연결
-  let executions_url = Url::parse(&format!("{}/ws/executions", ws_base))?;
+  // 체결 수신용 WebSocket 연결 (익명 공개 체결 채널)
+  let executions_url = Url::parse(&format!("{}/ws/trades", ws_base))?;
   let (executions_ws, _) = connect_async(executions_url).await?;
-  let (mut exec_write, mut exec_read) = executions_ws.split();
+  let (_, mut exec_read) = executions_ws.split();
   
   println!("체결 스트림에 연결됨");
   
@@ -49,8 +49,9 @@ async fn main() -> Result<(), Box<dyn Error>> {
             }
           };
           
+          let trade = &json["data"];
           println!("체결 발생: {} - 가격: {}, 수량: {}",
-                   json["symbol"], format_number(json["price"].as_u64().unwrap_or(0)), json["quantity"]);
+                   trade["symbol"], trade["price"], trade["quantity"]);
         },
         Ok(Message::Close(_)) => break,
         Err(e) => {
@@ -63,7 +64,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
   });
   
   // 시장 데이터 조회 - 현재 오더북 상태
-  let resp = client.get(&format!("{}/api/v1/orderbook/BTC-KRW", server_base))
+  let resp = client.get(format!("{}/api/v1/orderbook/BTC-KRW", server_base))
     .send()
     .await?;
   
@@ -75,7 +76,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     if let Some(asks) = orderbook["asks"].as_array() {
       for (i, ask) in asks.iter().take(5).enumerate() {
         println!("  #{}: 가격 {}, 수량 {}",
-                 i+1, format_number(ask["price"].as_u64().unwrap_or(0)), ask["volume"]);
+                 i+1, format_number(ask["price"].as_str().unwrap_or("0")), ask["volume"].as_str().unwrap_or("0"));
       }
     }
     
@@ -83,7 +84,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     if let Some(bids) = orderbook["bids"].as_array() {
       for (i, bid) in bids.iter().take(5).enumerate() {
         println!("  #{}: 가격 {}, 수량 {}",
-                 i+1, format_number(bid["price"].as_u64().unwrap_or(0)), bid["volume"]);
+                 i+1, format_number(bid["price"].as_str().unwrap_or("0")), bid["volume"].as_str().unwrap_or("0"));
       }
     }
   } else {
@@ -95,25 +96,22 @@ async fn main() -> Result<(), Box<dyn Error>> {
   let buy_order = json!({
         "symbol": "BTC-KRW",
         "side": "Buy",
-        "price": 50000000,
+        "price": "50000000",
         "order_type": "Limit",
-        "quantity": 1
+        "quantity": "1"
     });
   
-  let resp = client.post(&format!("{}/v1/order", server_base))
+  let resp = client.post(format!("{}/v1/order", server_base))
+    .header("x-api-key", &api_key)
     .json(&buy_order)
     .send()
     .await?;
   
-  let buy_order_id: String;
-  
   if resp.status().is_success() {
     let order: Value = resp.json().await?;
-    buy_order_id = order["order_id"].as_str().unwrap_or("").to_string();
-    println!("매수 주문 생성됨: ID = {}", buy_order_id);
+    println!("매수 주문 생성됨: ID = {}", order["order_id"].as_str().unwrap_or(""));
   } else {
     eprintln!("매수 주문 생성 실패: {}", resp.status());
-    buy_order_id = String::new();
   }
   
   // 잠시 대기
@@ -124,12 +122,13 @@ async fn main() -> Result<(), Box<dyn Error>> {
   let sell_order = json!({
         "symbol": "BTC-KRW",
         "side": "Sell",
-        "price": 50000000,  // 동일 가격으로 매칭되도록
+        "price": "50000000",  // 동일 가격으로 매칭되도록
         "order_type": "Limit",
-        "quantity": 1
+        "quantity": "1"
     });
   
-  let resp = client.post(&format!("{}/v1/order", server_base))
+  let resp = client.post(format!("{}/v1/order", server_base))
+    .header("x-api-key", &api_key)
     .json(&sell_order)
     .send()
     .await?;
@@ -147,7 +146,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
   
   // MDP API를 통한 체결 내역 조회
   println!("\n체결 내역 조회 중...");
-  let resp = client.get(&format!("{}/api/v1/executions/BTC-KRW", server_base))
+  let resp = client.get(format!("{}/api/v1/executions/BTC-KRW", server_base))
     .send()
     .await?;
   
@@ -157,7 +156,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     
     for (i, exec) in executions.iter().enumerate().take(5) {
       println!("#{}: 주문 ID: {}, 가격: {}, 수량: {}",
-               i+1, exec["order_id"], format_number(exec["price"].as_u64().unwrap_or(0)), exec["volume"]);
+               i+1, exec["order_id"], format_number(exec["price"].as_str().unwrap_or("0")), exec["volume"].as_str().unwrap_or("0"));
     }
   } else {
     eprintln!("체결 내역 조회 실패: {}", resp.status());
@@ -165,25 +164,25 @@ async fn main() -> Result<(), Box<dyn Error>> {
   
   // 시장 통계 조회
   println!("\n시장 통계 조회 중...");
-  let resp = client.get(&format!("{}/api/v1/statistics/BTC-KRW", server_base))
+  let resp = client.get(format!("{}/api/v1/statistics/BTC-KRW", server_base))
     .send()
     .await?;
   
   if resp.status().is_success() {
     let stats: Value = resp.json().await?;
     println!("BTC-KRW 시장 통계:");
-    println!("마지막 가격: {}", format_number(stats["last_price"].as_u64().unwrap_or(0)));
-    println!("24시간 고가: {}", format_number(stats["high_price_24h"].as_u64().unwrap_or(0)));
-    println!("24시간 저가: {}", format_number(stats["low_price_24h"].as_u64().unwrap_or(0)));
-    println!("24시간 거래량: {}", stats["volume_24h"]);
-    println!("24시간 변동률: {}%", stats["price_change_24h"]);
+    println!("마지막 가격: {}", format_number(stats["last_price"].as_str().unwrap_or("0")));
+    println!("24시간 고가: {}", format_number(stats["high_price_24h"].as_str().unwrap_or("0")));
+    println!("24시간 저가: {}", format_number(stats["low_price_24h"].as_str().unwrap_or("0")));
+    println!("24시간 거래량: {}", stats["volume_24h"].as_str().unwrap_or("0"));
+    println!("24시간 변동률: {}bp", stats["price_change_bps_24h"]);
   } else {
     eprintln!("시장 통계 조회 실패: {}", resp.status());
   }
   
   // 캔들스틱 데이터 조회
   println!("\n캔들스틱 데이터 조회 중...");
-  let resp = client.get(&format!("{}/api/v1/klines/BTC-KRW/1m", server_base))
+  let resp = client.get(format!("{}/api/v1/klines/BTC-KRW/1m", server_base))
     .send()
     .await?;
   
@@ -195,11 +194,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
       println!("#{}: 시간: {}, 시가: {}, 고가: {}, 저가: {}, 종가: {}, 거래량: {}",
                i+1,
                candle["open_time"],
-               format_number(candle["open"].as_u64().unwrap_or(0)),
-               format_number(candle["high"].as_u64().unwrap_or(0)),
-               format_number(candle["low"].as_u64().unwrap_or(0)),
-               format_number(candle["close"].as_u64().unwrap_or(0)),
-               candle["volume"]);
+               format_number(candle["open"].as_str().unwrap_or("0")),
+               format_number(candle["high"].as_str().unwrap_or("0")),
+               format_number(candle["low"].as_str().unwrap_or("0")),
+               format_number(candle["close"].as_str().unwrap_or("0")),
+               candle["volume"].as_str().unwrap_or("0"));
     }
   } else {
     eprintln!("캔들스틱 데이터 조회 실패: {}", resp.status());
@@ -215,18 +214,22 @@ async fn main() -> Result<(), Box<dyn Error>> {
   Ok(())
 }
 
-/// 숫자 포맷팅 헬퍼 함수 (천 단위 구분자 추가)
-fn format_number(num: u64) -> String {
-  let num_str = num.to_string();
+/// 숫자 포맷팅 헬퍼 함수 (10진수 문자열의 정수 부분에 천 단위 구분자 추가)
+fn format_number(num: &str) -> String {
+  let (int_part, frac_part) = num.split_once('.').map_or((num, None), |(int_part, frac_part)| (int_part, Some(frac_part)));
   let mut result = String::new();
-  let len = num_str.len();
+  let len = int_part.len();
   
-  for (i, c) in num_str.chars().enumerate() {
-    if i > 0 && (len - i) % 3 == 0 {
+  for (i, c) in int_part.chars().enumerate() {
+    if i > 0 && (len - i).is_multiple_of(3) {
       result.push(',');
     }
     result.push(c);
   }
+  if let Some(frac_part) = frac_part {
+    result.push('.');
+    result.push_str(frac_part);
+  }
   
   result
 }
\ No newline at end of file
@@ -0,0 +1,126 @@
+#![no_main]
+
+/*
+* filename : order_book
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 주문책 불변 조건 퍼즈 타깃 (cargo fuzz run order_book)
+**/
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono::Utc;
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+
+use xTraderz::instrument::{Instrument, InstrumentRegistry};
+use xTraderz::matching_engine::{MatchingEngine, OrderOutcome};
+use xTraderz::models::{Amend, Order, OrderBook, OrderMessage, Side, OrderType, OrderStatus, Price, Qty};
+
+#[derive(Arbitrary, Debug)]
+enum Command {
+  Limit { buy: bool, price: u8, quantity: u8 },
+  Market { buy: bool, quantity: u8 },
+  Cancel { index: u8 },
+  Amend { index: u8, price: u8, quantity: u8 },
+}
+
+fn order(order_id: String, buy: bool, order_type: OrderType, price: u64, quantity: u64) -> Order {
+  Order {
+    order_id,
+    symbol: "FUZZ".into(),
+    account: String::new(),
+    price: Price(price),
+    quantity: Qty(quantity),
+    side: if buy { Side::Buy } else { Side::Sell },
+    order_type,
+    status: OrderStatus::New,
+    filled_quantity: Qty::ZERO,
+    remain_quantity: Qty(quantity),
+    entry_time: Utc::now()
+  }
+}
+
+fn cancel(order_id: String) -> Order {
+  let mut cancel = order(order_id, true, OrderType::Limit, 0, 0);
+  cancel.status = OrderStatus::Cancelled;
+  cancel
+}
+
+fuzz_target!(|commands: Vec<Command>| {
+  let instruments = Arc::new(InstrumentRegistry::new());
+  instruments.insert(Instrument::new("FUZZ"));
+  let mut engine = MatchingEngine::new(instruments);
+  let empty = OrderBook::new();
+  let mut filled: HashMap<String, Qty> = HashMap::new();
+  let mut next_id = 0u64;
+  
+  for command in commands {
+    let book = engine.book("FUZZ").unwrap_or(&empty);
+    let mut live: Vec<String> = book.order_map.keys().cloned().collect();
+    live.sort();
+    let pick = |index: u8| live.get(index as usize % live.len().max(1)).cloned();
+    
+    next_id += 1;
+    let fresh_id = format!("f{}", next_id);
+    
+    let message = match command {
+      // 수량 0 주문은 별도 검증 대상이므로 1 이상으로 보정
+      Command::Limit { buy, price, quantity } => {
+        OrderMessage::Order(order(fresh_id, buy, OrderType::Limit, price as u64, quantity.max(1) as u64))
+      }
+      Command::Market { buy, quantity } => {
+        OrderMessage::Order(order(fresh_id, buy, OrderType::Market, 0, quantity.max(1) as u64))
+      }
+      Command::Cancel { index } => match pick(index) {
+        Some(target) => OrderMessage::Order(cancel(target)),
+        None => continue,
+      },
+      Command::Amend { index, price, quantity } => match pick(index) {
+        Some(order_id) => OrderMessage::Amend(Amend {
+          order_id,
+          account: String::new(),
+          price: Price(price as u64),
+          quantity: Qty(quantity.max(1) as u64),
+        }),
+        None => continue,
+      },
+    };
+    
+    let output = engine.handle(message.clone());
+    let book = engine.book("FUZZ").unwrap_or(&empty);
+    assert_eq!(output.executions.len() % 2, 0);
+    
+    // 체결은 (테이커, 메이커) 쌍으로 발생하며 수량이 보존되어야 함
+    // 정정된 주문은 주문 ID와 체결 수량을 유지하므로 주문 ID별로 누적
+    for pair in output.executions.chunks(2) {
+      assert_eq!(pair[0].quantity, pair[1].quantity);
+      assert_eq!(pair[0].price, pair[1].price);
+      assert_ne!(pair[0].side, pair[1].side);
+      assert!(!pair[0].is_maker && pair[1].is_maker);
+      for execution in pair {
+        *filled.entry(execution.order_id.clone()).or_insert(Qty::ZERO) += execution.quantity;
+      }
+    }
+    
+    // 적용된 정정은 주문이 남아 있다면 새 가격과 수량으로 남김
+    if let OrderMessage::Amend(amend) = &message {
+      let applied = output.outcomes.iter().any(|outcome| matches!(outcome, OrderOutcome::Amended { order_id, .. } if *order_id == amend.order_id));
+      if let Some(resting) = book.get_order(&amend.order_id) {
+        assert!(applied);
+        assert_eq!((resting.price, resting.quantity), (amend.price, amend.quantity));
+      }
+    }
+    
+    if let Err(e) = book.validate() {
+      panic!("invariant violated: {}", e);
+    }
+    
+    for level in book.buy_book.limits.values().chain(book.sell_book.limits.values()) {
+      for resting in &level.orders {
+        assert!(resting.filled_quantity <= resting.quantity);
+        assert_eq!(resting.filled_quantity, filled.get(&resting.order_id).cloned().unwrap_or(Qty::ZERO));
+      }
+    }
+  }
+});
@@ -0,0 +1,486 @@
+/*
+* filename : admin
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 거래 정지/재개, 거래 세션 상태 변경, 일괄 취소, 종목 및 리스크 한도 관리 API (토큰 인증, 감사 로그)
+**/
+
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use tokio::sync::mpsc::Sender;
+use warp::{Filter, Rejection, Reply, http::StatusCode};
+use crate::error::ApiError;
+use crate::models::{MassCancel, OrderMessage};
+use crate::instrument::{Instrument, InstrumentRegistry, TradingStatus};
+use crate::risk::{RiskLimits, RiskManager};
+
+// A single admin request and its outcome
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuditEntry {
+  pub timestamp: DateTime<Utc>,
+  pub actor: String,
+  pub action: String,
+  pub details: Value,
+  pub outcome: String,
+}
+
+// Append-only record of admin requests, including rejected ones.
+// The latest entries are kept in memory and, when a path is given, appended to a JSON lines
+// file by a writer thread so that requests never wait for the disk. Refused requests are
+// recorded without their arguments and at most MAX_REFUSED_PER_SEC times a second; the
+// next recorded one counts those left out.
+pub struct AuditLog {
+  entries: Mutex<VecDeque<AuditEntry>>,
+  refused: Mutex<RefusedWindow>,
+  file_tx: Option<SyncSender<AuditEntry>>,
+}
+
+struct RefusedWindow {
+  started: Instant,
+  recorded: u32,
+  suppressed: u64,
+}
+
+impl Default for AuditLog {
+  fn default() -> Self {
+    AuditLog::new()
+  }
+}
+
+impl AuditLog {
+  pub const MAX_ENTRIES: usize = 10_000;
+  pub const MAX_REFUSED_PER_SEC: u32 = 10;
+  pub const MAX_ACTOR_LEN: usize = 64;
+  const FILE_QUEUE: usize = 1024;
+  
+  pub fn new() -> Self {
+    AuditLog {
+      entries: Mutex::new(VecDeque::new()),
+      refused: Mutex::new(RefusedWindow { started: Instant::now(), recorded: 0, suppressed: 0 }),
+      file_tx: None,
+    }
+  }
+  
+  pub fn with_file(path: impl Into<PathBuf>) -> Self {
+    let path = path.into();
+    let (file_tx, file_rx) = sync_channel(Self::FILE_QUEUE);
+    std::thread::spawn(move || write_audit_file(path, file_rx));
+    AuditLog { file_tx: Some(file_tx), ..AuditLog::new() }
+  }
+  
+  pub fn record(&self, actor: &str, action: &str, details: Value, outcome: &str) {
+    let entry = AuditEntry {
+      timestamp: Utc::now(),
+      actor: actor.chars().take(Self::MAX_ACTOR_LEN).collect(),
+      action: action.to_string(),
+      details,
+      outcome: outcome.to_string(),
+    };
+    
+    if let Some(file_tx) = &self.file_tx {
+      match file_tx.try_send(entry.clone()) {
+        Ok(()) => {}
+        Err(TrySendError::Full(_)) => eprintln!("Audit log file writer is behind, entry not written: {} {}", entry.actor, entry.action),
+        Err(TrySendError::Disconnected(_)) => eprintln!("Audit log file writer stopped, entry not written: {} {}", entry.actor, entry.action),
+      }
+    }
+    let mut entries = self.entries.lock().unwrap();
+    if entries.len() == Self::MAX_ENTRIES {
+      entries.pop_front();
+    }
+    entries.push_back(entry);
+  }
+  
+  // A request without valid credentials
+  pub fn record_refused(&self, actor: &str, action: &str) {
+    let suppressed = {
+      let mut refused = self.refused.lock().unwrap();
+      let now = Instant::now();
+      if now.duration_since(refused.started) >= Duration::from_secs(1) {
+        refused.started = now;
+        refused.recorded = 0;
+      }
+      if refused.recorded == Self::MAX_REFUSED_PER_SEC {
+        refused.suppressed += 1;
+        return;
+      }
+      refused.recorded += 1;
+      std::mem::take(&mut refused.suppressed)
+    };
+    self.record(actor, action, serde_json::json!({ "suppressed": suppressed }), "unauthorized");
+  }
+  
+  pub fn entries(&self) -> Vec<AuditEntry> {
+    self.entries.lock().unwrap().iter().cloned().collect()
+  }
+}
+
+fn write_audit_file(path: PathBuf, entries: Receiver<AuditEntry>) {
+  for entry in entries {
+    let written = OpenOptions::new().create(true).append(true).open(&path)
+      .and_then(|mut file| writeln!(file, "{}", serde_json::to_string(&entry).unwrap_or_default()));
+    if let Err(e) = written {
+      eprintln!("Failed to write audit log {}: {}", path.display(), e);
+    }
+  }
+}
+
+// Shared state of the admin API. Without a token the admin API is not served at all.
+pub struct AdminState {
+  token: Option<String>,
+  order_tx: Sender<OrderMessage>,
+  instruments: Arc<InstrumentRegistry>,
+  risk: Arc<RiskManager>,
+  audit: Arc<AuditLog>,
+}
+
+// Credentials sent with every admin request: "Authorization: Bearer <token>" and the
+// operator's name in "X-Admin-User", which is recorded in the audit log
+#[derive(Clone, Debug, Default)]
+struct Credentials {
+  authorization: Option<String>,
+  user: Option<String>,
+}
+
+type JsonReply = warp::reply::WithStatus<warp::reply::Json>;
+
+// Compare without returning early so that the time taken does not reveal a matching prefix
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+  a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+impl AdminState {
+  // Manage the limits of the risk manager used by the order entry gateways
+  pub fn new(
+    token: Option<String>,
+    order_tx: Sender<OrderMessage>,
+    instruments: Arc<InstrumentRegistry>,
+    risk: Arc<RiskManager>,
+    audit: Arc<AuditLog>
+  ) -> Self {
+    AdminState { token, order_tx, instruments, risk, audit }
+  }
+  
+  // Returns the acting user, or the reply to send. Refused requests are audited as well.
+  fn authorize(&self, credentials: &Credentials, action: &str) -> Result<String, JsonReply> {
+    let actor = credentials.user.clone().unwrap_or_else(|| "unknown".to_string());
+    let presented = credentials.authorization.as_deref().and_then(|value| value.strip_prefix("Bearer "));
+    
+    let authorized = match (&self.token, presented) {
+      (Some(token), Some(presented)) => constant_time_eq(token.as_bytes(), presented.as_bytes()),
+      _ => false,
+    };
+    if !authorized {
+      self.audit.record_refused(&actor, action);
+      return Err(ApiError::Unauthorized.reply());
+    }
+    Ok(actor)
+  }
+  
+  // Authenticate, sequence the command behind all previously received orders and audit it
+  async fn submit(&self, credentials: Credentials, action: &str, details: Value, message: OrderMessage) -> JsonReply {
+    let actor = match self.authorize(&credentials, action) {
+      Ok(actor) => actor,
+      Err(reply) => return reply,
+    };
+    
+    if let Some(symbol) = details.get("symbol").and_then(Value::as_str) {
+      if self.instruments.get(symbol).is_none() {
+        self.audit.record(&actor, action, details.clone(), "unknown symbol");
+        return ApiError::UnknownSymbol(symbol.to_string()).reply();
+      }
+    }
+    
+    if self.order_tx.send(message).await.is_err() {
+      self.audit.record(&actor, action, details, "engine unavailable");
+      return ApiError::EngineUnavailable.reply();
+    }
+    
+    self.audit.record(&actor, action, details.clone(), "accepted");
+    warp::reply::with_status(
+      warp::reply::json(&serde_json::json!({ "action": action, "details": details })),
+      StatusCode::ACCEPTED
+    )
+  }
+  
+  // Authenticate and apply a change to reference data that does not go through the sequencer
+  fn apply(&self, credentials: &Credentials, action: &str, details: Value, change: impl FnOnce() -> Result<Value, ApiError>) -> JsonReply {
+    let actor = match self.authorize(credentials, action) {
+      Ok(actor) => actor,
+      Err(reply) => return reply,
+    };
+    
+    match change() {
+      Ok(body) => {
+        self.audit.record(&actor, action, details, "applied");
+        warp::reply::with_status(warp::reply::json(&body), StatusCode::OK)
+      }
+      Err(e) => {
+        self.audit.record(&actor, action, details, &e.to_string());
+        e.reply()
+      }
+    }
+  }
+  
+  // Authenticate a read-only request
+  fn read(&self, credentials: &Credentials, action: &str, body: impl FnOnce() -> Value) -> JsonReply {
+    match self.authorize(credentials, action) {
+      Ok(_) => warp::reply::with_status(warp::reply::json(&body()), StatusCode::OK),
+      Err(reply) => reply,
+    }
+  }
+}
+
+fn with_state(state: Arc<AdminState>) -> impl Filter<Extract = (Arc<AdminState>,), Error = std::convert::Infallible> + Clone {
+  warp::any().map(move || state.clone())
+}
+
+fn with_credentials() -> impl Filter<Extract = (Credentials,), Error = Rejection> + Clone {
+  warp::header::optional::<String>("authorization")
+    .and(warp::header::optional::<String>("x-admin-user"))
+    .map(|authorization, user| Credentials { authorization, user })
+}
+
+// Admin API: kill switch, session phases, instruments and risk limits. Kill switch and phase
+// commands go through the input sequencer like an order, so they take effect after everything
+// received before them.
+pub fn routes(state: Arc<AdminState>) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+  let halt_symbol = warp::path!("admin" / "v1" / "halt" / String)
+    .and(warp::post())
+    .and(with_credentials())
+    .and(with_state(state.clone()))
+    .and_then(handle_halt);
+  
+  let halt_all = warp::path!("admin" / "v1" / "halt")
+    .and(warp::post())
+    .and(with_credentials())
+    .and(with_state(state.clone()))
+    .and_then(handle_halt_all);
+  
+  let resume_symbol = warp::path!("admin" / "v1" / "resume" / String)
+    .and(warp::post())
+    .and(with_credentials())
+    .and(with_state(state.clone()))
+    .and_then(handle_resume);
+  
+  let resume_all = warp::path!("admin" / "v1" / "resume")
+    .and(warp::post())
+    .and(with_credentials())
+    .and(with_state(state.clone()))
+    .and_then(handle_resume_all);
+  
+  let mass_cancel = warp::path!("admin" / "v1" / "mass-cancel")
+    .and(warp::post())
+    .and(warp::body::json())
+    .and(with_credentials())
+    .and(with_state(state.clone()))
+    .and_then(handle_mass_cancel);
+  
+  let set_status = warp::path!("admin" / "v1" / "instruments" / String / "status")
+    .and(warp::put())
+    .and(warp::body::json())
+    .and(with_credentials())
+    .and(with_state(state.clone()))
+    .and_then(handle_set_status);
+  
+  let audit = warp::path!("admin" / "v1" / "audit")
+    .and(warp::get())
+    .and(with_credentials())
+    .and(with_state(state.clone()))
+    .and_then(handle_get_audit);
+  
+  // Without a token no admin path is registered, so requests fall through like unknown paths
+  let enabled = state.token.is_some();
+  let served = warp::any()
+    .and_then(move || async move {
+      if enabled { Ok(()) } else { Err(warp::reject::not_found()) }
+    })
+    .untuple_one();
+  
+  let kill_switch = halt_symbol.or(halt_all).or(resume_symbol).or(resume_all).or(mass_cancel).or(set_status).or(audit);
+  served.and(kill_switch.or(instrument_routes(state.clone())).or(risk_routes(state)))
+}
+
+// Instrument management: create or replace a definition, and delete
+fn instrument_routes(state: Arc<AdminState>) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+  let put_instrument = warp::path!("admin" / "v1" / "instruments" / String)
+    .and(warp::put())
+    .and(warp::body::json())
+    .and(with_credentials())
+    .and(with_state(state.clone()))
+    .map(|symbol: String, body: Value, credentials: Credentials, state: Arc<AdminState>| {
+      let details = serde_json::json!({ "symbol": symbol, "definition": body });
+      state.apply(&credentials, "define_instrument", details, || {
+        // Phase changes must be sequenced with the order flow, see handle_set_status
+        if body.get("status").is_some() {
+          return Err(ApiError::InvalidRequest(
+            format!("status is changed through PUT /admin/v1/instruments/{}/status", symbol)
+          ));
+        }
+        let mut instrument: Instrument = serde_json::from_value(body)
+          .map_err(|e| ApiError::InvalidRequest(e.to_string()))?;
+        // The path is authoritative for the symbol
+        instrument.symbol = symbol;
+        let defined = state.instruments.define(instrument)
+          .map_err(|e| ApiError::InvalidRequest(e.to_string()))?;
+        Ok(serde_json::to_value(defined).unwrap_or_default())
+      })
+    });
+  
+  let delete_instrument = warp::path!("admin" / "v1" / "instruments" / String)
+    .and(warp::delete())
+    .and(with_credentials())
+    .and(with_state(state))
+    .and_then(handle_delete_instrument);
+  
+  put_instrument.or(delete_instrument)
+}
+
+fn limits_body(account: Option<&str>, limits: &RiskLimits) -> Value {
+  serde_json::json!({ "account": account, "limits": limits })
+}
+
+// Risk limit management: default limits and per-account overrides
+fn risk_routes(state: Arc<AdminState>) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+  let get_defaults = warp::path!("admin" / "v1" / "risk" / "limits")
+    .and(warp::get())
+    .and(with_credentials())
+    .and(with_state(state.clone()))
+    .map(|credentials: Credentials, state: Arc<AdminState>| {
+      state.read(&credentials, "read_risk_limits", || {
+        limits_body(None, &state.risk.default_limits())
+      })
+    });
+  
+  let put_defaults = warp::path!("admin" / "v1" / "risk" / "limits")
+    .and(warp::put())
+    .and(warp::body::json())
+    .and(with_credentials())
+    .and(with_state(state.clone()))
+    .map(|limits: RiskLimits, credentials: Credentials, state: Arc<AdminState>| {
+      let details = limits_body(None, &limits);
+      state.apply(&credentials, "set_risk_limits", details.clone(), || {
+        state.risk.set_default_limits(limits);
+        Ok(details)
+      })
+    });
+  
+  let get_account = warp::path!("admin" / "v1" / "risk" / "limits" / String)
+    .and(warp::get())
+    .and(with_credentials())
+    .and(with_state(state.clone()))
+    .map(|account: String, credentials: Credentials, state: Arc<AdminState>| {
+      state.read(&credentials, "read_risk_limits", || {
+        limits_body(Some(&account), &state.risk.limits(&account))
+      })
+    });
+  
+  let put_account = warp::path!("admin" / "v1" / "risk" / "limits" / String)
+    .and(warp::put())
+    .and(warp::body::json())
+    .and(with_credentials())
+    .and(with_state(state.clone()))
+    .map(|account: String, limits: RiskLimits, credentials: Credentials, state: Arc<AdminState>| {
+      let details = limits_body(Some(&account), &limits);
+      state.apply(&credentials, "set_risk_limits", details.clone(), || {
+        state.risk.set_limits(&account, limits);
+        Ok(details)
+      })
+    });
+  
+  let delete_account = warp::path!("admin" / "v1" / "risk" / "limits" / String)
+    .and(warp::delete())
+    .and(with_credentials())
+    .and(with_state(state))
+    .map(|account: String, credentials: Credentials, state: Arc<AdminState>| {
+      state.apply(&credentials, "remove_risk_limits", serde_json::json!({ "account": account }), || {
+        state.risk.remove_limits(&account);
+        Ok(limits_body(Some(&account), &state.risk.limits(&account)))
+      })
+    });
+  
+  get_defaults.or(put_defaults).or(get_account).or(put_account).or(delete_account)
+}
+
+// Halted symbols are cancel-only: new orders are rejected, resting orders can still be cancelled
+async fn handle_halt(symbol: String, credentials: Credentials, state: Arc<AdminState>) -> Result<impl Reply, Rejection> {
+  let details = serde_json::json!({ "symbol": symbol });
+  let message = OrderMessage::SetTradingStatus { symbol, status: TradingStatus::Halted };
+  Ok(state.submit(credentials, "halt", details, message).await)
+}
+
+async fn handle_halt_all(credentials: Credentials, state: Arc<AdminState>) -> Result<impl Reply, Rejection> {
+  Ok(state.submit(credentials, "halt_all", serde_json::json!({}), OrderMessage::HaltAll).await)
+}
+
+async fn handle_resume(symbol: String, credentials: Credentials, state: Arc<AdminState>) -> Result<impl Reply, Rejection> {
+  let details = serde_json::json!({ "symbol": symbol });
+  let message = OrderMessage::SetTradingStatus { symbol, status: TradingStatus::Trading };
+  Ok(state.submit(credentials, "resume", details, message).await)
+}
+
+async fn handle_resume_all(credentials: Credentials, state: Arc<AdminState>) -> Result<impl Reply, Rejection> {
+  Ok(state.submit(credentials, "resume_all", serde_json::json!({}), OrderMessage::ResumeAll).await)
+}
+
+async fn handle_mass_cancel(request: MassCancel, credentials: Credentials, state: Arc<AdminState>) -> Result<impl Reply, Rejection> {
+  let details = serde_json::to_value(&request).unwrap_or_default();
+  Ok(state.submit(credentials, "mass_cancel", details, OrderMessage::MassCancel(request)).await)
+}
+
+// Orders resting on a deleted symbol could never trade again, so they are mass cancelled.
+// The definition goes first: orders sequenced ahead of the mass cancel are then rejected as
+// unknown instead of resting.
+async fn handle_delete_instrument(symbol: String, credentials: Credentials, state: Arc<AdminState>) -> Result<impl Reply, Rejection> {
+  let details = serde_json::json!({ "symbol": symbol });
+  let actor = match state.authorize(&credentials, "delete_instrument") {
+    Ok(actor) => actor,
+    Err(reply) => return Ok(reply),
+  };
+  
+  let instrument = match state.instruments.remove(&symbol) {
+    Some(instrument) => instrument,
+    None => {
+      let e = ApiError::UnknownSymbol(symbol);
+      state.audit.record(&actor, "delete_instrument", details, &e.to_string());
+      return Ok(e.reply());
+    }
+  };
+  let cancel = MassCancel { symbol: Some(symbol), account: None, side: None };
+  if state.order_tx.send(OrderMessage::MassCancel(cancel)).await.is_err() {
+    state.instruments.insert(instrument);
+    state.audit.record(&actor, "delete_instrument", details, "engine unavailable");
+    return Ok(ApiError::EngineUnavailable.reply());
+  }
+  
+  state.audit.record(&actor, "delete_instrument", details, "applied");
+  Ok(warp::reply::with_status(warp::reply::json(&instrument), StatusCode::OK))
+}
+
+#[derive(Deserialize)]
+struct StatusChange {
+  status: TradingStatus,
+}
+
+// Session phase changes are sequenced with the order flow so that auctions
+// uncross exactly the orders received before the phase ended
+async fn handle_set_status(symbol: String, change: StatusChange, credentials: Credentials, state: Arc<AdminState>) -> Result<impl Reply, Rejection> {
+  let details = serde_json::json!({ "symbol": symbol, "status": change.status });
+  let message = OrderMessage::SetTradingStatus { symbol, status: change.status };
+  Ok(state.submit(credentials, "set_status", details, message).await)
+}
+
+async fn handle_get_audit(credentials: Credentials, state: Arc<AdminState>) -> Result<impl Reply, Rejection> {
+  Ok(match state.authorize(&credentials, "read_audit") {
+    Ok(_) => warp::reply::with_status(warp::reply::json(&state.audit.entries()), StatusCode::OK),
+    Err(reply) => reply,
+  })
+}
\ No newline at end of file
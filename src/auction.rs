@@ -0,0 +1,233 @@
+/*
+* filename : auction
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 개장/폐장 단일가 매매 (균형 가격 산출 및 일괄 체결)
+**/
+
+use uuid::Uuid;
+use chrono::Utc;
+use crate::models::{Execution, OrderBook, OrderStatus, Side, Price, Qty};
+
+// Result of an uncrossing calculation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Uncross {
+  pub price: Price,
+  pub volume: Qty,
+  pub imbalance: Qty,               // Unmatched quantity on the larger side at the price
+  pub imbalance_side: Option<Side>, // None when both sides are equal
+}
+
+// Cumulative quantity willing to trade at each limit price, ascending by price
+fn cumulative_volumes(book: &OrderBook) -> Vec<(Price, Qty, Qty)> {
+  let mut prices: Vec<Price> = book.buy_book.limits.keys()
+    .chain(book.sell_book.limits.keys())
+    .cloned()
+    .collect();
+  prices.sort();
+  prices.dedup();
+  
+  // Buyers at a price are all bids at or above it, sellers all asks at or below it
+  let mut result: Vec<(Price, Qty, Qty)> = prices.iter().map(|&p| (p, Qty::ZERO, Qty::ZERO)).collect();
+  
+  let mut sell = 0u64;
+  let mut asks = book.sell_book.limits.iter().peekable();
+  for entry in result.iter_mut() {
+    while let Some((_, level)) = asks.next_if(|(&price, _)| price <= entry.0) {
+      sell = sell.saturating_add(level.total_volume.0);
+    }
+    entry.2 = Qty(sell);
+  }
+  
+  let mut buy = 0u64;
+  let mut bids = book.buy_book.limits.iter().rev().peekable();
+  for entry in result.iter_mut().rev() {
+    while let Some((_, level)) = bids.next_if(|(&price, _)| price >= entry.0) {
+      buy = buy.saturating_add(level.total_volume.0);
+    }
+    entry.1 = Qty(buy);
+  }
+  
+  result
+}
+
+// Pick the single price that maximizes executable volume. Ties are broken by the smallest
+// imbalance, then by the distance to the reference price (usually the last trade), then by the
+// lower price. Returns None when the book does not cross.
+pub fn equilibrium(book: &OrderBook, reference: Option<Price>) -> Option<Uncross> {
+  let mut best: Option<Uncross> = None;
+  
+  for (price, buy, sell) in cumulative_volumes(book) {
+    let volume = buy.min(sell);
+    if volume.is_zero() {
+      continue;
+    }
+    
+    let imbalance = buy.max(sell) - volume;
+    let imbalance_side = match buy.cmp(&sell) {
+      std::cmp::Ordering::Greater => Some(Side::Buy),
+      std::cmp::Ordering::Less => Some(Side::Sell),
+      std::cmp::Ordering::Equal => None,
+    };
+    let candidate = Uncross { price, volume, imbalance, imbalance_side };
+    
+    let better = match &best {
+      None => true,
+      Some(current) => {
+        let distance = |p: Price| reference.map(|r| p.0.abs_diff(r.0)).unwrap_or(0);
+        (candidate.volume, std::cmp::Reverse(candidate.imbalance), std::cmp::Reverse(distance(candidate.price)))
+          > (current.volume, std::cmp::Reverse(current.imbalance), std::cmp::Reverse(distance(current.price)))
+      }
+    };
+    
+    // Prices are visited in ascending order, so an equal candidate never replaces the lower price
+    if better {
+      best = Some(candidate);
+    }
+  }
+  
+  best
+}
+
+// Execute the uncrossing volume at a single price, in price-time priority on both sides.
+// Executions are emitted in (buy, sell) pairs; neither side is the maker.
+pub fn execute(book: &mut OrderBook, uncross: &Uncross) -> Vec<Execution> {
+  let mut executions = Vec::new();
+  let mut remaining = uncross.volume;
+  
+  while !remaining.is_zero() {
+    let (bid_price, ask_price) = match (book.buy_book.best_level, book.sell_book.best_level) {
+      (Some(bid), Some(ask)) if bid >= uncross.price && ask <= uncross.price => (bid, ask),
+      _ => break,
+    };
+    
+    let bid_level = book.buy_book.limits.get_mut(&bid_price).unwrap();
+    let ask_level = book.sell_book.limits.get_mut(&ask_price).unwrap();
+    let quantity = remaining
+      .min(bid_level.orders.front().unwrap().remain_quantity)
+      .min(ask_level.orders.front().unwrap().remain_quantity);
+    
+    let (buy_order, _) = bid_level.match_order(quantity).unwrap();
+    let (sell_order, _) = ask_level.match_order(quantity).unwrap();
+    remaining -= quantity;
+    
+    let trade_id = Uuid::new_v4().to_string();
+    for order in [&buy_order, &sell_order] {
+      executions.push(Execution {
+        exec_id: Uuid::new_v4().to_string(),
+        order_id: order.order_id.clone(),
+        trade_id: trade_id.clone(),
+        account: order.account.clone(),
+        symbol: order.symbol.clone(),
+        side: order.side.clone(),
+        price: uncross.price,
+        quantity,
+        is_maker: false,
+        fee: 0,
+        transaction_time: Utc::now()
+      });
+      
+      if order.status == OrderStatus::Filled {
+        book.order_map.remove(&order.order_id);
+      }
+    }
+    
+    if bid_level.is_empty() {
+      book.buy_book.limits.remove(&bid_price);
+      book.buy_book.update_best_level();
+    }
+    if ask_level.is_empty() {
+      book.sell_book.limits.remove(&ask_price);
+      book.sell_book.update_best_level();
+    }
+  }
+  
+  executions
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::models::{Order, OrderType};
+  
+  fn add(book: &mut OrderBook, order_id: &str, side: Side, price: u64, quantity: u64) {
+    book.insert_order(Order {
+      order_id: order_id.into(),
+      symbol: "SYM".into(),
+      account: String::new(),
+      price: Price(price),
+      quantity: Qty(quantity),
+      side,
+      order_type: OrderType::Limit,
+      status: OrderStatus::New,
+      filled_quantity: Qty(0),
+      remain_quantity: Qty(quantity),
+      entry_time: Utc::now()
+    });
+  }
+  
+  #[test]
+  fn test_equilibrium_maximizes_volume() {
+    let mut book = OrderBook::new();
+    add(&mut book, "b1", Side::Buy, 102, 10);
+    add(&mut book, "b2", Side::Buy, 101, 5);
+    add(&mut book, "b3", Side::Buy, 99, 10);
+    add(&mut book, "s1", Side::Sell, 98, 4);
+    add(&mut book, "s2", Side::Sell, 100, 8);
+    add(&mut book, "s3", Side::Sell, 103, 10);
+    
+    // At 100 and 101 buyers want 15, sellers offer 12; 101 is chosen for the lower distance
+    // to the reference. Without a reference the lower price wins.
+    let uncross = equilibrium(&book, Some(Price(105))).unwrap();
+    assert_eq!(uncross, Uncross { price: Price(101), volume: Qty(12), imbalance: Qty(3), imbalance_side: Some(Side::Buy) });
+    assert_eq!(equilibrium(&book, None).unwrap().price, Price(100));
+  }
+  
+  #[test]
+  fn test_equilibrium_minimizes_imbalance() {
+    let mut book = OrderBook::new();
+    add(&mut book, "b1", Side::Buy, 102, 10);
+    add(&mut book, "s1", Side::Sell, 100, 10);
+    add(&mut book, "s2", Side::Sell, 101, 5);
+    
+    // Volume is 10 at every price, but only 100 leaves no sellers unmatched
+    let uncross = equilibrium(&book, Some(Price(102))).unwrap();
+    assert_eq!(uncross.price, Price(100));
+    assert_eq!(uncross.imbalance, Qty(0));
+    assert_eq!(uncross.imbalance_side, None);
+  }
+  
+  #[test]
+  fn test_no_cross() {
+    let mut book = OrderBook::new();
+    add(&mut book, "b1", Side::Buy, 99, 10);
+    add(&mut book, "s1", Side::Sell, 100, 10);
+    assert_eq!(equilibrium(&book, None), None);
+  }
+  
+  #[test]
+  fn test_execute_uncross() {
+    let mut book = OrderBook::new();
+    add(&mut book, "b1", Side::Buy, 102, 10);
+    add(&mut book, "b2", Side::Buy, 101, 5);
+    add(&mut book, "b3", Side::Buy, 99, 10);
+    add(&mut book, "s1", Side::Sell, 98, 4);
+    add(&mut book, "s2", Side::Sell, 100, 8);
+    add(&mut book, "s3", Side::Sell, 103, 10);
+    
+    let uncross = equilibrium(&book, Some(Price(105))).unwrap();
+    let executions = execute(&mut book, &uncross);
+    
+    assert!(executions.iter().all(|e| e.price == Price(101)));
+    let bought: Qty = executions.iter().filter(|e| e.side == Side::Buy).map(|e| e.quantity).sum();
+    let sold: Qty = executions.iter().filter(|e| e.side == Side::Sell).map(|e| e.quantity).sum();
+    assert_eq!(bought, Qty(12));
+    assert_eq!(sold, Qty(12));
+    
+    // b2 keeps its unfilled 3, the book is left uncrossed
+    assert_eq!(book.buy_book.best_level, Some(Price(101)));
+    assert_eq!(book.buy_book.get_best_level().unwrap().total_volume, Qty(3));
+    assert_eq!(book.sell_book.best_level, Some(Price(103)));
+    assert_eq!(book.validate(), Ok(()));
+  }
+}
\ No newline at end of file
@@ -0,0 +1,89 @@
+/*
+* filename : auth
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 거래 계정 API 키 인증
+**/
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::RwLock;
+use serde::Deserialize;
+use crate::fix::store::is_valid_comp_id;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct AccountCredential {
+  pub account: String,
+  pub api_key: String,
+  // FIX SenderCompIDs the account logs on with
+  #[serde(default)]
+  pub fix_comp_ids: Vec<String>,
+}
+
+// API keys of the trading accounts. An account may have several keys and FIX sessions.
+pub struct AccountRegistry {
+  keys: RwLock<HashMap<String, String>>,     // API key -> account
+  comp_ids: RwLock<HashMap<String, String>>, // FIX SenderCompID -> account
+}
+
+impl Default for AccountRegistry {
+  fn default() -> Self {
+    AccountRegistry::new()
+  }
+}
+
+impl AccountRegistry {
+  pub fn new() -> Self {
+    AccountRegistry {
+      keys: RwLock::new(HashMap::new()),
+      comp_ids: RwLock::new(HashMap::new()),
+    }
+  }
+  
+  pub fn insert(&self, account: &str, api_key: &str) {
+    self.keys.write().unwrap().insert(api_key.to_string(), account.to_string());
+  }
+  
+  // Let the account log on to the FIX gateway as comp_id
+  pub fn bind_comp_id(&self, account: &str, comp_id: &str) {
+    self.comp_ids.write().unwrap().insert(comp_id.to_string(), account.to_string());
+  }
+  
+  // Load credentials from a JSON file containing an array of {"account", "api_key"} with
+  // optional "fix_comp_ids"
+  pub fn load_from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)
+      .map_err(|e| anyhow::anyhow!("failed to read {}: {}", path.display(), e))?;
+    let credentials: Vec<AccountCredential> = serde_json::from_str(&content)
+      .map_err(|e| anyhow::anyhow!("invalid account config {}: {}", path.display(), e))?;
+    
+    let registry = AccountRegistry::new();
+    for credential in credentials {
+      if credential.account.is_empty() || credential.api_key.is_empty() {
+        anyhow::bail!("account without a name or API key in {}", path.display());
+      }
+      registry.insert(&credential.account, &credential.api_key);
+      for comp_id in &credential.fix_comp_ids {
+        if !is_valid_comp_id(comp_id) {
+          anyhow::bail!("invalid FIX SenderCompID {:?} in {}", comp_id, path.display());
+        }
+        if registry.comp_id_account(comp_id).is_some_and(|account| account != credential.account) {
+          anyhow::bail!("FIX SenderCompID {} is bound to more than one account in {}", comp_id, path.display());
+        }
+        registry.bind_comp_id(&credential.account, comp_id);
+      }
+    }
+    Ok(registry)
+  }
+  
+  // The account owning the API key
+  pub fn authenticate(&self, api_key: &str) -> Option<String> {
+    self.keys.read().unwrap().get(api_key).cloned()
+  }
+  
+  // The account a FIX SenderCompID is bound to
+  pub fn comp_id_account(&self, comp_id: &str) -> Option<String> {
+    self.comp_ids.read().unwrap().get(comp_id).cloned()
+  }
+}
\ No newline at end of file
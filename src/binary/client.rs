@@ -0,0 +1,104 @@
+/*
+* filename : client
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 바이너리 주문 입력 프로토콜 클라이언트 라이브러리
+**/
+
+use std::io;
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, ToSocketAddrs};
+
+use crate::models::{OrderType, Price, Qty, Side};
+use crate::util::binary_codec::{BinaryMessage, CodecError, RejectReason};
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+  #[error("I/O error: {0}")]
+  Io(#[from] io::Error),
+  #[error("invalid message: {0}")]
+  Codec(#[from] CodecError),
+  #[error("connection closed by the gateway")]
+  Closed,
+  #[error("logon rejected: {0:?}")]
+  LogonRejected(RejectReason),
+  #[error("unexpected message {0:?}")]
+  UnexpectedMessage(BinaryMessage),
+}
+
+// A logged on connection to the binary order entry gateway. Requests are only sent;
+// acknowledgements, rejects and fills are read in order with recv.
+pub struct BinaryClient {
+  stream: TcpStream,
+  buffer: Vec<u8>,
+  account: String,
+}
+
+impl BinaryClient {
+  pub async fn connect(addr: impl ToSocketAddrs, api_key: &str) -> Result<Self, ClientError> {
+    let stream = TcpStream::connect(addr).await?;
+    stream.set_nodelay(true)?;
+    let mut client = BinaryClient { stream, buffer: Vec::new(), account: String::new() };
+    
+    client.send(&BinaryMessage::Logon { api_key: api_key.to_string() }).await?;
+    match client.recv().await? {
+      BinaryMessage::LogonAccepted { account } => client.account = account,
+      BinaryMessage::Reject { reason, .. } => return Err(ClientError::LogonRejected(reason)),
+      other => return Err(ClientError::UnexpectedMessage(other)),
+    }
+    Ok(client)
+  }
+  
+  pub fn account(&self) -> &str {
+    &self.account
+  }
+  
+  pub async fn new_order(
+    &mut self,
+    client_order_id: &str,
+    symbol: &str,
+    side: Side,
+    order_type: OrderType,
+    price: Price,
+    quantity: Qty
+  ) -> Result<(), ClientError> {
+    self.send(&BinaryMessage::NewOrder {
+      client_order_id: client_order_id.to_string(),
+      symbol: symbol.to_string(),
+      side,
+      order_type,
+      price,
+      quantity,
+    }).await
+  }
+  
+  pub async fn cancel(&mut self, client_order_id: &str) -> Result<(), ClientError> {
+    self.send(&BinaryMessage::Cancel { client_order_id: client_order_id.to_string() }).await
+  }
+  
+  // Quantity includes what has been filled already
+  pub async fn amend(&mut self, client_order_id: &str, price: Price, quantity: Qty) -> Result<(), ClientError> {
+    self.send(&BinaryMessage::Amend { client_order_id: client_order_id.to_string(), price, quantity }).await
+  }
+  
+  pub async fn send(&mut self, message: &BinaryMessage) -> Result<(), ClientError> {
+    self.stream.write_all(&message.encode()?).await?;
+    Ok(())
+  }
+  
+  // The next message from the gateway
+  pub async fn recv(&mut self) -> Result<BinaryMessage, ClientError> {
+    let mut chunk = [0u8; 4096];
+    loop {
+      if let Some(message) = BinaryMessage::decode(&mut self.buffer)? {
+        return Ok(message);
+      }
+      let n = self.stream.read(&mut chunk).await?;
+      if n == 0 {
+        return Err(ClientError::Closed);
+      }
+      self.buffer.extend_from_slice(&chunk[..n]);
+    }
+  }
+}
\ No newline at end of file
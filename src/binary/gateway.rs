@@ -0,0 +1,514 @@
+/*
+* filename : gateway
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 바이너리 주문 입력 게이트웨이 - TCP 연결별 로그온, 주문 입력 및 체결 보고
+**/
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use chrono::Utc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::sync::mpsc::{self, Sender};
+use uuid::Uuid;
+
+use crate::auth::AccountRegistry;
+use crate::error::ApiError;
+use crate::instrument::{InstrumentRegistry, OrderValidationError};
+use crate::matching_engine::{CancelRejectReason, OrderOutcome};
+use crate::models::{Amend, Execution, Order, OrderMessage, OrderStatus, OrderType, Price, Qty, Side};
+use crate::risk::RiskManager;
+use crate::throttle::Throttle;
+use crate::util::binary_codec::{BinaryMessage, ExecType, ExecutionReport, RejectReason};
+
+const LOGON_TIMEOUT: Duration = Duration::from_secs(10);
+// Closed orders are followed a little longer for fills sequenced before the cancel and
+// for the engine's answer to changes still in flight
+const CLOSED_ORDER_RETENTION: Duration = Duration::from_secs(60);
+
+// Disconnect with the reason, or carry on
+type Flow = Result<(), String>;
+
+// An order entered over a binary connection, as far as the gateway knows it
+#[derive(Clone, Debug)]
+struct BinaryOrder {
+  order_id: Uuid,
+  connection: u64,
+  client_order_id: String,
+  symbol: String,
+  side: Side,
+  order_type: OrderType,
+  price: Price,
+  quantity: Qty,
+  cum_qty: Qty,
+  closed_at: Option<Instant>,
+}
+
+impl BinaryOrder {
+  fn leaves_qty(&self) -> Qty {
+    if self.closed_at.is_some() || self.cum_qty >= self.quantity {
+      Qty::ZERO
+    } else {
+      self.quantity - self.cum_qty
+    }
+  }
+  
+  fn report(&self, exec_type: ExecType) -> ExecutionReport {
+    ExecutionReport {
+      client_order_id: self.client_order_id.clone(),
+      order_id: self.order_id,
+      exec_type,
+      side: self.side.clone(),
+      price: self.price,
+      quantity: self.quantity,
+      leaves_quantity: self.leaves_qty(),
+      transact_time: Utc::now(),
+    }
+  }
+}
+
+#[derive(Default)]
+struct OrderTable {
+  orders: HashMap<String, BinaryOrder>,                   // order id -> order
+  client_order_ids: HashMap<(u64, String), String>,      // (connection, client order id) -> order id
+  connections: HashMap<u64, Sender<BinaryMessage>>,      // Logged on connection -> its writer task
+}
+
+impl OrderTable {
+  fn forget(&mut self, order_id: &str) {
+    if let Some(order) = self.orders.remove(order_id) {
+      self.client_order_ids.remove(&(order.connection, order.client_order_id));
+    }
+  }
+}
+
+// Order entry over plain TCP with the fixed-layout messages of util::binary_codec.
+// A connection logs on with an account API key; client order ids are scoped to the
+// connection and the orders it leaves open are cancelled when it disconnects.
+pub struct BinaryGateway {
+  accounts: Arc<AccountRegistry>,
+  instruments: Arc<InstrumentRegistry>,
+  order_tx: Sender<OrderMessage>,
+  risk: Arc<RiskManager>,
+  throttle: Arc<Throttle>,
+  orders: Mutex<OrderTable>,
+  next_connection: AtomicU64,
+}
+
+impl BinaryGateway {
+  // Orders, cancels and amends are rate limited like REST requests, and new orders and
+  // amends pass the risk stage shared with the other order entry paths
+  pub fn new(
+    accounts: Arc<AccountRegistry>,
+    instruments: Arc<InstrumentRegistry>,
+    order_tx: Sender<OrderMessage>,
+    risk: Arc<RiskManager>,
+    throttle: Arc<Throttle>
+  ) -> Self {
+    BinaryGateway {
+      accounts,
+      instruments,
+      order_tx,
+      risk,
+      throttle,
+      orders: Mutex::new(OrderTable::default()),
+      next_connection: AtomicU64::new(1),
+    }
+  }
+  
+  // Accept connections until the listener fails
+  pub async fn serve(self: Arc<Self>, listener: TcpListener) {
+    loop {
+      match listener.accept().await {
+        Ok((stream, peer)) => {
+          let _ = stream.set_nodelay(true);
+          tokio::spawn(handle_connection(self.clone(), stream, peer.ip()));
+        }
+        Err(e) => {
+          eprintln!("Binary order entry listener failed: {}", e);
+          break;
+        }
+      }
+    }
+  }
+  
+  // Report an execution to the connection that entered the order. Executions of other
+  // orders are ignored.
+  pub async fn on_execution(&self, execution: &Execution) {
+    let (report, connection) = {
+      let mut table = self.orders.lock().unwrap();
+      let order = match table.orders.get_mut(&execution.order_id) {
+        Some(order) => order,
+        None => return,
+      };
+      order.cum_qty += execution.quantity;
+      if order.cum_qty >= order.quantity && order.closed_at.is_none() {
+        order.closed_at = Some(Instant::now());
+      }
+      let report = ExecutionReport {
+        price: execution.price,
+        quantity: execution.quantity,
+        transact_time: execution.transaction_time,
+        ..order.report(ExecType::Trade)
+      };
+      let connection = order.connection;
+      (report, table.connections.get(&connection).cloned())
+    };
+    
+    if let Some(connection) = connection {
+      let _ = connection.send(BinaryMessage::ExecutionReport(report)).await;
+    }
+  }
+  
+  // Report the engine's outcome of an order the gateway acknowledged: rejects, cancels
+  // (including the unfilled rest of a market order) and amends. A rejected replacement of
+  // an amend ends the order, since the engine already removed the original.
+  pub async fn on_outcome(&self, outcome: &OrderOutcome) {
+    let (message, connection) = {
+      let mut table = self.orders.lock().unwrap();
+      let order = match table.orders.get_mut(outcome.order_id()) {
+        Some(order) => order,
+        None => return,
+      };
+      let message = match outcome {
+        OrderOutcome::Rejected { reason, .. } => {
+          order.closed_at.get_or_insert_with(Instant::now);
+          BinaryMessage::Reject { client_order_id: order.client_order_id.clone(), reason: reject_reason(reason) }
+        }
+        OrderOutcome::Cancelled { .. } => {
+          order.closed_at.get_or_insert_with(Instant::now);
+          BinaryMessage::ExecutionReport(order.report(ExecType::Cancelled))
+        }
+        OrderOutcome::Amended { price, quantity, .. } => {
+          order.price = *price;
+          order.quantity = *quantity;
+          BinaryMessage::ExecutionReport(order.report(ExecType::Amended))
+        }
+        OrderOutcome::CancelRejected { reason, .. } => {
+          let reason = match reason {
+            CancelRejectReason::UnknownOrder => RejectReason::UnknownOrder,
+            CancelRejectReason::NotTrading(_) => RejectReason::NotTrading,
+          };
+          BinaryMessage::Reject { client_order_id: order.client_order_id.clone(), reason }
+        }
+      };
+      let connection = order.connection;
+      (message, table.connections.get(&connection).cloned())
+    };
+    
+    if let Some(connection) = connection {
+      let _ = connection.send(message).await;
+    }
+  }
+  
+  fn purge_closed(&self) {
+    let mut table = self.orders.lock().unwrap();
+    let expired: Vec<String> = table.orders.iter()
+      .filter(|(_, order)| order.closed_at.is_some_and(|closed| closed.elapsed() >= CLOSED_ORDER_RETENTION))
+      .map(|(order_id, _)| order_id.clone())
+      .collect();
+    for order_id in expired {
+      table.forget(&order_id);
+    }
+  }
+}
+
+struct Connection {
+  gateway: Arc<BinaryGateway>,
+  writer: OwnedWriteHalf,
+  id: u64,
+  ip: IpAddr,
+  account: Option<String>,
+  api_key: String, // Rate limits are those of the API key the connection logged on with
+  reports: Sender<BinaryMessage>,
+}
+
+async fn handle_connection(gateway: Arc<BinaryGateway>, stream: TcpStream, ip: IpAddr) {
+  let (mut reader, writer) = stream.into_split();
+  let (report_tx, mut report_rx) = mpsc::channel::<BinaryMessage>(1000);
+  let id = gateway.next_connection.fetch_add(1, Ordering::Relaxed);
+  let mut connection = Connection { gateway, writer, id, ip, account: None, api_key: String::new(), reports: report_tx };
+  
+  let connected = Instant::now();
+  let mut buffer = Vec::new();
+  let mut chunk = [0u8; 4096];
+  let mut tick = tokio::time::interval(Duration::from_secs(1));
+  
+  loop {
+    let flow = tokio::select! {
+      read = reader.read(&mut chunk) => match read {
+        Ok(0) | Err(_) => Err("connection closed".to_string()),
+        Ok(n) => {
+          buffer.extend_from_slice(&chunk[..n]);
+          connection.on_data(&mut buffer).await
+        }
+      },
+      Some(report) = report_rx.recv() => connection.send(report).await,
+      _ = tick.tick() => {
+        connection.gateway.purge_closed();
+        if connection.account.is_none() && connected.elapsed() >= LOGON_TIMEOUT {
+          Err("no logon received".to_string())
+        } else {
+          Ok(())
+        }
+      }
+    };
+    if flow.is_err() {
+      break;
+    }
+  }
+  
+  // Nobody could report on or cancel the connection's open orders any more, so they are
+  // cancelled as one batch
+  let account = match connection.account {
+    Some(account) => account,
+    None => return,
+  };
+  let cancels: Vec<OrderMessage> = {
+    let mut table = connection.gateway.orders.lock().unwrap();
+    table.connections.remove(&connection.id);
+    let orders: Vec<BinaryOrder> = table.orders.values()
+      .filter(|order| order.connection == connection.id)
+      .cloned()
+      .collect();
+    for order in &orders {
+      table.forget(&order.order_id.to_string());
+    }
+    orders.into_iter()
+      .filter(|order| order.leaves_qty() > Qty::ZERO)
+      .map(|order| OrderMessage::Order(cancel_order(&order, &account)))
+      .collect()
+  };
+  if !cancels.is_empty() && connection.gateway.order_tx.send(OrderMessage::Batch(cancels)).await.is_err() {
+    eprintln!("Binary connection {} of {} closed and its open orders could not be cancelled: engine unavailable", connection.id, account);
+  }
+}
+
+impl Connection {
+  async fn on_data(&mut self, buffer: &mut Vec<u8>) -> Flow {
+    loop {
+      match BinaryMessage::decode(buffer) {
+        Ok(Some(message)) => self.on_message(message).await?,
+        Ok(None) => return Ok(()),
+        Err(e) => {
+          eprintln!("Invalid binary order entry message: {}", e);
+          self.reject("", RejectReason::InvalidMessage).await?;
+        }
+      }
+    }
+  }
+  
+  async fn on_message(&mut self, message: BinaryMessage) -> Flow {
+    let account = match self.account.clone() {
+      Some(account) => account,
+      None => return match message {
+        BinaryMessage::Logon { api_key } => self.logon(&api_key).await,
+        _ => self.reject("", RejectReason::NotLoggedOn).await,
+      },
+    };
+    let client_order_id = match &message {
+      BinaryMessage::NewOrder { client_order_id, .. }
+      | BinaryMessage::Cancel { client_order_id }
+      | BinaryMessage::Amend { client_order_id, .. } => client_order_id.clone(),
+      _ => String::new(),
+    };
+    if let Err(e) = self.gateway.throttle.admit(Some(&self.api_key), Some(self.ip), &self.gateway.order_tx) {
+      return self.reject(&client_order_id, throttled(&e)).await;
+    }
+    match message {
+      BinaryMessage::NewOrder { client_order_id, symbol, side, order_type, price, quantity } => {
+        let price = if order_type == OrderType::Market { Price::ZERO } else { price };
+        let order = Order {
+          order_id: Uuid::new_v4().to_string(),
+          symbol,
+          account,
+          price,
+          quantity,
+          side,
+          order_type,
+          status: OrderStatus::New,
+          filled_quantity: Qty::ZERO,
+          remain_quantity: quantity,
+          entry_time: Utc::now()
+        };
+        self.new_order(client_order_id, order).await
+      }
+      BinaryMessage::Cancel { client_order_id } => self.cancel(account, client_order_id).await,
+      BinaryMessage::Amend { client_order_id, price, quantity } => self.amend(account, client_order_id, price, quantity).await,
+      _ => self.reject("", RejectReason::InvalidMessage).await,
+    }
+  }
+  
+  async fn logon(&mut self, api_key: &str) -> Flow {
+    match self.gateway.accounts.authenticate(api_key) {
+      Some(account) => {
+        self.gateway.orders.lock().unwrap().connections.insert(self.id, self.reports.clone());
+        self.account = Some(account.clone());
+        self.api_key = api_key.to_string();
+        self.send(BinaryMessage::LogonAccepted { account }).await
+      }
+      None => {
+        self.reject("", RejectReason::AuthenticationFailed).await?;
+        Err("authentication failed".to_string())
+      }
+    }
+  }
+  
+  async fn new_order(&mut self, client_order_id: String, order: Order) -> Flow {
+    let key = (self.id, client_order_id.clone());
+    if self.gateway.orders.lock().unwrap().client_order_ids.contains_key(&key) {
+      return self.reject(&client_order_id, RejectReason::DuplicateClientOrderId).await;
+    }
+    let validated = self.gateway.instruments.get(&order.symbol)
+      .ok_or_else(|| OrderValidationError::UnknownSymbol(order.symbol.clone()))
+      .and_then(|instrument| instrument.validate_order(&order).map(|_| instrument));
+    let instrument = match validated {
+      Ok(instrument) => instrument,
+      Err(e) => return self.reject(&client_order_id, reject_reason(&e)).await,
+    };
+    if self.gateway.risk.admit(&order, &instrument).is_err() {
+      return self.reject(&client_order_id, RejectReason::RiskLimit).await;
+    }
+    
+    let binary_order = BinaryOrder {
+      order_id: Uuid::parse_str(&order.order_id).unwrap(),
+      connection: self.id,
+      client_order_id: client_order_id.clone(),
+      symbol: order.symbol.clone(),
+      side: order.side.clone(),
+      order_type: order.order_type.clone(),
+      price: order.price,
+      quantity: order.quantity,
+      cum_qty: Qty::ZERO,
+      closed_at: None,
+    };
+    // Registered before sequencing so that no fill can miss the order
+    {
+      let mut table = self.gateway.orders.lock().unwrap();
+      table.client_order_ids.insert(key, order.order_id.clone());
+      table.orders.insert(order.order_id.clone(), binary_order.clone());
+    }
+    let order_id = order.order_id.clone();
+    if let Err(e) = self.gateway.throttle.try_send(&self.gateway.order_tx, OrderMessage::Order(order)) {
+      self.gateway.orders.lock().unwrap().forget(&order_id);
+      self.gateway.risk.release(&order_id);
+      return self.reject(&client_order_id, throttled(&e)).await;
+    }
+    self.send(BinaryMessage::ExecutionReport(binary_order.report(ExecType::New))).await
+  }
+  
+  async fn cancel(&mut self, account: String, client_order_id: String) -> Flow {
+    let order = match self.open_order(&client_order_id) {
+      Ok(order) => order,
+      Err(reason) => return self.reject(&client_order_id, reason).await,
+    };
+    
+    let cancel = cancel_order(&order, &account);
+    // Answered with the engine's outcome: Cancelled, or a reject if the order is gone
+    if let Err(e) = self.gateway.throttle.try_send(&self.gateway.order_tx, OrderMessage::Order(cancel)) {
+      return self.reject(&client_order_id, throttled(&e)).await;
+    }
+    Ok(())
+  }
+  
+  async fn amend(&mut self, account: String, client_order_id: String, price: Price, quantity: Qty) -> Flow {
+    let order = match self.open_order(&client_order_id) {
+      Ok(order) => order,
+      Err(reason) => return self.reject(&client_order_id, reason).await,
+    };
+    if order.order_type != OrderType::Limit || quantity <= order.cum_qty {
+      return self.reject(&client_order_id, RejectReason::NotAmendable).await;
+    }
+    
+    let probe = Order {
+      order_id: order.order_id.to_string(),
+      symbol: order.symbol.clone(),
+      account,
+      price,
+      quantity,
+      side: order.side.clone(),
+      order_type: OrderType::Limit,
+      status: OrderStatus::New,
+      filled_quantity: Qty::ZERO,
+      remain_quantity: quantity,
+      entry_time: Utc::now()
+    };
+    let validated = self.gateway.instruments.get(&order.symbol)
+      .ok_or_else(|| OrderValidationError::UnknownSymbol(order.symbol.clone()))
+      .and_then(|instrument| instrument.validate_order(&probe).map(|_| instrument));
+    let instrument = match validated {
+      Ok(instrument) => instrument,
+      Err(e) => return self.reject(&client_order_id, reject_reason(&e)).await,
+    };
+    if self.gateway.risk.admit_amend(&probe, &instrument).is_err() {
+      return self.reject(&client_order_id, RejectReason::RiskLimit).await;
+    }
+    
+    // Answered with the engine's outcome: Amended, or a reject
+    let amend = Amend { order_id: order.order_id.to_string(), account: probe.account.clone(), price, quantity };
+    if let Err(e) = self.gateway.throttle.try_send(&self.gateway.order_tx, OrderMessage::Amend(amend)) {
+      self.gateway.risk.release_amend(&probe.order_id);
+      return self.reject(&client_order_id, throttled(&e)).await;
+    }
+    Ok(())
+  }
+  
+  // The open order of this connection with the client order id
+  fn open_order(&self, client_order_id: &str) -> Result<BinaryOrder, RejectReason> {
+    let table = self.gateway.orders.lock().unwrap();
+    let order = table.client_order_ids.get(&(self.id, client_order_id.to_string()))
+      .and_then(|order_id| table.orders.get(order_id));
+    match order {
+      Some(order) if order.closed_at.is_none() && order.cum_qty < order.quantity => Ok(order.clone()),
+      _ => Err(RejectReason::UnknownOrder),
+    }
+  }
+  
+  async fn reject(&mut self, client_order_id: &str, reason: RejectReason) -> Flow {
+    self.send(BinaryMessage::Reject { client_order_id: client_order_id.to_string(), reason }).await
+  }
+  
+  async fn send(&mut self, message: BinaryMessage) -> Flow {
+    let frame = message.encode().map_err(|e| format!("failed to encode a message: {}", e))?;
+    self.writer.write_all(&frame).await.map_err(|e| format!("write failed: {}", e))
+  }
+}
+
+// A cancel of the order, sequenced like a new order with a Cancelled status
+fn cancel_order(order: &BinaryOrder, account: &str) -> Order {
+  Order {
+    order_id: order.order_id.to_string(),
+    symbol: order.symbol.clone(),
+    account: account.to_string(),
+    price: Price::ZERO,
+    quantity: Qty::ZERO,
+    side: order.side.clone(),
+    order_type: OrderType::Limit,
+    status: OrderStatus::Cancelled,
+    filled_quantity: Qty::ZERO,
+    remain_quantity: Qty::ZERO,
+    entry_time: Utc::now()
+  }
+}
+
+// Reject reason of a request refused by the throttle or not taken by the sequencer
+fn throttled(e: &ApiError) -> RejectReason {
+  match e {
+    ApiError::RateLimited { .. } => RejectReason::RateLimited,
+    ApiError::Overloaded { .. } => RejectReason::Overloaded,
+    _ => RejectReason::EngineUnavailable,
+  }
+}
+
+fn reject_reason(e: &OrderValidationError) -> RejectReason {
+  match e {
+    OrderValidationError::UnknownSymbol(_) => RejectReason::UnknownSymbol,
+    OrderValidationError::NotTrading { .. } => RejectReason::NotTrading,
+    _ => RejectReason::InvalidOrder,
+  }
+}
\ No newline at end of file
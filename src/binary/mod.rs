@@ -0,0 +1,9 @@
+/*
+* filename : mod
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 바이너리 주문 입력 게이트웨이 및 클라이언트 (메시지 형식은 util::binary_codec)
+**/
+
+pub mod gateway;
+pub mod client;
\ No newline at end of file
@@ -0,0 +1,235 @@
+/*
+* filename : circuit_breaker
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 동적 가격 제한폭 및 변동성 서킷 브레이커
+**/
+
+use std::collections::VecDeque;
+use chrono::{DateTime, Duration, Utc};
+use crate::models::{Order, OrderType, Side, Price, Qty};
+use crate::instrument::{BandAction, BandReference, CircuitBreaker, Instrument, OrderValidationError};
+
+// now + secs, or None if out of range. Definitions are limited to MAX_TIMER_SECS, so this only
+// fails for instruments put into the registry without validation.
+pub fn after(now: DateTime<Utc>, secs: u64) -> Option<DateTime<Utc>> {
+  now.checked_add_signed(Duration::try_seconds(i64::try_from(secs).ok()?)?)
+}
+
+// now - secs, or None if out of range
+pub fn before(now: DateTime<Utc>, secs: u64) -> Option<DateTime<Utc>> {
+  now.checked_sub_signed(Duration::try_seconds(i64::try_from(secs).ok()?)?)
+}
+
+// Recent trades of a symbol, oldest first
+#[derive(Debug, Default)]
+pub struct TradeWindow {
+  trades: VecDeque<(DateTime<Utc>, Price, Qty)>,
+}
+
+impl TradeWindow {
+  pub fn new() -> Self {
+    TradeWindow { trades: VecDeque::new() }
+  }
+  
+  pub fn record(&mut self, time: DateTime<Utc>, price: Price, quantity: Qty) {
+    self.trades.push_back((time, price, quantity));
+  }
+  
+  // Drop trades older than the given time
+  pub fn prune(&mut self, since: DateTime<Utc>) {
+    while self.trades.front().is_some_and(|(time, _, _)| *time < since) {
+      self.trades.pop_front();
+    }
+  }
+  
+  pub fn clear(&mut self) {
+    self.trades.clear();
+  }
+  
+  fn since(&self, since: DateTime<Utc>) -> impl Iterator<Item = &(DateTime<Utc>, Price, Qty)> {
+    self.trades.iter().filter(move |(time, _, _)| *time >= since)
+  }
+  
+  // Volume weighted average price of the trades since the given time, truncated
+  pub fn vwap(&self, since: DateTime<Utc>) -> Option<Price> {
+    let (notional, volume) = self.since(since).fold((0u128, 0u128), |(n, v), (_, price, quantity)| {
+      (n + price.0 as u128 * quantity.0 as u128, v + quantity.0 as u128)
+    });
+    if volume == 0 {
+      return None;
+    }
+    u64::try_from(notional / volume).ok().map(Price)
+  }
+  
+  // Lowest and highest trade price since the given time
+  pub fn range(&self, since: DateTime<Utc>) -> Option<(Price, Price)> {
+    self.since(since).fold(None, |range, &(_, price, _)| match range {
+      None => Some((price, price)),
+      Some((low, high)) => Some((low.min(price), high.max(price))),
+    })
+  }
+}
+
+// Inclusive price range orders may trade in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Band {
+  pub low: Price,
+  pub high: Price,
+}
+
+impl Band {
+  // band_bps around the reference, narrowed to the tick grid so that both edges are valid prices
+  pub fn around(reference: Price, band_bps: u64, tick_size: Price) -> Band {
+    let offset = (reference.0 as u128 * band_bps as u128 / 10_000) as u64;
+    let tick = tick_size.0.max(1);
+    
+    let low = reference.0.saturating_sub(offset);
+    let high = reference.0.saturating_add(offset);
+    Band {
+      low: Price(low.div_ceil(tick).saturating_mul(tick)),
+      high: Price(high / tick * tick),
+    }
+  }
+  
+  // Worst price the aggressive side of an order may trade at
+  pub fn limit_for(&self, side: &Side) -> Price {
+    match side {
+      Side::Buy => self.high,
+      Side::Sell => self.low,
+    }
+  }
+}
+
+// The instrument's price band around the current reference price, if one applies
+pub fn price_band(instrument: &Instrument, window: Option<&TradeWindow>, last_price: Option<Price>, now: DateTime<Utc>) -> Option<Band> {
+  let config = &instrument.price_band;
+  if config.band_bps == 0 {
+    return None;
+  }
+  
+  // The VWAP falls back to the last trade when the window holds no trades
+  let reference = match config.reference {
+    BandReference::LastTrade => last_price,
+    BandReference::Vwap => window.zip(before(now, config.vwap_window_secs))
+      .and_then(|(w, since)| w.vwap(since))
+      .or(last_price),
+  }?;
+  Some(Band::around(reference, config.band_bps, instrument.tick_size))
+}
+
+// Check an incoming order against the band. Limit orders priced through the far edge are
+// rejected, or clipped to the edge with BandAction::Clip. Market orders are always accepted
+// and bounded by the edge during matching.
+pub fn apply_band(order: &mut Order, band: &Band, action: &BandAction) -> Result<(), OrderValidationError> {
+  if order.order_type == OrderType::Market {
+    return Ok(());
+  }
+  
+  let edge = band.limit_for(&order.side);
+  let outside = match order.side {
+    Side::Buy => order.price > edge,
+    Side::Sell => order.price < edge,
+  };
+  if !outside {
+    return Ok(());
+  }
+  
+  match action {
+    BandAction::Reject => Err(OrderValidationError::PriceOutsideBand { price: order.price, low: band.low, high: band.high }),
+    BandAction::Clip => {
+      order.price = edge;
+      Ok(())
+    }
+  }
+}
+
+// Whether the trades in the breaker's window moved by threshold_bps or more
+pub fn breaker_tripped(config: &CircuitBreaker, window: &TradeWindow, now: DateTime<Utc>) -> bool {
+  if config.threshold_bps == 0 {
+    return false;
+  }
+  
+  match before(now, config.window_secs).and_then(|since| window.range(since)) {
+    Some((low, high)) => (high.0 - low.0) as u128 * 10_000 >= low.0 as u128 * config.threshold_bps as u128,
+    None => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::models::OrderStatus;
+  
+  fn order(side: Side, order_type: OrderType, price: u64) -> Order {
+    Order {
+      order_id: "o1".into(),
+      symbol: "SYM".into(),
+      account: String::new(),
+      price: Price(price),
+      quantity: Qty(1),
+      side,
+      order_type,
+      status: OrderStatus::New,
+      filled_quantity: Qty(0),
+      remain_quantity: Qty(1),
+      entry_time: Utc::now()
+    }
+  }
+  
+  #[test]
+  fn test_band_is_aligned_to_ticks() {
+    // 5% of 10,050 is 502.5, so the raw band is 9548..=10552
+    let band = Band::around(Price(10050), 500, Price(10));
+    assert_eq!(band, Band { low: Price(9550), high: Price(10550) });
+    assert_eq!(Band::around(Price(100), 20000, Price(1)), Band { low: Price(0), high: Price(300) });
+  }
+  
+  #[test]
+  fn test_apply_band() {
+    let band = Band { low: Price(90), high: Price(110) };
+    
+    let mut buy = order(Side::Buy, OrderType::Limit, 120);
+    assert_eq!(apply_band(&mut buy, &band, &BandAction::Reject).unwrap_err().code(), "PRICE_OUTSIDE_BAND");
+    assert_eq!(apply_band(&mut buy, &band, &BandAction::Clip), Ok(()));
+    assert_eq!(buy.price, Price(110));
+    
+    // Passive orders away from the reference are not affected
+    let mut sell = order(Side::Sell, OrderType::Limit, 200);
+    assert_eq!(apply_band(&mut sell, &band, &BandAction::Reject), Ok(()));
+    let mut sell = order(Side::Sell, OrderType::Limit, 80);
+    assert_eq!(apply_band(&mut sell, &band, &BandAction::Clip), Ok(()));
+    assert_eq!(sell.price, Price(90));
+    
+    let mut market = order(Side::Buy, OrderType::Market, 0);
+    assert_eq!(apply_band(&mut market, &band, &BandAction::Reject), Ok(()));
+    assert_eq!(band.limit_for(&market.side), Price(110));
+  }
+  
+  #[test]
+  fn test_trade_window() {
+    let now = Utc::now();
+    let mut window = TradeWindow::new();
+    window.record(now - Duration::seconds(120), Price(50), Qty(10));
+    window.record(now - Duration::seconds(30), Price(100), Qty(1));
+    window.record(now, Price(110), Qty(3));
+    
+    let minute = now - Duration::seconds(60);
+    assert_eq!(window.vwap(minute), Some(Price(107)));
+    assert_eq!(window.range(minute), Some((Price(100), Price(110))));
+    assert_eq!(window.range(now - Duration::seconds(300)), Some((Price(50), Price(110))));
+    
+    window.prune(minute);
+    assert_eq!(window.range(now - Duration::seconds(300)), Some((Price(100), Price(110))));
+    
+    let breaker = CircuitBreaker { threshold_bps: 1000, window_secs: 60, ..CircuitBreaker::default() };
+    assert!(breaker_tripped(&breaker, &window, now));
+    assert!(!breaker_tripped(&CircuitBreaker { threshold_bps: 1001, ..breaker.clone() }, &window, now));
+    assert!(!breaker_tripped(&CircuitBreaker::default(), &window, now));
+    
+    // Windows out of range do not panic
+    assert_eq!(before(now, u64::MAX), None);
+    assert_eq!(after(now, i64::MAX as u64), None);
+    assert!(!breaker_tripped(&CircuitBreaker { window_secs: u64::MAX, ..breaker }, &window, now));
+  }
+}
\ No newline at end of file
@@ -0,0 +1,163 @@
+/*
+* filename : error
+* author : HAMA
+* date: 2026. 10. 18.
+* description: REST API 공통 오류 타입 및 warp 거부(rejection) 처리기
+**/
+
+use std::convert::Infallible;
+use std::time::Duration;
+use thiserror::Error;
+use warp::{Rejection, Reply, http::StatusCode};
+use warp::reply::Response;
+use crate::instrument::OrderValidationError;
+use crate::risk::RiskRejection;
+
+// Every REST error is returned as {"error": code, "message": text} with the matching status
+#[derive(Debug, Error)]
+pub enum ApiError {
+  #[error("{0}")]
+  InvalidRequest(String),
+  #[error(transparent)]
+  Validation(#[from] OrderValidationError),
+  #[error("client_order_id must be 1 to {max_len} characters")]
+  InvalidClientOrderId { max_len: usize },
+  #[error("a batch holds 1 to {max} items")]
+  BatchTooLarge { max: usize },
+  #[error("another item in the batch was rejected")]
+  BatchRejected,
+  #[error(transparent)]
+  Risk(#[from] RiskRejection),
+  #[error("insufficient balance in account {account}")]
+  InsufficientBalance { account: String },
+  #[error("unknown symbol {0}")]
+  UnknownSymbol(String),
+  #[error("valid credentials are required")]
+  Unauthorized,
+  #[error("no such order")]
+  UnknownOrder,
+  #[error("{0}")]
+  NotFound(String),
+  #[error("method not allowed")]
+  MethodNotAllowed,
+  #[error("too many requests")]
+  RateLimited { retry_after: Duration },
+  #[error("the matching engine is overloaded")]
+  Overloaded { retry_after: Duration },
+  #[error("the matching engine is not running")]
+  EngineUnavailable,
+  #[error("internal error")]
+  Internal,
+}
+
+// warp converts any Reject into a Rejection, so handlers can use `?` and `.into()`
+impl warp::reject::Reject for ApiError {}
+
+impl ApiError {
+  // Stable machine readable code
+  pub fn code(&self) -> &'static str {
+    match self {
+      ApiError::InvalidRequest(_) => "INVALID_REQUEST",
+      ApiError::Validation(e) => e.code(),
+      ApiError::InvalidClientOrderId { .. } => "INVALID_CLIENT_ORDER_ID",
+      ApiError::BatchTooLarge { .. } => "BATCH_TOO_LARGE",
+      ApiError::BatchRejected => "BATCH_REJECTED",
+      ApiError::Risk(e) => e.code(),
+      ApiError::InsufficientBalance { .. } => "INSUFFICIENT_BALANCE",
+      ApiError::UnknownSymbol(_) => "UNKNOWN_SYMBOL",
+      ApiError::Unauthorized => "UNAUTHORIZED",
+      ApiError::UnknownOrder => "UNKNOWN_ORDER",
+      ApiError::NotFound(_) => "NOT_FOUND",
+      ApiError::MethodNotAllowed => "METHOD_NOT_ALLOWED",
+      ApiError::RateLimited { .. } => "RATE_LIMITED",
+      ApiError::Overloaded { .. } => "OVERLOADED",
+      ApiError::EngineUnavailable => "ENGINE_UNAVAILABLE",
+      ApiError::Internal => "INTERNAL_ERROR",
+    }
+  }
+  
+  pub fn status(&self) -> StatusCode {
+    match self {
+      ApiError::InvalidRequest(_)
+      | ApiError::Validation(_)
+      | ApiError::InvalidClientOrderId { .. }
+      | ApiError::BatchTooLarge { .. }
+      | ApiError::BatchRejected
+      | ApiError::Risk(_)
+      | ApiError::InsufficientBalance { .. } => StatusCode::BAD_REQUEST,
+      ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+      ApiError::UnknownSymbol(_) | ApiError::UnknownOrder | ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+      ApiError::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+      ApiError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+      ApiError::Overloaded { .. } | ApiError::EngineUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+      ApiError::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+  }
+  
+  // Whole seconds, rounded up
+  pub fn retry_after(&self) -> Option<u64> {
+    match self {
+      ApiError::RateLimited { retry_after } | ApiError::Overloaded { retry_after } => {
+        Some(retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0))
+      }
+      _ => None,
+    }
+  }
+  
+  pub fn body(&self) -> serde_json::Value {
+    serde_json::json!({
+      "error": self.code(),
+      "message": self.to_string()
+    })
+  }
+  
+  // For handlers that reply with errors directly instead of rejecting
+  pub fn reply(&self) -> warp::reply::WithStatus<warp::reply::Json> {
+    warp::reply::with_status(warp::reply::json(&self.body()), self.status())
+  }
+  
+  pub fn response(&self) -> Response {
+    let mut response = self.reply().into_response();
+    if let Some(secs) = self.retry_after() {
+      response.headers_mut().insert("retry-after", secs.into());
+    }
+    response
+  }
+}
+
+// Turns ApiError and warp's own rejections for a matched route into JSON errors.
+// Unmatched paths pass through so that the remaining routes are tried.
+pub async fn handle_rejection(rejection: Rejection) -> Result<Response, Rejection> {
+  if rejection.is_not_found() {
+    return Err(rejection);
+  }
+  if let Some(error) = rejection.find::<ApiError>() {
+    return Ok(error.response());
+  }
+  let error = if let Some(e) = rejection.find::<warp::filters::body::BodyDeserializeError>() {
+    ApiError::InvalidRequest(e.to_string())
+  } else if let Some(e) = rejection.find::<warp::reject::InvalidQuery>() {
+    ApiError::InvalidRequest(e.to_string())
+  } else if let Some(e) = rejection.find::<warp::reject::UnsupportedMediaType>() {
+    ApiError::InvalidRequest(e.to_string())
+  } else if let Some(e) = rejection.find::<warp::reject::PayloadTooLarge>() {
+    ApiError::InvalidRequest(e.to_string())
+  } else if rejection.find::<warp::reject::MethodNotAllowed>().is_some() {
+    ApiError::MethodNotAllowed
+  } else {
+    return Err(rejection);
+  };
+  Ok(error.response())
+}
+
+// Last handler of the server: everything becomes a JSON error, including unknown paths
+pub async fn handle_unmatched(rejection: Rejection) -> Result<Response, Infallible> {
+  Ok(match handle_rejection(rejection).await {
+    Ok(response) => response,
+    Err(rejection) if rejection.is_not_found() => ApiError::NotFound("no such endpoint".to_string()).response(),
+    Err(rejection) => {
+      eprintln!("Unhandled rejection: {:?}", rejection);
+      ApiError::Internal.response()
+    }
+  })
+}
\ No newline at end of file
@@ -0,0 +1,1146 @@
+/*
+* filename : gateway
+* author : HAMA
+* date: 2026. 10. 18.
+* description: FIX 4.4 주문 입력 게이트웨이 (acceptor) - 세션 관리 및 주문/체결 메시지 변환
+**/
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::{mpsc as std_mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+use chrono::Utc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::sync::mpsc::{self, Sender};
+use tokio::sync::oneshot;
+
+use crate::auth::AccountRegistry;
+use crate::error::ApiError;
+use crate::instrument::{Instrument, InstrumentRegistry, OrderValidationError};
+use crate::matching_engine::{CancelReason, CancelRejectReason, OrderOutcome};
+use crate::models::{Amend, Execution, Order, OrderMessage, OrderStatus, OrderType, Price, Qty, Side};
+use crate::order_manager::{prepare_order, NewOrder};
+use crate::risk::RiskManager;
+use crate::throttle::Throttle;
+use crate::util::decimal::DecimalInput;
+use super::message::{self, msg_type, tags, FixMessage};
+use super::store::{is_valid_comp_id, SessionStore, SessionStores};
+
+pub const MAX_HEARTBEAT_SECS: u64 = 300;
+const LOGON_TIMEOUT: Duration = Duration::from_secs(10);
+// Cancelled orders are followed a little longer for fills sequenced before the cancel
+const CLOSED_ORDER_RETENTION: Duration = Duration::from_secs(60);
+
+// Disconnect with the reason, or carry on
+type Flow = Result<(), String>;
+
+// An order entered through a FIX session, as far as the gateway knows it
+#[derive(Clone, Debug)]
+struct FixOrder {
+  order_id: String,
+  session: String, // Client's SenderCompID
+  cl_ord_id: String, // Latest ClOrdID of the order
+  cl_ord_ids: Vec<String>, // Every ClOrdID used for the order
+  symbol: String,
+  side: Side,
+  order_type: OrderType,
+  price: Price,
+  quantity: Qty,
+  cum_qty: Qty,
+  notional: u128, // Sum of price * quantity of the fills in raw units, for AvgPx
+  closed_at: Option<Instant>,
+  // Cancel or cancel/replace sent to the engine and not answered yet
+  pending: Option<PendingChange>,
+}
+
+#[derive(Clone, Debug)]
+struct PendingChange {
+  cl_ord_id: String,
+  orig_cl_ord_id: String,
+  replace: bool,
+}
+
+impl PendingChange {
+  // CxlRejResponseTo (434) 1: Order cancel request, 2: Order cancel/replace request
+  fn response_to(&self) -> &'static str {
+    if self.replace { "2" } else { "1" }
+  }
+}
+
+impl FixOrder {
+  fn leaves_qty(&self) -> Qty {
+    if self.closed_at.is_some() || self.cum_qty >= self.quantity {
+      Qty::ZERO
+    } else {
+      self.quantity - self.cum_qty
+    }
+  }
+  
+  fn avg_px(&self) -> Price {
+    match self.cum_qty.0 {
+      0 => Price::ZERO,
+      cum_qty => Price((self.notional / cum_qty as u128) as u64),
+    }
+  }
+  
+  // OrdStatus (39)
+  fn ord_status(&self) -> &'static str {
+    if self.cum_qty >= self.quantity {
+      "2"
+    } else if self.closed_at.is_some() {
+      "4"
+    } else if !self.cum_qty.is_zero() {
+      "1"
+    } else {
+      "0"
+    }
+  }
+}
+
+#[derive(Default)]
+struct OrderTable {
+  orders: HashMap<String, FixOrder>,                // order id -> order
+  cl_ord_ids: HashMap<(String, String), String>,    // (session, ClOrdID) -> order id
+  connections: HashMap<String, Sender<Report>>,     // Logged on session -> its connection
+}
+
+// What happened to a gateway order, with the order as it is afterwards and the cancel or
+// cancel/replace request it answers
+enum Report {
+  Fill { order: FixOrder, execution: Execution },
+  // The engine rejected the order, or the replacement of a cancel/replace
+  Rejected { order: FixOrder, change: Option<PendingChange>, reason: OrderValidationError },
+  Replaced { order: FixOrder, change: Option<PendingChange> },
+  Cancelled { order: FixOrder, change: Option<PendingChange>, reason: CancelReason },
+  CancelRejected { order: FixOrder, change: PendingChange, reason: CancelRejectReason },
+}
+
+impl Report {
+  fn order(&self) -> &FixOrder {
+    match self {
+      Report::Fill { order, .. }
+      | Report::Rejected { order, .. }
+      | Report::Replaced { order, .. }
+      | Report::Cancelled { order, .. }
+      | Report::CancelRejected { order, .. } => order,
+    }
+  }
+}
+
+// Work for the thread that stores the reports of logged out sessions, in the order the
+// reports were made
+enum Offline {
+  Report { session: String, order_id: String, message: FixMessage },
+  // Answered once everything queued before it is stored
+  Flush(oneshot::Sender<()>),
+}
+
+// FIX 4.4 acceptor. Clients log on with their SenderCompID and an account API key in
+// Password (554); orders are validated like REST orders and sequenced as OrderMessages,
+// and the engine's executions are reported back as ExecutionReports.
+pub struct FixGateway {
+  comp_id: String,
+  stores: Arc<SessionStores>,
+  offline: std_mpsc::Sender<Offline>,
+  accounts: Arc<AccountRegistry>,
+  instruments: Arc<InstrumentRegistry>,
+  order_tx: Sender<OrderMessage>,
+  risk: Arc<RiskManager>,
+  throttle: Arc<Throttle>,
+  orders: Mutex<OrderTable>,
+}
+
+impl FixGateway {
+  // Orders, cancels and replaces are rate limited like REST requests, and new orders and
+  // replaces pass the risk stage shared with the other order entry paths
+  pub fn new(
+    comp_id: &str,
+    store_dir: impl Into<PathBuf>,
+    accounts: Arc<AccountRegistry>,
+    instruments: Arc<InstrumentRegistry>,
+    order_tx: Sender<OrderMessage>,
+    risk: Arc<RiskManager>,
+    throttle: Arc<Throttle>
+  ) -> Self {
+    let stores = Arc::new(SessionStores::new(store_dir));
+    let (offline_tx, offline_rx) = std_mpsc::channel();
+    let (writer_stores, writer_comp_id) = (stores.clone(), comp_id.to_string());
+    std::thread::spawn(move || store_offline(offline_rx, &writer_stores, &writer_comp_id));
+    FixGateway {
+      comp_id: comp_id.to_string(),
+      stores,
+      offline: offline_tx,
+      accounts,
+      instruments,
+      order_tx,
+      risk,
+      throttle,
+      orders: Mutex::new(OrderTable::default()),
+    }
+  }
+  
+  // Accept FIX connections until the listener fails
+  pub async fn serve(self: Arc<Self>, listener: TcpListener) {
+    loop {
+      match listener.accept().await {
+        Ok((stream, peer)) => {
+          println!("FIX connection from {}", peer);
+          tokio::spawn(handle_connection(self.clone(), stream, peer.ip()));
+        }
+        Err(e) => {
+          eprintln!("FIX listener failed: {}", e);
+          break;
+        }
+      }
+    }
+  }
+  
+  // Report an execution to the session that entered the order, if it is logged on.
+  // Executions of other orders are ignored.
+  pub async fn on_execution(&self, execution: &Execution) {
+    let report = {
+      let mut table = self.orders.lock().unwrap();
+      let order = match table.orders.get_mut(&execution.order_id) {
+        Some(order) => order,
+        None => return,
+      };
+      order.cum_qty += execution.quantity;
+      order.notional += execution.price.0 as u128 * execution.quantity.0 as u128;
+      let report = Report::Fill { order: order.clone(), execution: execution.clone() };
+      
+      // A filled order is kept a little longer when a cancel or replace still awaits its answer
+      if order.cum_qty >= order.quantity {
+        if order.pending.is_some() {
+          order.closed_at.get_or_insert_with(Instant::now);
+        } else {
+          forget_order(&mut table, &execution.order_id);
+        }
+      }
+      report
+    };
+    self.report(report).await;
+  }
+  
+  // Report what the engine did with a gateway order: rejects, cancels and the answers to
+  // cancel and cancel/replace requests. A rejected replacement ends the order, since the
+  // engine already removed the original.
+  pub async fn on_outcome(&self, outcome: &OrderOutcome) {
+    let report = {
+      let mut table = self.orders.lock().unwrap();
+      let order = match table.orders.get_mut(outcome.order_id()) {
+        Some(order) => order,
+        None => return,
+      };
+      match outcome {
+        OrderOutcome::Rejected { reason, replacement, .. } => {
+          let change = if *replacement { order.pending.take() } else { None };
+          if let Some(change) = &change {
+            order.cl_ord_id = change.cl_ord_id.clone();
+          }
+          order.closed_at = Some(Instant::now());
+          Report::Rejected { order: order.clone(), change, reason: reason.clone() }
+        }
+        OrderOutcome::Amended { price, quantity, .. } => {
+          let change = order.pending.take();
+          if let Some(change) = &change {
+            order.cl_ord_id = change.cl_ord_id.clone();
+          }
+          order.price = *price;
+          order.quantity = *quantity;
+          Report::Replaced { order: order.clone(), change }
+        }
+        OrderOutcome::Cancelled { reason, .. } => {
+          let change = if *reason == CancelReason::Requested { order.pending.take() } else { None };
+          if let Some(change) = &change {
+            order.cl_ord_id = change.cl_ord_id.clone();
+          }
+          order.closed_at = Some(Instant::now());
+          Report::Cancelled { order: order.clone(), change, reason: *reason }
+        }
+        OrderOutcome::CancelRejected { reason, .. } => match order.pending.take() {
+          Some(change) => Report::CancelRejected { order: order.clone(), change, reason: reason.clone() },
+          None => return,
+        },
+      }
+    };
+    self.report(report).await;
+  }
+  
+  // Send a report to the session's connection. Reports for a session that is not logged on
+  // are stored under its next sequence number, to be resent after it logs on again.
+  async fn report(&self, mut report: Report) {
+    loop {
+      let connection = {
+        let table = self.orders.lock().unwrap();
+        match table.connections.get(&report.order().session) {
+          Some(connection) => connection.clone(),
+          // Queued under the lock, so that a logon flushes the store after this report
+          None => return self.store_report(&report),
+        }
+      };
+      match connection.send(report).await {
+        Ok(()) => return,
+        // The connection closed meanwhile and stored what it had not sent
+        Err(unsent) => report = unsent.0,
+      }
+    }
+  }
+  
+  // Queue a report of a logged out session for the store writer thread
+  fn store_report(&self, report: &Report) {
+    let offline = Offline::Report {
+      session: report.order().session.clone(),
+      order_id: report.order().order_id.clone(),
+      message: self.report_message(report),
+    };
+    let _ = self.offline.send(offline);
+  }
+  
+  // The ExecutionReport or OrderCancelReject for a report
+  fn report_message(&self, report: &Report) -> FixMessage {
+    match report {
+      Report::Fill { order, execution } => {
+        let instrument = self.instrument(&order.symbol);
+        // ExecType F: Trade
+        let mut message = self.execution_report(order, "F", order.ord_status());
+        message.set(tags::EXEC_ID, &execution.exec_id);
+        message.set(tags::LAST_QTY, instrument.format_quantity(execution.quantity));
+        message.set(tags::LAST_PX, instrument.format_price(execution.price));
+        message.set(tags::TRANSACT_TIME, message::format_timestamp(execution.transaction_time));
+        message
+      }
+      // ExecType 8: Rejected
+      Report::Rejected { order, change, reason } => {
+        let message = self.execution_report(order, "8", "8")
+          .with(tags::ORD_REJ_REASON, ord_rej_reason(reason))
+          .with(tags::TEXT, format!("{}: {}", reason.code(), reason));
+        answering(message, change)
+      }
+      // ExecType 5: Replaced
+      Report::Replaced { order, change } => answering(self.execution_report(order, "5", order.ord_status()), change),
+      // ExecType 4: Canceled
+      Report::Cancelled { order, change, reason } => {
+        let mut message = self.execution_report(order, "4", "4");
+        match reason {
+          CancelReason::Requested => {}
+          CancelReason::MassCancel => message.set(tags::TEXT, "cancelled by a mass cancel"),
+          CancelReason::Unfilled => message.set(tags::TEXT, "unfilled quantity of a market order"),
+        }
+        answering(message, change)
+      }
+      Report::CancelRejected { order, change, reason } => {
+        // CxlRejReason 0: Too late to cancel, 2: Exchange option
+        let code = match reason {
+          CancelRejectReason::UnknownOrder => "0",
+          CancelRejectReason::NotTrading(_) => "2",
+        };
+        cancel_rejected(&change.orig_cl_ord_id, &change.cl_ord_id, change.response_to(), code, &reason.to_string())
+          .with(tags::ORDER_ID, &order.order_id)
+          .with(tags::ORD_STATUS, order.ord_status())
+      }
+    }
+  }
+  
+  fn execution_report(&self, order: &FixOrder, exec_type: &str, ord_status: &str) -> FixMessage {
+    let instrument = self.instrument(&order.symbol);
+    let mut report = FixMessage::new(msg_type::EXECUTION_REPORT)
+      .with(tags::ORDER_ID, &order.order_id)
+      .with(tags::CL_ORD_ID, &order.cl_ord_id)
+      .with(tags::EXEC_ID, uuid::Uuid::new_v4())
+      .with(tags::EXEC_TYPE, exec_type)
+      .with(tags::ORD_STATUS, ord_status)
+      .with(tags::SYMBOL, &order.symbol)
+      .with(tags::SIDE, format_side(&order.side))
+      .with(tags::ORD_TYPE, if order.order_type == OrderType::Market { "1" } else { "2" })
+      .with(tags::ORDER_QTY, instrument.format_quantity(order.quantity));
+    if order.order_type == OrderType::Limit {
+      report.set(tags::PRICE, instrument.format_price(order.price));
+    }
+    report
+      .with(tags::LEAVES_QTY, instrument.format_quantity(order.leaves_qty()))
+      .with(tags::CUM_QTY, instrument.format_quantity(order.cum_qty))
+      .with(tags::AVG_PX, instrument.format_price(order.avg_px()))
+      .with(tags::TRANSACT_TIME, message::format_timestamp(Utc::now()))
+  }
+  
+  fn purge_closed(&self) {
+    let mut table = self.orders.lock().unwrap();
+    let expired: Vec<String> = table.orders.values()
+      .filter(|order| order.closed_at.is_some_and(|closed| closed.elapsed() >= CLOSED_ORDER_RETENTION))
+      .map(|order| order.order_id.clone())
+      .collect();
+    for order_id in expired {
+      forget_order(&mut table, &order_id);
+    }
+  }
+  
+  fn instrument(&self, symbol: &str) -> Instrument {
+    self.instruments.get(symbol).unwrap_or_else(|| Instrument::new(symbol))
+  }
+}
+
+// Store the reports of logged out sessions, away from the engine's output path
+fn store_offline(offline_rx: std_mpsc::Receiver<Offline>, stores: &SessionStores, comp_id: &str) {
+  for offline in offline_rx {
+    match offline {
+      Offline::Report { session, order_id, message } => {
+        let stored = stores.get(&session).and_then(|store| {
+          let mut store = store.lock().unwrap();
+          let seq = store.next_out();
+          store.store_outgoing(seq, &header(&message, comp_id, &session, seq, None).encode())
+        });
+        if let Err(e) = stored {
+          eprintln!("FIX session {} is not logged on and report for order {} could not be stored: {}", session, order_id, e);
+        }
+      }
+      Offline::Flush(done) => {
+        let _ = done.send(());
+      }
+    }
+  }
+}
+
+fn forget_order(table: &mut OrderTable, order_id: &str) {
+  if let Some(order) = table.orders.remove(order_id) {
+    for cl_ord_id in order.cl_ord_ids {
+      table.cl_ord_ids.remove(&(order.session.clone(), cl_ord_id));
+    }
+  }
+}
+
+// State of a logged on session
+struct Session {
+  comp_id: String,
+  account: String,
+  api_key: String, // Rate limits are those of the API key the session logged on with
+  store: Arc<Mutex<SessionStore>>,
+  heartbeat: Duration,
+  last_sent: Instant,
+  last_received: Instant,
+  test_request: Option<Instant>,
+  // Highest sequence number seen beyond a gap we asked to be resent
+  resend_until: Option<u64>,
+}
+
+struct Connection {
+  gateway: Arc<FixGateway>,
+  writer: OwnedWriteHalf,
+  reports: Sender<Report>,
+  session: Option<Session>,
+  ip: IpAddr,
+  next_test_req_id: u64,
+}
+
+async fn handle_connection(gateway: Arc<FixGateway>, stream: TcpStream, ip: IpAddr) {
+  let (mut reader, writer) = stream.into_split();
+  let (report_tx, mut report_rx) = mpsc::channel::<Report>(1000);
+  let mut connection = Connection { gateway, writer, reports: report_tx, session: None, ip, next_test_req_id: 1 };
+  
+  let connected = Instant::now();
+  let mut buffer = Vec::new();
+  let mut chunk = [0u8; 4096];
+  let mut tick = tokio::time::interval(Duration::from_secs(1));
+  
+  let result: Flow = loop {
+    let flow = tokio::select! {
+      read = reader.read(&mut chunk) => match read {
+        Ok(0) | Err(_) => Err("connection closed".to_string()),
+        Ok(n) => {
+          buffer.extend_from_slice(&chunk[..n]);
+          match connection.on_data(&mut buffer).await {
+            // What is left is the start of a message too large to be accepted
+            Ok(()) if buffer.len() > message::MAX_MESSAGE_SIZE => Err(format!("message larger than {} bytes", message::MAX_MESSAGE_SIZE)),
+            flow => flow,
+          }
+        }
+      },
+      Some(report) = report_rx.recv() => connection.on_report(report).await,
+      _ = tick.tick() => connection.on_tick(connected).await,
+    };
+    if flow.is_err() {
+      break flow;
+    }
+  };
+  
+  let reason = result.err().unwrap_or_default();
+  let comp_id = match &connection.session {
+    Some(session) => session.comp_id.clone(),
+    None => return,
+  };
+  println!("FIX session {} disconnected: {}", comp_id, reason);
+  
+  // Reports not sent yet are queued for the store, before any report made while logged out
+  let gateway = connection.gateway.clone();
+  let mut table = gateway.orders.lock().unwrap();
+  if table.connections.get(&comp_id).is_some_and(|tx| tx.same_channel(&connection.reports)) {
+    table.connections.remove(&comp_id);
+  }
+  report_rx.close();
+  while let Ok(report) = report_rx.try_recv() {
+    gateway.store_report(&report);
+  }
+}
+
+impl Connection {
+  async fn on_data(&mut self, buffer: &mut Vec<u8>) -> Flow {
+    loop {
+      match FixMessage::decode(buffer) {
+        Ok(Some(message)) => self.on_message(message).await?,
+        Ok(None) => return Ok(()),
+        // Garbled messages are ignored and will be caught by the sequence number check
+        Err(e) => eprintln!("Ignored FIX message: {}", e),
+      }
+    }
+  }
+  
+  async fn on_message(&mut self, message: FixMessage) -> Flow {
+    if self.session.is_none() {
+      return self.logon(message).await;
+    }
+    let session = self.session.as_mut().unwrap();
+    session.last_received = Instant::now();
+    session.test_request = None;
+    
+    let seq = match message.seq_num() {
+      Some(seq) => seq,
+      None => return self.logout("MsgSeqNum missing").await,
+    };
+    let expected = session.store.lock().unwrap().next_in();
+    
+    // A sequence reset moves the expected number without the skipped messages being resent
+    if message.msg_type() == msg_type::SEQUENCE_RESET {
+      let new_seq = message.get(tags::NEW_SEQ_NO).and_then(|value| value.parse::<u64>().ok());
+      let gap_fill = message.get(tags::GAP_FILL_FLAG) == Some("Y");
+      return match new_seq {
+        Some(new_seq) if (!gap_fill || seq == expected) && new_seq > expected => self.set_next_in(new_seq),
+        Some(_) if gap_fill && seq > expected => self.request_resend(expected, seq).await,
+        _ => Ok(()),
+      };
+    }
+    
+    if seq < expected {
+      if message.is_poss_dup() {
+        return Ok(());
+      }
+      return self.logout(&format!("MsgSeqNum too low, expecting {} but received {}", expected, seq)).await;
+    }
+    if seq > expected {
+      self.request_resend(expected, seq).await?;
+      // Only resend requests and logouts are acted upon beyond a gap
+      return match message.msg_type() {
+        msg_type::RESEND_REQUEST => self.resend(&message).await,
+        msg_type::LOGOUT => self.logout_reply().await,
+        _ => Ok(()),
+      };
+    }
+    
+    self.set_next_in(seq + 1)?;
+    match message.msg_type() {
+      msg_type::HEARTBEAT | msg_type::REJECT => Ok(()),
+      msg_type::TEST_REQUEST => {
+        let mut heartbeat = FixMessage::new(msg_type::HEARTBEAT);
+        if let Some(id) = message.get(tags::TEST_REQ_ID) {
+          heartbeat.set(tags::TEST_REQ_ID, id);
+        }
+        self.send(heartbeat).await
+      }
+      msg_type::RESEND_REQUEST => self.resend(&message).await,
+      msg_type::LOGOUT => self.logout_reply().await,
+      msg_type::NEW_ORDER_SINGLE => self.new_order_single(&message).await,
+      msg_type::ORDER_CANCEL_REQUEST => self.cancel_request(&message).await,
+      msg_type::ORDER_CANCEL_REPLACE_REQUEST => self.cancel_replace_request(&message).await,
+      other => {
+        // SessionRejectReason 11: Invalid MsgType
+        let reject = session_reject(seq, other, 11, "unsupported MsgType");
+        self.send(reject).await
+      }
+    }
+  }
+  
+  async fn logon(&mut self, message: FixMessage) -> Flow {
+    if message.msg_type() != msg_type::LOGON {
+      return Err(format!("expected Logon, received MsgType {}", message.msg_type()));
+    }
+    let comp_id = message.get(tags::SENDER_COMP_ID).unwrap_or("").to_string();
+    let target = message.get(tags::TARGET_COMP_ID).unwrap_or("");
+    let heartbeat = message.get(tags::HEART_BT_INT).and_then(|value| value.parse::<u64>().ok()).unwrap_or(0);
+    let seq = message.seq_num().unwrap_or(0);
+    
+    // Each SenderCompID is bound to one account, whose API key is the Password
+    let bound = if is_valid_comp_id(&comp_id) { self.gateway.accounts.comp_id_account(&comp_id) } else { None };
+    let api_key = message.get(tags::PASSWORD).unwrap_or("").to_string();
+    let account = self.gateway.accounts.authenticate(&api_key);
+    let refusal = if bound.is_none() || target != self.gateway.comp_id {
+      Some("unknown SenderCompID or TargetCompID".to_string())
+    } else if account.is_none() || account != bound {
+      Some("invalid Password".to_string())
+    } else if heartbeat == 0 || heartbeat > MAX_HEARTBEAT_SECS {
+      Some(format!("HeartBtInt must be between 1 and {}", MAX_HEARTBEAT_SECS))
+    } else if seq == 0 {
+      Some("MsgSeqNum missing".to_string())
+    } else {
+      None
+    };
+    if let Some(reason) = refusal {
+      self.send_unsequenced(&comp_id, FixMessage::new(msg_type::LOGOUT).with(tags::TEXT, &reason)).await;
+      return Err(format!("logon refused: {}", reason));
+    }
+    
+    // The connection is registered and the store writer flushed under the order table lock,
+    // so that reports made while the session was logged out are all in the store
+    let reset = message.get(tags::RESET_SEQ_NUM_FLAG) == Some("Y");
+    let store = self.gateway.stores.get(&comp_id).map_err(|e| format!("failed to open the session store: {}", e))?;
+    let opened = {
+      let mut table = self.gateway.orders.lock().unwrap();
+      let next_in = store.lock().unwrap().next_in();
+      if table.connections.contains_key(&comp_id) {
+        Err("session is already logged on".to_string())
+      } else if !reset && seq < next_in {
+        Err(format!("MsgSeqNum too low, expecting {} but received {}", next_in, seq))
+      } else {
+        table.connections.insert(comp_id.clone(), self.reports.clone());
+        let (flushed_tx, flushed_rx) = oneshot::channel();
+        let _ = self.gateway.offline.send(Offline::Flush(flushed_tx));
+        Ok(flushed_rx)
+      }
+    };
+    match opened {
+      Ok(flushed) => {
+        let _ = flushed.await;
+      }
+      Err(reason) => {
+        self.send_unsequenced(&comp_id, FixMessage::new(msg_type::LOGOUT).with(tags::TEXT, &reason)).await;
+        return Err(format!("logon refused: {}", reason));
+      }
+    }
+    
+    let now = Instant::now();
+    self.session = Some(Session {
+      comp_id: comp_id.clone(),
+      account: account.unwrap_or_default(),
+      api_key,
+      store: store.clone(),
+      heartbeat: Duration::from_secs(heartbeat),
+      last_sent: now,
+      last_received: now,
+      test_request: None,
+      resend_until: None,
+    });
+    if reset {
+      store.lock().unwrap().reset().map_err(|e| format!("failed to reset the session store: {}", e))?;
+    }
+    let expected = store.lock().unwrap().next_in();
+    println!("FIX session {} logged on", comp_id);
+    
+    let mut reply = FixMessage::new(msg_type::LOGON)
+      .with(tags::ENCRYPT_METHOD, 0)
+      .with(tags::HEART_BT_INT, heartbeat);
+    if reset {
+      reply.set(tags::RESET_SEQ_NUM_FLAG, "Y");
+    }
+    self.send(reply).await?;
+    
+    // Messages missed while disconnected are requested right after the logon
+    if seq > expected {
+      self.request_resend(expected, seq).await
+    } else {
+      self.set_next_in(seq + 1)
+    }
+  }
+  
+  async fn on_tick(&mut self, connected: Instant) -> Flow {
+    let session = match &mut self.session {
+      Some(session) => session,
+      None if connected.elapsed() >= LOGON_TIMEOUT => return Err("no Logon received".to_string()),
+      None => return Ok(()),
+    };
+    self.gateway.purge_closed();
+    
+    // A TestRequest is sent after a heartbeat interval of silence, and the connection is
+    // dropped when it is not answered within another interval
+    let silence = session.last_received.elapsed();
+    let heartbeat = session.heartbeat;
+    match session.test_request {
+      Some(sent) if sent.elapsed() >= heartbeat => return Err("heartbeat timeout".to_string()),
+      None if silence >= heartbeat + heartbeat / 5 => {
+        session.test_request = Some(Instant::now());
+        let id = format!("TEST{}", self.next_test_req_id);
+        self.next_test_req_id += 1;
+        self.send(FixMessage::new(msg_type::TEST_REQUEST).with(tags::TEST_REQ_ID, id)).await?;
+      }
+      _ => {}
+    }
+    
+    let idle = self.session.as_ref().map(|session| session.last_sent.elapsed()).unwrap_or_default();
+    if idle >= heartbeat {
+      self.send(FixMessage::new(msg_type::HEARTBEAT)).await?;
+    }
+    Ok(())
+  }
+  
+  fn set_next_in(&mut self, next_in: u64) -> Flow {
+    let session = self.session.as_mut().unwrap();
+    if session.resend_until.is_some_and(|until| next_in > until) {
+      session.resend_until = None;
+    }
+    session.store.lock().unwrap().set_next_in(next_in).map_err(|e| format!("failed to persist sequence numbers: {}", e))
+  }
+  
+  // Ask for everything from the first missing message, once per gap
+  async fn request_resend(&mut self, expected: u64, received: u64) -> Flow {
+    let session = self.session.as_mut().unwrap();
+    if session.resend_until.is_some() {
+      session.resend_until = session.resend_until.max(Some(received));
+      return Ok(());
+    }
+    session.resend_until = Some(received);
+    let request = FixMessage::new(msg_type::RESEND_REQUEST)
+      .with(tags::BEGIN_SEQ_NO, expected)
+      .with(tags::END_SEQ_NO, 0);
+    self.send(request).await
+  }
+  
+  // Resend stored application messages with PossDupFlag, replacing session messages
+  // and anything missing from the store by gap fills
+  async fn resend(&mut self, request: &FixMessage) -> Flow {
+    let begin = request.get(tags::BEGIN_SEQ_NO).and_then(|value| value.parse::<u64>().ok()).unwrap_or(1).max(1);
+    let last_sent = self.session.as_ref().unwrap().store.lock().unwrap().next_out() - 1;
+    let end = match request.get(tags::END_SEQ_NO).and_then(|value| value.parse::<u64>().ok()) {
+      Some(end) if end != 0 => end.min(last_sent),
+      _ => last_sent,
+    };
+    if begin > end {
+      return Ok(());
+    }
+    
+    let stored = self.session.as_ref().unwrap().store.lock().unwrap().outgoing(begin, end);
+    let mut gap_start: Option<u64> = None;
+    let mut next = begin;
+    for (seq, mut raw) in stored {
+      if seq > next {
+        gap_start.get_or_insert(next);
+      }
+      next = seq + 1;
+      match FixMessage::decode(&mut raw) {
+        Ok(Some(original)) if !msg_type::is_admin(original.msg_type()) => {
+          if let Some(start) = gap_start.take() {
+            self.send_gap_fill(start, seq).await?;
+          }
+          self.send_poss_dup(seq, &original).await?;
+        }
+        _ => {
+          gap_start.get_or_insert(seq);
+        }
+      }
+    }
+    if next <= end {
+      gap_start.get_or_insert(next);
+    }
+    if let Some(start) = gap_start {
+      self.send_gap_fill(start, end + 1).await?;
+    }
+    Ok(())
+  }
+  
+  async fn send_gap_fill(&mut self, seq: u64, new_seq: u64) -> Flow {
+    let gap_fill = FixMessage::new(msg_type::SEQUENCE_RESET)
+      .with(tags::GAP_FILL_FLAG, "Y")
+      .with(tags::NEW_SEQ_NO, new_seq);
+    let session = self.session.as_ref().unwrap();
+    let message = header(&gap_fill, &self.gateway.comp_id, &session.comp_id, seq, Some(message::format_timestamp(Utc::now())));
+    self.write(&message.encode()).await
+  }
+  
+  async fn send_poss_dup(&mut self, seq: u64, original: &FixMessage) -> Flow {
+    let session = self.session.as_ref().unwrap();
+    let original_time = original.get(tags::SENDING_TIME).unwrap_or("").to_string();
+    let message = header(original, &self.gateway.comp_id, &session.comp_id, seq, Some(original_time));
+    self.write(&message.encode()).await
+  }
+  
+  async fn new_order_single(&mut self, message: &FixMessage) -> Flow {
+    let seq = message.seq_num().unwrap_or(0);
+    let fields = required(message, &[tags::CL_ORD_ID, tags::SYMBOL, tags::SIDE, tags::ORD_TYPE, tags::ORDER_QTY]);
+    let fields = match fields {
+      Ok(fields) => fields,
+      Err(tag) => return self.send(missing_tag(seq, message.msg_type(), tag)).await,
+    };
+    let (cl_ord_id, symbol) = (fields[0].to_string(), fields[1].to_string());
+    let side = match parse_side(fields[2]) {
+      Some(side) => side,
+      None => return self.send(invalid_value(seq, message.msg_type(), tags::SIDE)).await,
+    };
+    let order_type = match fields[3] {
+      "1" => OrderType::Market,
+      "2" => OrderType::Limit,
+      _ => return self.send(invalid_value(seq, message.msg_type(), tags::ORD_TYPE)).await,
+    };
+    let price = match (message.get(tags::PRICE), &order_type) {
+      (Some(price), _) => price.to_string(),
+      (None, OrderType::Market) => "0".to_string(),
+      (None, OrderType::Limit) => return self.send(missing_tag(seq, message.msg_type(), tags::PRICE)).await,
+    };
+    
+    let session = self.session.as_ref().unwrap();
+    let key = (session.comp_id.clone(), cl_ord_id.clone());
+    let mut rejected = FixOrder {
+      order_id: "NONE".to_string(),
+      session: session.comp_id.clone(),
+      cl_ord_id: cl_ord_id.clone(),
+      cl_ord_ids: Vec::new(),
+      symbol: symbol.clone(),
+      side: side.clone(),
+      order_type: order_type.clone(),
+      price: Price::ZERO,
+      quantity: Qty::ZERO,
+      cum_qty: Qty::ZERO,
+      notional: 0,
+      closed_at: None,
+      pending: None,
+    };
+    if let Err(e) = self.admit() {
+      return self.send(order_rejected(&rejected, "99", &format!("{}: {}", e.code(), e))).await;
+    }
+    if self.gateway.orders.lock().unwrap().cl_ord_ids.contains_key(&key) {
+      // OrdRejReason 6: Duplicate order
+      return self.send(order_rejected(&rejected, "6", "duplicate ClOrdID")).await;
+    }
+    
+    let new = NewOrder {
+      symbol: symbol.clone(),
+      account: session.account.clone(),
+      side,
+      price: DecimalInput(price),
+      order_type,
+      quantity: DecimalInput(fields[4].to_string()),
+      client_order_id: None,
+    };
+    let (order, instrument) = match prepare_order(new, &self.gateway.instruments) {
+      Ok(prepared) => prepared,
+      Err(e) => return self.send(order_rejected(&rejected, ord_rej_reason(&e), &format!("{}: {}", e.code(), e))).await,
+    };
+    if let Err(e) = self.gateway.risk.admit(&order, &instrument) {
+      // OrdRejReason 3: Order exceeds limit
+      return self.send(order_rejected(&rejected, "3", &format!("{}: {}", e.code(), e))).await;
+    }
+    
+    let fix_order = FixOrder {
+      order_id: order.order_id.clone(),
+      cl_ord_ids: vec![cl_ord_id],
+      price: order.price,
+      quantity: order.quantity,
+      ..rejected.clone()
+    };
+    // Registered before sequencing so that no fill can miss the order
+    {
+      let mut table = self.gateway.orders.lock().unwrap();
+      table.cl_ord_ids.insert(key, order.order_id.clone());
+      table.orders.insert(order.order_id.clone(), fix_order.clone());
+    }
+    if let Err(e) = self.gateway.throttle.try_send(&self.gateway.order_tx, OrderMessage::Order(order)) {
+      forget_order(&mut self.gateway.orders.lock().unwrap(), &fix_order.order_id);
+      self.gateway.risk.release(&fix_order.order_id);
+      rejected.quantity = fix_order.quantity;
+      return self.send(order_rejected(&rejected, "99", &format!("{}: {}", e.code(), e))).await;
+    }
+    
+    // ExecType 0: New
+    let report = self.gateway.execution_report(&fix_order, "0", "0");
+    self.send(report).await
+  }
+  
+  async fn cancel_request(&mut self, message: &FixMessage) -> Flow {
+    let seq = message.seq_num().unwrap_or(0);
+    let fields = match required(message, &[tags::ORIG_CL_ORD_ID, tags::CL_ORD_ID]) {
+      Ok(fields) => fields,
+      Err(tag) => return self.send(missing_tag(seq, message.msg_type(), tag)).await,
+    };
+    let (orig_cl_ord_id, cl_ord_id) = (fields[0].to_string(), fields[1].to_string());
+    
+    // CxlRejResponseTo 1: Order cancel request
+    if let Err(e) = self.admit() {
+      return self.send(cancel_rejected(&orig_cl_ord_id, &cl_ord_id, "1", "99", &format!("{}: {}", e.code(), e))).await;
+    }
+    let order = match self.open_order(&orig_cl_ord_id, &cl_ord_id) {
+      Ok(order) => order,
+      Err((reason, text)) => return self.send(cancel_rejected(&orig_cl_ord_id, &cl_ord_id, "1", reason, text)).await,
+    };
+    
+    let cancel = Order {
+      order_id: order.order_id.clone(),
+      symbol: order.symbol.clone(),
+      account: self.session.as_ref().unwrap().account.clone(),
+      price: Price::ZERO,
+      quantity: Qty::ZERO,
+      side: order.side.clone(),
+      order_type: OrderType::Limit,
+      status: OrderStatus::Cancelled,
+      filled_quantity: Qty::ZERO,
+      remain_quantity: Qty::ZERO,
+      entry_time: Utc::now()
+    };
+    if let Err(e) = self.gateway.throttle.try_send(&self.gateway.order_tx, OrderMessage::Order(cancel)) {
+      return self.send(cancel_rejected(&orig_cl_ord_id, &cl_ord_id, "1", "99", &format!("{}: {}", e.code(), e))).await;
+    }
+    
+    // Canceled (4) follows once the engine has removed the order
+    let order = self.pending_change(&order.order_id, PendingChange { cl_ord_id, orig_cl_ord_id, replace: false });
+    // ExecType 6: Pending Cancel
+    let report = self.gateway.execution_report(&order, "6", "6");
+    self.send(answering(report, &order.pending)).await
+  }
+  
+  async fn cancel_replace_request(&mut self, message: &FixMessage) -> Flow {
+    let seq = message.seq_num().unwrap_or(0);
+    let fields = match required(message, &[tags::ORIG_CL_ORD_ID, tags::CL_ORD_ID, tags::ORDER_QTY]) {
+      Ok(fields) => fields,
+      Err(tag) => return self.send(missing_tag(seq, message.msg_type(), tag)).await,
+    };
+    let (orig_cl_ord_id, cl_ord_id, quantity) = (fields[0].to_string(), fields[1].to_string(), fields[2].to_string());
+    
+    // CxlRejResponseTo 2: Order cancel/replace request
+    if let Err(e) = self.admit() {
+      return self.send(cancel_rejected(&orig_cl_ord_id, &cl_ord_id, "2", "99", &format!("{}: {}", e.code(), e))).await;
+    }
+    let order = match self.open_order(&orig_cl_ord_id, &cl_ord_id) {
+      Ok(order) => order,
+      Err((reason, text)) => return self.send(cancel_rejected(&orig_cl_ord_id, &cl_ord_id, "2", reason, text)).await,
+    };
+    if order.order_type != OrderType::Limit {
+      return self.send(cancel_rejected(&orig_cl_ord_id, &cl_ord_id, "2", "99", "only limit orders can be replaced")).await;
+    }
+    
+    let instrument = self.gateway.instrument(&order.symbol);
+    let amended = instrument.parse_quantity(&quantity).and_then(|quantity| {
+      let price = match message.get(tags::PRICE) {
+        Some(price) => instrument.parse_price(price)?,
+        None => order.price,
+      };
+      let probe = Order {
+        order_id: order.order_id.clone(),
+        symbol: order.symbol.clone(),
+        account: self.session.as_ref().unwrap().account.clone(),
+        price,
+        quantity,
+        side: order.side.clone(),
+        order_type: OrderType::Limit,
+        status: OrderStatus::New,
+        filled_quantity: Qty::ZERO,
+        remain_quantity: quantity,
+        entry_time: Utc::now()
+      };
+      instrument.validate_order(&probe)?;
+      Ok(probe)
+    });
+    let amended = match amended {
+      Ok(amended) => amended,
+      Err(e) => return self.send(cancel_rejected(&orig_cl_ord_id, &cl_ord_id, "2", "99", &format!("{}: {}", e.code(), e))).await,
+    };
+    let (price, quantity) = (amended.price, amended.quantity);
+    if quantity <= order.cum_qty {
+      return self.send(cancel_rejected(&orig_cl_ord_id, &cl_ord_id, "2", "99", "OrderQty must exceed CumQty")).await;
+    }
+    if let Err(e) = self.gateway.risk.admit_amend(&amended, &instrument) {
+      return self.send(cancel_rejected(&orig_cl_ord_id, &cl_ord_id, "2", "99", &format!("{}: {}", e.code(), e))).await;
+    }
+    
+    let amend = Amend { order_id: order.order_id.clone(), account: amended.account.clone(), price, quantity };
+    if let Err(e) = self.gateway.throttle.try_send(&self.gateway.order_tx, OrderMessage::Amend(amend)) {
+      self.gateway.risk.release_amend(&order.order_id);
+      return self.send(cancel_rejected(&orig_cl_ord_id, &cl_ord_id, "2", "99", &format!("{}: {}", e.code(), e))).await;
+    }
+    
+    // Replaced (5) follows with the new price and quantity once the engine has applied them
+    let order = self.pending_change(&order.order_id, PendingChange { cl_ord_id, orig_cl_ord_id, replace: true });
+    // ExecType E: Pending Replace
+    let report = self.gateway.execution_report(&order, "E", "E");
+    self.send(answering(report, &order.pending)).await
+  }
+  
+  // Rate limits and the sequencer queue check, like a REST request of the session's API key
+  fn admit(&self) -> Result<(), ApiError> {
+    let session = self.session.as_ref().unwrap();
+    self.gateway.throttle.admit(Some(&session.api_key), Some(self.ip), &self.gateway.order_tx)
+  }
+  
+  // The open order known by OrigClOrdID, or the CxlRejReason and text to reject with
+  fn open_order(&self, orig_cl_ord_id: &str, cl_ord_id: &str) -> Result<FixOrder, (&'static str, &'static str)> {
+    let session = self.session.as_ref().unwrap();
+    let table = self.gateway.orders.lock().unwrap();
+    if table.cl_ord_ids.contains_key(&(session.comp_id.clone(), cl_ord_id.to_string())) {
+      // CxlRejReason 6: Duplicate ClOrdID
+      return Err(("6", "duplicate ClOrdID"));
+    }
+    let order = table.cl_ord_ids.get(&(session.comp_id.clone(), orig_cl_ord_id.to_string()))
+      .and_then(|order_id| table.orders.get(order_id));
+    match order {
+      // CxlRejReason 3: Order already in Pending Cancel or Pending Replace status
+      Some(order) if order.pending.is_some() => Err(("3", "a cancel or replace is already pending")),
+      Some(order) if order.closed_at.is_none() && order.cum_qty < order.quantity => Ok(order.clone()),
+      // CxlRejReason 0: Too late to cancel, 1: Unknown order
+      Some(_) => Err(("0", "order is no longer open")),
+      None => Err(("1", "unknown order")),
+    }
+  }
+  
+  // Register the ClOrdID of a cancel or cancel/replace sent to the engine. The order keeps
+  // its ClOrdID until the engine answers.
+  fn pending_change(&self, order_id: &str, change: PendingChange) -> FixOrder {
+    let mut table = self.gateway.orders.lock().unwrap();
+    let table = &mut *table;
+    let order = table.orders.get_mut(order_id).unwrap();
+    order.cl_ord_ids.push(change.cl_ord_id.clone());
+    table.cl_ord_ids.insert((order.session.clone(), change.cl_ord_id.clone()), order_id.to_string());
+    order.pending = Some(change);
+    order.clone()
+  }
+  
+  async fn on_report(&mut self, report: Report) -> Flow {
+    let message = self.gateway.report_message(&report);
+    self.send(message).await
+  }
+  
+  async fn logout(&mut self, reason: &str) -> Flow {
+    self.send(FixMessage::new(msg_type::LOGOUT).with(tags::TEXT, reason)).await?;
+    Err(reason.to_string())
+  }
+  
+  async fn logout_reply(&mut self) -> Flow {
+    self.send(FixMessage::new(msg_type::LOGOUT)).await?;
+    Err("logout".to_string())
+  }
+  
+  // Send a message under the next outgoing sequence number, storing it for resends
+  async fn send(&mut self, message: FixMessage) -> Flow {
+    let raw = self.store_outgoing(message)?;
+    self.session.as_mut().unwrap().last_sent = Instant::now();
+    self.write(&raw).await
+  }
+  
+  // Frame and store a message under the next outgoing sequence number
+  fn store_outgoing(&mut self, message: FixMessage) -> Result<Vec<u8>, String> {
+    let session = self.session.as_ref().unwrap();
+    let mut store = session.store.lock().unwrap();
+    let seq = store.next_out();
+    let raw = header(&message, &self.gateway.comp_id, &session.comp_id, seq, None).encode();
+    store.store_outgoing(seq, &raw).map_err(|e| format!("failed to persist an outgoing message: {}", e))?;
+    Ok(raw)
+  }
+  
+  // Logon refusals are sent before a session exists and do not use its sequence numbers
+  async fn send_unsequenced(&mut self, target: &str, message: FixMessage) {
+    let message = header(&message, &self.gateway.comp_id, target, 1, None);
+    let _ = self.write(&message.encode()).await;
+  }
+  
+  async fn write(&mut self, raw: &[u8]) -> Flow {
+    self.writer.write_all(raw).await.map_err(|e| format!("write failed: {}", e))
+  }
+}
+
+// The standard header followed by the body of the message. Resent messages carry
+// PossDupFlag and their original SendingTime.
+fn header(message: &FixMessage, sender: &str, target: &str, seq: u64, orig_sending_time: Option<String>) -> FixMessage {
+  let mut framed = FixMessage::new(message.msg_type())
+    .with(tags::SENDER_COMP_ID, sender)
+    .with(tags::TARGET_COMP_ID, target)
+    .with(tags::MSG_SEQ_NUM, seq);
+  if orig_sending_time.is_some() {
+    framed.set(tags::POSS_DUP_FLAG, "Y");
+  }
+  framed.set(tags::SENDING_TIME, message::format_timestamp(Utc::now()));
+  if let Some(time) = orig_sending_time {
+    framed.set(tags::ORIG_SENDING_TIME, time);
+  }
+  for (tag, value) in message.body() {
+    framed.set(*tag, value);
+  }
+  framed
+}
+
+// Values of the tags in order, or the first missing tag
+fn required<'a>(message: &'a FixMessage, required: &[u32]) -> Result<Vec<&'a str>, u32> {
+  required.iter().map(|&tag| message.get(tag).filter(|value| !value.is_empty()).ok_or(tag)).collect()
+}
+
+fn session_reject(ref_seq: u64, ref_msg_type: &str, reason: u32, text: &str) -> FixMessage {
+  FixMessage::new(msg_type::REJECT)
+    .with(tags::REF_SEQ_NUM, ref_seq)
+    .with(tags::REF_MSG_TYPE, ref_msg_type)
+    .with(tags::SESSION_REJECT_REASON, reason)
+    .with(tags::TEXT, text)
+}
+
+// SessionRejectReason 1: Required tag missing
+fn missing_tag(ref_seq: u64, ref_msg_type: &str, tag: u32) -> FixMessage {
+  session_reject(ref_seq, ref_msg_type, 1, "required tag missing").with(tags::REF_TAG_ID, tag)
+}
+
+// SessionRejectReason 5: Value is incorrect (out of range) for this tag
+fn invalid_value(ref_seq: u64, ref_msg_type: &str, tag: u32) -> FixMessage {
+  session_reject(ref_seq, ref_msg_type, 5, "value is incorrect for this tag").with(tags::REF_TAG_ID, tag)
+}
+
+// ExecType 8: Rejected
+fn order_rejected(order: &FixOrder, reason: &str, text: &str) -> FixMessage {
+  FixMessage::new(msg_type::EXECUTION_REPORT)
+    .with(tags::ORDER_ID, &order.order_id)
+    .with(tags::CL_ORD_ID, &order.cl_ord_id)
+    .with(tags::EXEC_ID, uuid::Uuid::new_v4())
+    .with(tags::EXEC_TYPE, "8")
+    .with(tags::ORD_STATUS, "8")
+    .with(tags::SYMBOL, &order.symbol)
+    .with(tags::SIDE, format_side(&order.side))
+    .with(tags::LEAVES_QTY, 0)
+    .with(tags::CUM_QTY, 0)
+    .with(tags::AVG_PX, 0)
+    .with(tags::ORD_REJ_REASON, reason)
+    .with(tags::TEXT, text)
+}
+
+// ClOrdID (11) and OrigClOrdID (41) of the request a report answers
+fn answering(mut report: FixMessage, change: &Option<PendingChange>) -> FixMessage {
+  if let Some(change) = change {
+    report.set(tags::CL_ORD_ID, &change.cl_ord_id);
+    report.set(tags::ORIG_CL_ORD_ID, &change.orig_cl_ord_id);
+  }
+  report
+}
+
+// OrdRejReason 1: Unknown symbol, 2: Exchange closed, 99: Other
+fn ord_rej_reason(e: &OrderValidationError) -> &'static str {
+  match e {
+    OrderValidationError::UnknownSymbol(_) => "1",
+    OrderValidationError::NotTrading { .. } => "2",
+    _ => "99",
+  }
+}
+
+fn cancel_rejected(orig_cl_ord_id: &str, cl_ord_id: &str, response_to: &str, reason: &str, text: &str) -> FixMessage {
+  FixMessage::new(msg_type::ORDER_CANCEL_REJECT)
+    .with(tags::ORDER_ID, "NONE")
+    .with(tags::CL_ORD_ID, cl_ord_id)
+    .with(tags::ORIG_CL_ORD_ID, orig_cl_ord_id)
+    .with(tags::ORD_STATUS, "8")
+    .with(tags::CXL_REJ_RESPONSE_TO, response_to)
+    .with(tags::CXL_REJ_REASON, reason)
+    .with(tags::TEXT, text)
+}
+
+fn parse_side(value: &str) -> Option<Side> {
+  match value {
+    "1" => Some(Side::Buy),
+    "2" => Some(Side::Sell),
+    _ => None,
+  }
+}
+
+fn format_side(side: &Side) -> &'static str {
+  match side {
+    Side::Buy => "1",
+    Side::Sell => "2",
+  }
+}
\ No newline at end of file
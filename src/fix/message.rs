@@ -0,0 +1,282 @@
+/*
+* filename : message
+* author : HAMA
+* date: 2026. 10. 18.
+* description: FIX 4.4 tag=value 메시지 인코딩 및 디코딩
+**/
+
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+
+pub const BEGIN_STRING: &str = "FIX.4.4";
+pub const SOH: u8 = 0x01;
+// Largest message accepted, from BeginString to CheckSum
+pub const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+// 10=nnn<SOH>
+const TRAILER_LEN: usize = 7;
+
+// Tags used by the gateway
+pub mod tags {
+  pub const AVG_PX: u32 = 6;
+  pub const BEGIN_SEQ_NO: u32 = 7;
+  pub const CL_ORD_ID: u32 = 11;
+  pub const CUM_QTY: u32 = 14;
+  pub const END_SEQ_NO: u32 = 16;
+  pub const EXEC_ID: u32 = 17;
+  pub const LAST_PX: u32 = 31;
+  pub const LAST_QTY: u32 = 32;
+  pub const MSG_SEQ_NUM: u32 = 34;
+  pub const MSG_TYPE: u32 = 35;
+  pub const NEW_SEQ_NO: u32 = 36;
+  pub const ORDER_ID: u32 = 37;
+  pub const ORDER_QTY: u32 = 38;
+  pub const ORD_STATUS: u32 = 39;
+  pub const ORD_TYPE: u32 = 40;
+  pub const ORIG_CL_ORD_ID: u32 = 41;
+  pub const POSS_DUP_FLAG: u32 = 43;
+  pub const PRICE: u32 = 44;
+  pub const REF_SEQ_NUM: u32 = 45;
+  pub const SENDER_COMP_ID: u32 = 49;
+  pub const SENDING_TIME: u32 = 52;
+  pub const SIDE: u32 = 54;
+  pub const SYMBOL: u32 = 55;
+  pub const TARGET_COMP_ID: u32 = 56;
+  pub const TEXT: u32 = 58;
+  pub const TRANSACT_TIME: u32 = 60;
+  pub const ENCRYPT_METHOD: u32 = 98;
+  pub const CXL_REJ_REASON: u32 = 102;
+  pub const ORD_REJ_REASON: u32 = 103;
+  pub const HEART_BT_INT: u32 = 108;
+  pub const TEST_REQ_ID: u32 = 112;
+  pub const ORIG_SENDING_TIME: u32 = 122;
+  pub const GAP_FILL_FLAG: u32 = 123;
+  pub const RESET_SEQ_NUM_FLAG: u32 = 141;
+  pub const EXEC_TYPE: u32 = 150;
+  pub const LEAVES_QTY: u32 = 151;
+  pub const REF_TAG_ID: u32 = 371;
+  pub const REF_MSG_TYPE: u32 = 372;
+  pub const SESSION_REJECT_REASON: u32 = 373;
+  pub const CXL_REJ_RESPONSE_TO: u32 = 434;
+  pub const PASSWORD: u32 = 554;
+}
+
+// MsgType (35) values
+pub mod msg_type {
+  pub const HEARTBEAT: &str = "0";
+  pub const TEST_REQUEST: &str = "1";
+  pub const RESEND_REQUEST: &str = "2";
+  pub const REJECT: &str = "3";
+  pub const SEQUENCE_RESET: &str = "4";
+  pub const LOGOUT: &str = "5";
+  pub const EXECUTION_REPORT: &str = "8";
+  pub const ORDER_CANCEL_REJECT: &str = "9";
+  pub const LOGON: &str = "A";
+  pub const NEW_ORDER_SINGLE: &str = "D";
+  pub const ORDER_CANCEL_REQUEST: &str = "F";
+  pub const ORDER_CANCEL_REPLACE_REQUEST: &str = "G";
+  
+  // Session level messages, replaced by a gap fill when resent
+  pub fn is_admin(msg_type: &str) -> bool {
+    matches!(msg_type, HEARTBEAT | TEST_REQUEST | RESEND_REQUEST | REJECT | SEQUENCE_RESET | LOGOUT | LOGON)
+  }
+}
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum FixError {
+  #[error("garbled message: {0}")]
+  Garbled(String),
+  #[error("checksum mismatch: expected {expected:03}, received {received}")]
+  CheckSum { expected: u8, received: String },
+}
+
+// A FIX message as an ordered list of fields, without BeginString, BodyLength and CheckSum
+// which are added when encoding. MsgType is always the first field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FixMessage {
+  fields: Vec<(u32, String)>,
+}
+
+impl FixMessage {
+  pub fn new(msg_type: &str) -> Self {
+    FixMessage {
+      fields: vec![(tags::MSG_TYPE, msg_type.to_string())],
+    }
+  }
+  
+  pub fn with(mut self, tag: u32, value: impl ToString) -> Self {
+    self.set(tag, value);
+    self
+  }
+  
+  // Replace the first occurrence of the tag, or append it
+  pub fn set(&mut self, tag: u32, value: impl ToString) {
+    let value = value.to_string();
+    match self.fields.iter_mut().find(|(t, _)| *t == tag) {
+      Some(field) => field.1 = value,
+      None => self.fields.push((tag, value)),
+    }
+  }
+  
+  pub fn get(&self, tag: u32) -> Option<&str> {
+    self.fields.iter().find(|(t, _)| *t == tag).map(|(_, value)| value.as_str())
+  }
+  
+  pub fn msg_type(&self) -> &str {
+    self.get(tags::MSG_TYPE).unwrap_or("")
+  }
+  
+  pub fn seq_num(&self) -> Option<u64> {
+    self.get(tags::MSG_SEQ_NUM).and_then(|value| value.parse().ok())
+  }
+  
+  pub fn is_poss_dup(&self) -> bool {
+    self.get(tags::POSS_DUP_FLAG) == Some("Y")
+  }
+  
+  // Fields other than the standard header, in order
+  pub fn body(&self) -> impl Iterator<Item = &(u32, String)> {
+    self.fields.iter().filter(|(tag, _)| !matches!(*tag,
+      tags::MSG_TYPE | tags::SENDER_COMP_ID | tags::TARGET_COMP_ID | tags::MSG_SEQ_NUM |
+      tags::SENDING_TIME | tags::POSS_DUP_FLAG | tags::ORIG_SENDING_TIME))
+  }
+  
+  pub fn encode(&self) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (tag, value) in &self.fields {
+      body.extend_from_slice(format!("{}={}", tag, value).as_bytes());
+      body.push(SOH);
+    }
+    
+    let mut message = format!("8={}\x019={}\x01", BEGIN_STRING, body.len()).into_bytes();
+    message.extend_from_slice(&body);
+    let check_sum = checksum(&message);
+    message.extend_from_slice(format!("10={:03}\x01", check_sum).as_bytes());
+    message
+  }
+  
+  // Take one complete message off the front of the buffer. Returns Ok(None) until a whole
+  // message has been received. Bytes before the next BeginString are discarded.
+  pub fn decode(buffer: &mut Vec<u8>) -> Result<Option<FixMessage>, FixError> {
+    let start = match find(buffer, b"8=") {
+      Some(start) => start,
+      None => {
+        buffer.clear();
+        return Ok(None);
+      }
+    };
+    buffer.drain(..start);
+    
+    // 8=FIX.4.4<SOH>9=<length><SOH>
+    let begin_end = match buffer.iter().position(|&b| b == SOH) {
+      Some(end) => end,
+      None => return Ok(None),
+    };
+    let length_end = match buffer[begin_end + 1..].iter().position(|&b| b == SOH) {
+      Some(end) => begin_end + 1 + end,
+      None => return Ok(None),
+    };
+    let length_field = String::from_utf8_lossy(&buffer[begin_end + 1..length_end]).to_string();
+    let length: usize = match length_field.strip_prefix("9=").and_then(|value| value.parse().ok()) {
+      Some(length) => length,
+      None => {
+        buffer.drain(..begin_end + 1);
+        return Err(FixError::Garbled(format!("invalid BodyLength field {:?}", length_field)));
+      }
+    };
+    
+    let body_end = match length_end.checked_add(1).and_then(|start| start.checked_add(length)) {
+      Some(end) if end.checked_add(TRAILER_LEN).is_some_and(|end| end <= MAX_MESSAGE_SIZE) => end,
+      _ => {
+        buffer.drain(..begin_end + 1);
+        return Err(FixError::Garbled(format!("BodyLength {} exceeds the maximum message size", length)));
+      }
+    };
+    
+    // The trailer is 10=nnn<SOH>
+    let trailer_end = match buffer.get(body_end..).and_then(|rest| rest.iter().position(|&b| b == SOH)) {
+      Some(end) => body_end + end,
+      None => return Ok(None),
+    };
+    let raw: Vec<u8> = buffer.drain(..=trailer_end).collect();
+    
+    let trailer = String::from_utf8_lossy(&raw[body_end..trailer_end]).to_string();
+    let received = match trailer.strip_prefix("10=") {
+      Some(received) => received.to_string(),
+      None => return Err(FixError::Garbled(format!("expected CheckSum, found {:?}", trailer))),
+    };
+    let expected = checksum(&raw[..body_end]);
+    if received.parse::<u16>().ok() != Some(expected as u16) {
+      return Err(FixError::CheckSum { expected, received });
+    }
+    
+    let mut fields = Vec::new();
+    for field in raw[length_end + 1..body_end].split(|&b| b == SOH).filter(|field| !field.is_empty()) {
+      let field = String::from_utf8_lossy(field);
+      let parsed = field.split_once('=').and_then(|(tag, value)| Some((tag.parse::<u32>().ok()?, value.to_string())));
+      match parsed {
+        Some(field) => fields.push(field),
+        None => return Err(FixError::Garbled(format!("invalid field {:?}", field))),
+      }
+    }
+    if fields.first().map(|(tag, _)| *tag) != Some(tags::MSG_TYPE) {
+      return Err(FixError::Garbled("MsgType must be the first field of the body".to_string()));
+    }
+    Ok(Some(FixMessage { fields }))
+  }
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+  bytes.iter().fold(0u8, |sum, &b| sum.wrapping_add(b))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+  haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+// UTCTimestamp with milliseconds, e.g. 20261018-09:30:00.000
+pub fn format_timestamp(time: DateTime<Utc>) -> String {
+  time.format("%Y%m%d-%H:%M:%S%.3f").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  
+  #[test]
+  fn test_encode_decode_round_trip() {
+    let message = FixMessage::new(msg_type::NEW_ORDER_SINGLE)
+      .with(tags::SENDER_COMP_ID, "CLIENT")
+      .with(tags::MSG_SEQ_NUM, 2)
+      .with(tags::CL_ORD_ID, "c1");
+    let mut buffer = b"garbage".to_vec();
+    let encoded = message.encode();
+    buffer.extend_from_slice(&encoded);
+    
+    // Partial input is kept until the rest arrives
+    let mut partial = buffer[..buffer.len() - 3].to_vec();
+    assert_eq!(FixMessage::decode(&mut partial), Ok(None));
+    
+    assert_eq!(FixMessage::decode(&mut buffer), Ok(Some(message.clone())));
+    assert!(buffer.is_empty());
+    assert!(String::from_utf8(encoded).unwrap().starts_with("8=FIX.4.4\x019=26\x0135=D\x01"));
+  }
+  
+  #[test]
+  fn test_decode_rejects_oversized_body_length() {
+    for length in [usize::MAX, MAX_MESSAGE_SIZE] {
+      let mut buffer = format!("8=FIX.4.4\x019={}\x0135=A\x01", length).into_bytes();
+      assert!(matches!(FixMessage::decode(&mut buffer), Err(FixError::Garbled(_))));
+      assert_eq!(FixMessage::decode(&mut buffer), Ok(None));
+      assert!(buffer.is_empty());
+    }
+  }
+  
+  #[test]
+  fn test_decode_rejects_bad_checksum() {
+    let mut encoded = FixMessage::new(msg_type::HEARTBEAT).encode();
+    let len = encoded.len();
+    encoded[len - 2] = if encoded[len - 2] == b'0' { b'1' } else { b'0' };
+    assert!(matches!(FixMessage::decode(&mut encoded), Err(FixError::CheckSum { .. })));
+    assert!(encoded.is_empty());
+  }
+}
\ No newline at end of file
@@ -0,0 +1,10 @@
+/*
+* filename : mod
+* author : HAMA
+* date: 2026. 10. 18.
+* description: FIX 4.4 주문 입력 게이트웨이
+**/
+
+pub mod message;
+pub mod store;
+pub mod gateway;
\ No newline at end of file
@@ -0,0 +1,175 @@
+/*
+* filename : store
+* author : HAMA
+* date: 2026. 10. 18.
+* description: FIX 세션 시퀀스 번호 및 송신 메시지 저장소 (재전송 요청 처리용)
+**/
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+pub const MAX_COMP_ID_LEN: usize = 64;
+// Sent messages kept for resends. Beyond this the older half is dropped and resent as a gap fill.
+pub const MAX_STORED_MESSAGES: usize = 10_000;
+
+// Session names become file names, so only letters, digits, '-' and '_' are allowed
+pub fn is_valid_comp_id(comp_id: &str) -> bool {
+  !comp_id.is_empty()
+    && comp_id.len() <= MAX_COMP_ID_LEN
+    && comp_id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+// Sequence numbers and sent messages of one FIX session, persisted in two files:
+// <session>.seqnums holds "<next outgoing> <next incoming>" and <session>.messages
+// holds one "<seq> <raw message>" line per sent message, up to MAX_STORED_MESSAGES.
+pub struct SessionStore {
+  seqnums_path: PathBuf,
+  messages_path: PathBuf,
+  next_out: u64,
+  next_in: u64,
+  messages: BTreeMap<u64, Vec<u8>>,
+}
+
+impl SessionStore {
+  // Open the store of a session, resuming its sequence numbers if it has been used before
+  pub fn open(dir: impl AsRef<Path>, session: &str) -> io::Result<Self> {
+    if !is_valid_comp_id(session) {
+      return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid session name {:?}", session)));
+    }
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    let mut store = SessionStore {
+      seqnums_path: dir.join(format!("{}.seqnums", session)),
+      messages_path: dir.join(format!("{}.messages", session)),
+      next_out: 1,
+      next_in: 1,
+      messages: BTreeMap::new(),
+    };
+    
+    // A session that has been used before resumes from its sequence numbers or does not open
+    if store.seqnums_path.exists() {
+      let content = fs::read_to_string(&store.seqnums_path)?;
+      let numbers: Vec<u64> = content.split_whitespace().filter_map(|n| n.parse().ok()).collect();
+      match numbers[..] {
+        [next_out, next_in] => {
+          store.next_out = next_out;
+          store.next_in = next_in;
+        }
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("corrupt sequence numbers in {}", store.seqnums_path.display()))),
+      }
+    }
+    if let Ok(content) = fs::read(&store.messages_path) {
+      for line in content.split(|&b| b == b'\n').filter(|line| !line.is_empty()) {
+        let separator = match line.iter().position(|&b| b == b' ') {
+          Some(separator) => separator,
+          None => continue,
+        };
+        if let Some(seq) = std::str::from_utf8(&line[..separator]).ok().and_then(|seq| seq.parse().ok()) {
+          store.messages.insert(seq, line[separator + 1..].to_vec());
+        }
+      }
+      if store.messages.len() > MAX_STORED_MESSAGES {
+        store.trim()?;
+      }
+    }
+    Ok(store)
+  }
+  
+  pub fn next_out(&self) -> u64 {
+    self.next_out
+  }
+  
+  pub fn next_in(&self) -> u64 {
+    self.next_in
+  }
+  
+  pub fn set_next_in(&mut self, next_in: u64) -> io::Result<()> {
+    self.next_in = next_in;
+    self.save_seqnums()
+  }
+  
+  // Record a sent message under the next outgoing sequence number
+  pub fn store_outgoing(&mut self, seq: u64, raw: &[u8]) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(&self.messages_path)?;
+    let mut line = format!("{} ", seq).into_bytes();
+    line.extend_from_slice(raw);
+    line.push(b'\n');
+    file.write_all(&line)?;
+    
+    self.messages.insert(seq, raw.to_vec());
+    self.next_out = seq + 1;
+    if self.messages.len() > MAX_STORED_MESSAGES {
+      self.trim()?;
+    }
+    self.save_seqnums()
+  }
+  
+  // Sent messages with sequence numbers in begin..=end
+  pub fn outgoing(&self, begin: u64, end: u64) -> Vec<(u64, Vec<u8>)> {
+    self.messages.range(begin..=end).map(|(seq, raw)| (*seq, raw.clone())).collect()
+  }
+  
+  // Start both sequences at 1 again, e.g. on a logon with ResetSeqNumFlag
+  pub fn reset(&mut self) -> io::Result<()> {
+    self.next_out = 1;
+    self.next_in = 1;
+    self.messages.clear();
+    if self.messages_path.exists() {
+      fs::remove_file(&self.messages_path)?;
+    }
+    self.save_seqnums()
+  }
+  
+  // Keep the newer half of the sent messages, in memory and on disk
+  fn trim(&mut self) -> io::Result<()> {
+    while self.messages.len() > MAX_STORED_MESSAGES / 2 {
+      self.messages.pop_first();
+    }
+    let mut content = Vec::new();
+    for (seq, raw) in &self.messages {
+      content.extend_from_slice(format!("{} ", seq).as_bytes());
+      content.extend_from_slice(raw);
+      content.push(b'\n');
+    }
+    replace_file(&self.messages_path, &content)
+  }
+  
+  fn save_seqnums(&self) -> io::Result<()> {
+    replace_file(&self.seqnums_path, format!("{} {}", self.next_out, self.next_in).as_bytes())
+  }
+}
+
+// Write a file through a temporary file and a rename, so that a crash leaves either the old
+// or the new content
+fn replace_file(path: &Path, content: &[u8]) -> io::Result<()> {
+  let mut tmp = path.as_os_str().to_owned();
+  tmp.push(".tmp");
+  fs::write(&tmp, content)?;
+  fs::rename(&tmp, path)
+}
+
+// The stores of the sessions of one gateway. Each store is opened on first use and kept open,
+// shared by the session's connection and the reports stored while it is logged out.
+pub struct SessionStores {
+  dir: PathBuf,
+  stores: Mutex<HashMap<String, Arc<Mutex<SessionStore>>>>,
+}
+
+impl SessionStores {
+  pub fn new(dir: impl Into<PathBuf>) -> Self {
+    SessionStores { dir: dir.into(), stores: Mutex::new(HashMap::new()) }
+  }
+  
+  pub fn get(&self, session: &str) -> io::Result<Arc<Mutex<SessionStore>>> {
+    let mut stores = self.stores.lock().unwrap();
+    if let Some(store) = stores.get(session) {
+      return Ok(store.clone());
+    }
+    let store = Arc::new(Mutex::new(SessionStore::open(&self.dir, session)?));
+    stores.insert(session.to_string(), store.clone());
+    Ok(store)
+  }
+}
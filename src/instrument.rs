@@ -0,0 +1,525 @@
+/*
+* filename : instrument
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 종목별 호가 단위, 수량 단위 및 주문 제약 조건
+**/
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use warp::Filter;
+use warp::http::StatusCode;
+use crate::models::{Order, OrderType, Price, Qty};
+use crate::util::decimal::{self, DecimalError};
+use crate::error::ApiError;
+
+// Session phases. PreOpen and PreClose are call phases: orders are collected without matching
+// and the book is uncrossed in a single auction when the phase ends.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TradingStatus { PreOpen, Trading, PreClose, Halted, Closed }
+
+impl TradingStatus {
+  pub fn is_call_phase(&self) -> bool {
+    matches!(self, TradingStatus::PreOpen | TradingStatus::PreClose)
+  }
+  
+  pub fn accepts_orders(&self) -> bool {
+    matches!(self, TradingStatus::Trading) || self.is_call_phase()
+  }
+}
+
+// Fees in basis points of notional. Negative maker fees are rebates.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FeeSchedule {
+  pub maker_fee_bps: i64,
+  pub taker_fee_bps: i64,
+}
+
+// Reference price for the dynamic price band
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BandReference {
+  #[default]
+  LastTrade,
+  Vwap,
+}
+
+// What happens to a limit order priced through the band
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BandAction {
+  #[default]
+  Reject,
+  Clip,
+}
+
+// Dynamic price band of band_bps around the reference price, applied to incoming orders during
+// continuous trading. A band_bps of 0 disables it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct PriceBand {
+  pub band_bps: u64,
+  pub reference: BandReference,
+  pub vwap_window_secs: u64, // Trades averaged for BandReference::Vwap
+  pub action: BandAction,
+}
+
+// Halts trading when trade prices move more than threshold_bps within window_secs.
+// Trading stays halted for halt_secs and resumes through a re-opening auction of
+// reopen_auction_secs. A threshold_bps of 0 disables it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct CircuitBreaker {
+  pub threshold_bps: u64,
+  pub window_secs: u64,
+  pub halt_secs: u64,
+  pub reopen_auction_secs: u64,
+}
+
+// Longest band window, breaker window, halt or re-opening auction a definition may set
+pub const MAX_TIMER_SECS: u64 = 7 * 24 * 60 * 60;
+// Most decimal places a price or quantity may have; 10^scale has to fit in a u64
+pub const MAX_SCALE: u32 = 18;
+
+// Reference data and trading constraints for a single symbol.
+// Prices and quantities are in raw integer units; the scales give the number of decimal places.
+// min_notional is a quote amount in raw units of price_scale.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Instrument {
+  pub symbol: String,
+  pub base_asset: String,
+  pub quote_asset: String,
+  pub price_scale: u32,
+  pub quantity_scale: u32,
+  pub tick_size: Price,
+  pub lot_size: Qty,
+  pub min_quantity: Qty,
+  pub max_quantity: Qty,
+  pub min_notional: u64,
+  pub min_price: Price,
+  pub max_price: Price,
+  pub status: TradingStatus,
+  pub fees: FeeSchedule,
+  pub price_band: PriceBand,
+  pub circuit_breaker: CircuitBreaker,
+}
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum OrderValidationError {
+  #[error("unknown symbol {0}")]
+  UnknownSymbol(String),
+  #[error("{symbol} is not open for trading ({status:?})")]
+  NotTrading { symbol: String, status: TradingStatus },
+  #[error("invalid price: {0}")]
+  InvalidPrice(DecimalError),
+  #[error("invalid quantity: {0}")]
+  InvalidQuantity(DecimalError),
+  #[error("market orders are not accepted during the {0:?} auction call")]
+  MarketOrderInAuction(TradingStatus),
+  #[error("quantity must be greater than zero")]
+  ZeroQuantity,
+  #[error("quantity {quantity} is below the minimum {min}")]
+  QuantityTooSmall { quantity: Qty, min: Qty },
+  #[error("quantity {quantity} is above the maximum {max}")]
+  QuantityTooLarge { quantity: Qty, max: Qty },
+  #[error("quantity {quantity} is not a multiple of the lot size {lot_size}")]
+  InvalidLotSize { quantity: Qty, lot_size: Qty },
+  #[error("price {price} is not a multiple of the tick size {tick_size}")]
+  InvalidTickSize { price: Price, tick_size: Price },
+  #[error("price {price} is outside the allowed range {min}..={max}")]
+  PriceOutOfRange { price: Price, min: Price, max: Price },
+  #[error("notional {notional} is below the minimum {min}")]
+  NotionalTooSmall { notional: u64, min: u64 },
+  #[error("notional of {quantity} at {price} is out of range")]
+  NotionalOverflow { price: Price, quantity: Qty },
+  #[error("price {price} is outside the price band {low}..={high}")]
+  PriceOutsideBand { price: Price, low: Price, high: Price },
+}
+
+// Definitions the registry refuses, e.g. from PUT /admin/v1/instruments/{symbol}
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum DefinitionError {
+  #[error("{field} must not exceed {max}")]
+  ScaleTooLarge { field: &'static str, max: u32 },
+  #[error("{0} must be greater than zero")]
+  ZeroIncrement(&'static str),
+  #[error("min_{0} must not exceed max_{0}")]
+  EmptyRange(&'static str),
+  #[error("{field} must not exceed {max} seconds")]
+  DurationTooLong { field: &'static str, max: u64 },
+}
+
+impl OrderValidationError {
+  // Stable machine readable code for API responses
+  pub fn code(&self) -> &'static str {
+    match self {
+      OrderValidationError::UnknownSymbol(_) => "UNKNOWN_SYMBOL",
+      OrderValidationError::NotTrading { .. } => "NOT_TRADING",
+      OrderValidationError::InvalidPrice(_) => "INVALID_PRICE",
+      OrderValidationError::InvalidQuantity(_) => "INVALID_QUANTITY",
+      OrderValidationError::MarketOrderInAuction(_) => "MARKET_ORDER_IN_AUCTION",
+      OrderValidationError::ZeroQuantity => "ZERO_QUANTITY",
+      OrderValidationError::QuantityTooSmall { .. } => "QUANTITY_TOO_SMALL",
+      OrderValidationError::QuantityTooLarge { .. } => "QUANTITY_TOO_LARGE",
+      OrderValidationError::InvalidLotSize { .. } => "INVALID_LOT_SIZE",
+      OrderValidationError::InvalidTickSize { .. } => "INVALID_TICK_SIZE",
+      OrderValidationError::PriceOutOfRange { .. } => "PRICE_OUT_OF_RANGE",
+      OrderValidationError::NotionalTooSmall { .. } => "NOTIONAL_TOO_SMALL",
+      OrderValidationError::NotionalOverflow { .. } => "NOTIONAL_OVERFLOW",
+      OrderValidationError::PriceOutsideBand { .. } => "PRICE_OUTSIDE_BAND",
+    }
+  }
+}
+
+impl Default for Instrument {
+  fn default() -> Self {
+    Instrument::new("")
+  }
+}
+
+impl Instrument {
+  // Permissive defaults: any positive integer price and quantity.
+  // Base and quote assets are taken from a "BASE-QUOTE" symbol.
+  pub fn new(symbol: &str) -> Self {
+    let mut assets = symbol.splitn(2, '-');
+    Instrument {
+      symbol: symbol.to_string(),
+      base_asset: assets.next().unwrap_or("").to_string(),
+      quote_asset: assets.next().unwrap_or("").to_string(),
+      price_scale: 0,
+      quantity_scale: 0,
+      tick_size: Price(1),
+      lot_size: Qty(1),
+      min_quantity: Qty(1),
+      max_quantity: Qty(u64::MAX),
+      min_notional: 0,
+      min_price: Price(1),
+      max_price: Price(u64::MAX),
+      status: TradingStatus::Trading,
+      fees: FeeSchedule::default(),
+      price_band: PriceBand::default(),
+      circuit_breaker: CircuitBreaker::default(),
+    }
+  }
+  
+  // Check a definition before the registry accepts it
+  pub fn validate_definition(&self) -> Result<(), DefinitionError> {
+    if self.price_scale > MAX_SCALE {
+      return Err(DefinitionError::ScaleTooLarge { field: "price_scale", max: MAX_SCALE });
+    }
+    if self.quantity_scale > MAX_SCALE {
+      return Err(DefinitionError::ScaleTooLarge { field: "quantity_scale", max: MAX_SCALE });
+    }
+    if self.tick_size.is_zero() {
+      return Err(DefinitionError::ZeroIncrement("tick_size"));
+    }
+    if self.lot_size.is_zero() {
+      return Err(DefinitionError::ZeroIncrement("lot_size"));
+    }
+    if self.min_price > self.max_price {
+      return Err(DefinitionError::EmptyRange("price"));
+    }
+    if self.min_quantity > self.max_quantity {
+      return Err(DefinitionError::EmptyRange("quantity"));
+    }
+    
+    let durations = [
+      ("price_band.vwap_window_secs", self.price_band.vwap_window_secs),
+      ("circuit_breaker.window_secs", self.circuit_breaker.window_secs),
+      ("circuit_breaker.halt_secs", self.circuit_breaker.halt_secs),
+      ("circuit_breaker.reopen_auction_secs", self.circuit_breaker.reopen_auction_secs),
+    ];
+    if let Some(&(field, _)) = durations.iter().find(|(_, secs)| *secs > MAX_TIMER_SECS) {
+      return Err(DefinitionError::DurationTooLong { field, max: MAX_TIMER_SECS });
+    }
+    Ok(())
+  }
+  
+  // Decimal string conversions using the instrument's scales
+  pub fn parse_price(&self, value: &str) -> Result<Price, OrderValidationError> {
+    Price::from_decimal(value, self.price_scale).map_err(OrderValidationError::InvalidPrice)
+  }
+  
+  pub fn parse_quantity(&self, value: &str) -> Result<Qty, OrderValidationError> {
+    Qty::from_decimal(value, self.quantity_scale).map_err(OrderValidationError::InvalidQuantity)
+  }
+  
+  pub fn format_price(&self, price: Price) -> String {
+    price.to_decimal(self.price_scale)
+  }
+  
+  pub fn format_quantity(&self, quantity: Qty) -> String {
+    quantity.to_decimal(self.quantity_scale)
+  }
+  
+  // Quote amounts (notionals and fees) share the price scale
+  pub fn format_amount(&self, amount: i64) -> String {
+    let formatted = decimal::format_scaled(amount.unsigned_abs(), self.price_scale);
+    if amount < 0 { format!("-{}", formatted) } else { formatted }
+  }
+  
+  // price * quantity in raw quote units (price_scale), truncated.
+  // None if the result does not fit in a u64.
+  pub fn notional(&self, price: Price, quantity: Qty) -> Option<u64> {
+    let unit = 10u128.checked_pow(self.quantity_scale)?;
+    let notional = (price.0 as u128).checked_mul(quantity.0 as u128)? / unit;
+    u64::try_from(notional).ok()
+  }
+  
+  // Fee in raw quote units, truncated toward zero. Negative values are rebates.
+  pub fn fee(&self, price: Price, quantity: Qty, is_maker: bool) -> Option<i64> {
+    let bps = if is_maker { self.fees.maker_fee_bps } else { self.fees.taker_fee_bps };
+    let fee = (self.notional(price, quantity)? as i128).checked_mul(bps as i128)? / 10_000;
+    i64::try_from(fee).ok()
+  }
+  
+  pub fn validate_order(&self, order: &Order) -> Result<(), OrderValidationError> {
+    if !self.status.accepts_orders() {
+      return Err(OrderValidationError::NotTrading { symbol: self.symbol.clone(), status: self.status.clone() });
+    }
+    // Market orders have no price to take part in the equilibrium calculation
+    if order.order_type == OrderType::Market && self.status.is_call_phase() {
+      return Err(OrderValidationError::MarketOrderInAuction(self.status.clone()));
+    }
+    
+    let quantity = order.quantity;
+    
+    if quantity.is_zero() {
+      return Err(OrderValidationError::ZeroQuantity);
+    }
+    if quantity < self.min_quantity {
+      return Err(OrderValidationError::QuantityTooSmall { quantity, min: self.min_quantity });
+    }
+    if quantity > self.max_quantity {
+      return Err(OrderValidationError::QuantityTooLarge { quantity, max: self.max_quantity });
+    }
+    if self.lot_size > Qty(1) && !quantity.0.is_multiple_of(self.lot_size.0) {
+      return Err(OrderValidationError::InvalidLotSize { quantity, lot_size: self.lot_size });
+    }
+    
+    // Market orders carry no price, so price constraints only apply to limit orders
+    if order.order_type == OrderType::Limit {
+      let price = order.price;
+      
+      if price < self.min_price || price > self.max_price {
+        return Err(OrderValidationError::PriceOutOfRange { price, min: self.min_price, max: self.max_price });
+      }
+      if self.tick_size > Price(1) && !price.0.is_multiple_of(self.tick_size.0) {
+        return Err(OrderValidationError::InvalidTickSize { price, tick_size: self.tick_size });
+      }
+      
+      let notional = self.notional(price, quantity)
+        .ok_or(OrderValidationError::NotionalOverflow { price, quantity })?;
+      if notional < self.min_notional {
+        return Err(OrderValidationError::NotionalTooSmall { notional, min: self.min_notional });
+      }
+    }
+    
+    Ok(())
+  }
+}
+
+// Instrument definitions shared by the order manager and the matching engine
+pub struct InstrumentRegistry {
+  instruments: RwLock<HashMap<String, Instrument>>,
+}
+
+impl Default for InstrumentRegistry {
+  fn default() -> Self {
+    InstrumentRegistry::new()
+  }
+}
+
+impl InstrumentRegistry {
+  pub fn new() -> Self {
+    InstrumentRegistry {
+      instruments: RwLock::new(HashMap::new()),
+    }
+  }
+  
+  pub fn insert(&self, instrument: Instrument) {
+    let mut instruments = self.instruments.write().unwrap();
+    instruments.insert(instrument.symbol.clone(), instrument);
+  }
+  
+  // Load instrument definitions from a JSON file containing an array of instruments
+  pub fn load_from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)
+      .map_err(|e| anyhow::anyhow!("failed to read {}: {}", path.display(), e))?;
+    let instruments: Vec<Instrument> = serde_json::from_str(&content)
+      .map_err(|e| anyhow::anyhow!("invalid instrument config {}: {}", path.display(), e))?;
+    
+    let registry = InstrumentRegistry::new();
+    for instrument in instruments {
+      if instrument.symbol.is_empty() {
+        anyhow::bail!("instrument without a symbol in {}", path.display());
+      }
+      if let Err(e) = instrument.validate_definition() {
+        anyhow::bail!("invalid instrument {} in {}: {}", instrument.symbol, path.display(), e);
+      }
+      registry.insert(instrument);
+    }
+    Ok(registry)
+  }
+  
+  // Create or replace a definition without touching the trading status, which only the
+  // matching engine changes. New symbols start in the status given by the definition.
+  pub fn define(&self, mut instrument: Instrument) -> Result<Instrument, DefinitionError> {
+    instrument.validate_definition()?;
+    let mut instruments = self.instruments.write().unwrap();
+    if let Some(current) = instruments.get(&instrument.symbol) {
+      instrument.status = current.status.clone();
+    }
+    instruments.insert(instrument.symbol.clone(), instrument.clone());
+    Ok(instrument)
+  }
+  
+  pub fn get(&self, symbol: &str) -> Option<Instrument> {
+    self.instruments.read().unwrap().get(symbol).cloned()
+  }
+  
+  // Returns the previous status, or None for an unknown symbol
+  pub fn set_status(&self, symbol: &str, status: TradingStatus) -> Option<TradingStatus> {
+    let mut instruments = self.instruments.write().unwrap();
+    let instrument = instruments.get_mut(symbol)?;
+    Some(std::mem::replace(&mut instrument.status, status))
+  }
+  
+  pub fn remove(&self, symbol: &str) -> Option<Instrument> {
+    self.instruments.write().unwrap().remove(symbol)
+  }
+  
+  // All instruments ordered by symbol
+  pub fn list(&self) -> Vec<Instrument> {
+    let mut instruments: Vec<Instrument> = self.instruments.read().unwrap().values().cloned().collect();
+    instruments.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+    instruments
+  }
+  
+  // Validate against the symbol's definition. Orders for unregistered symbols are rejected.
+  pub fn validate_order(&self, order: &Order) -> Result<(), OrderValidationError> {
+    let instruments = self.instruments.read().unwrap();
+    match instruments.get(&order.symbol) {
+      Some(instrument) => instrument.validate_order(order),
+      None => Err(OrderValidationError::UnknownSymbol(order.symbol.clone())),
+    }
+  }
+}
+
+fn unknown_symbol(symbol: &str) -> warp::reply::WithStatus<warp::reply::Json> {
+  ApiError::UnknownSymbol(symbol.to_string()).reply()
+}
+
+// Reference data API
+pub fn routes(registry: Arc<InstrumentRegistry>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+  let list_registry = registry.clone();
+  let list_instruments = warp::path!("api" / "v1" / "instruments")
+    .and(warp::get())
+    .map(move || warp::reply::json(&list_registry.list()));
+  
+  let get_instrument = warp::path!("api" / "v1" / "instruments" / String)
+    .and(warp::get())
+    .map(move |symbol: String| match registry.get(&symbol) {
+      Some(instrument) => warp::reply::with_status(warp::reply::json(&instrument), StatusCode::OK),
+      None => unknown_symbol(&symbol),
+    });
+  
+  list_instruments.or(get_instrument)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::Utc;
+  use crate::models::{Side, OrderStatus};
+  
+  fn order(price: u64, quantity: u64) -> Order {
+    Order {
+      order_id: "o1".into(),
+      symbol: "SYM".into(),
+      account: String::new(),
+      price: Price(price),
+      quantity: Qty(quantity),
+      side: Side::Buy,
+      order_type: OrderType::Limit,
+      status: OrderStatus::New,
+      filled_quantity: Qty(0),
+      remain_quantity: Qty(quantity),
+      entry_time: Utc::now()
+    }
+  }
+  
+  #[test]
+  fn test_validate_order_constraints() {
+    let instrument = Instrument {
+      tick_size: Price(5),
+      lot_size: Qty(10),
+      min_quantity: Qty(10),
+      max_quantity: Qty(1000),
+      min_notional: 5000,
+      min_price: Price(100),
+      max_price: Price(10000),
+      ..Instrument::new("SYM")
+    };
+    
+    assert_eq!(instrument.validate_order(&order(500, 20)), Ok(()));
+    assert_eq!(instrument.validate_order(&order(500, 0)), Err(OrderValidationError::ZeroQuantity));
+    assert_eq!(instrument.validate_order(&order(502, 20)).unwrap_err().code(), "INVALID_TICK_SIZE");
+    assert_eq!(instrument.validate_order(&order(500, 25)).unwrap_err().code(), "INVALID_LOT_SIZE");
+    assert_eq!(instrument.validate_order(&order(500, 2000)).unwrap_err().code(), "QUANTITY_TOO_LARGE");
+    assert_eq!(instrument.validate_order(&order(50, 20)).unwrap_err().code(), "PRICE_OUT_OF_RANGE");
+    assert_eq!(instrument.validate_order(&order(100, 10)).unwrap_err().code(), "NOTIONAL_TOO_SMALL");
+  }
+  
+  #[test]
+  fn test_registry_validation() {
+    let registry = InstrumentRegistry::new();
+    assert_eq!(registry.validate_order(&order(100, 1)), Err(OrderValidationError::UnknownSymbol("SYM".into())));
+    
+    registry.insert(Instrument::new("SYM"));
+    assert_eq!(registry.validate_order(&order(100, 1)), Ok(()));
+    assert_eq!(registry.validate_order(&order(100, 0)), Err(OrderValidationError::ZeroQuantity));
+    
+    registry.insert(Instrument { status: TradingStatus::Halted, ..Instrument::new("SYM") });
+    assert_eq!(registry.validate_order(&order(100, 1)).unwrap_err().code(), "NOT_TRADING");
+  }
+  
+  #[test]
+  fn test_scaled_notional_and_fees() {
+    // KRW prices with 2 decimals, BTC quantities with 8
+    let instrument = Instrument {
+      price_scale: 2,
+      quantity_scale: 8,
+      min_notional: 500000,
+      fees: FeeSchedule { maker_fee_bps: -2, taker_fee_bps: 10 },
+      ..Instrument::new("BTC-KRW")
+    };
+    
+    let price = instrument.parse_price("50000000.5").unwrap();
+    let quantity = instrument.parse_quantity("0.001").unwrap();
+    assert_eq!(price, Price(5000000050));
+    assert_eq!(quantity, Qty(100000));
+    assert_eq!(instrument.format_price(price), "50000000.5");
+    assert_eq!(instrument.format_quantity(quantity), "0.001");
+    assert_eq!(instrument.parse_price("1.005").unwrap_err().code(), "INVALID_PRICE");
+    assert_eq!(instrument.parse_quantity("abc").unwrap_err().code(), "INVALID_QUANTITY");
+    
+    // 0.001 BTC at 50,000,000.5 KRW = 50,000.0005 KRW, truncated to the price scale
+    assert_eq!(instrument.notional(price, quantity), Some(5000000));
+    assert_eq!(instrument.fee(price, quantity, false), Some(5000));
+    assert_eq!(instrument.fee(price, quantity, true), Some(-1000));
+    assert_eq!(instrument.format_amount(-1000), "-10");
+    
+    // Below 5,000 KRW notional
+    let small = Order { price, ..order(0, 9000) };
+    assert_eq!(instrument.validate_order(&small).unwrap_err().code(), "NOTIONAL_TOO_SMALL");
+    
+    let huge = Order { price: Price(u64::MAX), quantity: Qty(u64::MAX), ..small };
+    let unscaled = Instrument::new("SYM");
+    assert_eq!(unscaled.notional(huge.price, huge.quantity), None);
+    assert_eq!(unscaled.validate_order(&huge).unwrap_err().code(), "NOTIONAL_OVERFLOW");
+  }
+}
\ No newline at end of file
@@ -1,15 +1,28 @@
-/**
+/*
 * filename : lib
 * author : HAMA
 * date: 2025. 4. 10.
 * description: 
 **/
 
+// 크레이트 이름(xTraderz)은 snake case가 아님
+#![allow(non_snake_case)]
+
 pub mod models;
+pub mod instrument;
+pub mod auction;
+pub mod circuit_breaker;
 pub mod matching_engine;
 pub mod sequencer;
 pub mod order_manager;
+pub mod risk;
+pub mod throttle;
+pub mod error;
+pub mod admin;
+pub mod auth;
 pub mod websocket;
+pub mod fix;
+pub mod binary;
 pub mod market_data_publisher;
 pub mod util;
 
@@ -1,107 +1,300 @@
-/**
+/*
 * filename : main
 * author : HAMA
 * date: 2025. 4. 10.
 * description: HTTP API 서버 구현
 **/
 
-mod models;
-mod matching_engine;
-mod sequencer;
-mod order_manager;
-mod websocket;
-mod market_data_publisher;
-mod util;
+// 크레이트 이름(xTraderz)은 snake case가 아님
+#![allow(non_snake_case)]
 
 use warp::Filter;
 use tokio::sync::mpsc;
 use std::sync::Arc;
-use chrono::Utc;
 
-use crate::models::{OrderMessage, Execution, OrderBook, Side, OrderType, OrderStatus, Order};
-use crate::websocket::execution_push::ExecutionPushManager;
-use crate::market_data_publisher::publisher::MarketDataPublisher;
+use xTraderz::{admin, error, instrument, market_data_publisher, order_manager, sequencer, websocket};
+use xTraderz::websocket::execution_push::ExecutionPushManager;
+use xTraderz::websocket::public_trades::PublicTradeManager;
+use xTraderz::websocket::market_stream::MarketStreamHub;
+use xTraderz::websocket::orderbook_relay::OrderBookRelayManager;
+use xTraderz::websocket::l3_feed::L3FeedManager;
+use xTraderz::market_data_publisher::publisher::MarketDataPublisher;
+use xTraderz::market_data_publisher::models::MarketDataEvent;
+use xTraderz::instrument::InstrumentRegistry;
+use xTraderz::admin::{AdminState, AuditLog};
+use xTraderz::auth::AccountRegistry;
+use xTraderz::websocket::session::SessionManager;
+use xTraderz::fix::gateway::FixGateway;
+use xTraderz::binary::gateway::BinaryGateway;
+use xTraderz::risk::RiskManager;
+use xTraderz::throttle::{RateLimit, Throttle, ThrottleConfig};
+use xTraderz::sequencer::SequencerOutputs;
+use xTraderz::order_manager::{OrderApi, OrderStore};
 
 #[tokio::main]
 async fn main() {
   // 기본 채널 생성 - 주문 처리 흐름
   let (order_tx, order_rx) = mpsc::channel(100);
   let (exec_tx, mut exec_rx) = mpsc::channel(100);
+  let (md_tx, mut md_rx) = mpsc::channel(100);
+  let (order_event_tx, mut order_event_rx) = mpsc::channel(100);
+  let (outcome_tx, mut outcome_rx) = mpsc::channel(100);
   
-  // WebSocket 관리자 생성 (실시간 체결 정보 푸시)
-  let execution_push_manager = Arc::new(ExecutionPushManager::new());
+  // 종목 정의 로드 (호가 단위, 수량 단위 등 주문 제약 조건)
+  let instruments_path = std::env::var("INSTRUMENTS_CONFIG")
+    .unwrap_or_else(|_| "config/instruments.json".to_string());
+  let instruments = match InstrumentRegistry::load_from_file(&instruments_path) {
+    Ok(registry) => Arc::new(registry),
+    Err(e) => {
+      eprintln!("종목 설정을 불러오지 못했습니다: {}", e);
+      Arc::new(InstrumentRegistry::new())
+    }
+  };
+  let instruments_for_engine = instruments.clone();
+  
+  // 시장 데이터 발행자 (MDP) 생성 (호가 묶음은 종목 호가 단위 기준)
+  let market_data_publisher = Arc::new(MarketDataPublisher::with_instruments(instruments.clone()));
+  
+  // 거래 계정 API 키 로드 (REST 주문, WebSocket 거래 세션, FIX, 바이너리 주문 입력 인증용)
+  let accounts_path = std::env::var("ACCOUNTS_CONFIG")
+    .unwrap_or_else(|_| "config/accounts.json".to_string());
+  let accounts = match AccountRegistry::load_from_file(&accounts_path) {
+    Ok(registry) => Arc::new(registry),
+    Err(e) => {
+      eprintln!("거래 계정 설정을 불러오지 못했습니다 (거래 세션 비활성화): {}", e);
+      Arc::new(AccountRegistry::new())
+    }
+  };
+  let session_manager = Arc::new(SessionManager::new(accounts.clone(), order_tx.clone()));
+  
+  // 사전 리스크 관리 (REST, WebSocket 거래 세션, FIX, 바이너리 주문 입력이 공유)
+  // 계정별 한도는 관리 API로 변경, 기본값은 한도 없음
+  let risk_manager = Arc::new(RiskManager::new());
+  let risk_for_exec = risk_manager.clone();
+  
+  // API 키와 IP별 요청 속도 제한, 시퀀서 큐가 80% 이상 차면 거부 (모든 주문 입력 경로가 공유)
+  let throttle = Arc::new(Throttle::new(ThrottleConfig {
+    per_api_key: RateLimit { per_second: 50, burst: 100 },
+    per_ip: RateLimit { per_second: 100, burst: 200 },
+    ..ThrottleConfig::default()
+  }));
+  
+  // FIX 4.4 주문 입력 게이트웨이 (별도 TCP 포트)
+  let fix_port: u16 = std::env::var("FIX_PORT").ok().and_then(|port| port.parse().ok()).unwrap_or(9878);
+  let fix_comp_id = std::env::var("FIX_COMP_ID").unwrap_or_else(|_| "XTRADERZ".to_string());
+  let fix_store_dir = std::env::var("FIX_STORE_DIR").unwrap_or_else(|_| "fix_store".to_string());
+  let fix_gateway = Arc::new(FixGateway::new(&fix_comp_id, fix_store_dir, accounts.clone(), instruments.clone(), order_tx.clone(), risk_manager.clone(), throttle.clone()));
+  match tokio::net::TcpListener::bind(("127.0.0.1", fix_port)).await {
+    Ok(listener) => {
+      tokio::spawn(fix_gateway.clone().serve(listener));
+    }
+    Err(e) => eprintln!("FIX 게이트웨이를 시작하지 못했습니다: {}", e),
+  }
+  
+  // 바이너리 주문 입력 게이트웨이 (길이 접두 고정 레이아웃 메시지, 별도 TCP 포트)
+  let binary_port: u16 = std::env::var("BINARY_PORT").ok().and_then(|port| port.parse().ok()).unwrap_or(9879);
+  let binary_gateway = Arc::new(BinaryGateway::new(accounts.clone(), instruments.clone(), order_tx.clone(), risk_manager.clone(), throttle.clone()));
+  match tokio::net::TcpListener::bind(("127.0.0.1", binary_port)).await {
+    Ok(listener) => {
+      tokio::spawn(binary_gateway.clone().serve(listener));
+    }
+    Err(e) => eprintln!("바이너리 주문 입력 게이트웨이를 시작하지 못했습니다: {}", e),
+  }
+  
+  // WebSocket 관리자 생성 (실시간 체결 정보 푸시, 거래 세션 cancel-on-disconnect)
+  let execution_push_manager = Arc::new(ExecutionPushManager::new(instruments.clone(), session_manager, risk_manager.clone(), throttle.clone()));
   
-  // 시장 데이터 발행자 (MDP) 생성
-  let market_data_publisher = Arc::new(MarketDataPublisher::new());
-  let mdp_clone = market_data_publisher.clone();
+  // 구독 기반 다중화 시장 데이터 스트림 (/ws)
+  let market_stream_hub = Arc::new(MarketStreamHub::new(instruments.clone()));
+  tokio::spawn(websocket::market_stream::run_market_stream_broadcaster(market_stream_hub.clone(), market_data_publisher.clone()));
   
-  // 오더북 상태 저장소 생성
-  let orderbook_store = Arc::new(tokio::sync::Mutex::new(OrderBook::new()));
-  let orderbook_store_for_mdp = orderbook_store.clone();
+  // 체결이 없어도 24시간 이동 통계에서 오래된 체결 제거
+  tokio::spawn(market_data_publisher::publisher::run_statistics_refresh(market_data_publisher.clone()));
+  
+  // 공개 체결 채널 (주문 ID, 계정, 수수료를 제외한 익명 체결, /ws의 trades 채널에도 전송)
+  let public_trade_manager = Arc::new(PublicTradeManager::with_stream(instruments.clone(), market_stream_hub.clone()));
   
   // 시퀀서 실행 (주문 처리 파이프라인)
   tokio::spawn(async move {
-    sequencer::run(order_rx, exec_tx).await;
+    sequencer::run(order_rx, SequencerOutputs { exec_tx, md_tx, order_event_tx, outcome_tx }, instruments_for_engine).await;
+  });
+  
+  // 매칭 엔진의 주문 이벤트 전달: 오더북 중계, 시장 데이터 발행자, L3 피드가 각자 오더북 사본을 유지
+  // (REST 오더북 조회와 WebSocket 호가 델타, L3 피드의 원본)
+  let orderbook_relay_manager = Arc::new(OrderBookRelayManager::with_instruments(instruments.clone()));
+  let l3_feed_manager = Arc::new(L3FeedManager::with_instruments(instruments.clone()));
+  let relay_for_books = orderbook_relay_manager.clone();
+  let l3_for_books = l3_feed_manager.clone();
+  let mdp_for_books = market_data_publisher.clone();
+  tokio::spawn(async move {
+    while let Some(update) = order_event_rx.recv().await {
+      l3_for_books.update(&update);
+      relay_for_books.apply_book_update(&update);
+      mdp_for_books.apply_book_update(&update);
+    }
   });
   
-  // 체결 수신 및 분배
+  // 매칭 엔진 시장 데이터 이벤트 (동시호가 예상 체결가 등) 전달
+  // 최우선 호가(BBO) 변경은 /ws의 bbo 채널로 바로 전송
+  let mdp_for_events = market_data_publisher.clone();
+  let stream_for_events = market_stream_hub.clone();
+  tokio::spawn(async move {
+    while let Some(event) = md_rx.recv().await {
+      mdp_for_events.process_market_data_event(&event);
+      if let MarketDataEvent::Bbo(bbo) = &event {
+        if let Some(data) = mdp_for_events.get_bbo(&bbo.symbol) {
+          websocket::market_stream::publish_bbo(&stream_for_events, &data);
+        }
+      }
+    }
+  });
+  
+  // REST로 입력한 주문 (클라이언트 주문 ID 중복 확인, 매칭 엔진의 거부 사유 조회)
+  let rest_orders = Arc::new(std::sync::Mutex::new(OrderStore::new()));
+  let rest_orders_for_exec = rest_orders.clone();
+  
+  // 체결 및 주문 결과(거부 등) 수신 및 분배
+  // 시퀀서가 보낸 순서대로 처리하도록 대기 중인 체결을 먼저 처리
   let exec_push_manager_clone = execution_push_manager.clone();
+  let public_trades_for_exec = public_trade_manager.clone();
+  let fix_gateway_for_exec = fix_gateway.clone();
+  let binary_gateway_for_exec = binary_gateway.clone();
   let mdp_for_exec = market_data_publisher.clone();
+  let exec_store = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+  let exec_store_for_exec = exec_store.clone();
   tokio::spawn(async move {
-    while let Some(exec) = exec_rx.recv().await {
-      println!("체결 발생: 심볼 = {}, 가격 = {}, 수량 = {}",
-               exec.symbol, exec.price, exec.quantity);
-      
-      // 1. WebSocket을 통해 체결 정보 브로드캐스트 (실시간 알림)
-      exec_push_manager_clone.broadcast_execution(&exec).await;
-      
-      // 2. 오더북 상태 업데이트 (체결에 따른 변경)
-      let mut orderbook_guard = orderbook_store.lock().await;
-      // 실제로는 체결에 따른 오더북 상태 변경 로직이 필요합니다.
-      // 예시 로직: orderbook_guard.update_after_execution(&exec);
-      
-      // 3. 시장 데이터 발행자에 오더북 상태 및 체결 정보 전달
-      let orderbook_clone = orderbook_guard.clone();
-      let symbol = exec.symbol.clone();
-      
-      // 락을 해제한 후 MDP 호출
-      drop(orderbook_guard);
-      
-      // 오더북 상태 업데이트
-      mdp_for_exec.update_orderbook(&symbol, orderbook_clone);
-      
-      // 체결 정보 처리 (캔들스틱, 시장 통계 등 업데이트)
-      mdp_for_exec.process_execution(&exec);
+    loop {
+      tokio::select! {
+        biased;
+        exec = exec_rx.recv() => {
+          let Some(exec) = exec else { break };
+          // 1. WebSocket을 통해 체결 정보 전송 (계정별 실시간 알림, 익명 공개 체결)
+          exec_push_manager_clone.broadcast_execution(&exec).await;
+          public_trades_for_exec.publish(&exec);
+          fix_gateway_for_exec.on_execution(&exec).await;
+          binary_gateway_for_exec.on_execution(&exec).await;
+          risk_for_exec.on_execution(&exec);
+          rest_orders_for_exec.lock().unwrap().on_execution(&exec);
+          order_manager::store_execution(&mut *exec_store_for_exec.lock().await, exec.clone());
+          
+          // 2. 체결 정보 처리 (캔들스틱, 시장 통계 등 업데이트)
+          mdp_for_exec.process_execution(&exec);
+        }
+        Some(outcome) = outcome_rx.recv() => {
+          // 매칭 엔진의 거부 등을 주문을 입력한 경로(REST, WebSocket, FIX, 바이너리)로 전달
+          // 거부, 취소(일괄 취소, 시장가 잔량 포함)된 주문은 리스크 미체결 주문에서 제외
+          risk_for_exec.on_outcome(&outcome);
+          rest_orders_for_exec.lock().unwrap().on_outcome(&outcome);
+          exec_push_manager_clone.broadcast_outcome(&outcome).await;
+          fix_gateway_for_exec.on_outcome(&outcome).await;
+          binary_gateway_for_exec.on_outcome(&outcome).await;
+        }
+      }
     }
   });
   
   // REST API 라우트 - 주문 관리자
-  let api_routes = order_manager::routes(order_tx.clone(), Arc::new(tokio::sync::Mutex::new(Vec::new())));
+  // 주문 계정은 API 키(X-API-Key)로 인증, 키가 없거나 유효하지 않으면 401 응답
+  // 속도 제한을 넘으면 429, 시퀀서 큐가 밀리면 503 응답
+  let api_routes = order_manager::routes(OrderApi {
+    risk: risk_manager.clone(),
+    throttle,
+    orders: rest_orders,
+    ..OrderApi::new(order_tx.clone(), exec_store, instruments.clone(), accounts.clone())
+  });
   
   // WebSocket 라우트 - 체결 정보
   let ws_exec_routes = websocket::execution_push::ws_execution_route(
     execution_push_manager.clone()
   );
   
+  // WebSocket 라우트 - 공개 체결
+  let ws_trade_routes = websocket::public_trades::ws_trades_route(public_trade_manager);
+  
+  // WebSocket 라우트 - 오더북 델타 및 REST 스냅샷
+  let ws_orderbook_routes = websocket::orderbook_relay::ws_orderbook_route(orderbook_relay_manager.clone());
+  let orderbook_snapshot_routes = websocket::orderbook_relay::routes(orderbook_relay_manager);
+  
+  // WebSocket 라우트 - 개별 주문 단위(L3) 피드 및 REST 스냅샷
+  let ws_l3_routes = websocket::l3_feed::ws_l3_route(l3_feed_manager.clone());
+  let l3_snapshot_routes = websocket::l3_feed::routes(l3_feed_manager);
+  
+  // WebSocket 라우트 - 다중화 시장 데이터 구독
+  let ws_stream_routes = websocket::market_stream::ws_stream_route(market_stream_hub);
+  
   // 시장 데이터 API 라우트
   let market_data_routes = market_data_publisher.routes();
   
+  // 종목 참조 데이터 API 라우트
+  let instrument_routes = instrument::routes(instruments.clone());
+  
+  // 관리자 API (거래 정지/재개, 거래 세션 상태 변경, 일괄 취소, 종목 및 리스크 한도 관리)
+  // ADMIN_TOKEN이 없으면 관리자 API를 제공하지 않습니다 (등록되지 않은 경로와 같이 404)
+  let admin_token = std::env::var("ADMIN_TOKEN").ok().filter(|token| !token.is_empty());
+  if admin_token.is_none() {
+    eprintln!("ADMIN_TOKEN이 설정되지 않아 관리자 API가 비활성화됩니다");
+  }
+  let audit_path = std::env::var("ADMIN_AUDIT_LOG").unwrap_or_else(|_| "admin_audit.log".to_string());
+  let admin_state = Arc::new(AdminState::new(
+    admin_token,
+    order_tx.clone(),
+    instruments.clone(),
+    risk_manager,
+    Arc::new(AuditLog::with_file(audit_path))
+  ));
+  let admin_routes = admin::routes(admin_state);
+  
   // 모든 라우트 합치기
   let routes = api_routes
     .or(ws_exec_routes)
+    .or(ws_trade_routes)
+    .or(ws_stream_routes)
+    .or(ws_orderbook_routes)
+    .or(orderbook_snapshot_routes)
+    .or(ws_l3_routes)
+    .or(l3_snapshot_routes)
     .or(market_data_routes)
+    .or(instrument_routes)
+    .or(admin_routes)
+    .recover(error::handle_unmatched)
     .with(warp::cors().allow_any_origin());
   
   // 서버 시작
   println!("주문 매칭 엔진이 http://127.0.0.1:3030 에서 시작합니다");
   println!("API 엔드포인트:");
   println!("  - 주문 생성/취소: POST /v1/order, POST /v1/order/cancel");
+  println!("  - 주문 조회: GET /v1/order?order_id=... 또는 ?account=...&client_order_id=...");
+  println!("  - 시퀀서 큐 지표: GET /v1/metrics");
   println!("  - 체결 WebSocket: ws://127.0.0.1:3030/ws/executions");
+  println!("      거래 세션: ?api_key=...&cancel_on_disconnect=true&heartbeat_secs=30");
+  println!("      거래 세션 주문 입력: new, cancel, amend, batch");
+  println!("      계정 인증(api_key 또는 login 메시지) 후 자기 계정의 체결만 수신");
+  println!("  - 공개 체결 WebSocket: ws://127.0.0.1:3030/ws/trades?symbol=...");
+  println!("  - 오더북 델타 WebSocket: ws://127.0.0.1:3030/ws/orderbook/{{symbol}}");
+  println!("  - L3 주문 단위 WebSocket: ws://127.0.0.1:3030/ws/l3/{{symbol}}");
+  println!("  - 시장 데이터 구독 WebSocket: ws://127.0.0.1:3030/ws");
+  println!("      채널: trades:{{symbol}}, book:{{symbol}}:{{depth}}[:{{group}}], ticker:{{symbol}}, kline:{{symbol}}:{{interval}}, bbo:{{symbol}}");
+  println!("  - FIX 4.4 주문 입력: tcp://127.0.0.1:{} (TargetCompID {})", fix_port, fix_comp_id);
+  println!("  - 바이너리 주문 입력: tcp://127.0.0.1:{}", binary_port);
   println!("  - 시장 데이터 API: ");
-  println!("      GET /api/v1/orderbook/{symbol}");
-  println!("      GET /api/v1/executions/{symbol}");
-  println!("      GET /api/v1/statistics/{symbol}");
-  println!("      GET /api/v1/klines/{symbol}/{interval}");
+  println!("      GET /api/v1/orderbook/{{symbol}}?depth=...&group=...");
+  println!("      GET /api/v1/orderbook/{{symbol}}/snapshot");
+  println!("      GET /api/v1/l3/{{symbol}}");
+  println!("      GET /api/v1/executions/{{symbol}}");
+  println!("      GET /api/v1/statistics/{{symbol}}");
+  println!("      GET /api/v1/klines/{{symbol}}/{{interval}}");
+  println!("      GET /api/v1/auction/{{symbol}}");
+  println!("      GET /api/v1/ticker/bookTicker/{{symbol}}");
+  println!("  - 종목 참조 데이터 API: ");
+  println!("      GET /api/v1/instruments");
+  println!("      GET /api/v1/instruments/{{symbol}}");
+  println!("  - 종목 관리 API: PUT/DELETE /admin/v1/instruments/{{symbol}}");
+  println!("  - 거래 세션 변경: PUT /admin/v1/instruments/{{symbol}}/status");
+  println!("  - 리스크 한도 관리: GET/PUT /admin/v1/risk/limits, GET/PUT/DELETE /admin/v1/risk/limits/{{account}}");
+  println!("  - 관리자 API (Authorization: Bearer <ADMIN_TOKEN>): ");
+  println!("      POST /admin/v1/halt, POST /admin/v1/halt/{{symbol}}");
+  println!("      POST /admin/v1/resume, POST /admin/v1/resume/{{symbol}}");
+  println!("      POST /admin/v1/mass-cancel, GET /admin/v1/audit");
   
   warp::serve(routes).run(([127, 0, 0, 1], 3030)).await;
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-/**
+/*
 * filename : candlestick
 * author : HAMA
 * date: 2025. 5. 1.
@@ -7,16 +7,60 @@
 
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use chrono::{DateTime, Duration, Utc};
-use crate::models::Execution;
+use chrono::{DateTime, Duration, Utc, TimeZone};
+use crate::instrument::Instrument;
+use crate::models::{Execution, Price, Qty};
 use super::models::{Candle, CandleInterval, CircularBuffer};
 
+// 심볼 -> 간격 -> 값
+type ByInterval<T> = HashMap<String, HashMap<CandleInterval, T>>;
+
+// 정수 단위로 집계 중인 캔들 (조회할 때 종목 소수 자릿수를 적용)
+#[derive(Clone, Debug)]
+struct CandleState {
+  symbol: String,
+  open_time: DateTime<Utc>,
+  close_time: DateTime<Utc>,
+  interval: CandleInterval,
+  open: Price,
+  high: Price,
+  low: Price,
+  close: Price,
+  volume: Qty,
+  trade_count: usize,
+}
+
+impl CandleState {
+  fn to_candle(&self, instrument: &Instrument) -> Candle {
+    Candle {
+      symbol: self.symbol.clone(),
+      open_time: self.open_time,
+      close_time: self.close_time,
+      interval: self.interval,
+      open: instrument.format_price(self.open),
+      high: instrument.format_price(self.high),
+      low: instrument.format_price(self.low),
+      close: instrument.format_price(self.close),
+      volume: instrument.format_quantity(self.volume),
+      trade_count: self.trade_count,
+    }
+  }
+}
+
 // 봉차트 관리자
 pub struct CandlestickManager {
   // 심볼 -> 간격 -> 캔들 버퍼
-  candles: Arc<Mutex<HashMap<String, HashMap<CandleInterval, CircularBuffer<Candle>>>>>,
+  candles: Arc<Mutex<ByInterval<CircularBuffer<CandleState>>>>,
   // 심볼 -> 간격 -> 현재 진행 중인 캔들
-  current_candles: Arc<Mutex<HashMap<String, HashMap<CandleInterval, Candle>>>>,
+  current_candles: Arc<Mutex<ByInterval<CandleState>>>,
+  // 마지막으로 반영한 체결의 trade_id (한 매칭의 양쪽 체결 중복 방지)
+  last_trade_id: Mutex<String>,
+}
+
+impl Default for CandlestickManager {
+  fn default() -> Self {
+    CandlestickManager::new()
+  }
 }
 
 impl CandlestickManager {
@@ -25,11 +69,24 @@ impl CandlestickManager {
     CandlestickManager {
       candles: Arc::new(Mutex::new(HashMap::new())),
       current_candles: Arc::new(Mutex::new(HashMap::new())),
+      last_trade_id: Mutex::new(String::new()),
     }
   }
   
   // 체결 처리
+  // 한 번의 매칭에서 나오는 양쪽 체결 중 메이커가 아닌 첫 체결만 반영합니다
   pub fn process_execution(&self, execution: &Execution) {
+    if execution.is_maker {
+      return;
+    }
+    {
+      let mut last_trade_id = self.last_trade_id.lock().unwrap();
+      if !execution.trade_id.is_empty() && *last_trade_id == execution.trade_id {
+        return;
+      }
+      last_trade_id.clone_from(&execution.trade_id);
+    }
+    
     let timestamp = execution.transaction_time;
     let symbol = &execution.symbol;
     let price = execution.price;
@@ -56,7 +113,7 @@ impl CandlestickManager {
   }
   
   // 캔들 업데이트
-  fn update_candle(&self, symbol: &str, timestamp: DateTime<Utc>, price: u64, volume: u64, interval: CandleInterval) {
+  fn update_candle(&self, symbol: &str, timestamp: DateTime<Utc>, price: Price, volume: Qty, interval: CandleInterval) {
     let interval_seconds = interval.to_seconds();
     
     // 현재 캔들의 시작 시간 계산
@@ -68,7 +125,7 @@ impl CandlestickManager {
     // 심볼에 대한 현재 캔들 맵 가져오기
     let symbol_candles = current_candles
       .entry(symbol.to_string())
-      .or_insert_with(HashMap::new);
+      .or_default();
     
     // 현재 진행 중인 캔들 가져오기
     if let Some(candle) = symbol_candles.get_mut(&interval) {
@@ -80,7 +137,7 @@ impl CandlestickManager {
         // 종가 업데이트
         candle.close = price;
         // 거래량 업데이트
-        candle.volume += volume;
+        candle.volume = candle.volume.saturating_add(volume);
         // 거래 횟수 증가
         candle.trade_count += 1;
       } else {
@@ -102,7 +159,7 @@ impl CandlestickManager {
   fn calculate_candle_start_time(&self, timestamp: DateTime<Utc>, interval_seconds: i64) -> DateTime<Utc> {
     let seconds_since_epoch = timestamp.timestamp();
     let candle_start_seconds = (seconds_since_epoch / interval_seconds) * interval_seconds;
-    Utc.timestamp(candle_start_seconds, 0)
+    Utc.timestamp_opt(candle_start_seconds, 0).unwrap()
   }
   
   // 새 캔들 생성
@@ -112,10 +169,10 @@ impl CandlestickManager {
     start_time: DateTime<Utc>,
     end_time: DateTime<Utc>,
     interval: CandleInterval,
-    price: u64,
-    volume: u64
-  ) -> Candle {
-    Candle {
+    price: Price,
+    volume: Qty
+  ) -> CandleState {
+    CandleState {
       symbol: symbol.to_string(),
       open_time: start_time,
       close_time: end_time,
@@ -130,17 +187,17 @@ impl CandlestickManager {
   }
   
   // 완료된 캔들 저장
-  fn store_completed_candle(&self, candle: Candle) {
+  fn store_completed_candle(&self, candle: CandleState) {
     let mut candles = self.candles.lock().unwrap();
     
     // 심볼에 대한 캔들 맵 가져오기
     let symbol_candles = candles
       .entry(candle.symbol.clone())
-      .or_insert_with(HashMap::new);
+      .or_default();
     
     // 간격에 대한 캔들 버퍼 가져오기
     let candle_buffer = symbol_candles
-      .entry(candle.interval.clone())
+      .entry(candle.interval)
       .or_insert_with(|| CircularBuffer::new(self.get_buffer_capacity(&candle.interval)));
     
     // 캔들 저장
@@ -161,20 +218,21 @@ impl CandlestickManager {
     }
   }
   
-  // 캔들 데이터 가져오기
-  pub fn get_candles(&self, symbol: &str, interval: CandleInterval, limit: Option<usize>) -> Vec<Candle> {
+  // 캔들 데이터 가져오기 (가격과 거래량은 종목 소수 자릿수를 적용)
+  pub fn get_candles(&self, instrument: &Instrument, interval: CandleInterval, limit: Option<usize>) -> Vec<Candle> {
     let candles = self.candles.lock().unwrap();
     
     // 심볼에 대한 캔들 맵 확인
-    if let Some(symbol_candles) = candles.get(symbol) {
+    if let Some(symbol_candles) = candles.get(&instrument.symbol) {
       // 간격에 대한 캔들 버퍼 확인
       if let Some(candle_buffer) = symbol_candles.get(&interval) {
         // 제한이 있으면 최근 N개만 반환, 없으면 전체 반환
-        if let Some(count) = limit {
+        let states = if let Some(count) = limit {
           candle_buffer.get_recent(count)
         } else {
           candle_buffer.get_all()
-        }
+        };
+        states.iter().map(|state| state.to_candle(instrument)).collect()
       } else {
         Vec::new()
       }
@@ -184,11 +242,11 @@ impl CandlestickManager {
   }
   
   // 현재 진행 중인 캔들 가져오기
-  pub fn get_current_candle(&self, symbol: &str, interval: CandleInterval) -> Option<Candle> {
+  pub fn get_current_candle(&self, instrument: &Instrument, interval: CandleInterval) -> Option<Candle> {
     let current_candles = self.current_candles.lock().unwrap();
     
-    current_candles.get(symbol)
+    current_candles.get(&instrument.symbol)
       .and_then(|symbol_candles| symbol_candles.get(&interval))
-      .cloned()
+      .map(|state| state.to_candle(instrument))
   }
 }
\ No newline at end of file
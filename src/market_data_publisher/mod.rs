@@ -1,4 +1,4 @@
-/**
+/*
 * filename : mod
 * author : HAMA
 * date: 2025. 4. 30.
@@ -7,7 +7,5 @@
 
 pub mod publisher;
 pub mod candlestick;
+pub mod statistics;
 pub mod models;
-mod models;
-mod candlestick;
-mod publisher;
\ No newline at end of file
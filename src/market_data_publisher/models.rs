@@ -1,4 +1,4 @@
-/**
+/*
 * filename : models
 * author : HAMA
 * date: 2025. 5. 1.
@@ -6,9 +6,11 @@
 **/
 
 use serde::{Serialize, Deserialize};
+use std::fmt;
 use chrono::{DateTime, Utc};
 use std::collections::VecDeque;
-use std::sync::Mutex;
+use crate::instrument::Instrument;
+use crate::models::{Price, Qty};
 
 // 시장 데이터 유형
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -19,6 +21,9 @@ pub enum MarketDataType {
   Statistics,  // 시장 통계 데이터
 }
 
+// API로 공개하는 시장 데이터의 가격, 수량, 금액은 종목 소수 자릿수를 적용한 10진수 문자열
+// 매칭 엔진 이벤트(BestBidOffer, IndicativeAuction)는 정수 단위이며 공개할 때 변환합니다
+
 // 호가창 데이터
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct OrderBookData {
@@ -31,9 +36,45 @@ pub struct OrderBookData {
 // 가격 레벨
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PriceLevel {
-  pub price: u64,
-  pub volume: u64,
+  pub price: String,
+  pub volume: String,
   pub order_count: usize,
+  #[serde(default)]
+  pub cumulative_volume: String, // 최우선 호가부터 이 레벨까지의 누적 수량 (뎁스 차트용)
+}
+
+// 호가 조회 깊이 (5, 10, 20, 50, 100 레벨 또는 전체)
+pub const BOOK_DEPTHS: [usize; 5] = [5, 10, 20, 50, 100];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BookDepth {
+  Levels(usize),
+  Full,
+}
+
+impl BookDepth {
+  pub fn parse(s: &str) -> Option<Self> {
+    match s {
+      "full" => Some(BookDepth::Full),
+      _ => s.parse::<usize>().ok().filter(|depth| BOOK_DEPTHS.contains(depth)).map(BookDepth::Levels),
+    }
+  }
+  
+  pub fn limit(&self) -> usize {
+    match self {
+      BookDepth::Levels(depth) => *depth,
+      BookDepth::Full => usize::MAX,
+    }
+  }
+}
+
+impl std::fmt::Display for BookDepth {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      BookDepth::Levels(depth) => write!(f, "{}", depth),
+      BookDepth::Full => write!(f, "full"),
+    }
+  }
 }
 
 // 체결 데이터
@@ -41,8 +82,8 @@ pub struct PriceLevel {
 pub struct ExecutionData {
   pub symbol: String,
   pub timestamp: DateTime<Utc>,
-  pub price: u64,
-  pub volume: u64,
+  pub price: String,
+  pub volume: String,
   pub side: String,            // "Buy" 또는 "Sell"
   pub is_market_maker: bool,   // 메이커 여부
 }
@@ -54,30 +95,38 @@ pub struct Candle {
   pub open_time: DateTime<Utc>,
   pub close_time: DateTime<Utc>,
   pub interval: CandleInterval,
-  pub open: u64,
-  pub high: u64,
-  pub low: u64,
-  pub close: u64,
-  pub volume: u64,
+  pub open: String,
+  pub high: String,
+  pub low: String,
+  pub close: String,
+  pub volume: String,
   pub trade_count: usize,
 }
 
-// 봉차트 간격
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+// 봉차트 간격 (JSON에서는 "1m", "1h" 등 API 경로와 같은 이름)
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum CandleInterval {
+  #[serde(rename = "1m")]
   Minute1,
+  #[serde(rename = "5m")]
   Minute5,
+  #[serde(rename = "15m")]
   Minute15,
+  #[serde(rename = "30m")]
   Minute30,
+  #[serde(rename = "1h")]
   Hour1,
+  #[serde(rename = "4h")]
   Hour4,
+  #[serde(rename = "1d")]
   Day1,
+  #[serde(rename = "1w")]
   Week1,
 }
 
 impl CandleInterval {
   // 간격을 초 단위로 변환
-  pub fn to_seconds(&self) -> i64 {
+  pub fn to_seconds(self) -> i64 {
     match self {
       CandleInterval::Minute1 => 60,
       CandleInterval::Minute5 => 300,
@@ -90,20 +139,6 @@ impl CandleInterval {
     }
   }
   
-  // 문자열로 변환
-  pub fn to_string(&self) -> String {
-    match self {
-      CandleInterval::Minute1 => "1m".to_string(),
-      CandleInterval::Minute5 => "5m".to_string(),
-      CandleInterval::Minute15 => "15m".to_string(),
-      CandleInterval::Minute30 => "30m".to_string(),
-      CandleInterval::Hour1 => "1h".to_string(),
-      CandleInterval::Hour4 => "4h".to_string(),
-      CandleInterval::Day1 => "1d".to_string(),
-      CandleInterval::Week1 => "1w".to_string(),
-    }
-  }
-  
   // 문자열에서 변환
   pub fn from_string(s: &str) -> Option<Self> {
     match s {
@@ -120,19 +155,123 @@ impl CandleInterval {
   }
 }
 
+// 문자열로 변환
+impl fmt::Display for CandleInterval {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match self {
+      CandleInterval::Minute1 => "1m",
+      CandleInterval::Minute5 => "5m",
+      CandleInterval::Minute15 => "15m",
+      CandleInterval::Minute30 => "30m",
+      CandleInterval::Hour1 => "1h",
+      CandleInterval::Hour4 => "4h",
+      CandleInterval::Day1 => "1d",
+      CandleInterval::Week1 => "1w",
+    };
+    f.write_str(name)
+  }
+}
+
 // 시장 통계 데이터
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MarketStatistics {
   pub symbol: String,
   pub timestamp: DateTime<Utc>,
-  pub open_price_24h: u64,     // 24시간 시작가
-  pub high_price_24h: u64,     // 24시간 최고가
-  pub low_price_24h: u64,      // 24시간 최저가
-  pub last_price: u64,         // 최근 거래가
-  pub volume_24h: u64,         // 24시간 거래량
-  pub price_change_24h: f64,   // 24시간 가격 변화율
-  pub bid_price: u64,          // 최고 매수가
-  pub ask_price: u64,          // 최저 매도가
+  pub open_price_24h: String,   // 24시간 전 가격 (그 이전 체결이 없으면 기간 내 첫 체결가)
+  pub high_price_24h: String,   // 24시간 최고가
+  pub low_price_24h: String,    // 24시간 최저가
+  pub last_price: String,       // 최근 거래가
+  pub volume_24h: String,       // 24시간 거래량
+  pub quote_volume_24h: String, // 24시간 거래대금 (호가 통화)
+  pub trade_count_24h: u64,     // 24시간 체결 건수
+  pub vwap_24h: String,         // 24시간 거래량 가중 평균가
+  pub price_change_24h: String, // 24시간 가격 변화 (하락이면 음수)
+  pub price_change_bps_24h: i64, // 24시간 가격 변화율 (bp, 1bp = 0.01%)
+  pub bid_price: String,        // 최고 매수가
+  pub ask_price: String,        // 최저 매도가
+}
+
+// 단일가 매매 예상 체결 정보 (동시호가 중 공개)
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IndicativeAuction {
+  pub symbol: String,
+  pub timestamp: DateTime<Utc>,
+  pub price: Option<u64>,             // 예상 체결가 (호가가 교차하지 않으면 None)
+  pub volume: u64,                    // 예상 체결량
+  pub imbalance: u64,                 // 예상 체결가에서 체결되지 않는 잔량
+  pub imbalance_side: Option<String>, // 잔량이 남는 쪽 ("Buy" 또는 "Sell")
+}
+
+// 공개용 예상 체결 정보
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IndicativeAuctionData {
+  pub symbol: String,
+  pub timestamp: DateTime<Utc>,
+  pub price: Option<String>,
+  pub volume: String,
+  pub imbalance: String,
+  pub imbalance_side: Option<String>,
+}
+
+impl IndicativeAuction {
+  pub fn to_data(&self, instrument: &Instrument) -> IndicativeAuctionData {
+    IndicativeAuctionData {
+      symbol: self.symbol.clone(),
+      timestamp: self.timestamp,
+      price: self.price.map(|price| instrument.format_price(Price(price))),
+      volume: instrument.format_quantity(Qty(self.volume)),
+      imbalance: instrument.format_quantity(Qty(self.imbalance)),
+      imbalance_side: self.imbalance_side.clone(),
+    }
+  }
+}
+
+// 최우선 매수/매도 호가 (BBO)
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BestBidOffer {
+  pub symbol: String,
+  pub timestamp: DateTime<Utc>,
+  pub bid_price: Option<u64>, // 매수 호가가 없으면 None
+  pub bid_qty: u64,
+  pub ask_price: Option<u64>, // 매도 호가가 없으면 None
+  pub ask_qty: u64,
+}
+
+// 공개용 최우선 호가
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BestBidOfferData {
+  pub symbol: String,
+  pub timestamp: DateTime<Utc>,
+  pub bid_price: Option<String>,
+  pub bid_qty: String,
+  pub ask_price: Option<String>,
+  pub ask_qty: String,
+}
+
+impl BestBidOffer {
+  // 호가가 없는 심볼의 최우선 호가
+  pub fn empty(symbol: &str) -> Self {
+    BestBidOffer { symbol: symbol.to_string(), timestamp: Utc::now(), bid_price: None, bid_qty: 0, ask_price: None, ask_qty: 0 }
+  }
+  
+  pub fn to_data(&self, instrument: &Instrument) -> BestBidOfferData {
+    BestBidOfferData {
+      symbol: self.symbol.clone(),
+      timestamp: self.timestamp,
+      bid_price: self.bid_price.map(|price| instrument.format_price(Price(price))),
+      bid_qty: instrument.format_quantity(Qty(self.bid_qty)),
+      ask_price: self.ask_price.map(|price| instrument.format_price(Price(price))),
+      ask_qty: instrument.format_quantity(Qty(self.ask_qty)),
+    }
+  }
+}
+
+// 매칭 엔진이 시장 데이터 발행자에 전달하는 이벤트
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum MarketDataEvent {
+  AuctionIndicative(IndicativeAuction), // 동시호가 중 주문 변경 시마다 갱신
+  AuctionEnded { symbol: String },      // 단일가 체결 완료 또는 동시호가 종료
+  Bbo(BestBidOffer),                    // 최우선 호가의 가격이나 수량이 바뀔 때마다 갱신
 }
 
 // 원형 버퍼 구현 (봉차트 데이터 저장용)
@@ -1,4 +1,4 @@
-/**
+/*
 * filename : publisher
 * author : HAMA
 * date: 2025. 5. 1.
@@ -7,14 +7,21 @@
 
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use tokio::time::{self, Duration};
 use warp::Filter;
-use serde::Serialize;
 use warp::http::StatusCode;
 
-use crate::models::{Order, Execution, OrderBook};
+use crate::models::{Execution, OrderBook, Price, PriceLevel as BookLevel, Qty};
+use crate::matching_engine::BookUpdate;
+use crate::instrument::{Instrument, InstrumentRegistry};
+use crate::error::{self, ApiError};
 use super::candlestick::CandlestickManager;
-use super::models::{OrderBookData, PriceLevel, ExecutionData, MarketStatistics, CandleInterval, Candle};
+use super::statistics::RollingStatistics;
+use super::models::{
+  OrderBookData, PriceLevel, BookDepth, BOOK_DEPTHS, ExecutionData, MarketStatistics, CandleInterval, Candle,
+  IndicativeAuctionData, MarketDataEvent, BestBidOffer, BestBidOfferData
+};
 
 // 시장 데이터 발행자
 pub struct MarketDataPublisher {
@@ -22,12 +29,28 @@ pub struct MarketDataPublisher {
   orderbooks: Arc<Mutex<HashMap<String, OrderBook>>>,
   // 최근 체결 내역 (심볼 -> 체결 목록)
   recent_executions: Arc<Mutex<HashMap<String, Vec<ExecutionData>>>>,
+  // 최근 체결 내역에 마지막으로 추가한 체결의 trade_id
+  last_trade_id: Mutex<String>,
   // 시장 통계 (심볼 -> 통계)
   market_statistics: Arc<Mutex<HashMap<String, MarketStatistics>>>,
+  // 시장 통계 계산용 1분 단위 체결 집계 (심볼 -> 24시간 이동 통계)
+  rolling_statistics: Arc<Mutex<HashMap<String, RollingStatistics>>>,
+  // 동시호가 중인 심볼의 예상 체결 정보
+  auctions: Arc<Mutex<HashMap<String, IndicativeAuctionData>>>,
+  // 심볼별 최우선 매수/매도 호가 (매칭 엔진이 변경 시마다 전달)
+  bbos: Arc<Mutex<HashMap<String, BestBidOfferData>>>,
   // 캔들스틱 관리자
   candlestick_manager: Arc<CandlestickManager>,
   // 최대 저장 체결 수
   max_executions: usize,
+  // 소수 자릿수와 호가 묶음 단위 계산용 종목 정보 (없으면 정수 단위, 호가 단위 1)
+  instruments: Option<Arc<InstrumentRegistry>>,
+}
+
+impl Default for MarketDataPublisher {
+  fn default() -> Self {
+    MarketDataPublisher::new()
+  }
 }
 
 impl MarketDataPublisher {
@@ -36,9 +59,22 @@ impl MarketDataPublisher {
     MarketDataPublisher {
       orderbooks: Arc::new(Mutex::new(HashMap::new())),
       recent_executions: Arc::new(Mutex::new(HashMap::new())),
+      last_trade_id: Mutex::new(String::new()),
       market_statistics: Arc::new(Mutex::new(HashMap::new())),
+      rolling_statistics: Arc::new(Mutex::new(HashMap::new())),
+      auctions: Arc::new(Mutex::new(HashMap::new())),
+      bbos: Arc::new(Mutex::new(HashMap::new())),
       candlestick_manager: Arc::new(CandlestickManager::new()),
       max_executions: 1000, // 심볼당 최대 1000개 체결 저장
+      instruments: None,
+    }
+  }
+  
+  // 종목 소수 자릿수와 호가 단위를 적용하는 시장 데이터 발행자 생성
+  pub fn with_instruments(instruments: Arc<InstrumentRegistry>) -> Self {
+    MarketDataPublisher {
+      instruments: Some(instruments),
+      ..MarketDataPublisher::new()
     }
   }
   
@@ -48,6 +84,15 @@ impl MarketDataPublisher {
     orderbooks.insert(symbol.to_string(), orderbook);
   }
   
+  // 매칭 엔진의 주문 이벤트를 오더북 사본에 적용
+  pub fn apply_book_update(&self, update: &BookUpdate) {
+    let mut orderbooks = self.orderbooks.lock().unwrap();
+    let orderbook = orderbooks.entry(update.symbol.clone()).or_default();
+    for event in &update.events {
+      event.apply(orderbook);
+    }
+  }
+  
   // 체결 처리
   pub fn process_execution(&self, execution: &Execution) {
     // 1. 최근 체결 내역에 추가
@@ -61,21 +106,34 @@ impl MarketDataPublisher {
   }
   
   // 체결 내역 추가
+  // 한 번의 매칭에서 나오는 양쪽 체결 중 메이커가 아닌 첫 체결만 추가합니다
   fn add_execution(&self, execution: &Execution) {
+    if execution.is_maker {
+      return;
+    }
+    {
+      let mut last_trade_id = self.last_trade_id.lock().unwrap();
+      if !execution.trade_id.is_empty() && *last_trade_id == execution.trade_id {
+        return;
+      }
+      last_trade_id.clone_from(&execution.trade_id);
+    }
+    
     let mut executions = self.recent_executions.lock().unwrap();
     
     let symbol_executions = executions
       .entry(execution.symbol.clone())
-      .or_insert_with(Vec::new);
+      .or_default();
     
     // 새 체결 데이터 생성
+    let instrument = instrument_for(&self.instruments, &execution.symbol);
     let execution_data = ExecutionData {
       symbol: execution.symbol.clone(),
       timestamp: execution.transaction_time,
-      price: execution.price,
-      volume: execution.quantity,
+      price: instrument.format_price(execution.price),
+      volume: instrument.format_quantity(execution.quantity),
       side: format!("{:?}", execution.side),
-      is_market_maker: false, // 기본적으로 false로 설정
+      is_market_maker: execution.is_maker,
     };
     
     // 목록 시작에 추가 (최신 체결이 먼저 오도록)
@@ -89,57 +147,91 @@ impl MarketDataPublisher {
   
   // 시장 통계 업데이트
   fn update_market_statistics(&self, execution: &Execution) {
-    let mut stats = self.market_statistics.lock().unwrap();
-    let symbol = &execution.symbol;
-    
-    let symbol_stats = stats
-      .entry(symbol.clone())
-      .or_insert_with(|| MarketStatistics {
-        symbol: symbol.clone(),
-        timestamp: Utc::now(),
-        open_price_24h: execution.price,
-        high_price_24h: execution.price,
-        low_price_24h: execution.price,
-        last_price: execution.price,
-        volume_24h: 0,
-        price_change_24h: 0.0,
-        bid_price: 0,
-        ask_price: 0,
-      });
+    let quote_volume = instrument_for(&self.instruments, &execution.symbol)
+      .notional(execution.price, execution.quantity)
+      .unwrap_or(u64::MAX);
     
-    // 24시간 초과 시 통계 초기화
-    let now = Utc::now();
-    if (now - symbol_stats.timestamp).num_seconds() > 86400 {
-      symbol_stats.timestamp = now;
-      symbol_stats.open_price_24h = execution.price;
-      symbol_stats.high_price_24h = execution.price;
-      symbol_stats.low_price_24h = execution.price;
-      symbol_stats.volume_24h = 0;
+    let mut rolling = self.rolling_statistics.lock().unwrap();
+    let symbol_rolling = rolling.entry(execution.symbol.clone()).or_default();
+    if symbol_rolling.record(execution, quote_volume) {
+      self.publish_statistics(&execution.symbol, symbol_rolling, Utc::now());
     }
-    
-    // 통계 업데이트
-    symbol_stats.high_price_24h = symbol_stats.high_price_24h.max(execution.price);
-    symbol_stats.low_price_24h = symbol_stats.low_price_24h.min(execution.price);
-    symbol_stats.last_price = execution.price;
-    symbol_stats.volume_24h += execution.quantity;
-    
-    // 가격 변화율 계산
-    if symbol_stats.open_price_24h > 0 {
-      let price_diff = execution.price as f64 - symbol_stats.open_price_24h as f64;
-      symbol_stats.price_change_24h = price_diff / symbol_stats.open_price_24h as f64 * 100.0;
+  }
+  
+  // 24시간이 지난 체결을 통계에서 제거 (체결이 없어도 주기적으로 호출)
+  pub fn refresh_statistics(&self, now: DateTime<Utc>) {
+    let mut rolling = self.rolling_statistics.lock().unwrap();
+    for (symbol, symbol_rolling) in rolling.iter_mut() {
+      if symbol_rolling.advance(now) {
+        self.publish_statistics(symbol, symbol_rolling, now);
+      }
     }
+  }
+  
+  // 이동 통계와 현재 최고 매수가, 최저 매도가로 시장 통계 갱신
+  fn publish_statistics(&self, symbol: &str, rolling: &RollingStatistics, timestamp: DateTime<Utc>) {
+    let instrument = instrument_for(&self.instruments, symbol);
+    let mut stats = empty_statistics(symbol);
+    stats.timestamp = timestamp;
+    rolling.fill(&mut stats, &instrument);
     
-    // 현재 최고 매수가, 최저 매도가 업데이트
     let orderbooks = self.orderbooks.lock().unwrap();
     if let Some(orderbook) = orderbooks.get(symbol) {
       if let Some(best_bid) = orderbook.buy_book.get_best_level() {
-        symbol_stats.bid_price = best_bid.price;
+        stats.bid_price = instrument.format_price(best_bid.price);
       }
       
       if let Some(best_ask) = orderbook.sell_book.get_best_level() {
-        symbol_stats.ask_price = best_ask.price;
+        stats.ask_price = instrument.format_price(best_ask.price);
       }
     }
+    drop(orderbooks);
+    
+    self.market_statistics.lock().unwrap().insert(symbol.to_string(), stats);
+  }
+  
+  // 매칭 엔진 이벤트 처리
+  pub fn process_market_data_event(&self, event: &MarketDataEvent) {
+    match event {
+      MarketDataEvent::AuctionIndicative(indicative) => {
+        let data = indicative.to_data(&instrument_for(&self.instruments, &indicative.symbol));
+        self.auctions.lock().unwrap().insert(indicative.symbol.clone(), data);
+      }
+      MarketDataEvent::AuctionEnded { symbol } => {
+        self.auctions.lock().unwrap().remove(symbol);
+      }
+      MarketDataEvent::Bbo(bbo) => {
+        let data = bbo.to_data(&instrument_for(&self.instruments, &bbo.symbol));
+        self.bbos.lock().unwrap().insert(bbo.symbol.clone(), data);
+      }
+    }
+  }
+  
+  // 최우선 매수/매도 호가 조회
+  pub fn get_bbo(&self, symbol: &str) -> Option<BestBidOfferData> {
+    self.bbos.lock().unwrap().get(symbol).cloned()
+  }
+  
+  // 동시호가 예상 체결 정보 조회
+  pub fn get_indicative_auction(&self, symbol: &str) -> Option<IndicativeAuctionData> {
+    self.auctions.lock().unwrap().get(symbol).cloned()
+  }
+  
+  // 깊이를 제한하고 group 호가 단위씩 가격을 묶은 오더북 조회 (WebSocket 구독용)
+  pub fn get_orderbook(&self, symbol: &str, depth: BookDepth, group: u64) -> Option<OrderBookData> {
+    let orderbooks = self.orderbooks.lock().unwrap();
+    let instrument = instrument_for(&self.instruments, symbol);
+    Some(convert_to_orderbook_data(symbol, orderbooks.get(symbol)?, depth, &instrument, group))
+  }
+  
+  // 시장 통계 조회
+  pub fn get_statistics(&self, symbol: &str) -> Option<MarketStatistics> {
+    self.market_statistics.lock().unwrap().get(symbol).cloned()
+  }
+  
+  // 현재 진행 중인 캔들 조회
+  pub fn get_current_candle(&self, symbol: &str, interval: CandleInterval) -> Option<Candle> {
+    self.candlestick_manager.get_current_candle(&instrument_for(&self.instruments, symbol), interval)
   }
   
   // HTTP API 라우트 설정
@@ -148,27 +240,36 @@ impl MarketDataPublisher {
     let executions = self.recent_executions.clone();
     let statistics = self.market_statistics.clone();
     let candlestick_manager = self.candlestick_manager.clone();
+    let auctions = self.auctions.clone();
+    let instruments = self.instruments.clone();
+    let candle_instruments = self.instruments.clone();
+    let bbo_instruments = self.instruments.clone();
+    let bbos = self.bbos.clone();
     
-    // 오더북 조회 API
+    // 오더북 조회 API (?depth=5|10|20|50|100|full&group=N)
     let get_orderbook = warp::path!("api" / "v1" / "orderbook" / String)
       .and(warp::get())
-      .map(move |symbol: String| {
+      .and(warp::query::<HashMap<String, String>>())
+      .map(move |symbol: String, params: HashMap<String, String>| {
+        let (depth, group) = match parse_book_params(&params) {
+          Ok(params) => params,
+          Err(e) => return e.reply(),
+        };
         let orderbooks = orderbooks.lock().unwrap();
         
-        if let Some(orderbook) = orderbooks.get(&symbol) {
+        let orderbook_data = if let Some(orderbook) = orderbooks.get(&symbol) {
           // 오더북 DTO로 변환
-          let orderbook_data = convert_to_orderbook_data(&symbol, orderbook);
-          warp::reply::json(&orderbook_data)
+          convert_to_orderbook_data(&symbol, orderbook, depth, &instrument_for(&instruments, &symbol), group)
         } else {
           // 심볼을 찾을 수 없는 경우
-          let empty_response = OrderBookData {
+          OrderBookData {
             symbol: symbol.clone(),
             timestamp: Utc::now(),
             bids: Vec::new(),
             asks: Vec::new(),
-          };
-          warp::reply::json(&empty_response)
-        }
+          }
+        };
+        warp::reply::with_status(warp::reply::json(&orderbook_data), StatusCode::OK)
       });
     
     // 체결 내역 조회 API
@@ -207,19 +308,7 @@ impl MarketDataPublisher {
           warp::reply::json(&stats)
         } else {
           // 심볼을 찾을 수 없는 경우
-          let empty_statistics = MarketStatistics {
-            symbol: symbol.clone(),
-            timestamp: Utc::now(),
-            open_price_24h: 0,
-            high_price_24h: 0,
-            low_price_24h: 0,
-            last_price: 0,
-            volume_24h: 0,
-            price_change_24h: 0.0,
-            bid_price: 0,
-            ask_price: 0,
-          };
-          warp::reply::json(&empty_statistics)
+          warp::reply::json(&empty_statistics(&symbol))
         }
       });
     
@@ -232,12 +321,7 @@ impl MarketDataPublisher {
         let interval = match CandleInterval::from_string(&interval_str) {
           Some(interval) => interval,
           None => {
-            return warp::reply::with_status(
-              warp::reply::json(&serde_json::json!({
-                                "error": format!("Invalid interval: {}", interval_str)
-                            })),
-              StatusCode::BAD_REQUEST
-            );
+            return ApiError::InvalidRequest(format!("invalid interval: {}", interval_str)).reply();
           }
         };
         
@@ -247,15 +331,37 @@ impl MarketDataPublisher {
           .unwrap_or(100);
         
         // 캔들스틱 데이터 가져오기
-        let candles = candlestick_manager.get_candles(&symbol, interval, Some(limit));
+        let instrument = instrument_for(&candle_instruments, &symbol);
+        let candles = candlestick_manager.get_candles(&instrument, interval, Some(limit));
         
         // 현재 진행 중인 캔들 추가
         let mut result = candles;
-        if let Some(current_candle) = candlestick_manager.get_current_candle(&symbol, interval) {
+        if let Some(current_candle) = candlestick_manager.get_current_candle(&instrument, interval) {
           result.insert(0, current_candle);
         }
         
-        warp::reply::json(&result)
+        warp::reply::with_status(warp::reply::json(&result), StatusCode::OK)
+      });
+    
+    // 동시호가 예상 체결 정보 조회 API
+    let get_auction = warp::path!("api" / "v1" / "auction" / String)
+      .and(warp::get())
+      .map(move |symbol: String| {
+        let auctions = auctions.lock().unwrap();
+        
+        match auctions.get(&symbol) {
+          Some(indicative) => warp::reply::with_status(warp::reply::json(indicative), StatusCode::OK),
+          None => ApiError::NotFound(format!("no auction in progress for {}", symbol)).reply(),
+        }
+      });
+    
+    // 최우선 호가 조회 API (호가가 없으면 가격은 null)
+    let get_book_ticker = warp::path!("api" / "v1" / "ticker" / "bookTicker" / String)
+      .and(warp::get())
+      .map(move |symbol: String| {
+        let bbo = bbos.lock().unwrap().get(&symbol).cloned()
+          .unwrap_or_else(|| BestBidOffer::empty(&symbol).to_data(&instrument_for(&bbo_instruments, &symbol)));
+        warp::reply::json(&bbo)
       });
     
     // 모든 라우트 결합
@@ -263,40 +369,109 @@ impl MarketDataPublisher {
       .or(get_executions)
       .or(get_statistics)
       .or(get_candlesticks)
+      .or(get_auction)
+      .or(get_book_ticker)
+      .recover(error::handle_rejection)
   }
 }
 
-// OrderBook을 OrderBookData로 변환하는 헬퍼 함수
-fn convert_to_orderbook_data(symbol: &str, orderbook: &OrderBook) -> OrderBookData {
-  let mut bids = Vec::new();
-  let mut asks = Vec::new();
+// 1초마다 24시간 이동 통계의 기간을 현재 시각으로 옮김
+pub async fn run_statistics_refresh(mdp: Arc<MarketDataPublisher>) {
+  let mut interval = time::interval(Duration::from_secs(1));
   
-  // 매수 호가 변환
-  for (&price, level) in &orderbook.buy_book.limits {
-    bids.push(PriceLevel {
-      price,
-      volume: level.total_volume,
-      order_count: level.orders.len(),
-    });
+  loop {
+    interval.tick().await;
+    mdp.refresh_statistics(Utc::now());
   }
-  
-  // 매도 호가 변환
-  for (&price, level) in &orderbook.sell_book.limits {
-    asks.push(PriceLevel {
-      price,
-      volume: level.total_volume,
-      order_count: level.orders.len(),
-    });
+}
+
+// 체결이 없는 심볼의 시장 통계
+fn empty_statistics(symbol: &str) -> MarketStatistics {
+  MarketStatistics {
+    symbol: symbol.to_string(),
+    timestamp: Utc::now(),
+    open_price_24h: "0".to_string(),
+    high_price_24h: "0".to_string(),
+    low_price_24h: "0".to_string(),
+    last_price: "0".to_string(),
+    volume_24h: "0".to_string(),
+    quote_volume_24h: "0".to_string(),
+    trade_count_24h: 0,
+    vwap_24h: "0".to_string(),
+    price_change_24h: "0".to_string(),
+    price_change_bps_24h: 0,
+    bid_price: "0".to_string(),
+    ask_price: "0".to_string(),
   }
-  
-  // 가격 순서대로 정렬
-  bids.sort_by(|a, b| b.price.cmp(&a.price)); // 내림차순 (최고가 먼저)
-  asks.sort_by(|a, b| a.price.cmp(&b.price)); // 오름차순 (최저가 먼저)
-  
+}
+
+// 오더북 조회 파라미터: depth (기본 전체), group (기본 1 호가 단위)
+fn parse_book_params(params: &HashMap<String, String>) -> Result<(BookDepth, u64), ApiError> {
+  let depth = match params.get("depth") {
+    Some(depth) => BookDepth::parse(depth)
+      .ok_or_else(|| ApiError::InvalidRequest(format!("depth must be one of {:?} or full", BOOK_DEPTHS)))?,
+    None => BookDepth::Full,
+  };
+  let group = match params.get("group") {
+    Some(group) => group.parse::<u64>().ok().filter(|group| *group > 0)
+      .ok_or_else(|| ApiError::InvalidRequest("group must be a positive number of ticks".to_string()))?,
+    None => 1,
+  };
+  Ok((depth, group))
+}
+
+// 심볼의 종목 정보 (등록되지 않았으면 정수 단위, 호가 단위 1)
+fn instrument_for(instruments: &Option<Arc<InstrumentRegistry>>, symbol: &str) -> Instrument {
+  instruments.as_ref()
+    .and_then(|instruments| instruments.get(symbol))
+    .unwrap_or_else(|| Instrument::new(symbol))
+}
+
+// OrderBook을 OrderBookData로 변환하는 헬퍼 함수
+// group 호가 단위씩 묶으며, 매수 호가는 묶음 가격으로 내림, 매도 호가는 올림해 묶어도 매수와 매도 가격이 겹치지 않습니다
+fn convert_to_orderbook_data(symbol: &str, orderbook: &OrderBook, depth: BookDepth, instrument: &Instrument, group: u64) -> OrderBookData {
+  let bucket = instrument.tick_size.0.max(1).saturating_mul(group);
   OrderBookData {
     symbol: symbol.to_string(),
     timestamp: Utc::now(),
-    bids,
-    asks,
+    bids: aggregate_levels(orderbook.buy_book.limits.values().rev(), depth, instrument, |price| price / bucket * bucket), // 내림차순 (최고가 먼저)
+    asks: aggregate_levels(orderbook.sell_book.limits.values(), depth, instrument, |price| price.div_ceil(bucket).saturating_mul(bucket)), // 오름차순 (최저가 먼저)
+  }
+}
+
+// 최우선 호가부터 묶음 가격별로 수량과 누적 수량을 합산
+fn aggregate_levels<'a>(
+  levels: impl Iterator<Item = &'a BookLevel>,
+  depth: BookDepth,
+  instrument: &Instrument,
+  bucket_price: impl Fn(u64) -> u64
+) -> Vec<PriceLevel> {
+  // (묶음 가격, 수량, 주문 수, 누적 수량)
+  let mut result: Vec<(u64, Qty, usize, Qty)> = Vec::new();
+  let mut cumulative_volume = Qty::ZERO;
+  
+  for level in levels {
+    let price = bucket_price(level.price.0);
+    let volume = level.total_volume;
+    cumulative_volume = cumulative_volume.saturating_add(volume);
+    match result.last_mut() {
+      Some(last) if last.0 == price => {
+        last.1 = last.1.saturating_add(volume);
+        last.2 += level.orders.len();
+        last.3 = cumulative_volume;
+      }
+      _ => {
+        if result.len() == depth.limit() {
+          break;
+        }
+        result.push((price, volume, level.orders.len(), cumulative_volume));
+      }
+    }
   }
+  result.into_iter().map(|(price, volume, order_count, cumulative_volume)| PriceLevel {
+    price: instrument.format_price(Price(price)),
+    volume: instrument.format_quantity(volume),
+    order_count,
+    cumulative_volume: instrument.format_quantity(cumulative_volume),
+  }).collect()
 }
\ No newline at end of file
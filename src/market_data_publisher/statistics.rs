@@ -0,0 +1,143 @@
+/*
+* filename : statistics
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 1분 단위 버킷으로 집계하는 24시간 이동 시장 통계
+**/
+
+use std::collections::VecDeque;
+use chrono::{DateTime, Utc};
+use crate::instrument::Instrument;
+use crate::models::{Execution, Price, Qty};
+use crate::util::decimal;
+use super::models::MarketStatistics;
+
+// 통계 기간 (분)
+pub const WINDOW_MINUTES: i64 = 24 * 60;
+
+// 1분 동안의 체결 집계
+#[derive(Clone, Debug)]
+struct MinuteBucket {
+  minute: i64, // Unix 시간 기준 분
+  open: u64,
+  high: u64,
+  low: u64,
+  close: u64,
+  volume: u64,
+  quote_volume: u64,
+  price_volume: u128, // 가격 × 수량 합계 (VWAP 계산용)
+  trade_count: u64,
+}
+
+// 심볼별 24시간 이동 통계
+// 최근 1440개의 1분 버킷만 유지하므로 체결이 없어도 시간이 지나면 오래된 체결이 빠집니다
+#[derive(Default)]
+pub struct RollingStatistics {
+  // 오래된 버킷이 앞
+  buckets: VecDeque<MinuteBucket>,
+  // 지금까지 본 가장 늦은 시각 (분)
+  current_minute: i64,
+  // 기간 밖으로 밀려난 마지막 체결가 (24시간 전 가격)
+  reference_price: Option<u64>,
+  last_price: u64,
+  last_trade_id: String,
+}
+
+impl RollingStatistics {
+  // 체결 반영 (집계했으면 true)
+  // 한 번의 매칭에서 나오는 양쪽 체결 중 메이커가 아닌 첫 체결만 집계합니다
+  pub fn record(&mut self, execution: &Execution, quote_volume: u64) -> bool {
+    if execution.is_maker || (!execution.trade_id.is_empty() && execution.trade_id == self.last_trade_id) {
+      return false;
+    }
+    self.last_trade_id = execution.trade_id.clone();
+    
+    let minute = minute_of(execution.transaction_time);
+    if minute <= self.current_minute - WINDOW_MINUTES {
+      return false;
+    }
+    let price = execution.price.0;
+    let volume = execution.quantity.0;
+    self.last_price = price;
+    
+    let index = match self.buckets.binary_search_by_key(&minute, |bucket| bucket.minute) {
+      Ok(index) => index,
+      Err(index) => {
+        self.buckets.insert(index, MinuteBucket {
+          minute,
+          open: price,
+          high: price,
+          low: price,
+          close: price,
+          volume: 0,
+          quote_volume: 0,
+          price_volume: 0,
+          trade_count: 0,
+        });
+        index
+      }
+    };
+    let bucket = &mut self.buckets[index];
+    bucket.high = bucket.high.max(price);
+    bucket.low = bucket.low.min(price);
+    bucket.close = price;
+    bucket.volume = bucket.volume.saturating_add(volume);
+    bucket.quote_volume = bucket.quote_volume.saturating_add(quote_volume);
+    bucket.price_volume += price as u128 * volume as u128;
+    bucket.trade_count += 1;
+    
+    self.advance(execution.transaction_time);
+    true
+  }
+  
+  // 기간을 now까지 옮겨 24시간이 지난 버킷 제거 (제거했으면 true)
+  pub fn advance(&mut self, now: DateTime<Utc>) -> bool {
+    self.current_minute = self.current_minute.max(minute_of(now));
+    let mut expired = false;
+    while let Some(bucket) = self.buckets.front() {
+      if bucket.minute > self.current_minute - WINDOW_MINUTES {
+        break;
+      }
+      self.reference_price = Some(bucket.close);
+      self.buckets.pop_front();
+      expired = true;
+    }
+    expired
+  }
+  
+  // 기간 내 버킷으로 통계 계산 (체결이 없으면 시가, 고가, 저가 모두 최근 거래가)
+  // 가격, 수량, 금액은 종목 소수 자릿수를 적용한 10진수 문자열
+  pub fn fill(&self, stats: &mut MarketStatistics, instrument: &Instrument) {
+    let open = self.reference_price
+      .or_else(|| self.buckets.front().map(|bucket| bucket.open))
+      .unwrap_or(self.last_price);
+    let volume = self.buckets.iter().fold(0u64, |total, bucket| total.saturating_add(bucket.volume));
+    let price_volume = self.buckets.iter().fold(0u128, |total, bucket| total.saturating_add(bucket.price_volume));
+    let price = |price: u64| instrument.format_price(Price(price));
+    
+    stats.open_price_24h = price(open);
+    stats.high_price_24h = price(self.buckets.iter().map(|bucket| bucket.high).max().unwrap_or(self.last_price));
+    stats.low_price_24h = price(self.buckets.iter().map(|bucket| bucket.low).min().unwrap_or(self.last_price));
+    stats.last_price = price(self.last_price);
+    stats.volume_24h = instrument.format_quantity(Qty(volume));
+    stats.quote_volume_24h = decimal::format_scaled(
+      self.buckets.iter().fold(0u64, |total, bucket| total.saturating_add(bucket.quote_volume)),
+      instrument.price_scale
+    );
+    stats.trade_count_24h = self.buckets.iter().map(|bucket| bucket.trade_count).sum();
+    stats.vwap_24h = price(if volume > 0 { (price_volume / volume as u128) as u64 } else { 0 });
+    
+    let price_diff = self.last_price as i128 - open as i128;
+    stats.price_change_24h = instrument.format_amount(price_diff.clamp(i64::MIN.into(), i64::MAX.into()) as i64);
+    // 하락폭은 -10000bp로 제한되므로 범위를 넘는 경우는 상승뿐
+    stats.price_change_bps_24h = if open > 0 {
+      i64::try_from(price_diff * 10_000 / open as i128).unwrap_or(i64::MAX)
+    } else {
+      0
+    };
+  }
+}
+
+fn minute_of(time: DateTime<Utc>) -> i64 {
+  time.timestamp().div_euclid(60)
+}
\ No newline at end of file
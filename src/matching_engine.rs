@@ -1,153 +1,843 @@
-/**
+/*
 * filename : matching_engine
 * author : HAMA
 * date: 2025. 4. 28.
 * description: 
 **/
 
+use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::sync::mpsc::{Receiver, Sender};
 use uuid::Uuid;
-use chrono::Utc;
-use crate::models::{Order, OrderMessage, Execution, OrderBook, Side, OrderType, OrderStatus};
-
-pub async fn run(mut order_rx: Receiver<OrderMessage>, exec_tx: Sender<Execution>) {
-  let mut book = OrderBook::new();
-  
-  while let Some(OrderMessage(mut order)) = order_rx.recv().await {
-    match order.order_type {
-      OrderType::Limit => {
-        let mut remaining = order.remain_quantity;
-        
-        match order.side {
-          Side::Buy => {
-            // Get matching levels from the opposite book
-            let levels = book.sell_book.get_levels_for_matching(order.price);
-            for price in levels {
-              if remaining == 0 { break; }
-              
-              if let Some(level) = book.sell_book.limits.get_mut(&price) {
-                // Match against orders at this level
-                while remaining > 0 && !level.is_empty() {
-                  if let Some((matched_order, matched_qty)) = level.match_order(remaining) {
-                    // Create execution record
-                    let exec = Execution {
-                      exec_id: Uuid::new_v4().to_string(),
-                      order_id: order.order_id.clone(),
-                      symbol: order.symbol.clone(),
-                      side: Side::Buy,
-                      price,
-                      quantity: matched_qty,
-                      fee: 0.0,
-                      transaction_time: Utc::now()
-                    };
-                    
-                    // Send execution
-                    exec_tx.send(exec).await.unwrap();
-                    
-                    // Update remaining quantity
-                    remaining -= matched_qty;
-                    order.filled_quantity += matched_qty;
-                    
-                    // Create execution for the matched order too
-                    let counter_exec = Execution {
-                      exec_id: Uuid::new_v4().to_string(),
-                      order_id: matched_order.order_id.clone(),
-                      symbol: matched_order.symbol.clone(),
-                      side: Side::Sell,
-                      price,
-                      quantity: matched_qty,
-                      fee: 0.0,
-                      transaction_time: Utc::now()
-                    };
-                    
-                    exec_tx.send(counter_exec).await.unwrap();
-                  }
-                }
-                
-                // Clean up empty levels
-                if level.is_empty() {
-                  book.sell_book.limits.remove(&price);
-                }
-              }
-            }
-            
-            // Update best sell level
-            book.sell_book.update_best_level();
-          },
-          Side::Sell => {
-            // Get matching levels from the opposite book
-            let levels = book.buy_book.get_levels_for_matching(order.price);
-            for price in levels {
-              if remaining == 0 { break; }
-              
-              if let Some(level) = book.buy_book.limits.get_mut(&price) {
-                // Match against orders at this level
-                while remaining > 0 && !level.is_empty() {
-                  if let Some((matched_order, matched_qty)) = level.match_order(remaining) {
-                    // Create execution record
-                    let exec = Execution {
-                      exec_id: Uuid::new_v4().to_string(),
-                      order_id: order.order_id.clone(),
-                      symbol: order.symbol.clone(),
-                      side: Side::Sell,
-                      price,
-                      quantity: matched_qty,
-                      fee: 0.0,
-                      transaction_time: Utc::now()
-                    };
-                    
-                    // Send execution
-                    exec_tx.send(exec).await.unwrap();
-                    
-                    // Update remaining quantity
-                    remaining -= matched_qty;
-                    order.filled_quantity += matched_qty;
-                    
-                    // Create execution for the matched order too
-                    let counter_exec = Execution {
-                      exec_id: Uuid::new_v4().to_string(),
-                      order_id: matched_order.order_id.clone(),
-                      symbol: matched_order.symbol.clone(),
-                      side: Side::Buy,
-                      price,
-                      quantity: matched_qty,
-                      fee: 0.0,
-                      transaction_time: Utc::now()
-                    };
-                    
-                    exec_tx.send(counter_exec).await.unwrap();
-                  }
-                }
-                
-                // Clean up empty levels
-                if level.is_empty() {
-                  book.buy_book.limits.remove(&price);
-                }
-              }
-            }
-            
-            // Update best buy level
-            book.buy_book.update_best_level();
-          }
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use thiserror::Error;
+use crate::models::{Order, OrderMessage, MassCancel, Amend, Execution, OrderBook, Book, Side, OrderType, OrderStatus, Price, Qty};
+use crate::instrument::{InstrumentRegistry, OrderValidationError, TradingStatus};
+use crate::auction;
+use crate::circuit_breaker::{self, TradeWindow};
+use crate::market_data_publisher::models::{BestBidOffer, IndicativeAuction, MarketDataEvent};
+
+pub async fn run(
+  mut order_rx: Receiver<OrderMessage>,
+  exec_tx: Sender<Execution>,
+  md_tx: Sender<MarketDataEvent>,
+  order_event_tx: Sender<BookUpdate>,
+  outcome_tx: Sender<OrderOutcome>,
+  instruments: Arc<InstrumentRegistry>
+) {
+  let mut engine = MatchingEngine::new(instruments);
+  // Drives timed session transitions such as re-opening after a circuit breaker halt
+  let mut timer = tokio::time::interval(std::time::Duration::from_millis(100));
+  
+  loop {
+    let output = tokio::select! {
+      message = order_rx.recv() => match message {
+        Some(message) => engine.handle(message),
+        None => break,
+      },
+      _ = timer.tick() => engine.poll_timers(Utc::now()),
+    };
+    
+    for exec in output.executions {
+      exec_tx.send(exec).await.unwrap();
+    }
+    // Outcomes follow the executions of the same message, so a remainder is cancelled after its fills
+    for outcome in output.outcomes {
+      let _ = outcome_tx.send(outcome).await;
+    }
+    // Market data consumers are optional, a closed channel must not stop matching
+    for event in output.market_data.into_iter().chain(output.bbo.into_iter().map(MarketDataEvent::Bbo)) {
+      let _ = md_tx.send(event).await;
+    }
+    // Order event consumers keep their own copy of each book and only receive the changes
+    if !order_event_tx.is_closed() {
+      for symbol in output.books {
+        let events = output.order_events.iter().filter(|e| e.symbol == symbol).cloned().collect();
+        let _ = order_event_tx.send(BookUpdate { symbol, events }).await;
+      }
+    }
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct EngineOutput {
+  pub executions: Vec<Execution>,
+  pub market_data: Vec<MarketDataEvent>,
+  pub books: Vec<String>, // Symbols whose book changed, in the order of the first change
+  pub order_events: Vec<OrderEvent>, // Changes to individual resting orders, in order
+  pub bbo: Vec<BestBidOffer>, // Top of book of each changed symbol whose best price or quantity moved
+  pub outcomes: Vec<OrderOutcome>, // Rejects, cancels and amends, in order
+}
+
+impl EngineOutput {
+  fn book_changed(&mut self, symbol: &str) {
+    if !self.books.iter().any(|s| s == symbol) {
+      self.books.push(symbol.to_string());
+    }
+  }
+}
+
+// The changes a message made to a symbol's book. Applying the events in order to a copy
+// of the book keeps it identical to the engine's.
+#[derive(Clone, Debug)]
+pub struct BookUpdate {
+  pub symbol: String,
+  pub events: Vec<OrderEvent>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderEventKind {
+  Add,     // The order rests on the book at the back of its price level
+  Modify,  // The order's open quantity was reduced in place, keeping its queue position
+  Execute, // The order traded, it leaves the book once nothing remains open
+  Delete,  // The order was cancelled or replaced
+}
+
+// Order-by-order (L3) change to a resting order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderEvent {
+  pub kind: OrderEventKind,
+  pub symbol: String,
+  pub order_id: String,
+  pub side: Side,
+  pub price: Price, // The trade price for executions, which may differ in auctions
+  pub quantity: Qty, // Added, remaining after a modify, executed or deleted quantity
+}
+
+impl OrderEvent {
+  fn new(kind: OrderEventKind, order: &Order) -> Self {
+    OrderEvent {
+      kind,
+      symbol: order.symbol.clone(),
+      order_id: order.order_id.clone(),
+      side: order.side.clone(),
+      price: order.price,
+      quantity: order.remain_quantity,
+    }
+  }
+  
+  fn executed(execution: &Execution) -> Self {
+    OrderEvent {
+      kind: OrderEventKind::Execute,
+      symbol: execution.symbol.clone(),
+      order_id: execution.order_id.clone(),
+      side: execution.side.clone(),
+      price: execution.price,
+      quantity: execution.quantity,
+    }
+  }
+  
+  // Side and price of the level the event changes in a copy of the book it has not been
+  // applied to yet. Executions are looked up, as their price is the trade price.
+  pub fn level(&self, book: &OrderBook) -> (Side, Price) {
+    match (self.kind, book.order_map.get(&self.order_id)) {
+      (OrderEventKind::Execute, Some(order_ref)) => (order_ref.side.clone(), order_ref.price),
+      _ => (self.side.clone(), self.price),
+    }
+  }
+  
+  // Apply the event to a copy of the book
+  pub fn apply(&self, book: &mut OrderBook) {
+    match self.kind {
+      OrderEventKind::Add => book.insert_order(Order {
+        order_id: self.order_id.clone(),
+        symbol: self.symbol.clone(),
+        account: String::new(),
+        price: self.price,
+        quantity: self.quantity,
+        side: self.side.clone(),
+        order_type: OrderType::Limit,
+        status: OrderStatus::New,
+        filled_quantity: Qty::ZERO,
+        remain_quantity: self.quantity,
+        entry_time: Utc::now(),
+      }),
+      OrderEventKind::Modify => {
+        if let Some(filled) = book.get_order(&self.order_id).map(|order| order.filled_quantity) {
+          book.reduce_order(&self.order_id, filled + self.quantity);
         }
-        
-        // Update order status
-        order.remain_quantity = remaining;
-        if remaining == 0 {
-          order.status = OrderStatus::Filled;
-        } else if order.filled_quantity > 0 {
-          order.status = OrderStatus::PartiallyFilled;
+      }
+      OrderEventKind::Execute => {
+        book.execute_order(&self.order_id, self.quantity);
+      }
+      OrderEventKind::Delete => {
+        book.cancel_order(&self.order_id);
+      }
+    }
+  }
+}
+
+// Why an order left the book before it was completely filled
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CancelReason {
+  Requested,  // Cancel request, or an amend down to the filled quantity
+  MassCancel, // Mass cancel by an operator or a disconnected session
+  Unfilled,   // Market order quantity left when the book or the price band ran out
+}
+
+// Why a cancel or amend was not applied
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum CancelRejectReason {
+  // Not resting: filled, cancelled or never accepted
+  #[error("the order is not resting in the book")]
+  UnknownOrder,
+  // Amends that replace the order need the market to accept orders
+  #[error("the order cannot be replaced while the market is {0:?}")]
+  NotTrading(TradingStatus),
+}
+
+impl CancelRejectReason {
+  pub fn code(&self) -> &'static str {
+    match self {
+      CancelRejectReason::UnknownOrder => "UNKNOWN_ORDER",
+      CancelRejectReason::NotTrading(_) => "NOT_TRADING",
+    }
+  }
+}
+
+// What became of an order message besides its executions, for the gateway that entered it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OrderOutcome {
+  // A new order, or the replacement of an amended order, was refused and nothing of it rests.
+  // For a replacement the original order is gone as well.
+  Rejected { order_id: String, account: String, symbol: String, reason: OrderValidationError, replacement: bool },
+  // The order left the book with the quantity still open
+  Cancelled { order_id: String, account: String, symbol: String, quantity: Qty, reason: CancelReason },
+  // The order rests with the new price and total quantity, `remaining` of it open
+  Amended { order_id: String, account: String, symbol: String, price: Price, quantity: Qty, remaining: Qty },
+  // A cancel or amend was not applied, the order is unchanged if it exists
+  CancelRejected { order_id: String, account: String, reason: CancelRejectReason },
+}
+
+impl OrderOutcome {
+  pub fn order_id(&self) -> &str {
+    match self {
+      OrderOutcome::Rejected { order_id, .. }
+      | OrderOutcome::Cancelled { order_id, .. }
+      | OrderOutcome::Amended { order_id, .. }
+      | OrderOutcome::CancelRejected { order_id, .. } => order_id,
+    }
+  }
+  
+  pub fn account(&self) -> &str {
+    match self {
+      OrderOutcome::Rejected { account, .. }
+      | OrderOutcome::Cancelled { account, .. }
+      | OrderOutcome::Amended { account, .. }
+      | OrderOutcome::CancelRejected { account, .. } => account,
+    }
+  }
+  
+  fn cancelled(order: &Order, reason: CancelReason) -> Self {
+    OrderOutcome::Cancelled {
+      order_id: order.order_id.clone(),
+      account: order.account.clone(),
+      symbol: order.symbol.clone(),
+      quantity: order.remain_quantity,
+      reason,
+    }
+  }
+  
+  fn rejected(order: &Order, reason: OrderValidationError, replacement: bool) -> Self {
+    OrderOutcome::Rejected {
+      order_id: order.order_id.clone(),
+      account: order.account.clone(),
+      symbol: order.symbol.clone(),
+      reason,
+      replacement,
+    }
+  }
+}
+
+// Best price and the quantity resting at it, if the side has orders
+type Top = Option<(Price, Qty)>;
+
+// Books and session state for every symbol, driven by sequenced messages
+pub struct MatchingEngine {
+  books: HashMap<String, OrderBook>,
+  last_prices: HashMap<String, Price>, // Reference prices for auctions and price bands
+  trade_windows: HashMap<String, TradeWindow>, // Recent trades for VWAP bands and circuit breakers
+  tops: HashMap<String, (Top, Top)>, // Last published best bid and ask
+  scheduled: HashMap<String, (DateTime<Utc>, TradingStatus)>, // Pending timed status changes
+  instruments: Arc<InstrumentRegistry>,
+}
+
+impl MatchingEngine {
+  pub fn new(instruments: Arc<InstrumentRegistry>) -> Self {
+    MatchingEngine {
+      books: HashMap::new(),
+      last_prices: HashMap::new(),
+      trade_windows: HashMap::new(),
+      tops: HashMap::new(),
+      scheduled: HashMap::new(),
+      instruments,
+    }
+  }
+  
+  pub fn book(&self, symbol: &str) -> Option<&OrderBook> {
+    self.books.get(symbol)
+  }
+  
+  pub fn handle(&mut self, message: OrderMessage) -> EngineOutput {
+    let mut output = EngineOutput::default();
+    
+    let continuous = match message {
+      OrderMessage::Order(order) if order.status == OrderStatus::Cancelled => {
+        self.cancel(order, &mut output);
+        false
+      }
+      OrderMessage::Order(order) => {
+        self.submit(order, false, &mut output);
+        true
+      }
+      OrderMessage::SetTradingStatus { symbol, status } => {
+        // Manual status changes override any pending circuit breaker transition
+        self.scheduled.remove(&symbol);
+        self.set_trading_status(&symbol, status, &mut output);
+        false
+      }
+      OrderMessage::HaltAll => {
+        self.set_status_where(|status| !matches!(status, TradingStatus::Halted | TradingStatus::Closed), TradingStatus::Halted, &mut output);
+        false
+      }
+      OrderMessage::ResumeAll => {
+        self.set_status_where(|status| *status == TradingStatus::Halted, TradingStatus::Trading, &mut output);
+        false
+      }
+      OrderMessage::MassCancel(request) => {
+        self.mass_cancel(&request, &mut output);
+        false
+      }
+      OrderMessage::Amend(amend) => self.amend(amend, &mut output),
+      OrderMessage::Batch(messages) => return self.handle_batch(messages),
+    };
+    
+    self.finish(&mut output, continuous);
+    output
+  }
+  
+  // Each message is handled in full, including any auction and market data it causes,
+  // before the next one
+  fn handle_batch(&mut self, messages: Vec<OrderMessage>) -> EngineOutput {
+    let mut output = EngineOutput::default();
+    for message in messages {
+      let handled = self.handle(message);
+      output.executions.extend(handled.executions);
+      output.market_data.extend(handled.market_data);
+      output.order_events.extend(handled.order_events);
+      output.bbo.extend(handled.bbo);
+      output.outcomes.extend(handled.outcomes);
+      for symbol in handled.books {
+        output.book_changed(&symbol);
+      }
+    }
+    output
+  }
+  
+  // Apply status changes that are due, e.g. the re-opening auction after a circuit breaker halt
+  pub fn poll_timers(&mut self, now: DateTime<Utc>) -> EngineOutput {
+    let mut output = EngineOutput::default();
+    
+    let mut due: Vec<(String, TradingStatus)> = self.scheduled.iter()
+      .filter(|(_, (at, _))| *at <= now)
+      .map(|(symbol, (_, status))| (symbol.clone(), status.clone()))
+      .collect();
+    due.sort_by(|a, b| a.0.cmp(&b.0));
+    
+    for (symbol, status) in due {
+      self.scheduled.remove(&symbol);
+      
+      // A halt ends with a re-opening auction call, which then opens continuous trading
+      if status == TradingStatus::PreOpen {
+        let reopen_secs = self.instruments.get(&symbol)
+          .map(|i| i.circuit_breaker.reopen_auction_secs)
+          .unwrap_or(0);
+        self.schedule(&symbol, now, reopen_secs, TradingStatus::Trading);
+      }
+      self.set_trading_status(&symbol, status, &mut output);
+    }
+    
+    self.finish(&mut output, false);
+    output
+  }
+  
+  // Fees, reference prices, circuit breakers and top of book changes for one message.
+  // Only continuous trading can trip a breaker; auction uncrosses are expected to move the price.
+  fn finish(&mut self, output: &mut EngineOutput, continuous: bool) {
+    for exec in output.executions.iter_mut() {
+      // Fees are charged on the notional in quote units; matching itself is fee-agnostic
+      if let Some(instrument) = self.instruments.get(&exec.symbol) {
+        exec.fee = instrument.fee(exec.price, exec.quantity, exec.is_maker).unwrap_or_else(|| {
+          eprintln!("Fee overflow on execution {}", exec.exec_id);
+          0
+        });
+      }
+      self.last_prices.insert(exec.symbol.clone(), exec.price);
+    }
+    
+    let mut traded: Vec<String> = Vec::new();
+    // Every trade has an execution for each side, so only the buy side is recorded
+    for exec in output.executions.iter().filter(|e| e.side == Side::Buy) {
+      self.trade_windows.entry(exec.symbol.clone()).or_default().record(exec.transaction_time, exec.price, exec.quantity);
+      if !traded.contains(&exec.symbol) {
+        traded.push(exec.symbol.clone());
+      }
+    }
+    
+    let now = Utc::now();
+    for symbol in traded {
+      let instrument = match self.instruments.get(&symbol) {
+        Some(instrument) => instrument,
+        None => continue,
+      };
+      let window = self.trade_windows.get_mut(&symbol).unwrap();
+      let keep = instrument.circuit_breaker.window_secs.max(instrument.price_band.vwap_window_secs);
+      if let Some(since) = circuit_breaker::before(now, keep) {
+        window.prune(since);
+      }
+      
+      if continuous && instrument.status == TradingStatus::Trading
+        && circuit_breaker::breaker_tripped(&instrument.circuit_breaker, window, now) {
+        // Trades before the halt must not trip the breaker again after re-opening
+        window.clear();
+        self.trip_circuit_breaker(&symbol, now, output);
+      }
+    }
+    
+    self.publish_tops(output);
+  }
+  
+  // Best levels are read directly from each side, so a top of book change costs no book copy
+  fn publish_tops(&mut self, output: &mut EngineOutput) {
+    let best = |book: &Book| book.get_best_level().map(|level| (level.price, level.total_volume));
+    for symbol in &output.books {
+      let top = match self.books.get(symbol) {
+        Some(book) => (best(&book.buy_book), best(&book.sell_book)),
+        None => continue,
+      };
+      if self.tops.get(symbol) == Some(&top) {
+        continue;
+      }
+      self.tops.insert(symbol.clone(), top);
+      
+      let (bid, ask) = top;
+      output.bbo.push(BestBidOffer {
+        symbol: symbol.clone(),
+        timestamp: Utc::now(),
+        bid_price: bid.map(|(price, _)| price.0),
+        bid_qty: bid.map(|(_, qty)| qty.0).unwrap_or(0),
+        ask_price: ask.map(|(price, _)| price.0),
+        ask_qty: ask.map(|(_, qty)| qty.0).unwrap_or(0),
+      });
+    }
+  }
+  
+  fn trip_circuit_breaker(&mut self, symbol: &str, now: DateTime<Utc>, output: &mut EngineOutput) {
+    let config = match self.instruments.get(symbol) {
+      Some(instrument) => instrument.circuit_breaker,
+      None => return,
+    };
+    
+    if config.halt_secs > 0 {
+      self.set_trading_status(symbol, TradingStatus::Halted, output);
+      self.schedule(symbol, now, config.halt_secs, TradingStatus::PreOpen);
+    } else {
+      self.set_trading_status(symbol, TradingStatus::PreOpen, output);
+      self.schedule(symbol, now, config.reopen_auction_secs, TradingStatus::Trading);
+    }
+  }
+  
+  // A status change that is out of range is not scheduled; the symbol then stays in its
+  // current status until an admin changes it
+  fn schedule(&mut self, symbol: &str, now: DateTime<Utc>, secs: u64, status: TradingStatus) {
+    match circuit_breaker::after(now, secs) {
+      Some(at) => {
+        self.scheduled.insert(symbol.to_string(), (at, status));
+      }
+      None => eprintln!("Cannot schedule {:?} for {} in {} seconds", status, symbol, secs),
+    }
+  }
+  
+  // Returns whether the order was accepted; refused orders are reported as rejected, as the
+  // replacement of an amend if replacing
+  fn submit(&mut self, mut order: Order, replacing: bool, output: &mut EngineOutput) -> bool {
+    // Orders are validated again here since the engine may be fed by other gateways
+    let validated = self.instruments.get(&order.symbol)
+      .ok_or_else(|| OrderValidationError::UnknownSymbol(order.symbol.clone()))
+      .and_then(|instrument| instrument.validate_order(&order).map(|_| instrument));
+    let instrument = match validated {
+      Ok(instrument) => instrument,
+      Err(e) => {
+        output.outcomes.push(OrderOutcome::rejected(&order, e, replacing));
+        return false;
+      }
+    };
+    
+    let symbol = order.symbol.clone();
+    
+    // During a call phase orders only rest on the book, which may cross until the auction
+    if instrument.status.is_call_phase() {
+      output.order_events.push(OrderEvent::new(OrderEventKind::Add, &order));
+      self.books.entry(symbol.clone()).or_default().insert_order(order);
+      output.book_changed(&symbol);
+      output.market_data.push(self.indicative(&symbol));
+      return true;
+    }
+    
+    // Orders priced through the band are rejected or clipped, market orders stop at its edge
+    let band = circuit_breaker::price_band(
+      &instrument,
+      self.trade_windows.get(&symbol),
+      self.last_prices.get(&symbol).cloned(),
+      Utc::now()
+    );
+    if let Some(band) = &band {
+      if let Err(e) = circuit_breaker::apply_band(&mut order, band, &instrument.price_band.action) {
+        output.outcomes.push(OrderOutcome::rejected(&order, e, replacing));
+        return false;
+      }
+    }
+    
+    let book = self.books.entry(symbol.clone()).or_default();
+    let bound = band.map(|band| band.limit_for(&order.side));
+    let mut unfilled = order.clone();
+    let executions = process_order_bounded(book, order, bound);
+    
+    // Resting orders trade first, then any remainder of the incoming order joins the book.
+    // A market order's remainder is cancelled instead.
+    output.order_events.extend(executions.iter().filter(|e| e.is_maker).map(OrderEvent::executed));
+    if let Some(rested) = book.get_order(&unfilled.order_id) {
+      output.order_events.push(OrderEvent::new(OrderEventKind::Add, rested));
+    } else {
+      let filled: Qty = executions.iter().filter(|e| !e.is_maker).map(|e| e.quantity).sum();
+      if filled < unfilled.remain_quantity {
+        unfilled.remain_quantity -= filled;
+        output.outcomes.push(OrderOutcome::cancelled(&unfilled, CancelReason::Unfilled));
+      }
+    }
+    output.executions.extend(executions);
+    output.book_changed(&symbol);
+    true
+  }
+  
+  fn cancel(&mut self, order: Order, output: &mut EngineOutput) {
+    // Cancel requests do not always carry a symbol, so the book is found by order id.
+    // A cancel with an account only applies to that account's orders.
+    let owned = |book: &OrderBook| book.get_order(&order.order_id)
+      .is_some_and(|resting| order.account.is_empty() || resting.account == order.account);
+    let symbol = match self.books.iter().find(|(_, book)| owned(book)) {
+      Some((symbol, _)) => symbol.clone(),
+      None => {
+        output.outcomes.push(OrderOutcome::CancelRejected {
+          order_id: order.order_id,
+          account: order.account,
+          reason: CancelRejectReason::UnknownOrder,
+        });
+        return;
+      }
+    };
+    
+    let book = self.books.get_mut(&symbol).unwrap();
+    if let Some(cancelled) = book.cancel_order(&order.order_id) {
+      output.order_events.push(OrderEvent::new(OrderEventKind::Delete, &cancelled));
+      output.outcomes.push(OrderOutcome::cancelled(&cancelled, CancelReason::Requested));
+    }
+    output.book_changed(&symbol);
+    
+    let in_call = self.instruments.get(&symbol).map(|i| i.status.is_call_phase()).unwrap_or(false);
+    if in_call {
+      output.market_data.push(self.indicative(&symbol));
+    }
+  }
+  
+  // Returns whether the amended order was resubmitted, which may match like a new order
+  fn amend(&mut self, amend: Amend, output: &mut EngineOutput) -> bool {
+    // Like a cancel, an amend with an account only applies to that account's orders
+    let owned = |book: &OrderBook| book.get_order(&amend.order_id)
+      .is_some_and(|resting| amend.account.is_empty() || resting.account == amend.account);
+    let symbol = self.books.iter()
+      .find(|(_, book)| owned(book))
+      .map(|(symbol, _)| symbol.clone());
+    let status = symbol.as_ref().and_then(|symbol| self.instruments.get(symbol)).map(|instrument| instrument.status);
+    let (symbol, status) = match (symbol, status) {
+      (Some(symbol), Some(status)) => (symbol, status),
+      _ => {
+        output.outcomes.push(OrderOutcome::CancelRejected {
+          order_id: amend.order_id,
+          account: amend.account,
+          reason: CancelRejectReason::UnknownOrder,
+        });
+        return false;
+      }
+    };
+    let in_call = status.is_call_phase();
+    let book = self.books.get_mut(&symbol).unwrap();
+    
+    let price = book.order_map[&amend.order_id].price;
+    if price == amend.price && book.reduce_order(&amend.order_id, amend.quantity) {
+      let reduced = book.get_order(&amend.order_id).unwrap();
+      output.order_events.push(OrderEvent::new(OrderEventKind::Modify, reduced));
+      output.outcomes.push(OrderOutcome::Amended {
+        order_id: reduced.order_id.clone(),
+        account: reduced.account.clone(),
+        symbol: symbol.clone(),
+        price: reduced.price,
+        quantity: reduced.quantity,
+        remaining: reduced.remain_quantity,
+      });
+      output.book_changed(&symbol);
+      if in_call {
+        output.market_data.push(self.indicative(&symbol));
+      }
+      return false;
+    }
+    
+    // Cancel/replace. A quantity at or below the filled quantity leaves the order cancelled,
+    // which cancel-only (halted) symbols allow like any cancel. Otherwise a halted symbol's
+    // order is left as it is.
+    let cancels = book.get_order(&amend.order_id).is_some_and(|order| amend.quantity <= order.filled_quantity);
+    if !cancels && !status.accepts_orders() {
+      let account = book.get_order(&amend.order_id).map(|order| order.account.clone()).unwrap_or_default();
+      output.outcomes.push(OrderOutcome::CancelRejected {
+        order_id: amend.order_id,
+        account,
+        reason: CancelRejectReason::NotTrading(status),
+      });
+      return false;
+    }
+    let mut order = book.cancel_order(&amend.order_id).unwrap();
+    output.order_events.push(OrderEvent::new(OrderEventKind::Delete, &order));
+    output.book_changed(&symbol);
+    if cancels {
+      output.outcomes.push(OrderOutcome::cancelled(&order, CancelReason::Requested));
+      if in_call {
+        output.market_data.push(self.indicative(&symbol));
+      }
+      return false;
+    }
+    order.price = amend.price;
+    order.quantity = amend.quantity;
+    order.remain_quantity = amend.quantity - order.filled_quantity;
+    order.entry_time = Utc::now();
+    
+    // A refused replacement is reported as rejected by submit, the original being gone
+    let order_id = order.order_id.clone();
+    let account = order.account.clone();
+    if self.submit(order, true, output) {
+      let remaining = self.books.get(&symbol).and_then(|book| book.get_order(&order_id)).map(|order| order.remain_quantity);
+      output.outcomes.push(OrderOutcome::Amended {
+        order_id,
+        account,
+        symbol,
+        price: amend.price,
+        quantity: amend.quantity,
+        remaining: remaining.unwrap_or(Qty::ZERO),
+      });
+    }
+    true
+  }
+  
+  fn set_trading_status(&mut self, symbol: &str, status: TradingStatus, output: &mut EngineOutput) {
+    let previous = match self.instruments.set_status(symbol, status.clone()) {
+      Some(previous) => previous,
+      None => {
+        eprintln!("Ignored status change for unknown symbol {}", symbol);
+        return;
+      }
+    };
+    
+    // Orders collected in a call phase are uncrossed in a single auction when matching resumes.
+    // A halted call keeps its orders until the next phase.
+    if !status.is_call_phase() && status != TradingStatus::Halted {
+      let reference = self.last_prices.get(symbol).cloned();
+      if let Some(book) = self.books.get_mut(symbol) {
+        if let Some(uncross) = auction::equilibrium(book, reference) {
+          // Both sides of an uncross were resting
+          let executions = auction::execute(book, &uncross);
+          output.order_events.extend(executions.iter().map(OrderEvent::executed));
+          output.executions.extend(executions);
+          output.book_changed(symbol);
         }
-        
-        // Insert remaining order to book if not fully filled
-        if remaining > 0 {
-          book.insert_order(order);
+      }
+    }
+    
+    if status.is_call_phase() {
+      output.market_data.push(self.indicative(symbol));
+    } else if previous.is_call_phase() {
+      output.market_data.push(MarketDataEvent::AuctionEnded { symbol: symbol.to_string() });
+    }
+  }
+  
+  // Change the status of every symbol whose current status matches, like a manual status change
+  fn set_status_where(&mut self, filter: impl Fn(&TradingStatus) -> bool, status: TradingStatus, output: &mut EngineOutput) {
+    for instrument in self.instruments.list() {
+      if filter(&instrument.status) {
+        self.scheduled.remove(&instrument.symbol);
+        self.set_trading_status(&instrument.symbol, status.clone(), output);
+      }
+    }
+  }
+  
+  fn mass_cancel(&mut self, request: &MassCancel, output: &mut EngineOutput) {
+    let mut symbols: Vec<String> = self.books.keys()
+      .filter(|symbol| request.symbol.as_ref().is_none_or(|s| s == *symbol))
+      .cloned()
+      .collect();
+    symbols.sort();
+    
+    for symbol in symbols {
+      let cancelled = self.books.get_mut(&symbol).unwrap().cancel_where(|order| request.matches(order));
+      if cancelled.is_empty() {
+        continue;
+      }
+      output.order_events.extend(cancelled.iter().map(|order| OrderEvent::new(OrderEventKind::Delete, order)));
+      output.outcomes.extend(cancelled.iter().map(|order| OrderOutcome::cancelled(order, CancelReason::MassCancel)));
+      output.book_changed(&symbol);
+      
+      let in_call = self.instruments.get(&symbol).map(|i| i.status.is_call_phase()).unwrap_or(false);
+      if in_call {
+        output.market_data.push(self.indicative(&symbol));
+      }
+    }
+  }
+  
+  // Indicative price and volume if the call phase ended now
+  fn indicative(&self, symbol: &str) -> MarketDataEvent {
+    let reference = self.last_prices.get(symbol).cloned();
+    let uncross = self.books.get(symbol).and_then(|book| auction::equilibrium(book, reference));
+    
+    MarketDataEvent::AuctionIndicative(IndicativeAuction {
+      symbol: symbol.to_string(),
+      timestamp: Utc::now(),
+      price: uncross.as_ref().map(|u| u.price.0),
+      volume: uncross.as_ref().map(|u| u.volume.0).unwrap_or(0),
+      imbalance: uncross.as_ref().map(|u| u.imbalance.0).unwrap_or(0),
+      imbalance_side: uncross.and_then(|u| u.imbalance_side).map(|side| format!("{:?}", side)),
+    })
+  }
+}
+
+// Apply a single order message to the book and return the resulting executions
+pub fn process_order(book: &mut OrderBook, order: Order) -> Vec<Execution> {
+  process_order_bounded(book, order, None)
+}
+
+// Same as process_order, with market orders matching no further than the bound
+pub fn process_order_bounded(book: &mut OrderBook, mut order: Order, bound: Option<Price>) -> Vec<Execution> {
+  let mut executions = Vec::new();
+  
+  // Cancel requests are encoded as orders with a Cancelled status
+  if order.status == OrderStatus::Cancelled {
+    book.cancel_order(&order.order_id);
+    return executions;
+  }
+  
+  // Market orders match at any price within the bound, limit orders only up to their limit price
+  let limit_price = match order.order_type {
+    OrderType::Limit => Some(order.price),
+    OrderType::Market => bound,
+  };
+  
+  let remaining = match_order(book, &mut order, limit_price, &mut executions);
+  
+  // Update order status
+  order.remain_quantity = remaining;
+  if remaining.is_zero() {
+    order.status = OrderStatus::Filled;
+  } else if !order.filled_quantity.is_zero() {
+    order.status = OrderStatus::PartiallyFilled;
+  }
+  
+  // Insert remaining limit order to book if not fully filled.
+  // Unfilled market order quantity is discarded.
+  if !remaining.is_zero() && order.order_type == OrderType::Limit {
+    book.insert_order(order);
+  }
+  
+  executions
+}
+
+// Match an incoming order against the opposite book, returning the unfilled quantity
+fn match_order(
+  book: &mut OrderBook,
+  order: &mut Order,
+  limit_price: Option<Price>,
+  executions: &mut Vec<Execution>
+) -> Qty {
+  let mut remaining = order.remain_quantity;
+  
+  let opposite = match order.side {
+    Side::Buy => &mut book.sell_book,
+    Side::Sell => &mut book.buy_book,
+  };
+  
+  // Get matching levels from the opposite book
+  let levels = match limit_price {
+    Some(price) => opposite.get_levels_for_matching(price),
+    None => opposite.get_all_levels(),
+  };
+  
+  for price in levels {
+    if remaining.is_zero() { break; }
+    
+    if let Some(level) = opposite.limits.get_mut(&price) {
+      // Match against orders at this level
+      while !remaining.is_zero() && !level.is_empty() {
+        if let Some((matched_order, matched_qty)) = level.match_order(remaining) {
+          // Create execution record
+          let trade_id = Uuid::new_v4().to_string();
+          executions.push(Execution {
+            exec_id: Uuid::new_v4().to_string(),
+            order_id: order.order_id.clone(),
+            trade_id: trade_id.clone(),
+            account: order.account.clone(),
+            symbol: order.symbol.clone(),
+            side: order.side.clone(),
+            price,
+            quantity: matched_qty,
+            is_maker: false,
+            fee: 0,
+            transaction_time: Utc::now()
+          });
+          
+          // Update remaining quantity
+          remaining -= matched_qty;
+          order.filled_quantity += matched_qty;
+          
+          // Create execution for the matched order too
+          executions.push(Execution {
+            exec_id: Uuid::new_v4().to_string(),
+            order_id: matched_order.order_id.clone(),
+            trade_id,
+            account: matched_order.account.clone(),
+            symbol: matched_order.symbol.clone(),
+            side: matched_order.side.clone(),
+            price,
+            quantity: matched_qty,
+            is_maker: true,
+            fee: 0,
+            transaction_time: Utc::now()
+          });
+          
+          // Fully filled resting orders leave the book
+          if matched_order.status == OrderStatus::Filled {
+            book.order_map.remove(&matched_order.order_id);
+          }
         }
-      },
-      OrderType::Market => {
-        // Handle market orders with similar logic but ignore price constraint
-        // Implementation omitted for brevity
+      }
+      
+      // Clean up empty levels
+      if level.is_empty() {
+        opposite.limits.remove(&price);
       }
     }
   }
+  
+  // Update best level of the opposite book
+  opposite.update_best_level();
+  
+  remaining
 }
\ No newline at end of file
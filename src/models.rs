@@ -1,4 +1,4 @@
-/**
+/*
 * filename : models
 * author : HAMA
 * date: 2025. 4. 28.
@@ -9,12 +9,103 @@ use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc};
 use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::cmp::Ordering;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use crate::util::decimal::{self, DecimalError};
+use crate::instrument::TradingStatus;
+
+// Fixed-point values in raw integer units. The number of decimal places is not stored in the
+// value; it comes from the instrument (price_scale / quantity_scale) at the API boundary.
+macro_rules! fixed_point {
+  ($name:ident) => {
+    #[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[serde(transparent)]
+    pub struct $name(pub u64);
+    
+    impl $name {
+      pub const ZERO: $name = $name(0);
+      
+      pub fn is_zero(&self) -> bool {
+        self.0 == 0
+      }
+      
+      // Parse a decimal string such as "0.001" with the given number of decimal places
+      pub fn from_decimal(value: &str, scale: u32) -> Result<Self, DecimalError> {
+        decimal::parse_scaled(value, scale).map($name)
+      }
+      
+      pub fn to_decimal(self, scale: u32) -> String {
+        decimal::format_scaled(self.0, scale)
+      }
+      
+      pub fn checked_add(self, other: $name) -> Option<$name> {
+        self.0.checked_add(other.0).map($name)
+      }
+      
+      pub fn checked_sub(self, other: $name) -> Option<$name> {
+        self.0.checked_sub(other.0).map($name)
+      }
+      
+      // For totals that may legitimately exceed u64, e.g. the volume of a price level when the
+      // instrument allows orders of up to u64::MAX
+      pub fn saturating_add(self, other: $name) -> $name {
+        $name(self.0.saturating_add(other.0))
+      }
+      
+      pub fn saturating_sub(self, other: $name) -> $name {
+        $name(self.0.saturating_sub(other.0))
+      }
+    }
+    
+    impl fmt::Display for $name {
+      fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+      }
+    }
+    
+    // The operators are for arithmetic that cannot overflow, such as taking a fill from the
+    // quantity it was matched against. Overflow is a bug: it panics in debug builds and
+    // saturates in release builds. Use checked_add/checked_sub where it can happen.
+    impl Add for $name {
+      type Output = $name;
+      fn add(self, other: $name) -> $name {
+        debug_assert!(self.0.checked_add(other.0).is_some(), "{} + {} overflows", self, other);
+        $name(self.0.saturating_add(other.0))
+      }
+    }
+    
+    impl Sub for $name {
+      type Output = $name;
+      fn sub(self, other: $name) -> $name {
+        debug_assert!(self.0 >= other.0, "{} - {} underflows", self, other);
+        $name(self.0.saturating_sub(other.0))
+      }
+    }
+    
+    impl AddAssign for $name {
+      fn add_assign(&mut self, other: $name) { *self = *self + other; }
+    }
+    
+    impl SubAssign for $name {
+      fn sub_assign(&mut self, other: $name) { *self = *self - other; }
+    }
+    
+    // Totals saturate, see saturating_add
+    impl Sum for $name {
+      fn sum<I: Iterator<Item = $name>>(iter: I) -> $name { iter.fold($name::ZERO, $name::saturating_add) }
+    }
+  };
+}
+
+fixed_point!(Price);
+fixed_point!(Qty);
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Side { Buy, Sell }
 
 impl Side {
-  pub fn compare(&self, a: &u64, b: &u64) -> Ordering {
+  pub fn compare(&self, a: &Price, b: &Price) -> Ordering {
     match self {
       Side::Buy => b.cmp(a),  // Buy: descending (highest price first)
       Side::Sell => a.cmp(b), // Sell: ascending (lowest price first)
@@ -26,19 +117,21 @@ impl Side {
 pub enum OrderType { Limit, Market }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
-pub enum OrderStatus { New, PartiallyFilled, Filled, Cancelled }
+pub enum OrderStatus { New, PartiallyFilled, Filled, Cancelled, Rejected }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Order {
   pub order_id: String,
   pub symbol: String,
-  pub price: u64,
-  pub quantity: u64,
+  #[serde(default)]
+  pub account: String, // Owning account, empty for anonymous orders
+  pub price: Price,
+  pub quantity: Qty,
   pub side: Side,
   pub order_type: OrderType,
   pub status: OrderStatus,
-  pub filled_quantity: u64,
-  pub remain_quantity: u64,
+  pub filled_quantity: Qty,
+  pub remain_quantity: Qty,
   pub entry_time: DateTime<Utc>,
 }
 
@@ -46,54 +139,96 @@ pub struct Order {
 pub struct Execution {
   pub exec_id: String,
   pub order_id: String,
+  #[serde(default)]
+  pub trade_id: String, // Shared by the executions of both orders of a match
+  #[serde(default)]
+  pub account: String, // Owner of the order
   pub symbol: String,
   pub side: Side,
-  pub price: u64,
-  pub quantity: u64,
-  pub fee: f64,
+  pub price: Price,
+  pub quantity: Qty,
+  pub is_maker: bool,
+  pub fee: i64, // In raw quote units (price_scale). Negative values are rebates.
   pub transaction_time: DateTime<Utc>,
 }
 
+// Resting orders to cancel in bulk. Every given criterion must match; none cancels everything.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MassCancel {
+  pub symbol: Option<String>,
+  pub account: Option<String>,
+  pub side: Option<Side>,
+}
+
+impl MassCancel {
+  pub fn matches(&self, order: &Order) -> bool {
+    self.symbol.as_ref().is_none_or(|symbol| &order.symbol == symbol)
+      && self.account.as_ref().is_none_or(|account| &order.account == account)
+      && self.side.as_ref().is_none_or(|side| &order.side == side)
+  }
+}
+
+// Messages sequenced into the matching engine
 #[derive(Clone, Debug)]
-pub struct OrderMessage(pub Order);
+pub enum OrderMessage {
+  Order(Order), // New orders, and cancels as orders with a Cancelled status
+  SetTradingStatus { symbol: String, status: TradingStatus },
+  HaltAll,   // Every trading symbol becomes Halted (cancel-only)
+  ResumeAll, // Every Halted symbol returns to Trading
+  MassCancel(MassCancel),
+  Amend(Amend),
+  Batch(Vec<OrderMessage>), // Handled back to back, with nothing sequenced in between
+}
+
+// New price and total quantity for a resting order. Lowering only the quantity keeps the
+// order's time priority; any other change re-queues it as if it were a new order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Amend {
+  pub order_id: String,
+  pub account: String, // Only that account's order is amended; empty for any account
+  pub price: Price,
+  pub quantity: Qty, // Including the quantity already filled
+}
 
 #[derive(Clone, Debug)]
 pub struct OrderReference {
-  pub price: u64,
-  pub position: usize, // Position in the queue for O(1) access
+  pub side: Side,
+  pub price: Price,
+  pub position: usize, // Position in the queue at insertion time, used as a lookup hint
 }
 
 #[derive(Clone, Debug)]
 pub struct PriceLevel {
-  pub price: u64,
-  pub total_volume: u64,
+  pub price: Price,
+  pub total_volume: Qty,
   pub orders: VecDeque<Order>,
 }
 
 impl PriceLevel {
-  pub fn new(price: u64) -> Self {
+  pub fn new(price: Price) -> Self {
     PriceLevel {
       price,
-      total_volume: 0,
+      total_volume: Qty::ZERO,
       orders: VecDeque::new()
     }
   }
   
+  // The total volume of a level saturates at u64::MAX, as orders of up to u64::MAX may rest
   pub fn add_order(&mut self, order: Order) -> usize {
-    self.total_volume += order.remain_quantity;
+    self.total_volume = self.total_volume.saturating_add(order.remain_quantity);
     let position = self.orders.len();
     self.orders.push_back(order);
     position
   }
   
-  pub fn match_order(&mut self, qty: u64) -> Option<(Order, u64)> {
-    if let Some(mut front) = self.orders.front_mut() {
+  pub fn match_order(&mut self, qty: Qty) -> Option<(Order, Qty)> {
+    if let Some(front) = self.orders.front_mut() {
       let matched = std::cmp::min(front.remain_quantity, qty);
       front.remain_quantity -= matched;
       front.filled_quantity += matched;
-      self.total_volume -= matched;
+      self.total_volume = self.total_volume.saturating_sub(matched);
       
-      if front.remain_quantity == 0 {
+      if front.remain_quantity.is_zero() {
         front.status = OrderStatus::Filled;
         return Some((self.orders.pop_front().unwrap(), matched));
       } else {
@@ -104,10 +239,43 @@ impl PriceLevel {
     None
   }
   
+  // Remove an order by id. Matching pops orders off the front of the queue, so the
+  // stored position is only a hint and the queue is searched when it no longer matches.
+  pub fn cancel_order(&mut self, order_id: &str, position_hint: usize) -> Option<Order> {
+    let position = self.position_of(order_id, position_hint)?;
+    self.cancel_order_at_position(position)
+  }
+  
+  fn position_of(&self, order_id: &str, position_hint: usize) -> Option<usize> {
+    match self.orders.get(position_hint) {
+      Some(order) if order.order_id == order_id => Some(position_hint),
+      _ => self.orders.iter().position(|o| o.order_id == order_id),
+    }
+  }
+  
+  // Lower an order's total quantity in place, keeping its time priority.
+  // Only reductions that leave some quantity open are applied.
+  pub fn reduce_order(&mut self, order_id: &str, position_hint: usize, quantity: Qty) -> bool {
+    let position = match self.position_of(order_id, position_hint) {
+      Some(position) => position,
+      None => return false,
+    };
+    let order = &mut self.orders[position];
+    if quantity >= order.quantity || quantity <= order.filled_quantity {
+      return false;
+    }
+    
+    let reduced = order.quantity - quantity;
+    order.quantity = quantity;
+    order.remain_quantity -= reduced;
+    self.total_volume = self.total_volume.saturating_sub(reduced);
+    true
+  }
+  
   pub fn cancel_order_at_position(&mut self, position: usize) -> Option<Order> {
     if position < self.orders.len() {
       let order = self.orders.remove(position).unwrap();
-      self.total_volume -= order.remain_quantity;
+      self.total_volume = self.total_volume.saturating_sub(order.remain_quantity);
       Some(order)
     } else {
       None
@@ -122,8 +290,8 @@ impl PriceLevel {
 #[derive(Clone, Debug)]
 pub struct Book {
   pub side: Side,
-  pub limits: BTreeMap<u64, PriceLevel>,
-  pub best_level: Option<u64>,
+  pub limits: BTreeMap<Price, PriceLevel>,
+  pub best_level: Option<Price>,
 }
 
 impl Book {
@@ -154,7 +322,7 @@ impl Book {
       }
     }
     
-    OrderReference { price, position }
+    OrderReference { side: self.side.clone(), price, position }
   }
   
   pub fn get_best_level(&self) -> Option<&PriceLevel> {
@@ -173,7 +341,15 @@ impl Book {
     };
   }
   
-  pub fn get_levels_for_matching(&self, price_point: u64) -> Vec<u64> {
+  // All level prices ordered from best to worst, used for market orders
+  pub fn get_all_levels(&self) -> Vec<Price> {
+    match self.side {
+      Side::Buy => self.limits.keys().rev().cloned().collect(),
+      Side::Sell => self.limits.keys().cloned().collect(),
+    }
+  }
+  
+  pub fn get_levels_for_matching(&self, price_point: Price) -> Vec<Price> {
     let mut result = Vec::new();
     
     match self.side {
@@ -208,6 +384,12 @@ pub struct OrderBook {
   pub order_map: HashMap<String, OrderReference>,
 }
 
+impl Default for OrderBook {
+  fn default() -> Self {
+    OrderBook::new()
+  }
+}
+
 impl OrderBook {
   pub fn new() -> Self {
     OrderBook {
@@ -229,13 +411,13 @@ impl OrderBook {
   
   pub fn cancel_order(&mut self, order_id: &str) -> Option<Order> {
     if let Some(order_ref) = self.order_map.remove(order_id) {
-      let book = match self.get_book_by_price(order_ref.price) {
-        Some(b) => b,
-        None => return None,
+      let book = match order_ref.side {
+        Side::Buy => &mut self.buy_book,
+        Side::Sell => &mut self.sell_book,
       };
       
       if let Some(level) = book.limits.get_mut(&order_ref.price) {
-        let cancelled_order = level.cancel_order_at_position(order_ref.position);
+        let cancelled_order = level.cancel_order(order_id, order_ref.position);
         
         // Clean up empty levels and update best price
         if level.is_empty() {
@@ -249,15 +431,134 @@ impl OrderBook {
     None
   }
   
-  fn get_book_by_price(&mut self, price: u64) -> Option<&mut Book> {
-    // Try to find the price in either book
-    if self.buy_book.limits.contains_key(&price) {
-      Some(&mut self.buy_book)
-    } else if self.sell_book.limits.contains_key(&price) {
-      Some(&mut self.sell_book)
+  pub fn get_order(&self, order_id: &str) -> Option<&Order> {
+    let order_ref = self.order_map.get(order_id)?;
+    let book = match order_ref.side {
+      Side::Buy => &self.buy_book,
+      Side::Sell => &self.sell_book,
+    };
+    let level = book.limits.get(&order_ref.price)?;
+    level.position_of(order_id, order_ref.position).map(|position| &level.orders[position])
+  }
+  
+  // Lower a resting order's quantity without losing its place in the queue
+  pub fn reduce_order(&mut self, order_id: &str, quantity: Qty) -> bool {
+    let order_ref = match self.order_map.get(order_id) {
+      Some(order_ref) => order_ref,
+      None => return false,
+    };
+    let book = match order_ref.side {
+      Side::Buy => &mut self.buy_book,
+      Side::Sell => &mut self.sell_book,
+    };
+    match book.limits.get_mut(&order_ref.price) {
+      Some(level) => level.reduce_order(order_id, order_ref.position, quantity),
+      None => false,
+    }
+  }
+  
+  // Fill part of a resting order wherever it is in its level's queue, removing it once
+  // nothing remains open. Used by copies of the book that follow the engine's order events.
+  pub fn execute_order(&mut self, order_id: &str, quantity: Qty) -> bool {
+    let order_ref = match self.order_map.get(order_id) {
+      Some(order_ref) => order_ref.clone(),
+      None => return false,
+    };
+    let book = match order_ref.side {
+      Side::Buy => &mut self.buy_book,
+      Side::Sell => &mut self.sell_book,
+    };
+    let level = match book.limits.get_mut(&order_ref.price) {
+      Some(level) => level,
+      None => return false,
+    };
+    let position = match level.position_of(order_id, order_ref.position) {
+      Some(position) => position,
+      None => return false,
+    };
+    
+    let order = &mut level.orders[position];
+    let executed = quantity.min(order.remain_quantity);
+    order.remain_quantity -= executed;
+    order.filled_quantity += executed;
+    level.total_volume = level.total_volume.saturating_sub(executed);
+    if order.remain_quantity.is_zero() {
+      self.cancel_order(order_id);
     } else {
-      None
+      order.status = OrderStatus::PartiallyFilled;
     }
+    true
+  }
+  
+  // Cancel every resting order accepted by the filter, returning the cancelled orders
+  pub fn cancel_where(&mut self, filter: impl Fn(&Order) -> bool) -> Vec<Order> {
+    let order_ids: Vec<String> = [&self.buy_book, &self.sell_book].into_iter()
+      .flat_map(|book| book.limits.values())
+      .flat_map(|level| level.orders.iter())
+      .filter(|order| filter(order))
+      .map(|order| order.order_id.clone())
+      .collect();
+    
+    order_ids.iter().filter_map(|order_id| self.cancel_order(order_id)).collect()
+  }
+  
+  // Check the structural invariants of the book, returning a description of the first violation
+  pub fn validate(&self) -> Result<(), String> {
+    let mut resting = 0;
+    
+    for book in [&self.buy_book, &self.sell_book] {
+      for (&price, level) in &book.limits {
+        if level.price != price {
+          return Err(format!("{:?} level keyed at {} has price {}", book.side, price, level.price));
+        }
+        if level.is_empty() {
+          return Err(format!("{:?} level at {} is empty", book.side, price));
+        }
+        
+        let volume: Qty = level.orders.iter().map(|o| o.remain_quantity).sum();
+        if level.total_volume != volume {
+          return Err(format!("{:?} level at {} has total_volume {} but resting quantity {}",
+                             book.side, price, level.total_volume, volume));
+        }
+        
+        for order in &level.orders {
+          if order.side != book.side || order.price != price {
+            return Err(format!("order {} is resting at the wrong level", order.order_id));
+          }
+          if order.remain_quantity.is_zero() {
+            return Err(format!("order {} is resting with no remaining quantity", order.order_id));
+          }
+          if order.filled_quantity + order.remain_quantity != order.quantity {
+            return Err(format!("order {} filled + remaining != quantity", order.order_id));
+          }
+          match self.order_map.get(&order.order_id) {
+            Some(order_ref) if order_ref.side == book.side && order_ref.price == price => {}
+            _ => return Err(format!("order {} has no matching order_map entry", order.order_id)),
+          }
+          resting += 1;
+        }
+      }
+      
+      let expected_best = match book.side {
+        Side::Buy => book.limits.keys().next_back().cloned(),
+        Side::Sell => book.limits.keys().next().cloned(),
+      };
+      if book.best_level != expected_best {
+        return Err(format!("{:?} best_level is {:?}, expected {:?}", book.side, book.best_level, expected_best));
+      }
+    }
+    
+    if self.order_map.len() != resting {
+      return Err(format!("order_map has {} entries for {} resting orders", self.order_map.len(), resting));
+    }
+    
+    if let (Some(bid), Some(ask)) = (self.buy_book.best_level, self.sell_book.best_level) {
+      if bid >= ask {
+        return Err(format!("book is crossed: best bid {} >= best ask {}", bid, ask));
+      }
+    }
+    
+    Ok(())
   }
 }
 
@@ -268,28 +569,51 @@ mod tests {
   
   #[test]
   fn test_limit_level_add_and_match() {
-    let mut pl = PriceLevel::new(100);
+    let mut pl = PriceLevel::new(Price(100));
     let order = Order {
       order_id: "o1".into(),
       symbol: "SYM".into(),
-      price: 100,
-      quantity: 10,
+      account: String::new(),
+      price: Price(100),
+      quantity: Qty(10),
       side: Side::Buy,
       order_type: OrderType::Limit,
       status: OrderStatus::New,
-      filled_quantity: 0,
-      remain_quantity: 10,
+      filled_quantity: Qty(0),
+      remain_quantity: Qty(10),
       entry_time: Utc::now()
     };
     
     pl.add_order(order.clone());
-    assert_eq!(pl.total_volume, 10);
+    assert_eq!(pl.total_volume, Qty(10));
+    
+    let (matched, qty) = pl.match_order(Qty(5)).unwrap();
+    assert_eq!(matched.remain_quantity, Qty(5));
+    assert_eq!(matched.filled_quantity, Qty(5));
+    assert_eq!(qty, Qty(5));
+    assert_eq!(pl.total_volume, Qty(5));
+  }
+  
+  #[test]
+  fn test_fixed_point_arithmetic() {
+    assert_eq!(Qty(u64::MAX).checked_add(Qty(1)), None);
+    assert_eq!(Qty(1).checked_sub(Qty(2)), None);
+    assert_eq!(Qty(3).checked_sub(Qty(2)), Some(Qty(1)));
+    
+    let mut volume = Qty(u64::MAX - 1);
+    volume = volume.saturating_add(Qty(5));
+    assert_eq!(volume, Qty(u64::MAX));
+    volume -= Qty(u64::MAX);
+    assert_eq!(volume, Qty::ZERO);
     
-    let (matched, qty) = pl.match_order(5).unwrap();
-    assert_eq!(matched.remain_quantity, 5);
-    assert_eq!(matched.filled_quantity, 5);
-    assert_eq!(qty, 5);
-    assert_eq!(pl.total_volume, 5);
+    assert_eq!([Price(u64::MAX), Price(1)].into_iter().sum::<Price>(), Price(u64::MAX));
+  }
+  
+  #[test]
+  #[cfg(debug_assertions)]
+  #[should_panic(expected = "overflows")]
+  fn test_fixed_point_overflow_is_a_bug() {
+    let _ = Qty(u64::MAX) + Qty(1);
   }
   
   #[test]
@@ -300,39 +624,41 @@ mod tests {
     let order1 = Order {
       order_id: "o1".into(),
       symbol: "SYM".into(),
-      price: 100,
-      quantity: 5,
+      account: String::new(),
+      price: Price(100),
+      quantity: Qty(5),
       side: Side::Buy,
       order_type: OrderType::Limit,
       status: OrderStatus::New,
-      filled_quantity: 0,
-      remain_quantity: 5,
+      filled_quantity: Qty(0),
+      remain_quantity: Qty(5),
       entry_time: Utc::now()
     };
     
     let order2 = Order {
       order_id: "o2".into(),
       symbol: "SYM".into(),
-      price: 105,
-      quantity: 3,
+      account: String::new(),
+      price: Price(105),
+      quantity: Qty(3),
       side: Side::Buy,
       order_type: OrderType::Limit,
       status: OrderStatus::New,
-      filled_quantity: 0,
-      remain_quantity: 3,
+      filled_quantity: Qty(0),
+      remain_quantity: Qty(3),
       entry_time: Utc::now()
     };
     
     book.add_order(order1);
-    assert_eq!(book.best_level, Some(100));
+    assert_eq!(book.best_level, Some(Price(100)));
     
     book.add_order(order2);
-    assert_eq!(book.best_level, Some(105)); // For buy book, highest price is best
+    assert_eq!(book.best_level, Some(Price(105))); // For buy book, highest price is best
     
     // Verify level retrieval
     let best = book.get_best_level().unwrap();
-    assert_eq!(best.price, 105);
-    assert_eq!(best.total_volume, 3);
+    assert_eq!(best.price, Price(105));
+    assert_eq!(best.total_volume, Qty(3));
   }
   
   #[test]
@@ -342,22 +668,23 @@ mod tests {
     let order = Order {
       order_id: "o2".into(),
       symbol: "SYM".into(),
-      price: 50,
-      quantity: 5,
+      account: String::new(),
+      price: Price(50),
+      quantity: Qty(5),
       side: Side::Buy,
       order_type: OrderType::Limit,
       status: OrderStatus::New,
-      filled_quantity: 0,
-      remain_quantity: 5,
+      filled_quantity: Qty(0),
+      remain_quantity: Qty(5),
       entry_time: Utc::now()
     };
     
     ob.insert_order(order.clone());
-    assert!(ob.buy_book.limits.contains_key(&50));
+    assert!(ob.buy_book.limits.contains_key(&Price(50)));
     
     let cancelled = ob.cancel_order("o2").unwrap();
     assert_eq!(cancelled.order_id, "o2");
-    assert!(!ob.buy_book.limits.contains_key(&50));
-    assert!(ob.order_map.get("o2").is_none());
+    assert!(!ob.buy_book.limits.contains_key(&Price(50)));
+    assert!(!ob.order_map.contains_key("o2"));
   }
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-/**
+/*
 * filename : order_manager
 * author : HAMA
 * date: 2025. 4. 28.
@@ -7,116 +7,752 @@
 
 use warp::{Filter, Rejection, Reply, http::StatusCode};
 use serde::Deserialize;
-use std::sync::Arc;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc::Sender;
-use crate::models::{Order, OrderStatus, OrderType, Side, OrderMessage, Execution};
-use chrono::Utc;
+use crate::auth::AccountRegistry;
+use crate::models::{Order, OrderStatus, OrderType, Side, OrderMessage, Execution, Price, Qty};
+use crate::instrument::{Instrument, InstrumentRegistry, OrderValidationError};
+use crate::matching_engine::OrderOutcome;
+use crate::risk::RiskManager;
+use crate::throttle::Throttle;
+use crate::error::{self, ApiError};
+use crate::util::decimal::DecimalInput;
+use crate::util::serializer::{order_to_dto, execution_to_dto, ExecutionDto, OrderDto};
+use chrono::{DateTime, Utc};
 
+// Price and quantity are decimal strings (or numbers) in the instrument's units, e.g. "0.001"
 #[derive(Deserialize)]
-struct NewOrder {
-  symbol: String,
-  side: Side,
-  price: u64,
-  order_type: OrderType,
-  quantity: u64
+pub(crate) struct NewOrder {
+  pub symbol: String,
+  // Set by the order entry path from the authenticated account, never taken from the request
+  #[serde(skip)]
+  pub account: String,
+  pub side: Side,
+  pub price: DecimalInput,
+  pub order_type: OrderType,
+  pub quantity: DecimalInput,
+  // Unique per account within CLIENT_ORDER_ID_RETENTION; resubmitting returns the original order
+  pub client_order_id: Option<String>
 }
 
+// An order is cancelled either by its order_id or by the account's client_order_id
 #[derive(Deserialize)]
 struct CancelOrder {
-  order_id: String
+  order_id: Option<String>,
+  client_order_id: Option<String>
+}
+
+// Orders and cancels in a batch are sequenced back to back. With all_or_nothing set, any
+// rejected item rejects the whole batch and nothing is sequenced.
+#[derive(Deserialize)]
+struct OrderBatch {
+  orders: Vec<NewOrder>,
+  #[serde(default)]
+  all_or_nothing: bool
+}
+
+#[derive(Deserialize)]
+struct CancelBatch {
+  cancels: Vec<CancelOrder>,
+  #[serde(default)]
+  all_or_nothing: bool
+}
+
+#[derive(Deserialize)]
+struct OrderQuery {
+  order_id: Option<String>,
+  client_order_id: Option<String>
 }
 
 #[derive(Deserialize)]
 struct ExecQuery {
   symbol: Option<String>,
   order_id: Option<String>,
-  start_time: Option<String>,
-  end_time: Option<String>
+  start_time: Option<DateTime<Utc>>, // RFC 3339, inclusive
+  end_time: Option<DateTime<Utc>>    // RFC 3339, exclusive
+}
+
+pub const CLIENT_ORDER_ID_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+pub const MAX_CLIENT_ORDER_ID_LEN: usize = 64;
+pub const MAX_BATCH_SIZE: usize = 100;
+pub const MAX_STORED_EXECUTIONS: usize = 100_000;
+
+// An order accepted through the REST API
+struct SubmittedOrder {
+  order: Order,
+  client_order_id: Option<String>,
+  submitted_at: Instant,
+  cancelled: bool,
+  filled: Qty,
+  rejected: Option<OrderValidationError>, // Set when the matching engine refused the order
+}
+
+// Orders submitted through the REST API during the retention window, for idempotent
+// resubmission and lookups by client order id. Engine outcomes and executions are fed in
+// with on_outcome and on_execution.
+#[derive(Default)]
+pub struct OrderStore {
+  orders: HashMap<String, SubmittedOrder>,             // order id -> order
+  client_order_ids: HashMap<(String, String), String>, // (account, client order id) -> order id
+  submissions: VecDeque<(Instant, String)>,            // (submitted at, order id), oldest first
 }
 
-pub fn routes(order_tx: Sender<OrderMessage>, exec_store: Arc<tokio::sync::Mutex<Vec<Execution>>>) -> impl Filter<Extract = impl Reply> + Clone {
+impl OrderStore {
+  pub fn new() -> Self {
+    OrderStore::default()
+  }
+  
+  // Track what the matching engine did with the REST orders: rejects, cancels for any
+  // reason (including the unfilled rest of a market order) and amends
+  pub fn on_outcome(&mut self, outcome: &OrderOutcome) {
+    let submitted = match self.orders.get_mut(outcome.order_id()) {
+      Some(submitted) => submitted,
+      None => return,
+    };
+    match outcome {
+      OrderOutcome::Rejected { reason, .. } => submitted.rejected = Some(reason.clone()),
+      OrderOutcome::Cancelled { .. } => submitted.cancelled = true,
+      OrderOutcome::Amended { price, quantity, .. } => {
+        submitted.order.price = *price;
+        submitted.order.quantity = *quantity;
+      }
+      OrderOutcome::CancelRejected { .. } => {}
+    }
+  }
+  
+  pub fn on_execution(&mut self, execution: &Execution) {
+    if let Some(submitted) = self.orders.get_mut(&execution.order_id) {
+      submitted.filled += execution.quantity;
+    }
+  }
+  
+  fn insert(&mut self, submitted: SubmittedOrder) {
+    self.submissions.push_back((submitted.submitted_at, submitted.order.order_id.clone()));
+    self.orders.insert(submitted.order.order_id.clone(), submitted);
+  }
+  
+  // Orders are submitted in time order, so only the oldest ones are looked at.
+  // Orders removed earlier are skipped.
+  fn purge_expired(&mut self) {
+    while let Some((submitted_at, _)) = self.submissions.front() {
+      if submitted_at.elapsed() < CLIENT_ORDER_ID_RETENTION {
+        break;
+      }
+      let (_, order_id) = self.submissions.pop_front().unwrap();
+      self.remove(&order_id);
+    }
+  }
+  
+  fn remove(&mut self, order_id: &str) {
+    if let Some(submitted) = self.orders.remove(order_id) {
+      if let Some(client_order_id) = submitted.client_order_id {
+        self.client_order_ids.remove(&(submitted.order.account, client_order_id));
+      }
+    }
+  }
+  
+  // Order id of the order given by order id, or by the account's client order id
+  fn resolve(&self, order_id: Option<&String>, account: &str, client_order_id: Option<&String>) -> Option<String> {
+    match (order_id, client_order_id) {
+      (Some(order_id), _) => Some(order_id.clone()),
+      (None, Some(client_order_id)) => self.client_order_ids.get(&(account.to_string(), client_order_id.clone())).cloned(),
+      (None, None) => None,
+    }
+  }
+}
+
+fn submitted_to_dto(submitted: &SubmittedOrder, instrument: &Instrument) -> OrderDto {
+  let mut dto = order_to_dto(&submitted.order, instrument);
+  dto.client_order_id = submitted.client_order_id.clone();
+  dto
+}
+
+// The order first submitted with the account's client order id
+fn original_order(store: &OrderStore, key: &(String, String), instruments: &InstrumentRegistry) -> Option<OrderDto> {
+  let original = store.orders.get(store.client_order_ids.get(key)?)?;
+  let instrument = instruments.get(&original.order.symbol).unwrap_or_else(|| Instrument::new(&original.order.symbol));
+  Some(submitted_to_dto(original, &instrument))
+}
+
+// Outcome of entering a new order into the store
+enum Submission {
+  Accepted(Order, OrderDto),
+  Duplicate(OrderDto), // The order first submitted with the same client order id
+  Rejected(ApiError),
+}
+
+// Validate a new order and register it before sequencing, so that a concurrent duplicate
+// finds it. A resubmission returns the original order even if it would no longer be
+// accepted, e.g. after a halt.
+fn submit(mut new: NewOrder, instruments: &InstrumentRegistry, risk: &RiskManager, store: &mut OrderStore) -> Submission {
+  let client_order_id = new.client_order_id.take();
+  if client_order_id.as_ref().is_some_and(|id| id.is_empty() || id.len() > MAX_CLIENT_ORDER_ID_LEN) {
+    return Submission::Rejected(ApiError::InvalidClientOrderId { max_len: MAX_CLIENT_ORDER_ID_LEN });
+  }
+  let key = client_order_id.clone().map(|id| (new.account.clone(), id));
+  if let Some(dto) = key.as_ref().and_then(|key| original_order(store, key, instruments)) {
+    return Submission::Duplicate(dto);
+  }
+  
+  let (order, instrument) = match prepare_order(new, instruments) {
+    Ok(accepted) => accepted,
+    Err(e) => return Submission::Rejected(e.into()),
+  };
+  if let Err(e) = risk.admit(&order, &instrument) {
+    return Submission::Rejected(e.into());
+  }
+  let submitted = SubmittedOrder { order: order.clone(), client_order_id, submitted_at: Instant::now(), cancelled: false, filled: Qty::ZERO, rejected: None };
+  let dto = submitted_to_dto(&submitted, &instrument);
+  if let Some(key) = key {
+    store.client_order_ids.insert(key, order.order_id.clone());
+  }
+  store.insert(submitted);
+  Submission::Accepted(order, dto)
+}
+
+// The cancel message for an order of the account given by order id or client order id.
+// Another account's order is unknown.
+fn build_cancel(cancel: &CancelOrder, account: &str, store: &OrderStore) -> Result<Order, ApiError> {
+  let order_id = store.resolve(cancel.order_id.as_ref(), account, cancel.client_order_id.as_ref());
+  let known = order_id.and_then(|order_id| store.orders.get(&order_id));
+  let (order_id, symbol, side) = match (known, &cancel.order_id) {
+    (Some(submitted), _) if submitted.order.account != account => return Err(ApiError::UnknownOrder),
+    (Some(submitted), _) => {
+      let order = &submitted.order;
+      (order.order_id.clone(), order.symbol.clone(), order.side.clone())
+    }
+    // Orders entered elsewhere are cancelled by order id alone; the matching engine only
+    // cancels them if they belong to the account
+    (None, Some(order_id)) => (order_id.clone(), String::new(), Side::Buy),
+    (None, None) => return Err(match cancel.client_order_id {
+      Some(_) => ApiError::UnknownOrder,
+      None => missing_order_id(),
+    }),
+  };
+  
+  Ok(Order {
+    order_id,
+    symbol,
+    account: account.to_string(),
+    price: Price::ZERO,
+    quantity: Qty::ZERO,
+    side,
+    order_type: OrderType::Limit,
+    status: OrderStatus::Cancelled,
+    filled_quantity: Qty::ZERO,
+    remain_quantity: Qty::ZERO,
+    entry_time: Utc::now()
+  })
+}
+
+fn missing_order_id() -> ApiError {
+  ApiError::InvalidRequest("order_id or client_order_id is required".to_string())
+}
+
+// Keep the most recent executions for GET /v1/execution, dropping the oldest in chunks
+pub fn store_execution(store: &mut Vec<Execution>, execution: Execution) {
+  store.push(execution);
+  if store.len() > MAX_STORED_EXECUTIONS {
+    store.drain(..store.len() - MAX_STORED_EXECUTIONS * 9 / 10);
+  }
+}
+
+fn mark_cancelled(store: &mut OrderStore, order_id: &str) {
+  if let Some(submitted) = store.orders.get_mut(order_id) {
+    submitted.cancelled = true;
+  }
+}
+
+// What the order routes share with the rest of the server. Risk limits, rate limits and the
+// order store start out as unconfigured ones of their own; set the fields to share them.
+// Order states follow the engine outcomes given to the store, see OrderStore::on_outcome.
+pub struct OrderApi {
+  pub order_tx: Sender<OrderMessage>,
+  pub exec_store: Arc<tokio::sync::Mutex<Vec<Execution>>>,
+  pub instruments: Arc<InstrumentRegistry>,
+  pub accounts: Arc<AccountRegistry>,
+  pub risk: Arc<RiskManager>, // New orders pass the pre-trade risk checks before they are sequenced
+  pub throttle: Arc<Throttle>, // Sequenced requests are rate limited and refused while the sequencer queue is backed up
+  pub orders: Arc<Mutex<OrderStore>>,
+}
+
+impl OrderApi {
+  pub fn new(
+    order_tx: Sender<OrderMessage>,
+    exec_store: Arc<tokio::sync::Mutex<Vec<Execution>>>,
+    instruments: Arc<InstrumentRegistry>,
+    accounts: Arc<AccountRegistry>
+  ) -> Self {
+    OrderApi {
+      order_tx,
+      exec_store,
+      instruments,
+      accounts,
+      risk: Arc::new(RiskManager::new()),
+      throttle: Arc::new(Throttle::default()),
+      orders: Arc::new(Mutex::new(OrderStore::new())),
+    }
+  }
+}
+
+// Orders are entered, looked up and cancelled for the account of the request's API key
+// (X-API-Key header)
+pub fn routes(api: OrderApi) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+  let OrderApi { order_tx, exec_store, instruments, accounts, risk, throttle, orders } = api;
+  
   let post_order = warp::path!("v1" / "order")
     .and(warp::post())
+    .and(admitted(accounts.clone(), throttle.clone(), order_tx.clone()))
     .and(warp::body::json())
     .and(with_tx(order_tx.clone()))
+    .and(with_throttle(throttle.clone()))
+    .and(with_instruments(instruments.clone()))
+    .and(with_risk(risk.clone()))
+    .and(with_orders(orders.clone()))
     .and_then(handle_post_order);
   
+  let get_order = warp::path!("v1" / "order")
+    .and(warp::get())
+    .and(authenticated(accounts.clone()))
+    .and(warp::query::<OrderQuery>())
+    .and(with_orders(orders.clone()))
+    .and(with_instruments(instruments.clone()))
+    .and_then(handle_get_order);
+  
   let cancel_order = warp::path!("v1" / "order" / "cancel")
     .and(warp::post())
+    .and(admitted(accounts.clone(), throttle.clone(), order_tx.clone()))
     .and(warp::body::json())
     .and(with_tx(order_tx.clone()))
+    .and(with_throttle(throttle.clone()))
+    .and(with_instruments(instruments.clone()))
+    .and(with_orders(orders.clone()))
     .and_then(handle_cancel_order);
   
+  let post_order_batch = warp::path!("v1" / "orders" / "batch")
+    .and(warp::post())
+    .and(admitted(accounts.clone(), throttle.clone(), order_tx.clone()))
+    .and(warp::body::json())
+    .and(with_tx(order_tx.clone()))
+    .and(with_throttle(throttle.clone()))
+    .and(with_instruments(instruments.clone()))
+    .and(with_risk(risk))
+    .and(with_orders(orders.clone()))
+    .and_then(handle_post_order_batch);
+  
+  let cancel_order_batch = warp::path!("v1" / "orders" / "cancel-batch")
+    .and(warp::post())
+    .and(admitted(accounts.clone(), throttle.clone(), order_tx.clone()))
+    .and(warp::body::json())
+    .and(with_tx(order_tx.clone()))
+    .and(with_throttle(throttle.clone()))
+    .and(with_instruments(instruments.clone()))
+    .and(with_orders(orders))
+    .and_then(handle_cancel_order_batch);
+  
   let get_executions = warp::path!("v1" / "execution")
     .and(warp::get())
+    .and(authenticated(accounts))
     .and(warp::query::<ExecQuery>())
     .and(with_store(exec_store.clone()))
+    .and(with_instruments(instruments.clone()))
     .and_then(handle_get_executions);
   
-  post_order.or(cancel_order).or(get_executions)
+  let get_metrics = warp::path!("v1" / "metrics")
+    .and(warp::get())
+    .map(move || warp::reply::json(&throttle.metrics(&order_tx)));
+  
+  post_order.or(get_order).or(cancel_order).or(post_order_batch).or(cancel_order_batch).or(get_executions).or(get_metrics)
+    .recover(error::handle_rejection)
 }
 
-fn with_tx(tx: Sender<OrderMessage>) -> impl Filter<Extract = (Sender<OrderMessage>,)> + Clone {
+// The account of the request's API key, for clients within their rate limits while the
+// sequencer keeps up. Requests are limited by IP before they are authenticated, and by API
+// key only once the key is known, so that made up keys are answered with 401.
+fn admitted(accounts: Arc<AccountRegistry>, throttle: Arc<Throttle>, tx: Sender<OrderMessage>) -> impl Filter<Extract = (String,), Error = Rejection> + Clone {
+  warp::header::optional::<String>("x-api-key")
+    .and(warp::addr::remote())
+    .and_then(move |api_key: Option<String>, addr: Option<SocketAddr>| {
+      let authenticated = api_key.and_then(|api_key| accounts.authenticate(&api_key).map(|account| (api_key, account)));
+      let admitted = throttle.admit(authenticated.as_ref().map(|(api_key, _)| api_key.as_str()), addr.map(|addr| addr.ip()), &tx)
+        .map_err(Rejection::from)
+        .and_then(|_| authenticated.map(|(_, account)| account).ok_or_else(|| Rejection::from(ApiError::Unauthorized)));
+      async move { admitted }
+    })
+}
+
+// The account of the request's API key, or 401
+fn authenticated(accounts: Arc<AccountRegistry>) -> impl Filter<Extract = (String,), Error = Rejection> + Clone {
+  warp::header::optional::<String>("x-api-key")
+    .and_then(move |api_key: Option<String>| {
+      let account = api_key.and_then(|api_key| accounts.authenticate(&api_key)).ok_or_else(|| Rejection::from(ApiError::Unauthorized));
+      async move { account }
+    })
+}
+
+fn with_throttle(throttle: Arc<Throttle>) -> impl Filter<Extract = (Arc<Throttle>,), Error = Infallible> + Clone {
+  warp::any().map(move || throttle.clone())
+}
+
+fn with_tx(tx: Sender<OrderMessage>) -> impl Filter<Extract = (Sender<OrderMessage>,), Error = Infallible> + Clone {
   warp::any().map(move || tx.clone())
 }
 
-fn with_store(store: Arc<tokio::sync::Mutex<Vec<Execution>>>) -> impl Filter<Extract = (Arc<tokio::sync::Mutex<Vec<Execution>>>,)> + Clone {
+fn with_instruments(instruments: Arc<InstrumentRegistry>) -> impl Filter<Extract = (Arc<InstrumentRegistry>,), Error = Infallible> + Clone {
+  warp::any().map(move || instruments.clone())
+}
+
+fn with_risk(risk: Arc<RiskManager>) -> impl Filter<Extract = (Arc<RiskManager>,), Error = Infallible> + Clone {
+  warp::any().map(move || risk.clone())
+}
+
+fn with_orders(orders: Arc<Mutex<OrderStore>>) -> impl Filter<Extract = (Arc<Mutex<OrderStore>>,), Error = Infallible> + Clone {
+  warp::any().map(move || orders.clone())
+}
+
+fn with_store(store: Arc<tokio::sync::Mutex<Vec<Execution>>>) -> impl Filter<Extract = (Arc<tokio::sync::Mutex<Vec<Execution>>>,), Error = Infallible> + Clone {
   warp::any().map(move || store.clone())
 }
 
-async fn handle_post_order(new: NewOrder, tx: Sender<OrderMessage>) -> Result<impl Reply, Rejection> {
-  // In a real system, we would validate account balance here
+// Convert the decimal request into an order in raw integer units
+fn build_order(new: NewOrder, instrument: &Instrument) -> Result<Order, OrderValidationError> {
+  let price = instrument.parse_price(&new.price.0)?;
+  let quantity = instrument.parse_quantity(&new.quantity.0)?;
   
-  let order = Order {
+  Ok(Order {
     order_id: uuid::Uuid::new_v4().to_string(),
     symbol: new.symbol,
-    price: new.price,
-    quantity: new.quantity,
+    account: new.account,
+    price,
+    quantity,
     side: new.side,
     order_type: new.order_type,
     status: OrderStatus::New,
-    filled_quantity: 0,
-    remain_quantity: new.quantity,
+    filled_quantity: Qty::ZERO,
+    remain_quantity: quantity,
     entry_time: Utc::now()
+  })
+}
+
+// Reject orders that cannot be represented in the instrument's scales or that violate
+// its tick, lot and size constraints before sequencing
+pub(crate) fn prepare_order(new: NewOrder, instruments: &InstrumentRegistry) -> Result<(Order, Instrument), OrderValidationError> {
+  let instrument = instruments.get(&new.symbol)
+    .ok_or_else(|| OrderValidationError::UnknownSymbol(new.symbol.clone()))?;
+  let order = build_order(new, &instrument)?;
+  instrument.validate_order(&order)?;
+  Ok((order, instrument))
+}
+
+async fn handle_post_order(
+  account: String,
+  mut new: NewOrder,
+  tx: Sender<OrderMessage>,
+  throttle: Arc<Throttle>,
+  instruments: Arc<InstrumentRegistry>,
+  risk: Arc<RiskManager>,
+  orders: Arc<Mutex<OrderStore>>
+) -> Result<impl Reply, Rejection> {
+  new.account = account;
+  let submission = {
+    let mut store = orders.lock().unwrap();
+    store.purge_expired();
+    submit(new, &instruments, &risk, &mut store)
+  };
+  let (order, dto) = match submission {
+    Submission::Accepted(order, dto) => (order, dto),
+    Submission::Duplicate(dto) => return Ok(warp::reply::with_status(warp::reply::json(&dto), StatusCode::OK)),
+    Submission::Rejected(e) => return Err(e.into()),
   };
   
-  tx.send(OrderMessage(order.clone())).await.map_err(|_| warp::reject())?;
-  Ok(warp::reply::with_status(warp::reply::json(&order), StatusCode::CREATED))
+  if let Err(e) = throttle.try_send(&tx, OrderMessage::Order(order.clone())) {
+    orders.lock().unwrap().remove(&order.order_id);
+    risk.release(&order.order_id);
+    return Err(e.into());
+  }
+  Ok(warp::reply::with_status(warp::reply::json(&dto), StatusCode::CREATED))
+}
+
+async fn handle_get_order(
+  account: String,
+  q: OrderQuery,
+  orders: Arc<Mutex<OrderStore>>,
+  instruments: Arc<InstrumentRegistry>
+) -> Result<impl Reply, Rejection> {
+  if q.order_id.is_none() && q.client_order_id.is_none() {
+    return Err(missing_order_id().into());
+  }
+  let store = orders.lock().unwrap();
+  let dto = match store.resolve(q.order_id.as_ref(), &account, q.client_order_id.as_ref()).and_then(|order_id| store.orders.get(&order_id)) {
+    Some(submitted) if submitted.order.account == account => current_order_dto(submitted, &instruments),
+    _ => return Err(ApiError::UnknownOrder.into()),
+  };
+  Ok(warp::reply::with_status(warp::reply::json(&dto), StatusCode::OK))
 }
 
-async fn handle_cancel_order(cancel: CancelOrder, tx: Sender<OrderMessage>) -> Result<impl Reply, Rejection> {
-  // In a real system, we would validate order ownership here
+// The order's state follows from its executions and whether it was cancelled
+fn current_order_dto(submitted: &SubmittedOrder, instruments: &InstrumentRegistry) -> OrderDto {
+  let mut order = submitted.order.clone();
+  order.filled_quantity = submitted.filled.min(order.quantity);
+  order.status = if submitted.rejected.is_some() {
+    OrderStatus::Rejected
+  } else if order.filled_quantity >= order.quantity {
+    OrderStatus::Filled
+  } else if submitted.cancelled {
+    OrderStatus::Cancelled
+  } else if !order.filled_quantity.is_zero() {
+    OrderStatus::PartiallyFilled
+  } else {
+    OrderStatus::New
+  };
+  order.remain_quantity = match order.status {
+    OrderStatus::Cancelled | OrderStatus::Filled | OrderStatus::Rejected => Qty::ZERO,
+    _ => order.quantity - order.filled_quantity,
+  };
   
-  let order = Order {
-    order_id: cancel.order_id,
-    symbol: String::new(), // These fields would be populated from a database lookup
-    price: 0,
-    quantity: 0,
-    side: Side::Buy,
-    order_type: OrderType::Limit,
-    status: OrderStatus::Cancelled,
-    filled_quantity: 0,
-    remain_quantity: 0,
-    entry_time: Utc::now()
+  let instrument = instruments.get(&order.symbol).unwrap_or_else(|| Instrument::new(&order.symbol));
+  let mut dto = order_to_dto(&order, &instrument);
+  dto.client_order_id = submitted.client_order_id.clone();
+  dto.reject_reason = submitted.rejected.as_ref().map(|reason| reason.code().to_string());
+  dto
+}
+
+// The order stops counting towards the risk limits once the engine reports it cancelled
+async fn handle_cancel_order(
+  account: String,
+  cancel: CancelOrder,
+  tx: Sender<OrderMessage>,
+  throttle: Arc<Throttle>,
+  instruments: Arc<InstrumentRegistry>,
+  orders: Arc<Mutex<OrderStore>>
+) -> Result<impl Reply, Rejection> {
+  let mut store = orders.lock().unwrap();
+  let order = build_cancel(&cancel, &account, &store)?;
+  
+  // A cancel the sequencer did not take leaves the order as it was
+  throttle.try_send(&tx, OrderMessage::Order(order.clone()))?;
+  mark_cancelled(&mut store, &order.order_id);
+  Ok(warp::reply::with_status(warp::reply::json(&cancelled_to_dto(&order, &store, &instruments)), StatusCode::OK))
+}
+
+// Cancels are answered with the cancelled order in the same decimal form as order queries;
+// orders entered elsewhere are only known by the cancel itself
+fn cancelled_to_dto(cancel: &Order, store: &OrderStore, instruments: &InstrumentRegistry) -> OrderDto {
+  match store.orders.get(&cancel.order_id) {
+    Some(submitted) => current_order_dto(submitted, instruments),
+    None => {
+      let instrument = instruments.get(&cancel.symbol).unwrap_or_else(|| Instrument::new(&cancel.symbol));
+      order_to_dto(cancel, &instrument)
+    }
+  }
+}
+
+fn check_batch_size(len: usize) -> Result<(), ApiError> {
+  if len == 0 || len > MAX_BATCH_SIZE {
+    return Err(ApiError::BatchTooLarge { max: MAX_BATCH_SIZE });
+  }
+  Ok(())
+}
+
+fn rejected_item(error: &ApiError) -> serde_json::Value {
+  serde_json::json!({ "status": "rejected", "error": error.code(), "message": error.to_string() })
+}
+
+// Every item is answered in request order; with all_or_nothing, items that were valid
+// are reported as BATCH_REJECTED when another item was rejected
+fn batch_reply(results: Vec<serde_json::Value>, batch_rejected: bool) -> warp::reply::WithStatus<warp::reply::Json> {
+  if batch_rejected {
+    let body = serde_json::json!({
+      "error": ApiError::BatchRejected.code(),
+      "message": "an item in the batch was rejected",
+      "results": results
+    });
+    return warp::reply::with_status(warp::reply::json(&body), ApiError::BatchRejected.status());
+  }
+  warp::reply::with_status(warp::reply::json(&serde_json::json!({ "results": results })), StatusCode::OK)
+}
+
+async fn handle_post_order_batch(
+  account: String,
+  batch: OrderBatch,
+  tx: Sender<OrderMessage>,
+  throttle: Arc<Throttle>,
+  instruments: Arc<InstrumentRegistry>,
+  risk: Arc<RiskManager>,
+  orders: Arc<Mutex<OrderStore>>
+) -> Result<impl Reply, Rejection> {
+  check_batch_size(batch.orders.len())?;
+  
+  let submissions: Vec<Submission> = {
+    let mut store = orders.lock().unwrap();
+    store.purge_expired();
+    let submissions: Vec<Submission> = batch.orders.into_iter()
+      .map(|new| submit(NewOrder { account: account.clone(), ..new }, &instruments, &risk, &mut store))
+      .collect();
+    let rejected = submissions.iter().any(|submission| matches!(submission, Submission::Rejected(..)));
+    if batch.all_or_nothing && rejected {
+      let mut results = Vec::with_capacity(submissions.len());
+      let mut withdrawn: Vec<String> = Vec::new();
+      for submission in submissions {
+        results.push(match submission {
+          Submission::Accepted(order, _) => {
+            store.remove(&order.order_id);
+            risk.release(&order.order_id);
+            withdrawn.push(order.order_id);
+            rejected_item(&ApiError::BatchRejected)
+          }
+          // A duplicate of an order withdrawn above was never entered either
+          Submission::Duplicate(dto) if withdrawn.contains(&dto.order_id) => {
+            rejected_item(&ApiError::BatchRejected)
+          }
+          Submission::Duplicate(dto) => serde_json::json!({ "status": "duplicate", "order": dto }),
+          Submission::Rejected(e) => rejected_item(&e),
+        });
+      }
+      return Ok(batch_reply(results, true));
+    }
+    submissions
   };
   
-  tx.send(OrderMessage(order.clone())).await.map_err(|_| warp::reject())?;
-  Ok(warp::reply::with_status(warp::reply::json(&order), StatusCode::OK))
+  let mut messages = Vec::new();
+  let mut results = Vec::with_capacity(submissions.len());
+  for submission in submissions {
+    results.push(match submission {
+      Submission::Accepted(order, dto) => {
+        messages.push(OrderMessage::Order(order));
+        serde_json::json!({ "status": "accepted", "order": dto })
+      }
+      Submission::Duplicate(dto) => serde_json::json!({ "status": "duplicate", "order": dto }),
+      Submission::Rejected(e) => rejected_item(&e),
+    });
+  }
+  
+  // One message, so that nothing is sequenced between the batch's orders
+  if !messages.is_empty() {
+    let order_ids: Vec<String> = messages.iter().filter_map(|message| match message {
+      OrderMessage::Order(order) => Some(order.order_id.clone()),
+      _ => None,
+    }).collect();
+    if let Err(e) = throttle.try_send(&tx, OrderMessage::Batch(messages)) {
+      let mut store = orders.lock().unwrap();
+      for order_id in order_ids {
+        store.remove(&order_id);
+        risk.release(&order_id);
+      }
+      return Err(e.into());
+    }
+  }
+  Ok(batch_reply(results, false))
 }
 
-async fn handle_get_executions(q: ExecQuery, store: Arc<tokio::sync::Mutex<Vec<Execution>>>) -> Result<impl Reply, Rejection> {
-  let data = store.lock().await.clone();
+async fn handle_cancel_order_batch(
+  account: String,
+  batch: CancelBatch,
+  tx: Sender<OrderMessage>,
+  throttle: Arc<Throttle>,
+  instruments: Arc<InstrumentRegistry>,
+  orders: Arc<Mutex<OrderStore>>
+) -> Result<impl Reply, Rejection> {
+  check_batch_size(batch.cancels.len())?;
   
-  let filtered: Vec<Execution> = data.into_iter().filter(|e| {
+  let mut accepted = Vec::new();
+  let mut results = Vec::with_capacity(batch.cancels.len());
+  let mut store = orders.lock().unwrap();
+  let cancels: Vec<_> = batch.cancels.iter().map(|cancel| build_cancel(cancel, &account, &store)).collect();
+  let rejected = cancels.iter().any(Result::is_err);
+  for cancel in cancels {
+    results.push(match cancel {
+      Ok(_) if batch.all_or_nothing && rejected => rejected_item(&ApiError::BatchRejected),
+      // Answered once the sequencer has taken the batch
+      Ok(order) => {
+        accepted.push((results.len(), order));
+        serde_json::Value::Null
+      }
+      Err(e) => rejected_item(&e),
+    });
+  }
+  if batch.all_or_nothing && rejected {
+    return Ok(batch_reply(results, true));
+  }
+  
+  if !accepted.is_empty() {
+    let messages = accepted.iter().map(|(_, order)| OrderMessage::Order(order.clone())).collect();
+    throttle.try_send(&tx, OrderMessage::Batch(messages))?;
+    for (index, order) in &accepted {
+      mark_cancelled(&mut store, &order.order_id);
+      results[*index] = serde_json::json!({ "status": "accepted", "order": cancelled_to_dto(order, &store, &instruments) });
+    }
+  }
+  Ok(batch_reply(results, false))
+}
+
+// The account's own executions only
+async fn handle_get_executions(
+  account: String,
+  q: ExecQuery,
+  store: Arc<tokio::sync::Mutex<Vec<Execution>>>,
+  instruments: Arc<InstrumentRegistry>
+) -> Result<impl Reply, Rejection> {
+  let filtered: Vec<ExecutionDto> = store.lock().await.iter().filter(|e| {
+    if e.account != account {
+      return false;
+    }
     if let Some(ref sym) = q.symbol {
       if &e.symbol != sym { return false; }
     }
     if let Some(ref oid) = q.order_id {
       if &e.order_id != oid { return false; }
     }
-    // Time-based filtering could be added here
-    true
+    q.start_time.is_none_or(|start| e.transaction_time >= start) && q.end_time.is_none_or(|end| e.transaction_time < end)
+  }).map(|e| {
+    let instrument = instruments.get(&e.symbol).unwrap_or_else(|| Instrument::new(&e.symbol));
+    execution_to_dto(e, &instrument)
   }).collect();
   
   Ok(warp::reply::json(&filtered))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  
+  fn submitted(order_id: &str, client_order_id: &str, submitted_at: Instant) -> SubmittedOrder {
+    let order = Order {
+      order_id: order_id.into(),
+      symbol: "SYM".into(),
+      account: "acc1".into(),
+      price: Price(100),
+      quantity: Qty(1),
+      side: Side::Buy,
+      order_type: OrderType::Limit,
+      status: OrderStatus::New,
+      filled_quantity: Qty::ZERO,
+      remain_quantity: Qty(1),
+      entry_time: Utc::now(),
+    };
+    SubmittedOrder { order, client_order_id: Some(client_order_id.into()), submitted_at, cancelled: false, filled: Qty::ZERO, rejected: None }
+  }
+  
+  #[test]
+  fn test_purge_expired() {
+    let mut store = OrderStore::new();
+    let expired_at = Instant::now() - CLIENT_ORDER_ID_RETENTION;
+    for (order_id, client_order_id) in [("o1", "c1"), ("o2", "c2")] {
+      store.client_order_ids.insert(("acc1".into(), client_order_id.into()), order_id.into());
+      store.insert(submitted(order_id, client_order_id, expired_at));
+    }
+    store.client_order_ids.insert(("acc1".into(), "c3".into()), "o3".into());
+    store.insert(submitted("o3", "c3", Instant::now()));
+    // Orders removed before they expire are skipped
+    store.remove("o2");
+    
+    store.purge_expired();
+    assert_eq!(store.orders.keys().collect::<Vec<_>>(), vec!["o3"]);
+    assert_eq!(store.client_order_ids.len(), 1);
+    assert_eq!(store.submissions.len(), 1);
+  }
+}
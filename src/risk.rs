@@ -0,0 +1,616 @@
+/*
+* filename : risk
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 계정별 사전 리스크 한도 (주문 금액, 미체결 주문 수, 포지션, 주문 속도, 가격 이탈)
+**/
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use crate::instrument::Instrument;
+use crate::matching_engine::OrderOutcome;
+use crate::models::{Execution, Order, OrderType, Price, Qty, Side};
+
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+// Per-account limits. A limit of 0 disables it.
+// max_order_notional is a quote amount in raw units of the instrument's price_scale,
+// max_position is the absolute net quantity per symbol including open orders, and
+// max_price_deviation_bps is the allowed distance of a limit price from the last trade.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RiskLimits {
+  pub max_order_notional: u64,
+  pub max_open_orders_per_symbol: u64,
+  pub max_position: Qty,
+  pub max_orders_per_second: u64,
+  pub max_price_deviation_bps: u64,
+}
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum RiskRejection {
+  #[error("order notional {notional} exceeds the limit {max}")]
+  MaxOrderNotional { notional: u64, max: u64 },
+  #[error("{open} open orders on {symbol} reach the limit {max}")]
+  MaxOpenOrders { symbol: String, open: u64, max: u64 },
+  #[error("position of {projected} on {symbol} would exceed the limit {max}")]
+  MaxPosition { symbol: String, projected: i128, max: Qty },
+  #[error("more than {max} orders per second")]
+  OrderRate { max: u64 },
+  #[error("price {price} is more than {max_bps} bps away from the last trade {last}")]
+  PriceDeviation { price: Price, last: Price, max_bps: u64 },
+  #[error("{message}")]
+  Custom { code: &'static str, message: String },
+}
+
+impl RiskRejection {
+  // Stable machine readable code for API responses
+  pub fn code(&self) -> &'static str {
+    match self {
+      RiskRejection::MaxOrderNotional { .. } => "RISK_MAX_ORDER_NOTIONAL",
+      RiskRejection::MaxOpenOrders { .. } => "RISK_MAX_OPEN_ORDERS",
+      RiskRejection::MaxPosition { .. } => "RISK_MAX_POSITION",
+      RiskRejection::OrderRate { .. } => "RISK_ORDER_RATE",
+      RiskRejection::PriceDeviation { .. } => "RISK_PRICE_DEVIATION",
+      RiskRejection::Custom { code, .. } => code,
+    }
+  }
+}
+
+// The account's exposure on the order's symbol before the order is admitted
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Exposure {
+  pub open_orders: u64,
+  pub open_buy: Qty,
+  pub open_sell: Qty,
+  pub position: i128, // Net filled quantity, negative when short
+  pub last_price: Option<Price>,
+}
+
+// Additional checks run after the built-in limits, e.g. restricted symbols or credit
+pub trait RiskCheck: Send + Sync {
+  fn check(&self, order: &Order, instrument: &Instrument, exposure: &Exposure) -> Result<(), RiskRejection>;
+}
+
+impl RiskLimits {
+  pub fn check(&self, order: &Order, instrument: &Instrument, exposure: &Exposure) -> Result<(), RiskRejection> {
+    // Market orders are valued at the last trade
+    let price = match order.order_type {
+      OrderType::Limit => Some(order.price),
+      OrderType::Market => exposure.last_price,
+    };
+    if let (true, Some(price)) = (self.max_order_notional > 0, price) {
+      let notional = instrument.notional(price, order.quantity).unwrap_or(u64::MAX);
+      if notional > self.max_order_notional {
+        return Err(RiskRejection::MaxOrderNotional { notional, max: self.max_order_notional });
+      }
+    }
+    
+    if let (true, OrderType::Limit, Some(last)) = (self.max_price_deviation_bps > 0, &order.order_type, exposure.last_price) {
+      let deviation = (order.price.0 as i128 - last.0 as i128).unsigned_abs() * 10_000;
+      if deviation > last.0 as u128 * self.max_price_deviation_bps as u128 {
+        return Err(RiskRejection::PriceDeviation { price: order.price, last, max_bps: self.max_price_deviation_bps });
+      }
+    }
+    
+    if self.max_open_orders_per_symbol > 0 && exposure.open_orders >= self.max_open_orders_per_symbol {
+      return Err(RiskRejection::MaxOpenOrders {
+        symbol: order.symbol.clone(),
+        open: exposure.open_orders,
+        max: self.max_open_orders_per_symbol,
+      });
+    }
+    
+    // Worst case: every open order on the same side fills as well
+    if !self.max_position.is_zero() {
+      let projected = match order.side {
+        Side::Buy => exposure.position + exposure.open_buy.0 as i128 + order.quantity.0 as i128,
+        Side::Sell => exposure.position - exposure.open_sell.0 as i128 - order.quantity.0 as i128,
+      };
+      if projected.unsigned_abs() > self.max_position.0 as u128 {
+        return Err(RiskRejection::MaxPosition { symbol: order.symbol.clone(), projected, max: self.max_position });
+      }
+    }
+    Ok(())
+  }
+}
+
+// An admitted order that may still fill
+struct OpenOrder {
+  account: String,
+  symbol: String,
+  side: Side,
+  filled: Qty,
+  remaining: Qty,
+  amends: VecDeque<Qty>, // Total quantities of admitted amends the engine has not answered yet
+}
+
+impl OpenOrder {
+  // What may still fill: the open quantity, or more if a pending amend raises it
+  fn exposed(&self) -> Qty {
+    self.amends.iter()
+      .map(|quantity| *quantity - (*quantity).min(self.filled))
+      .fold(self.remaining, Qty::max)
+  }
+}
+
+// Running totals of the open orders of an account on a symbol
+#[derive(Default)]
+struct OpenTotals {
+  orders: u64,
+  buy: u128,
+  sell: u128,
+}
+
+#[derive(Default)]
+struct RiskState {
+  open_orders: HashMap<String, OpenOrder>,             // order id -> order
+  open_totals: HashMap<(String, String), OpenTotals>,  // (account, symbol) -> open orders
+  positions: HashMap<(String, String), i128>,          // (account, symbol) -> net filled quantity
+  admitted: HashMap<String, VecDeque<Instant>>,        // account -> orders admitted within RATE_WINDOW
+  last_prices: HashMap<String, Price>,
+}
+
+// Add an open order to, or take it out of, the totals of its account and symbol
+fn count_open(totals: &mut HashMap<(String, String), OpenTotals>, open: &OpenOrder, added: bool) {
+  let key = (open.account.clone(), open.symbol.clone());
+  let entry = totals.entry(key.clone()).or_default();
+  let exposed = open.exposed().0 as u128;
+  let side = match open.side {
+    Side::Buy => &mut entry.buy,
+    Side::Sell => &mut entry.sell,
+  };
+  if added {
+    entry.orders += 1;
+    *side += exposed;
+  } else {
+    entry.orders -= 1;
+    *side -= exposed;
+  }
+  if entry.orders == 0 {
+    totals.remove(&key);
+  }
+}
+
+impl RiskState {
+  fn exposure(&self, account: &str, symbol: &str) -> Exposure {
+    let key = (account.to_string(), symbol.to_string());
+    let totals = self.open_totals.get(&key);
+    let quantity = |total: u128| Qty(u64::try_from(total).unwrap_or(u64::MAX));
+    Exposure {
+      open_orders: totals.map_or(0, |totals| totals.orders),
+      open_buy: quantity(totals.map_or(0, |totals| totals.buy)),
+      open_sell: quantity(totals.map_or(0, |totals| totals.sell)),
+      position: self.positions.get(&key).copied().unwrap_or(0),
+      last_price: self.last_prices.get(symbol).copied(),
+    }
+  }
+  
+  // The open orders only change through these three, which keep the totals up to date
+  fn add_open(&mut self, order_id: &str, open: OpenOrder) {
+    self.remove_open(order_id);
+    count_open(&mut self.open_totals, &open, true);
+    self.open_orders.insert(order_id.to_string(), open);
+  }
+  
+  fn remove_open(&mut self, order_id: &str) -> Option<OpenOrder> {
+    let open = self.open_orders.remove(order_id)?;
+    count_open(&mut self.open_totals, &open, false);
+    Some(open)
+  }
+  
+  fn update_open(&mut self, order_id: &str, change: impl FnOnce(&mut OpenOrder)) {
+    if let Some(open) = self.open_orders.get_mut(order_id) {
+      count_open(&mut self.open_totals, open, false);
+      change(open);
+      count_open(&mut self.open_totals, open, true);
+    }
+  }
+  
+  // Count an order towards the account's order rate, unless the rate is reached
+  fn take_rate(&mut self, account: &str, max_orders_per_second: u64) -> Result<(), RiskRejection> {
+    let now = Instant::now();
+    let admitted = self.admitted.entry(account.to_string()).or_default();
+    while admitted.front().is_some_and(|at| now.duration_since(*at) >= RATE_WINDOW) {
+      admitted.pop_front();
+    }
+    if max_orders_per_second > 0 && admitted.len() as u64 >= max_orders_per_second {
+      return Err(RiskRejection::OrderRate { max: max_orders_per_second });
+    }
+    admitted.push_back(now);
+    Ok(())
+  }
+}
+
+// Pre-trade risk stage shared by every order entry path (REST, WebSocket sessions, FIX and
+// binary) in front of the sequencer. Orders are checked against their account's limits (or
+// the defaults) and the exposure from orders admitted here, which is kept up to date from
+// executions and engine outcomes.
+pub struct RiskManager {
+  defaults: RwLock<RiskLimits>,
+  accounts: RwLock<HashMap<String, RiskLimits>>,
+  checks: RwLock<Vec<Box<dyn RiskCheck>>>,
+  state: Mutex<RiskState>,
+}
+
+impl Default for RiskManager {
+  fn default() -> Self {
+    RiskManager::new()
+  }
+}
+
+impl RiskManager {
+  // No limits until they are configured
+  pub fn new() -> Self {
+    RiskManager::with_limits(RiskLimits::default())
+  }
+  
+  pub fn with_limits(defaults: RiskLimits) -> Self {
+    RiskManager {
+      defaults: RwLock::new(defaults),
+      accounts: RwLock::new(HashMap::new()),
+      checks: RwLock::new(Vec::new()),
+      state: Mutex::new(RiskState::default()),
+    }
+  }
+  
+  pub fn add_check(&self, check: impl RiskCheck + 'static) {
+    self.checks.write().unwrap().push(Box::new(check));
+  }
+  
+  pub fn default_limits(&self) -> RiskLimits {
+    self.defaults.read().unwrap().clone()
+  }
+  
+  pub fn set_default_limits(&self, limits: RiskLimits) {
+    *self.defaults.write().unwrap() = limits;
+  }
+  
+  // The account's own limits, or the defaults
+  pub fn limits(&self, account: &str) -> RiskLimits {
+    self.accounts.read().unwrap().get(account).cloned().unwrap_or_else(|| self.default_limits())
+  }
+  
+  pub fn set_limits(&self, account: &str, limits: RiskLimits) {
+    self.accounts.write().unwrap().insert(account.to_string(), limits);
+  }
+  
+  // The account falls back to the defaults
+  pub fn remove_limits(&self, account: &str) -> Option<RiskLimits> {
+    self.accounts.write().unwrap().remove(account)
+  }
+  
+  pub fn exposure(&self, account: &str, symbol: &str) -> Exposure {
+    self.state.lock().unwrap().exposure(account, symbol)
+  }
+  
+  // Check the order and, if it passes, count it as open and towards the order rate
+  pub fn admit(&self, order: &Order, instrument: &Instrument) -> Result<(), RiskRejection> {
+    let limits = self.limits(&order.account);
+    let mut state = self.state.lock().unwrap();
+    let exposure = state.exposure(&order.account, &order.symbol);
+    limits.check(order, instrument, &exposure)?;
+    for check in self.checks.read().unwrap().iter() {
+      check.check(order, instrument, &exposure)?;
+    }
+    state.take_rate(&order.account, limits.max_orders_per_second)?;
+    
+    state.add_open(&order.order_id, OpenOrder {
+      account: order.account.clone(),
+      symbol: order.symbol.clone(),
+      side: order.side.clone(),
+      filled: Qty::ZERO,
+      remaining: order.quantity,
+      amends: VecDeque::new(),
+    });
+    Ok(())
+  }
+  
+  // Check an amend as the order it leaves: the new price and quantity (filled quantity
+  // included) in place of the open order. It counts towards the order rate like a new order.
+  // The open order keeps its quantity until the engine answers, and until then counts with
+  // the larger of the two.
+  pub fn admit_amend(&self, amended: &Order, instrument: &Instrument) -> Result<(), RiskRejection> {
+    let limits = self.limits(&amended.account);
+    let mut state = self.state.lock().unwrap();
+    let mut exposure = state.exposure(&amended.account, &amended.symbol);
+    let filled = match state.open_orders.get(&amended.order_id) {
+      Some(open) => {
+        exposure.open_orders -= 1;
+        match open.side {
+          Side::Buy => exposure.open_buy = exposure.open_buy.saturating_sub(open.exposed()),
+          Side::Sell => exposure.open_sell = exposure.open_sell.saturating_sub(open.exposed()),
+        }
+        open.filled
+      }
+      None => Qty::ZERO,
+    };
+    let remaining = amended.quantity - amended.quantity.min(filled);
+    let order = Order { quantity: remaining, remain_quantity: remaining, ..amended.clone() };
+    limits.check(&order, instrument, &exposure)?;
+    for check in self.checks.read().unwrap().iter() {
+      check.check(&order, instrument, &exposure)?;
+    }
+    state.take_rate(&order.account, limits.max_orders_per_second)?;
+    
+    // An order that is no longer open stays gone, the engine rejects the amend
+    state.update_open(&order.order_id, |open| open.amends.push_back(amended.quantity));
+    Ok(())
+  }
+  
+  // The order no longer counts as open because it was never sequenced.
+  // It still counts towards the order rate.
+  pub fn release(&self, order_id: &str) {
+    self.state.lock().unwrap().remove_open(order_id);
+  }
+  
+  // The last amend admitted for the order was never sequenced, the order keeps its quantity
+  pub fn release_amend(&self, order_id: &str) {
+    self.state.lock().unwrap().update_open(order_id, |open| {
+      open.amends.pop_back();
+    });
+  }
+  
+  // Orders stop counting as open once the engine rejects or cancels them, whether on
+  // request, by a mass cancel or as the unfilled rest of a market order. Amends set what
+  // may still fill once applied; a refused amend leaves the order as it was. The engine
+  // answers amends in the order they were sequenced.
+  pub fn on_outcome(&self, outcome: &OrderOutcome) {
+    let mut state = self.state.lock().unwrap();
+    match outcome {
+      OrderOutcome::Rejected { order_id, .. } | OrderOutcome::Cancelled { order_id, .. } => {
+        state.remove_open(order_id);
+      }
+      OrderOutcome::Amended { order_id, remaining, .. } => {
+        state.update_open(order_id, |open| {
+          open.remaining = *remaining;
+          open.amends.pop_front();
+        });
+        // The replacement may fill completely before the amend is answered
+        if state.open_orders.get(order_id).is_some_and(|open| open.remaining.is_zero() && open.amends.is_empty()) {
+          state.remove_open(order_id);
+        }
+      }
+      OrderOutcome::CancelRejected { order_id, account, .. } => {
+        // A cancel or amend from another account does not touch the owner's pending amends
+        if state.open_orders.get(order_id).is_some_and(|open| open.account == *account) {
+          state.update_open(order_id, |open| {
+            open.amends.pop_front();
+          });
+        }
+      }
+    }
+  }
+  
+  pub fn on_execution(&self, execution: &Execution) {
+    let mut state = self.state.lock().unwrap();
+    state.last_prices.insert(execution.symbol.clone(), execution.price);
+    
+    let (key, filled) = match state.open_orders.get(&execution.order_id) {
+      Some(open) => {
+        let filled = match open.side {
+          Side::Buy => execution.quantity.0 as i128,
+          Side::Sell => -(execution.quantity.0 as i128),
+        };
+        ((open.account.clone(), open.symbol.clone()), filled)
+      }
+      None => return,
+    };
+    state.update_open(&execution.order_id, |open| {
+      open.filled += execution.quantity;
+      open.remaining = open.remaining - open.remaining.min(execution.quantity);
+    });
+    if state.open_orders.get(&execution.order_id).is_some_and(|open| open.remaining.is_zero()) {
+      state.remove_open(&execution.order_id);
+    }
+    *state.positions.entry(key).or_insert(0) += filled;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::Utc;
+  use crate::models::OrderStatus;
+  
+  fn order(order_id: &str, side: Side, price: u64, quantity: u64) -> Order {
+    Order {
+      order_id: order_id.into(),
+      symbol: "SYM".into(),
+      account: "acc1".into(),
+      price: Price(price),
+      quantity: Qty(quantity),
+      side,
+      order_type: OrderType::Limit,
+      status: OrderStatus::New,
+      filled_quantity: Qty(0),
+      remain_quantity: Qty(quantity),
+      entry_time: Utc::now()
+    }
+  }
+  
+  fn fill(order_id: &str, side: Side, price: u64, quantity: u64) -> Execution {
+    Execution {
+      exec_id: format!("{}-fill", order_id),
+      order_id: order_id.into(),
+      trade_id: format!("{}-trade", order_id),
+      account: "acc1".into(),
+      symbol: "SYM".into(),
+      side,
+      price: Price(price),
+      quantity: Qty(quantity),
+      is_maker: true,
+      fee: 0,
+      transaction_time: Utc::now(),
+    }
+  }
+  
+  #[test]
+  fn test_limits_reject_with_reason_codes() {
+    let instrument = Instrument::new("SYM");
+    let risk = RiskManager::with_limits(RiskLimits {
+      max_order_notional: 5000,
+      max_open_orders_per_symbol: 2,
+      max_position: Qty(60),
+      max_price_deviation_bps: 1000,
+      ..RiskLimits::default()
+    });
+    
+    let code = |result: Result<(), RiskRejection>| result.err().map(|e| e.code());
+    assert_eq!(code(risk.admit(&order("o1", Side::Buy, 100, 51), &instrument)), Some("RISK_MAX_ORDER_NOTIONAL"));
+    assert_eq!(code(risk.admit(&order("o1", Side::Buy, 100, 40), &instrument)), None);
+    assert_eq!(code(risk.admit(&order("o2", Side::Buy, 100, 30), &instrument)), Some("RISK_MAX_POSITION"));
+    assert_eq!(code(risk.admit(&order("o2", Side::Sell, 100, 30), &instrument)), None);
+    assert_eq!(code(risk.admit(&order("o3", Side::Sell, 100, 1), &instrument)), Some("RISK_MAX_OPEN_ORDERS"));
+    
+    // Fills move the position and the last price used for the deviation check
+    risk.on_execution(&fill("o1", Side::Buy, 100, 40));
+    assert_eq!(risk.exposure("acc1", "SYM"), Exposure {
+      open_orders: 1,
+      open_buy: Qty(0),
+      open_sell: Qty(30),
+      position: 40,
+      last_price: Some(Price(100)),
+    });
+    assert_eq!(code(risk.admit(&order("o3", Side::Buy, 111, 20), &instrument)), Some("RISK_PRICE_DEVIATION"));
+    assert_eq!(code(risk.admit(&order("o3", Side::Buy, 110, 21), &instrument)), Some("RISK_MAX_POSITION"));
+    risk.release("o2");
+    assert_eq!(code(risk.admit(&order("o3", Side::Sell, 90, 50), &instrument)), None);
+  }
+  
+  #[test]
+  fn test_order_rate_and_custom_checks() {
+    struct NoSells;
+    impl RiskCheck for NoSells {
+      fn check(&self, order: &Order, _: &Instrument, _: &Exposure) -> Result<(), RiskRejection> {
+        match order.side {
+          Side::Sell => Err(RiskRejection::Custom { code: "SELLS_DISABLED", message: "sells are disabled".into() }),
+          Side::Buy => Ok(()),
+        }
+      }
+    }
+    
+    let instrument = Instrument::new("SYM");
+    let risk = RiskManager::new();
+    risk.set_limits("acc1", RiskLimits { max_orders_per_second: 2, ..RiskLimits::default() });
+    risk.add_check(NoSells);
+    
+    assert_eq!(risk.admit(&order("o1", Side::Sell, 100, 1), &instrument).unwrap_err().code(), "SELLS_DISABLED");
+    assert!(risk.admit(&order("o1", Side::Buy, 100, 1), &instrument).is_ok());
+    assert!(risk.admit(&order("o2", Side::Buy, 100, 1), &instrument).is_ok());
+    assert_eq!(risk.admit(&order("o3", Side::Buy, 100, 1), &instrument), Err(RiskRejection::OrderRate { max: 2 }));
+    
+    risk.remove_limits("acc1");
+    assert!(risk.admit(&order("o3", Side::Buy, 100, 1), &instrument).is_ok());
+  }
+  
+  #[test]
+  fn test_amends_and_outcomes_update_open_orders() {
+    let instrument = Instrument::new("SYM");
+    let risk = RiskManager::with_limits(RiskLimits { max_position: Qty(50), ..RiskLimits::default() });
+    assert!(risk.admit(&order("o1", Side::Buy, 100, 30), &instrument).is_ok());
+    risk.on_execution(&fill("o1", Side::Buy, 100, 10));
+    
+    // The amended order replaces the open one, its filled quantity stays in the position
+    assert_eq!(risk.admit_amend(&order("o1", Side::Buy, 100, 61), &instrument).unwrap_err().code(), "RISK_MAX_POSITION");
+    assert!(risk.admit_amend(&order("o1", Side::Buy, 100, 50), &instrument).is_ok());
+    assert_eq!((risk.exposure("acc1", "SYM").open_orders, risk.exposure("acc1", "SYM").open_buy), (1, Qty(40)));
+    risk.on_outcome(&OrderOutcome::Amended {
+      order_id: "o1".into(),
+      account: "acc1".into(),
+      symbol: "SYM".into(),
+      price: Price(100),
+      quantity: Qty(50),
+      remaining: Qty(40),
+    });
+    assert_eq!(risk.exposure("acc1", "SYM").open_buy, Qty(40));
+    
+    // A cancel by the engine, e.g. a mass cancel, releases the order
+    risk.on_outcome(&OrderOutcome::Cancelled {
+      order_id: "o1".into(),
+      account: "acc1".into(),
+      symbol: "SYM".into(),
+      quantity: Qty(40),
+      reason: crate::matching_engine::CancelReason::MassCancel,
+    });
+    assert_eq!(risk.exposure("acc1", "SYM"), Exposure { position: 10, last_price: Some(Price(100)), ..Exposure::default() });
+  }
+  
+  #[test]
+  fn test_amend_that_fills_on_replace_releases_the_order() {
+    let instrument = Instrument::new("SYM");
+    let risk = RiskManager::new();
+    assert!(risk.admit(&order("o1", Side::Buy, 100, 10), &instrument).is_ok());
+    
+    // Amended to a crossing price and a lower quantity, the replacement fills before the answer
+    assert!(risk.admit_amend(&order("o1", Side::Buy, 105, 5), &instrument).is_ok());
+    risk.on_execution(&fill("o1", Side::Buy, 105, 5));
+    assert_eq!(risk.exposure("acc1", "SYM").open_orders, 1);
+    risk.on_outcome(&OrderOutcome::Amended {
+      order_id: "o1".into(),
+      account: "acc1".into(),
+      symbol: "SYM".into(),
+      price: Price(105),
+      quantity: Qty(5),
+      remaining: Qty(0),
+    });
+    assert_eq!(risk.exposure("acc1", "SYM"), Exposure { position: 5, last_price: Some(Price(105)), ..Exposure::default() });
+    assert!(!risk.state.lock().unwrap().open_orders.contains_key("o1"));
+  }
+  
+  #[test]
+  fn test_rejected_amend_keeps_the_open_order() {
+    let instrument = Instrument::new("SYM");
+    let risk = RiskManager::with_limits(RiskLimits { max_position: Qty(50), ..RiskLimits::default() });
+    assert!(risk.admit(&order("o1", Side::Buy, 100, 30), &instrument).is_ok());
+    
+    // Until the engine answers, the order counts with the larger quantity
+    assert!(risk.admit_amend(&order("o1", Side::Buy, 100, 45), &instrument).is_ok());
+    assert_eq!(risk.exposure("acc1", "SYM").open_buy, Qty(45));
+    risk.on_outcome(&OrderOutcome::CancelRejected {
+      order_id: "o1".into(),
+      account: "acc1".into(),
+      reason: crate::matching_engine::CancelRejectReason::NotTrading(crate::instrument::TradingStatus::Halted),
+    });
+    assert_eq!((risk.exposure("acc1", "SYM").open_orders, risk.exposure("acc1", "SYM").open_buy), (1, Qty(30)));
+    
+    // A filled order stays gone when an amend for it is admitted
+    risk.on_execution(&fill("o1", Side::Buy, 100, 30));
+    assert!(risk.admit_amend(&order("o1", Side::Buy, 100, 20), &instrument).is_ok());
+    assert_eq!(risk.exposure("acc1", "SYM"), Exposure { position: 30, last_price: Some(Price(100)), ..Exposure::default() });
+  }
+  
+  #[test]
+  fn test_open_totals_are_kept_per_account_and_symbol() {
+    let instrument = Instrument::new("SYM");
+    let risk = RiskManager::new();
+    let mut other_account = order("o2", Side::Buy, 100, 7);
+    other_account.account = "acc2".into();
+    let mut other_symbol = order("o3", Side::Buy, 100, 9);
+    other_symbol.symbol = "OTHER".into();
+    for order in [order("o1", Side::Buy, 100, 5), other_account, other_symbol, order("o4", Side::Sell, 100, 3)] {
+      assert!(risk.admit(&order, &instrument).is_ok());
+    }
+    let open = |account: &str, symbol: &str| {
+      let exposure = risk.exposure(account, symbol);
+      (exposure.open_orders, exposure.open_buy, exposure.open_sell)
+    };
+    assert_eq!(open("acc1", "SYM"), (2, Qty(5), Qty(3)));
+    assert_eq!(open("acc2", "SYM"), (1, Qty(7), Qty(0)));
+    assert_eq!(open("acc1", "OTHER"), (1, Qty(9), Qty(0)));
+    
+    // Admitting the same order id again replaces the open order
+    assert!(risk.admit(&order("o1", Side::Buy, 100, 6), &instrument).is_ok());
+    assert_eq!(open("acc1", "SYM"), (2, Qty(6), Qty(3)));
+    
+    risk.on_execution(&fill("o1", Side::Buy, 100, 6));
+    risk.on_execution(&fill("o4", Side::Sell, 100, 1));
+    assert_eq!(open("acc1", "SYM"), (1, Qty(0), Qty(2)));
+    risk.release("o4");
+    assert_eq!(open("acc1", "SYM"), (0, Qty(0), Qty(0)));
+    assert!(!risk.state.lock().unwrap().open_totals.contains_key(&("acc1".to_string(), "SYM".to_string())));
+  }
+}
\ No newline at end of file
@@ -1,4 +1,4 @@
-/**
+/*
 * filename : routes
 * author : HAMA
 * date: 2025. 4. 11.
@@ -1,50 +1,92 @@
-/**
+/*
 * filename : sequencer
 * author : HAMA
 * date: 2025. 4. 28.
 * description: 
 **/
 
+use std::sync::Arc;
 use tokio::sync::mpsc::{Receiver, Sender, channel};
 use crate::models::{OrderMessage, Execution};
-use crate::matching_engine;
-
-// Input sequencer receives orders from the API and forwards them to the matching engine
-pub async fn run_input_sequencer(mut order_rx: Receiver<OrderMessage>, order_tx: Sender<OrderMessage>) {
-  while let Some(order_message) = order_rx.recv().await {
-    // In a real implementation, we could add sequence numbers, timestamps, etc.
-    // We could also perform basic validation here
-    if let Err(_) = order_tx.send(order_message).await {
-      // Handle error (e.g., matching engine channel closed)
-      break;
-    }
-  }
-}
+use crate::matching_engine::{self, BookUpdate, OrderOutcome};
+use crate::instrument::InstrumentRegistry;
+use crate::market_data_publisher::models::MarketDataEvent;
 
 // Output sequencer receives executions from the matching engine and forwards them to storage
 pub async fn run_output_sequencer(mut exec_rx: Receiver<Execution>, exec_tx: Sender<Execution>) {
   while let Some(execution) = exec_rx.recv().await {
     // In a real implementation, we could add sequence numbers, timestamps, etc.
     // We could also perform post-trade processing here
-    if let Err(_) = exec_tx.send(execution).await {
+    if exec_tx.send(execution).await.is_err() {
       // Handle error (e.g., storage channel closed)
       break;
     }
   }
 }
 
-// Main sequencer coordinator
-pub async fn run(order_rx: Receiver<OrderMessage>, exec_tx: Sender<Execution>) {
-  // Create channels between input sequencer, matching engine, and output sequencer
-  let (engine_order_tx, engine_order_rx) = channel(100);
+// Also forwards order outcomes. The engine sends the executions of a message before its
+// outcomes, and pending executions are always taken first, so that order is kept.
+// Outcome consumers are optional like market data consumers.
+pub async fn run_outcome_sequencer(
+  mut exec_rx: Receiver<Execution>,
+  mut outcome_rx: Receiver<OrderOutcome>,
+  exec_tx: Sender<Execution>,
+  outcome_tx: Sender<OrderOutcome>
+) {
+  loop {
+    tokio::select! {
+      biased;
+      execution = exec_rx.recv() => match execution {
+        Some(execution) => {
+          if exec_tx.send(execution).await.is_err() {
+            break;
+          }
+        }
+        None => break,
+      },
+      Some(outcome) = outcome_rx.recv() => {
+        let _ = outcome_tx.send(outcome).await;
+      }
+    }
+  }
+}
+
+// Where the engine's output goes. Order events (L3) and outcomes go nowhere unless their
+// senders are set, and the engine does not collect order events per symbol while nobody
+// receives them.
+pub struct SequencerOutputs {
+  pub exec_tx: Sender<Execution>,
+  pub md_tx: Sender<MarketDataEvent>,
+  pub order_event_tx: Sender<BookUpdate>, // Order events of every symbol changed by a message
+  pub outcome_tx: Sender<OrderOutcome>, // Rejects, cancels and amends, after the executions sequenced before them
+}
+
+impl SequencerOutputs {
+  pub fn new(exec_tx: Sender<Execution>, md_tx: Sender<MarketDataEvent>) -> Self {
+    SequencerOutputs {
+      exec_tx,
+      md_tx,
+      order_event_tx: channel(1).0,
+      outcome_tx: channel(1).0,
+    }
+  }
+}
+
+// Main sequencer coordinator. The matching engine takes messages straight from the input
+// queue in the order they were received, so the queue depth the throttle sees is the
+// engine's backlog.
+pub async fn run(
+  order_rx: Receiver<OrderMessage>,
+  outputs: SequencerOutputs,
+  instruments: Arc<InstrumentRegistry>
+) {
+  // Create channels between the matching engine and the output sequencer
   let (engine_exec_tx, engine_exec_rx) = channel(100);
-  
-  // Spawn input sequencer
-  tokio::spawn(run_input_sequencer(order_rx, engine_order_tx));
+  let (engine_outcome_tx, engine_outcome_rx) = channel(100);
   
   // Spawn matching engine
-  tokio::spawn(matching_engine::run(engine_order_rx, engine_exec_tx));
+  tokio::spawn(matching_engine::run(order_rx, engine_exec_tx, outputs.md_tx, outputs.order_event_tx, engine_outcome_tx, instruments));
   
   // Spawn output sequencer
-  run_output_sequencer(engine_exec_rx, exec_tx).await;
-}
\ No newline at end of file
+  run_outcome_sequencer(engine_exec_rx, engine_outcome_rx, outputs.exec_tx, outputs.outcome_tx).await;
+}
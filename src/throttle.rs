@@ -0,0 +1,261 @@
+/*
+* filename : throttle
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 주문 API 클라이언트별 요청 속도 제한 및 시퀀서 큐 과부하 응답
+**/
+
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::mpsc::error::TrySendError;
+use crate::error::ApiError;
+use crate::models::OrderMessage;
+
+// The longest tracked clients are dropped once a limiter tracks this many
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+// Token bucket refilled at per_second up to burst tokens (per_second when burst is 0).
+// A per_second of 0 disables the limit.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RateLimit {
+  pub per_second: u64,
+  pub burst: u64,
+}
+
+impl RateLimit {
+  fn capacity(&self) -> f64 {
+    if self.burst == 0 { self.per_second as f64 } else { self.burst as f64 }
+  }
+}
+
+// Order entry requests are limited per authenticated API key (X-API-Key header) and per
+// client IP.
+// New requests are refused with 503 while the sequencer queue is at least
+// overload_queue_pct percent full; 0 disables the check.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct ThrottleConfig {
+  pub per_api_key: RateLimit,
+  pub per_ip: RateLimit,
+  pub overload_queue_pct: u64,
+  pub overload_retry_after_secs: u64,
+}
+
+impl Default for ThrottleConfig {
+  fn default() -> Self {
+    ThrottleConfig {
+      per_api_key: RateLimit::default(),
+      per_ip: RateLimit::default(),
+      overload_queue_pct: 80,
+      overload_retry_after_secs: 1,
+    }
+  }
+}
+
+struct TokenBucket {
+  tokens: f64,
+  updated: Instant,
+}
+
+#[derive(Default)]
+struct Buckets {
+  buckets: HashMap<String, TokenBucket>,
+  tracked: VecDeque<String>, // Clients in the order they were first seen
+}
+
+struct RateLimiter {
+  limit: RateLimit,
+  buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+  fn new(limit: RateLimit) -> Self {
+    RateLimiter { limit, buckets: Mutex::new(Buckets::default()) }
+  }
+  
+  fn tokens(&self, bucket: Option<&TokenBucket>, now: Instant) -> f64 {
+    let capacity = self.limit.capacity();
+    match bucket {
+      Some(bucket) => (bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * self.limit.per_second as f64).min(capacity),
+      None => capacity,
+    }
+  }
+  
+  fn wait(&self, tokens: f64) -> Duration {
+    Duration::from_secs_f64((1.0 - tokens) / self.limit.per_second as f64)
+  }
+  
+  // How long until a token is available, without tracking the client
+  fn check(&self, client: &str, now: Instant) -> Result<(), Duration> {
+    if self.limit.per_second == 0 {
+      return Ok(());
+    }
+    let tokens = self.tokens(self.buckets.lock().unwrap().buckets.get(client), now);
+    if tokens >= 1.0 { Ok(()) } else { Err(self.wait(tokens)) }
+  }
+  
+  // Take a token, or return how long until one is available
+  fn take(&self, client: &str, now: Instant) -> Result<(), Duration> {
+    if self.limit.per_second == 0 {
+      return Ok(());
+    }
+    let mut guard = self.buckets.lock().unwrap();
+    let Buckets { buckets, tracked } = &mut *guard;
+    let tokens = self.tokens(buckets.get(client), now);
+    if !buckets.contains_key(client) {
+      if tracked.len() == MAX_TRACKED_CLIENTS {
+        if let Some(oldest) = tracked.pop_front() {
+          buckets.remove(&oldest);
+        }
+      }
+      tracked.push_back(client.to_string());
+    }
+    
+    let bucket = buckets.entry(client.to_string()).or_insert(TokenBucket { tokens, updated: now });
+    bucket.tokens = tokens;
+    bucket.updated = now;
+    if bucket.tokens >= 1.0 {
+      bucket.tokens -= 1.0;
+      Ok(())
+    } else {
+      Err(self.wait(bucket.tokens))
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ThrottleMetrics {
+  pub sequencer_queue_depth: usize,
+  pub sequencer_queue_capacity: usize,
+  pub rate_limited_requests: u64,
+  pub overloaded_requests: u64,
+}
+
+// Admission control for requests that are sequenced into the matching engine
+pub struct Throttle {
+  config: ThrottleConfig,
+  api_keys: RateLimiter,
+  ips: RateLimiter,
+  rate_limited: AtomicU64,
+  overloaded: AtomicU64,
+}
+
+impl Default for Throttle {
+  fn default() -> Self {
+    Throttle::new(ThrottleConfig::default())
+  }
+}
+
+impl Throttle {
+  pub fn new(config: ThrottleConfig) -> Self {
+    Throttle {
+      api_keys: RateLimiter::new(config.per_api_key.clone()),
+      ips: RateLimiter::new(config.per_ip.clone()),
+      config,
+      rate_limited: AtomicU64::new(0),
+      overloaded: AtomicU64::new(0),
+    }
+  }
+  
+  pub fn queue_depth(tx: &Sender<OrderMessage>) -> usize {
+    tx.max_capacity() - tx.capacity()
+  }
+  
+  // Both the API key's and the IP's bucket must have a token; the queue is checked first
+  // so that refused requests do not use up tokens. Only keys already authenticated may be
+  // passed, so that made up keys cannot fill the limiter.
+  pub fn admit(&self, api_key: Option<&str>, ip: Option<IpAddr>, tx: &Sender<OrderMessage>) -> Result<(), ApiError> {
+    let pct = self.config.overload_queue_pct;
+    if pct > 0 && Self::queue_depth(tx) * 100 >= tx.max_capacity() * pct as usize {
+      return Err(self.overloaded());
+    }
+    
+    let now = Instant::now();
+    let limited = api_key.map(|key| self.api_keys.check(key, now)).transpose()
+      .and_then(|_| ip.map(|ip| self.ips.take(&ip.to_string(), now)).transpose())
+      .and_then(|_| api_key.map(|key| self.api_keys.take(key, now)).transpose());
+    if let Err(retry_after) = limited {
+      self.rate_limited.fetch_add(1, Ordering::Relaxed);
+      return Err(ApiError::RateLimited { retry_after });
+    }
+    Ok(())
+  }
+  
+  // Sequence without waiting for queue space, so that one client cannot hold up the others
+  pub fn try_send(&self, tx: &Sender<OrderMessage>, message: OrderMessage) -> Result<(), ApiError> {
+    tx.try_send(message).map_err(|e| match e {
+      TrySendError::Full(_) => self.overloaded(),
+      TrySendError::Closed(_) => ApiError::EngineUnavailable,
+    })
+  }
+  
+  fn overloaded(&self) -> ApiError {
+    self.overloaded.fetch_add(1, Ordering::Relaxed);
+    ApiError::Overloaded { retry_after: Duration::from_secs(self.config.overload_retry_after_secs.max(1)) }
+  }
+  
+  pub fn metrics(&self, tx: &Sender<OrderMessage>) -> ThrottleMetrics {
+    ThrottleMetrics {
+      sequencer_queue_depth: Self::queue_depth(tx),
+      sequencer_queue_capacity: tx.max_capacity(),
+      rate_limited_requests: self.rate_limited.load(Ordering::Relaxed),
+      overloaded_requests: self.overloaded.load(Ordering::Relaxed),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  
+  #[test]
+  fn test_token_bucket_refills_up_to_burst() {
+    let limiter = RateLimiter::new(RateLimit { per_second: 10, burst: 2 });
+    let start = Instant::now();
+    assert!(limiter.take("k", start).is_ok());
+    assert!(limiter.take("k", start).is_ok());
+    let retry_after = limiter.take("k", start).unwrap_err();
+    assert!(retry_after > Duration::from_millis(99) && retry_after <= Duration::from_millis(100));
+    assert!(limiter.take("other", start).is_ok());
+    
+    assert!(limiter.take("k", start + Duration::from_millis(100)).is_ok());
+    assert!(limiter.take("k", start + Duration::from_secs(10)).is_ok());
+    assert!(limiter.take("k", start + Duration::from_secs(10)).is_ok());
+    assert!(limiter.take("k", start + Duration::from_secs(10)).is_err());
+  }
+  
+  #[test]
+  fn test_limiter_tracks_a_bounded_number_of_clients() {
+    let limiter = RateLimiter::new(RateLimit { per_second: 1, burst: 1 });
+    let start = Instant::now();
+    assert!(limiter.check("first", start).is_ok());
+    assert!(limiter.buckets.lock().unwrap().buckets.is_empty());
+    
+    for client in 0..MAX_TRACKED_CLIENTS + 5 {
+      assert!(limiter.take(&client.to_string(), start).is_ok());
+    }
+    let buckets = limiter.buckets.lock().unwrap();
+    assert_eq!((buckets.buckets.len(), buckets.tracked.len()), (MAX_TRACKED_CLIENTS, MAX_TRACKED_CLIENTS));
+    assert!(!buckets.buckets.contains_key("4") && buckets.buckets.contains_key("5"));
+  }
+  
+  #[test]
+  fn test_refused_ip_does_not_use_the_api_keys_token() {
+    let throttle = Throttle::new(ThrottleConfig {
+      per_api_key: RateLimit { per_second: 1, burst: 1 },
+      per_ip: RateLimit { per_second: 1, burst: 1 },
+      ..ThrottleConfig::default()
+    });
+    let (tx, _rx) = tokio::sync::mpsc::channel(10);
+    let ip: IpAddr = "10.0.0.1".parse().unwrap();
+    assert!(throttle.admit(None, Some(ip), &tx).is_ok());
+    assert!(throttle.admit(Some("key"), Some(ip), &tx).is_err());
+    assert!(throttle.admit(Some("key"), Some("10.0.0.2".parse().unwrap()), &tx).is_ok());
+  }
+}
\ No newline at end of file
@@ -0,0 +1,380 @@
+/*
+* filename : binary_codec
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 바이너리 주문 입력 프로토콜 - 길이 접두 고정 레이아웃 리틀 엔디언 메시지 인코딩 및 디코딩
+**/
+
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::models::{OrderType, Price, Qty, Side};
+
+// Every frame starts with the body length and the template id, both u16 little-endian,
+// followed by the fixed-layout body of the template. Prices and quantities are the raw
+// fixed-point units of the instrument, text fields are ASCII padded with zero bytes.
+pub const HEADER_LEN: usize = 4;
+pub const CLIENT_ORDER_ID_LEN: usize = 20;
+pub const SYMBOL_LEN: usize = 16;
+pub const API_KEY_LEN: usize = 64;
+pub const ACCOUNT_LEN: usize = 32;
+
+// Template ids. Client messages are below 100, gateway messages above.
+pub mod template {
+  pub const LOGON: u16 = 1;
+  pub const NEW_ORDER: u16 = 2;
+  pub const CANCEL: u16 = 3;
+  pub const AMEND: u16 = 4;
+  pub const LOGON_ACCEPTED: u16 = 101;
+  pub const EXECUTION_REPORT: u16 = 102;
+  pub const REJECT: u16 = 103;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExecType {
+  New = 0,
+  Cancelled = 1,
+  Amended = 2,
+  Trade = 3,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RejectReason {
+  InvalidMessage = 1,
+  NotLoggedOn = 2,
+  AuthenticationFailed = 3,
+  DuplicateClientOrderId = 4,
+  UnknownOrder = 5,
+  NotAmendable = 6,
+  UnknownSymbol = 7,
+  NotTrading = 8,
+  InvalidOrder = 9,
+  EngineUnavailable = 10,
+  RiskLimit = 11,
+  RateLimited = 12,
+  Overloaded = 13,
+}
+
+// Report of an order entered through the binary protocol. For trades price and quantity
+// are those of the fill, otherwise those of the order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionReport {
+  pub client_order_id: String,
+  pub order_id: Uuid,
+  pub exec_type: ExecType,
+  pub side: Side,
+  pub price: Price,
+  pub quantity: Qty,
+  pub leaves_quantity: Qty,
+  pub transact_time: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BinaryMessage {
+  Logon { api_key: String },
+  NewOrder {
+    client_order_id: String,
+    symbol: String,
+    side: Side,
+    order_type: OrderType,
+    price: Price,
+    quantity: Qty,
+  },
+  Cancel { client_order_id: String },
+  // Quantity includes what has been filled already
+  Amend { client_order_id: String, price: Price, quantity: Qty },
+  LogonAccepted { account: String },
+  ExecutionReport(ExecutionReport),
+  Reject { client_order_id: String, reason: RejectReason },
+}
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum CodecError {
+  #[error("unknown template {0}")]
+  UnknownTemplate(u16),
+  #[error("template {template} has a body of {expected} bytes, received {received}")]
+  InvalidLength { template: u16, expected: usize, received: usize },
+  #[error("invalid value for {0}")]
+  InvalidValue(&'static str),
+  #[error("{field} is longer than {max} bytes")]
+  FieldTooLong { field: &'static str, max: usize },
+}
+
+impl BinaryMessage {
+  pub fn template_id(&self) -> u16 {
+    match self {
+      BinaryMessage::Logon { .. } => template::LOGON,
+      BinaryMessage::NewOrder { .. } => template::NEW_ORDER,
+      BinaryMessage::Cancel { .. } => template::CANCEL,
+      BinaryMessage::Amend { .. } => template::AMEND,
+      BinaryMessage::LogonAccepted { .. } => template::LOGON_ACCEPTED,
+      BinaryMessage::ExecutionReport(_) => template::EXECUTION_REPORT,
+      BinaryMessage::Reject { .. } => template::REJECT,
+    }
+  }
+  
+  pub fn encode(&self) -> Result<Vec<u8>, CodecError> {
+    let mut body = Writer(Vec::with_capacity(64));
+    match self {
+      BinaryMessage::Logon { api_key } => body.text("api_key", api_key, API_KEY_LEN)?,
+      BinaryMessage::NewOrder { client_order_id, symbol, side, order_type, price, quantity } => {
+        body.text("client_order_id", client_order_id, CLIENT_ORDER_ID_LEN)?;
+        body.text("symbol", symbol, SYMBOL_LEN)?;
+        body.u8(encode_side(side));
+        body.u8(match order_type {
+          OrderType::Market => 1,
+          OrderType::Limit => 2,
+        });
+        body.u64(price.0);
+        body.u64(quantity.0);
+      }
+      BinaryMessage::Cancel { client_order_id } => body.text("client_order_id", client_order_id, CLIENT_ORDER_ID_LEN)?,
+      BinaryMessage::Amend { client_order_id, price, quantity } => {
+        body.text("client_order_id", client_order_id, CLIENT_ORDER_ID_LEN)?;
+        body.u64(price.0);
+        body.u64(quantity.0);
+      }
+      BinaryMessage::LogonAccepted { account } => body.text("account", account, ACCOUNT_LEN)?,
+      BinaryMessage::ExecutionReport(report) => {
+        body.text("client_order_id", &report.client_order_id, CLIENT_ORDER_ID_LEN)?;
+        body.0.extend_from_slice(&report.order_id.as_u128().to_le_bytes());
+        body.u8(report.exec_type as u8);
+        body.u8(encode_side(&report.side));
+        body.u64(report.price.0);
+        body.u64(report.quantity.0);
+        body.u64(report.leaves_quantity.0);
+        body.u64(report.transact_time.timestamp_nanos_opt().unwrap_or(0) as u64);
+      }
+      BinaryMessage::Reject { client_order_id, reason } => {
+        body.text("client_order_id", client_order_id, CLIENT_ORDER_ID_LEN)?;
+        body.u8(*reason as u8);
+      }
+    }
+    
+    let mut frame = Vec::with_capacity(HEADER_LEN + body.0.len());
+    frame.extend_from_slice(&(body.0.len() as u16).to_le_bytes());
+    frame.extend_from_slice(&self.template_id().to_le_bytes());
+    frame.extend_from_slice(&body.0);
+    Ok(frame)
+  }
+  
+  // Take one complete frame off the front of the buffer. Returns Ok(None) until the whole
+  // frame has been received. A frame that fails to decode is still consumed.
+  pub fn decode(buffer: &mut Vec<u8>) -> Result<Option<BinaryMessage>, CodecError> {
+    if buffer.len() < HEADER_LEN {
+      return Ok(None);
+    }
+    let length = u16::from_le_bytes([buffer[0], buffer[1]]) as usize;
+    let template = u16::from_le_bytes([buffer[2], buffer[3]]);
+    if buffer.len() < HEADER_LEN + length {
+      return Ok(None);
+    }
+    let frame: Vec<u8> = buffer.drain(..HEADER_LEN + length).collect();
+    
+    let expected = match template {
+      template::LOGON => API_KEY_LEN,
+      template::NEW_ORDER => CLIENT_ORDER_ID_LEN + SYMBOL_LEN + 2 + 16,
+      template::CANCEL => CLIENT_ORDER_ID_LEN,
+      template::AMEND => CLIENT_ORDER_ID_LEN + 16,
+      template::LOGON_ACCEPTED => ACCOUNT_LEN,
+      template::EXECUTION_REPORT => CLIENT_ORDER_ID_LEN + 16 + 2 + 32,
+      template::REJECT => CLIENT_ORDER_ID_LEN + 1,
+      _ => return Err(CodecError::UnknownTemplate(template)),
+    };
+    if length != expected {
+      return Err(CodecError::InvalidLength { template, expected, received: length });
+    }
+    
+    let mut body = Reader { bytes: &frame[HEADER_LEN..], position: 0 };
+    let message = match template {
+      template::LOGON => BinaryMessage::Logon { api_key: body.text("api_key", API_KEY_LEN)? },
+      template::NEW_ORDER => BinaryMessage::NewOrder {
+        client_order_id: body.text("client_order_id", CLIENT_ORDER_ID_LEN)?,
+        symbol: body.text("symbol", SYMBOL_LEN)?,
+        side: decode_side(body.u8())?,
+        order_type: match body.u8() {
+          1 => OrderType::Market,
+          2 => OrderType::Limit,
+          _ => return Err(CodecError::InvalidValue("order_type")),
+        },
+        price: Price(body.u64()),
+        quantity: Qty(body.u64()),
+      },
+      template::CANCEL => BinaryMessage::Cancel { client_order_id: body.text("client_order_id", CLIENT_ORDER_ID_LEN)? },
+      template::AMEND => BinaryMessage::Amend {
+        client_order_id: body.text("client_order_id", CLIENT_ORDER_ID_LEN)?,
+        price: Price(body.u64()),
+        quantity: Qty(body.u64()),
+      },
+      template::LOGON_ACCEPTED => BinaryMessage::LogonAccepted { account: body.text("account", ACCOUNT_LEN)? },
+      template::EXECUTION_REPORT => BinaryMessage::ExecutionReport(ExecutionReport {
+        client_order_id: body.text("client_order_id", CLIENT_ORDER_ID_LEN)?,
+        order_id: Uuid::from_u128(body.u128()),
+        exec_type: match body.u8() {
+          0 => ExecType::New,
+          1 => ExecType::Cancelled,
+          2 => ExecType::Amended,
+          3 => ExecType::Trade,
+          _ => return Err(CodecError::InvalidValue("exec_type")),
+        },
+        side: decode_side(body.u8())?,
+        price: Price(body.u64()),
+        quantity: Qty(body.u64()),
+        leaves_quantity: Qty(body.u64()),
+        transact_time: DateTime::from_timestamp_nanos(body.u64() as i64),
+      }),
+      _ => BinaryMessage::Reject {
+        client_order_id: body.text("client_order_id", CLIENT_ORDER_ID_LEN)?,
+        reason: decode_reject_reason(body.u8())?,
+      },
+    };
+    Ok(Some(message))
+  }
+}
+
+fn encode_side(side: &Side) -> u8 {
+  match side {
+    Side::Buy => 1,
+    Side::Sell => 2,
+  }
+}
+
+fn decode_side(value: u8) -> Result<Side, CodecError> {
+  match value {
+    1 => Ok(Side::Buy),
+    2 => Ok(Side::Sell),
+    _ => Err(CodecError::InvalidValue("side")),
+  }
+}
+
+fn decode_reject_reason(value: u8) -> Result<RejectReason, CodecError> {
+  let reasons = [
+    RejectReason::InvalidMessage,
+    RejectReason::NotLoggedOn,
+    RejectReason::AuthenticationFailed,
+    RejectReason::DuplicateClientOrderId,
+    RejectReason::UnknownOrder,
+    RejectReason::NotAmendable,
+    RejectReason::UnknownSymbol,
+    RejectReason::NotTrading,
+    RejectReason::InvalidOrder,
+    RejectReason::EngineUnavailable,
+    RejectReason::RiskLimit,
+    RejectReason::RateLimited,
+    RejectReason::Overloaded,
+  ];
+  reasons.into_iter().find(|reason| *reason as u8 == value).ok_or(CodecError::InvalidValue("reason"))
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+  fn u8(&mut self, value: u8) {
+    self.0.push(value);
+  }
+  
+  fn u64(&mut self, value: u64) {
+    self.0.extend_from_slice(&value.to_le_bytes());
+  }
+  
+  fn text(&mut self, field: &'static str, value: &str, len: usize) -> Result<(), CodecError> {
+    if value.len() > len {
+      return Err(CodecError::FieldTooLong { field, max: len });
+    }
+    if !value.is_ascii() || value.contains('\0') {
+      return Err(CodecError::InvalidValue(field));
+    }
+    self.0.extend_from_slice(value.as_bytes());
+    self.0.resize(self.0.len() + len - value.len(), 0);
+    Ok(())
+  }
+}
+
+// Reads fields in order from a body whose length has already been checked
+struct Reader<'a> {
+  bytes: &'a [u8],
+  position: usize,
+}
+
+impl Reader<'_> {
+  fn take<const N: usize>(&mut self) -> [u8; N] {
+    let bytes = self.bytes[self.position..self.position + N].try_into().unwrap();
+    self.position += N;
+    bytes
+  }
+  
+  fn u8(&mut self) -> u8 {
+    self.take::<1>()[0]
+  }
+  
+  fn u64(&mut self) -> u64 {
+    u64::from_le_bytes(self.take())
+  }
+  
+  fn u128(&mut self) -> u128 {
+    u128::from_le_bytes(self.take())
+  }
+  
+  fn text(&mut self, field: &'static str, len: usize) -> Result<String, CodecError> {
+    let bytes = &self.bytes[self.position..self.position + len];
+    self.position += len;
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(len);
+    if !bytes[end..].iter().all(|&b| b == 0) || !bytes[..end].is_ascii() {
+      return Err(CodecError::InvalidValue(field));
+    }
+    Ok(String::from_utf8_lossy(&bytes[..end]).to_string())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  
+  #[test]
+  fn test_encode_decode_round_trip() {
+    let order = BinaryMessage::NewOrder {
+      client_order_id: "c1".to_string(),
+      symbol: "SYM".to_string(),
+      side: Side::Sell,
+      order_type: OrderType::Limit,
+      price: Price(12345),
+      quantity: Qty(10),
+    };
+    let report = BinaryMessage::ExecutionReport(ExecutionReport {
+      client_order_id: "c1".to_string(),
+      order_id: Uuid::new_v4(),
+      exec_type: ExecType::Trade,
+      side: Side::Sell,
+      price: Price(12345),
+      quantity: Qty(4),
+      leaves_quantity: Qty(6),
+      transact_time: DateTime::from_timestamp_nanos(1_800_000_000_123_456_789),
+    });
+    
+    let mut buffer = Vec::new();
+    buffer.extend_from_slice(&order.encode().unwrap());
+    buffer.extend_from_slice(&report.encode().unwrap());
+    assert_eq!(buffer.len(), 2 * HEADER_LEN + 54 + 70);
+    
+    // Partial input is kept until the rest arrives
+    let mut partial = buffer[..HEADER_LEN + 53].to_vec();
+    assert_eq!(BinaryMessage::decode(&mut partial), Ok(None));
+    
+    assert_eq!(BinaryMessage::decode(&mut buffer), Ok(Some(order)));
+    assert_eq!(BinaryMessage::decode(&mut buffer), Ok(Some(report)));
+    assert!(buffer.is_empty());
+  }
+  
+  #[test]
+  fn test_invalid_frames_are_consumed() {
+    let mut buffer = BinaryMessage::Cancel { client_order_id: "c1".to_string() }.encode().unwrap();
+    buffer[2] = 99;
+    buffer.extend_from_slice(&BinaryMessage::Cancel { client_order_id: "c2".to_string() }.encode().unwrap());
+    assert_eq!(BinaryMessage::decode(&mut buffer), Err(CodecError::UnknownTemplate(99)));
+    assert_eq!(BinaryMessage::decode(&mut buffer), Ok(Some(BinaryMessage::Cancel { client_order_id: "c2".to_string() })));
+    
+    let too_long = BinaryMessage::Cancel { client_order_id: "x".repeat(CLIENT_ORDER_ID_LEN + 1) };
+    assert_eq!(too_long.encode(), Err(CodecError::FieldTooLong { field: "client_order_id", max: CLIENT_ORDER_ID_LEN }));
+  }
+}
\ No newline at end of file
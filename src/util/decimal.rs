@@ -0,0 +1,136 @@
+/*
+* filename : decimal
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 고정 소수점 정수와 10진수 문자열 간 변환
+**/
+
+use serde::{Deserialize, Deserializer};
+use thiserror::Error;
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum DecimalError {
+  #[error("invalid decimal {0:?}")]
+  Invalid(String),
+  #[error("{value} has more than {scale} decimal places")]
+  TooManyDecimals { value: String, scale: u32 },
+  #[error("{0} is out of range")]
+  Overflow(String),
+}
+
+// Decimal value as sent by clients, either a JSON string ("0.001") or a JSON number (0.001).
+// Strings are recommended since numbers may lose precision in client JSON libraries.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecimalInput(pub String);
+
+impl<'de> Deserialize<'de> for DecimalInput {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+      Text(String),
+      Number(serde_json::Number),
+    }
+    
+    Ok(match Raw::deserialize(deserializer)? {
+      Raw::Text(text) => DecimalInput(text),
+      Raw::Number(number) => DecimalInput(number.to_string()),
+    })
+  }
+}
+
+// 10^scale, None if it does not fit in u64
+pub fn pow10(scale: u32) -> Option<u64> {
+  10u64.checked_pow(scale)
+}
+
+// "12345.67" with scale 2 -> 1234567. Negative values and exponents are not accepted.
+pub fn parse_scaled(value: &str, scale: u32) -> Result<u64, DecimalError> {
+  let value = value.trim();
+  let (int_part, frac_part) = match value.split_once('.') {
+    Some((i, f)) => (i, f),
+    None => (value, ""),
+  };
+  
+  let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+  if (int_part.is_empty() && frac_part.is_empty()) || !is_digits(int_part) || !is_digits(frac_part) {
+    return Err(DecimalError::Invalid(value.to_string()));
+  }
+  
+  // Trailing zeros beyond the scale do not change the value
+  let frac_part = frac_part.trim_end_matches('0');
+  if frac_part.len() > scale as usize {
+    return Err(DecimalError::TooManyDecimals { value: value.to_string(), scale });
+  }
+  
+  let overflow = || DecimalError::Overflow(value.to_string());
+  let unit = pow10(scale).ok_or_else(overflow)?;
+  
+  let int_value = if int_part.is_empty() { 0 } else { int_part.parse::<u64>().map_err(|_| overflow())? };
+  let frac_value = if frac_part.is_empty() {
+    0
+  } else {
+    let digits = frac_part.parse::<u64>().map_err(|_| overflow())?;
+    digits * pow10(scale - frac_part.len() as u32).ok_or_else(overflow)?
+  };
+  
+  int_value.checked_mul(unit)
+    .and_then(|v| v.checked_add(frac_value))
+    .ok_or_else(overflow)
+}
+
+// 1234567 with scale 2 -> "12345.67". Trailing fractional zeros are dropped.
+pub fn format_scaled(value: u64, scale: u32) -> String {
+  format_scaled_wide(value as u128, scale)
+}
+
+// Same as format_scaled for intermediate values such as notionals
+pub fn format_scaled_wide(value: u128, scale: u32) -> String {
+  if scale == 0 {
+    return value.to_string();
+  }
+  
+  let unit = 10u128.pow(scale);
+  let int_value = value / unit;
+  let frac_value = value % unit;
+  if frac_value == 0 {
+    return int_value.to_string();
+  }
+  
+  let frac = format!("{:0width$}", frac_value, width = scale as usize);
+  format!("{}.{}", int_value, frac.trim_end_matches('0'))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  
+  #[test]
+  fn test_parse_scaled() {
+    assert_eq!(parse_scaled("12345.67", 2), Ok(1234567));
+    assert_eq!(parse_scaled("0.001", 8), Ok(100000));
+    assert_eq!(parse_scaled("5", 3), Ok(5000));
+    assert_eq!(parse_scaled(".5", 1), Ok(5));
+    assert_eq!(parse_scaled("1.500", 1), Ok(15));
+    assert!(matches!(parse_scaled("0.001", 2), Err(DecimalError::TooManyDecimals { .. })));
+    assert!(matches!(parse_scaled("-1", 2), Err(DecimalError::Invalid(_))));
+    assert!(matches!(parse_scaled("1e5", 2), Err(DecimalError::Invalid(_))));
+    assert!(matches!(parse_scaled("", 2), Err(DecimalError::Invalid(_))));
+    assert!(matches!(parse_scaled("184467440737095517", 2), Err(DecimalError::Overflow(_))));
+  }
+  
+  #[test]
+  fn test_decimal_input() {
+    let values: Vec<DecimalInput> = serde_json::from_str(r#"["0.001", 0.001, 5]"#).unwrap();
+    assert_eq!(values.iter().map(|v| parse_scaled(&v.0, 3).unwrap()).collect::<Vec<_>>(), vec![1, 1, 5000]);
+  }
+  
+  #[test]
+  fn test_format_scaled() {
+    assert_eq!(format_scaled(1234567, 2), "12345.67");
+    assert_eq!(format_scaled(100000, 8), "0.001");
+    assert_eq!(format_scaled(5000, 3), "5");
+    assert_eq!(format_scaled(42, 0), "42");
+    assert_eq!(parse_scaled(&format_scaled(987654321, 6), 6), Ok(987654321));
+  }
+}
\ No newline at end of file
@@ -1,8 +1,10 @@
-/**
+/*
 * filename : mod
 * author : HAMA
 * date: 2025. 4. 30.
 * description: 
 **/
 
-pub mod serializer;
\ No newline at end of file
+pub mod decimal;
+pub mod serializer;
+pub mod binary_codec;
\ No newline at end of file
@@ -1,4 +1,4 @@
-/**
+/*
 * filename : serializer
 * author : HAMA
 * date: 2025. 4. 30.
@@ -6,16 +6,18 @@
 **/
 
 use serde::{Serialize, Deserialize};
+use serde::ser::Error as _;
 use serde_json::{Value, Error as JsonError};
-use chrono::{DateTime, Utc, TimeZone};
+use chrono::Utc;
 
-use crate::models::{Order, Execution, OrderBook, Book, Side, OrderType, OrderStatus};
+use crate::models::{Order, Execution, OrderBook, PriceLevel};
+use crate::instrument::Instrument;
 
-/// 주문책 단계 직렬화용 구조체
+/// 주문책 단계 직렬화용 구조체 (가격, 수량은 종목 소수 자릿수를 적용한 10진수 문자열)
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PriceLevelDto {
-  pub price: u64,
-  pub volume: u64,
+  pub price: String,
+  pub volume: String,
   pub order_count: usize,
 }
 
@@ -24,99 +26,129 @@ pub struct PriceLevelDto {
 pub struct OrderBookDto {
   pub symbol: String,
   pub timestamp: i64,
+  #[serde(default)]
+  pub update_id: u64, // 심볼별 호가 변경 번호 (델타 동기화용)
   pub bids: Vec<PriceLevelDto>,
   pub asks: Vec<PriceLevelDto>,
 }
 
-/// 주문 직렬화용 구조체
+/// 주문 직렬화용 구조체 (가격, 수량은 종목 소수 자릿수를 적용한 10진수 문자열)
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct OrderDto {
   pub order_id: String,
   pub symbol: String,
-  pub price: u64,
-  pub quantity: u64,
+  pub account: String,
+  pub price: String,
+  pub quantity: String,
   pub side: String,
   pub order_type: String,
   pub status: String,
-  pub filled_quantity: u64,
-  pub remain_quantity: u64,
+  pub filled_quantity: String,
+  pub remain_quantity: String,
   pub entry_time: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub client_order_id: Option<String>,
+  /// 매칭 엔진이 거부한 주문의 사유 코드
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub reject_reason: Option<String>,
 }
 
-/// 체결 직렬화용 구조체
+/// 체결 직렬화용 구조체 (가격, 수량, 수수료는 10진수 문자열)
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ExecutionDto {
   pub exec_id: String,
   pub order_id: String,
+  #[serde(default)]
+  pub trade_id: String,
   pub symbol: String,
   pub side: String,
-  pub price: u64,
-  pub quantity: u64,
-  pub fee: f64,
+  pub price: String,
+  pub quantity: String,
+  pub is_maker: bool,
+  pub fee: String,
   pub transaction_time: String,
 }
 
-/// 주문책을 DTO로 변환
-pub fn orderbook_to_dto(orderbook: &OrderBook, symbol: &str) -> OrderBookDto {
-  let mut bids = Vec::new();
-  let mut asks = Vec::new();
-  
-  // 매수 호가 변환
-  for (&price, level) in &orderbook.buy_book.limits {
-    bids.push(PriceLevelDto {
-      price,
-      volume: level.total_volume,
-      order_count: level.orders.len(),
-    });
-  }
-  
-  // 매도 호가 변환
-  for (&price, level) in &orderbook.sell_book.limits {
-    asks.push(PriceLevelDto {
-      price,
-      volume: level.total_volume,
-      order_count: level.orders.len(),
-    });
+/// 공개 체결 직렬화용 구조체 (주문 ID, 계정, 수수료를 제외한 익명 체결)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TradeDto {
+  pub trade_id: String,
+  pub symbol: String,
+  pub side: String,
+  pub price: String,
+  pub quantity: String,
+  pub transaction_time: String,
+}
+
+/// 주문책 단계를 DTO로 변환
+pub fn price_level_to_dto(level: &PriceLevel, instrument: &Instrument) -> PriceLevelDto {
+  PriceLevelDto {
+    price: instrument.format_price(level.price),
+    volume: instrument.format_quantity(level.total_volume),
+    order_count: level.orders.len(),
   }
+}
+
+/// 주문책을 DTO로 변환
+pub fn orderbook_to_dto(orderbook: &OrderBook, symbol: &str, instrument: &Instrument) -> OrderBookDto {
+  let to_dto = |level| price_level_to_dto(level, instrument);
   
-  // 가격 순서대로 정렬
-  bids.sort_by(|a, b| b.price.cmp(&a.price)); // 내림차순 (최고가 먼저)
-  asks.sort_by(|a, b| a.price.cmp(&b.price)); // 오름차순 (최저가 먼저)
+  // 호가 단계는 가격순으로 저장되어 있으므로 정렬 없이 변환
+  let bids = orderbook.buy_book.limits.values().rev().map(to_dto).collect(); // 내림차순 (최고가 먼저)
+  let asks = orderbook.sell_book.limits.values().map(to_dto).collect(); // 오름차순 (최저가 먼저)
   
   OrderBookDto {
     symbol: symbol.to_string(),
     timestamp: Utc::now().timestamp_millis(),
+    update_id: 0,
     bids,
     asks,
   }
 }
 
 /// 주문을 DTO로 변환
-pub fn order_to_dto(order: &Order) -> OrderDto {
+pub fn order_to_dto(order: &Order, instrument: &Instrument) -> OrderDto {
   OrderDto {
     order_id: order.order_id.clone(),
     symbol: order.symbol.clone(),
-    price: order.price,
-    quantity: order.quantity,
+    account: order.account.clone(),
+    price: instrument.format_price(order.price),
+    quantity: instrument.format_quantity(order.quantity),
     side: format!("{:?}", order.side),
     order_type: format!("{:?}", order.order_type),
     status: format!("{:?}", order.status),
-    filled_quantity: order.filled_quantity,
-    remain_quantity: order.remain_quantity,
+    filled_quantity: instrument.format_quantity(order.filled_quantity),
+    remain_quantity: instrument.format_quantity(order.remain_quantity),
     entry_time: order.entry_time.to_rfc3339(),
+    client_order_id: None,
+    reject_reason: None,
   }
 }
 
 /// 체결을 DTO로 변환
-pub fn execution_to_dto(execution: &Execution) -> ExecutionDto {
+pub fn execution_to_dto(execution: &Execution, instrument: &Instrument) -> ExecutionDto {
   ExecutionDto {
     exec_id: execution.exec_id.clone(),
     order_id: execution.order_id.clone(),
+    trade_id: execution.trade_id.clone(),
+    symbol: execution.symbol.clone(),
+    side: format!("{:?}", execution.side),
+    price: instrument.format_price(execution.price),
+    quantity: instrument.format_quantity(execution.quantity),
+    is_maker: execution.is_maker,
+    fee: instrument.format_amount(execution.fee),
+    transaction_time: execution.transaction_time.to_rfc3339(),
+  }
+}
+
+/// 체결을 공개 체결 DTO로 변환 (side는 테이커 방향)
+pub fn trade_to_dto(execution: &Execution, instrument: &Instrument) -> TradeDto {
+  TradeDto {
+    trade_id: execution.trade_id.clone(),
     symbol: execution.symbol.clone(),
     side: format!("{:?}", execution.side),
-    price: execution.price,
-    quantity: execution.quantity,
-    fee: execution.fee,
+    price: instrument.format_price(execution.price),
+    quantity: instrument.format_quantity(execution.quantity),
     transaction_time: execution.transaction_time.to_rfc3339(),
   }
 }
@@ -141,12 +173,12 @@ pub fn calculate_orderbook_delta(
   // 1. 기존 호가 매핑 (가격 -> 인덱스)
   let mut old_bids_map = std::collections::HashMap::new();
   for (i, bid) in old_book.bids.iter().enumerate() {
-    old_bids_map.insert(bid.price, i);
+    old_bids_map.insert(bid.price.as_str(), i);
   }
   
   let mut old_asks_map = std::collections::HashMap::new();
   for (i, ask) in old_book.asks.iter().enumerate() {
-    old_asks_map.insert(ask.price, i);
+    old_asks_map.insert(ask.price.as_str(), i);
   }
   
   // 2. 변경된 호가 계산
@@ -155,14 +187,14 @@ pub fn calculate_orderbook_delta(
   let mut removed_bids = Vec::new();
   
   for bid in &new_book.bids {
-    if !old_bids_map.contains_key(&bid.price) {
+    if !old_bids_map.contains_key(bid.price.as_str()) {
       added_bids.push(bid.clone());
     } else {
-      let old_idx = old_bids_map[&bid.price];
-      if old_book.bids[old_idx].volume != bid.volume {
+      let old_idx = old_bids_map[bid.price.as_str()];
+      if old_book.bids[old_idx].volume != bid.volume || old_book.bids[old_idx].order_count != bid.order_count {
         updated_bids.push(bid.clone());
       }
-      old_bids_map.remove(&bid.price);
+      old_bids_map.remove(bid.price.as_str());
     }
   }
   
@@ -177,14 +209,14 @@ pub fn calculate_orderbook_delta(
   let mut removed_asks = Vec::new();
   
   for ask in &new_book.asks {
-    if !old_asks_map.contains_key(&ask.price) {
+    if !old_asks_map.contains_key(ask.price.as_str()) {
       added_asks.push(ask.clone());
     } else {
-      let old_idx = old_asks_map[&ask.price];
-      if old_book.asks[old_idx].volume != ask.volume {
+      let old_idx = old_asks_map[ask.price.as_str()];
+      if old_book.asks[old_idx].volume != ask.volume || old_book.asks[old_idx].order_count != ask.order_count {
         updated_asks.push(ask.clone());
       }
-      old_asks_map.remove(&ask.price);
+      old_asks_map.remove(ask.price.as_str());
     }
   }
   
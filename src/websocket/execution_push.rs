@@ -1,80 +1,164 @@
-/**
+/*
 * filename : execution_push
 * author : HAMA
 * date: 2025. 4. 30.
 * description: 
 **/
 
+use std::net::{IpAddr, SocketAddr};
 use std::sync::{Arc, Mutex};
 use futures::{FutureExt, StreamExt};
 use tokio::sync::mpsc::{Receiver, Sender};
+use tokio_stream::wrappers::ReceiverStream;
 use warp::ws::{Message, WebSocket};
-use warp::Filter;
+use warp::{Filter, Reply};
+use warp::http::StatusCode;
+use serde::Deserialize;
 use serde_json::json;
 
 use crate::models::Execution;
+use crate::matching_engine::OrderOutcome;
+use crate::instrument::{Instrument, InstrumentRegistry};
+use crate::risk::RiskManager;
+use crate::throttle::Throttle;
 use crate::util::serializer;
+use super::session::{SessionManager, TradingSession};
+use super::order_entry::OrderEntry;
 
 // WebSocket 연결을 관리하는 구조체
+// 체결은 인증된 계정의 연결에만 전송 (다른 계정의 체결은 공개 체결 채널에서 익명으로 제공)
 pub struct ExecutionPushManager {
-  connections: Arc<Mutex<Vec<Sender<Message>>>>,
+  connections: Mutex<Vec<(String, Sender<Message>)>>, // 계정, 연결
+  instruments: Arc<InstrumentRegistry>, // 가격, 수량의 10진수 변환용
+  sessions: Arc<SessionManager>, // API 키로 인증하는 거래 세션 (cancel-on-disconnect)
+  order_entry: OrderEntry, // 거래 세션의 주문 입력 및 체결 전달
 }
 
 impl ExecutionPushManager {
-  pub fn new() -> Self {
+  // 거래 세션의 신규 주문과 정정은 다른 주문 입력 경로와 같은 사전 리스크 한도와
+  // 요청 속도 제한을 거침
+  pub fn new(instruments: Arc<InstrumentRegistry>, sessions: Arc<SessionManager>, risk: Arc<RiskManager>, throttle: Arc<Throttle>) -> Self {
     ExecutionPushManager {
-      connections: Arc::new(Mutex::new(Vec::new())),
+      connections: Mutex::new(Vec::new()),
+      order_entry: OrderEntry::new(instruments.clone(), sessions.order_tx(), risk, throttle),
+      instruments,
+      sessions,
     }
   }
   
-  // 새 연결 추가
-  pub fn add_connection(&self, tx: Sender<Message>) {
+  // 인증된 계정의 연결 추가
+  pub fn add_connection(&self, account: &str, tx: Sender<Message>) {
     let mut connections = self.connections.lock().unwrap();
-    connections.push(tx);
+    connections.push((account.to_string(), tx));
   }
   
   // 연결 제거
   pub fn remove_connection(&self, tx: &Sender<Message>) {
     let mut connections = self.connections.lock().unwrap();
-    if let Some(pos) = connections.iter().position(|x| x.same_channel(tx)) {
+    if let Some(pos) = connections.iter().position(|(_, x)| x.same_channel(tx)) {
       connections.remove(pos);
     }
   }
   
-  // 모든 연결에 체결 정보 전송
+  // 주문 계정의 연결에 체결 정보 전송
   pub async fn broadcast_execution(&self, execution: &Execution) {
-    let connections = self.connections.lock().unwrap().clone();
+    let connections = self.connections.lock().unwrap().iter()
+      .filter(|(account, _)| !execution.account.is_empty() && account == &execution.account)
+      .map(|(_, tx)| tx.clone())
+      .collect::<Vec<_>>();
     
     // 체결을 DTO로 변환하고 직렬화
-    let exec_dto = serializer::execution_to_dto(execution);
+    let instrument = self.instruments.get(&execution.symbol)
+      .unwrap_or_else(|| Instrument::new(&execution.symbol));
+    let exec_dto = serializer::execution_to_dto(execution, &instrument);
     let exec_json = match serde_json::to_string(&exec_dto) {
       Ok(json) => json,
       Err(_) => return, // 직렬화 실패 시 종료
     };
     
     for tx in &connections {
-      if let Err(_) = tx.send(Message::text(exec_json.clone())).await {
-        // 에러가 발생하면 나중에 연결을 제거하기 위해 표시
-        // 실제 구현에서는 오류 처리 로직을 더 견고하게 구현해야 함
-      }
+      // 닫힌 연결은 연결 종료 처리에서 제거됨
+      let _ = tx.send(Message::text(exec_json.clone())).await;
+    }
+    
+    // 거래 세션에서 입력한 주문의 체결은 해당 세션에 client_order_id와 함께 전달
+    if let Some((tx, fill)) = self.order_entry.route_execution(execution, &instrument) {
+      let _ = tx.send(fill).await;
+    }
+  }
+  
+  // 거래 세션에서 입력한 주문의 매칭 엔진 결과(거부 등)를 해당 세션에 전달
+  pub async fn broadcast_outcome(&self, outcome: &OrderOutcome) {
+    if let Some((tx, message)) = self.order_entry.route_outcome(outcome) {
+      let _ = tx.send(message).await;
     }
   }
 }
 
+// 거래 세션 연결 옵션: /ws/executions?api_key=...&cancel_on_disconnect=true&heartbeat_secs=10
+#[derive(Debug, Default, Deserialize)]
+struct SessionParams {
+  api_key: Option<String>,
+  #[serde(default)]
+  cancel_on_disconnect: bool,
+  heartbeat_secs: Option<u64>,
+}
+
 // WebSocket 요청 처리 라우트
 pub fn ws_execution_route(
   manager: Arc<ExecutionPushManager>,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
   warp::path!("ws" / "executions")
     .and(warp::ws())
-    .map(move |ws: warp::ws::Ws| {
+    .and(warp::query::<SessionParams>())
+    .and(warp::addr::remote())
+    .map(move |ws: warp::ws::Ws, params: SessionParams, remote: Option<SocketAddr>| {
       let manager = manager.clone();
-      ws.on_upgrade(move |socket| handle_execution_connection(socket, manager))
+      let ip = remote.map(|remote| remote.ip());
+      
+      // 토큰 없는 연결은 login 메시지로 인증한 뒤부터 체결을 수신
+      if params.api_key.is_none() && !params.cancel_on_disconnect {
+        return ws.on_upgrade(move |socket| handle_execution_connection(socket, manager, ip)).into_response();
+      }
+      
+      // 인증 실패는 업그레이드 전에 거부
+      let session = params.api_key.as_ref()
+        .and_then(|api_key| manager.sessions.open(api_key, ip, params.cancel_on_disconnect, params.heartbeat_secs));
+      match session {
+        Some(session) => ws.on_upgrade(move |socket| handle_connection(socket, manager, Some(session), ip)).into_response(),
+        None => warp::reply::with_status(
+          warp::reply::json(&json!({ "error": "UNAUTHORIZED", "message": "a valid api_key is required for a trading session" })),
+          StatusCode::UNAUTHORIZED
+        ).into_response(),
+      }
     })
 }
 
 // 새 WebSocket 연결 처리
-async fn handle_execution_connection(ws: WebSocket, manager: Arc<ExecutionPushManager>) {
+async fn handle_execution_connection(ws: WebSocket, manager: Arc<ExecutionPushManager>, ip: Option<IpAddr>) {
+  handle_connection(ws, manager, None, ip).await
+}
+
+// 연결 시 로그인 메시지: {"type": "login", "api_key": "...", "cancel_on_disconnect": false, "heartbeat_secs": 30}
+#[derive(Debug, Deserialize)]
+struct LoginRequest {
+  api_key: String,
+  #[serde(default)]
+  cancel_on_disconnect: bool,
+  heartbeat_secs: Option<u64>,
+}
+
+// 인증된 연결을 계정에 등록하고 세션 정보를 전송
+async fn start_session(manager: &ExecutionPushManager, session: &TradingSession, tx: &Sender<Message>) {
+  manager.add_connection(&session.account, tx.clone());
+  let opened = json!({ "type": "session", "session_id": session.session_id, "account": session.account,
+                       "cancel_on_disconnect": session.cancel_on_disconnect, "heartbeat_secs": session.heartbeat.as_secs() });
+  let _ = tx.send(Message::text(opened.to_string())).await;
+}
+
+// 연결 수명 주기: 인증, 수신 대기, 종료 시 정리.
+// 거래 세션은 heartbeat 간격 안에 아무 메시지도 오지 않으면 끊긴 것으로 보고 종료합니다.
+async fn handle_connection(ws: WebSocket, manager: Arc<ExecutionPushManager>, mut session: Option<TradingSession>, ip: Option<IpAddr>) {
   let (ws_tx, mut ws_rx) = ws.split();
   
   // 토키오 채널 생성 (WebSocket 메시지 전송용)
@@ -82,30 +166,65 @@ async fn handle_execution_connection(ws: WebSocket, manager: Arc<ExecutionPushMa
   let manager_clone = manager.clone();
   
   // 메시지 수신 작업
-  tokio::task::spawn(rx.forward(ws_tx).map(|result| {
+  tokio::task::spawn(ReceiverStream::new(rx).map(Ok).forward(ws_tx).map(|result| {
     if let Err(e) = result {
       eprintln!("WebSocket send error: {}", e);
     }
   }));
   
-  // 연결 등록
-  manager.add_connection(tx.clone());
+  // 토큰으로 인증한 연결 등록
+  if let Some(session) = &session {
+    start_session(&manager, session, &tx).await;
+  }
   
-  // 클라이언트로부터의 메시지 처리 (여기서는 단순히 연결 상태 확인)
-  while let Some(result) = ws_rx.next().await {
-    match result {
-      Ok(_) => {
-        // 메시지 처리 (필요시)
-      }
-      Err(_) => {
-        manager_clone.remove_connection(&tx);
-        break;
+  // 클라이언트로부터의 메시지 처리 (heartbeat, 로그인, 거래 세션의 주문 입력)
+  let reason = loop {
+    let next = match &session {
+      Some(session) => match tokio::time::timeout(session.heartbeat, ws_rx.next()).await {
+        Ok(next) => next,
+        Err(_) => break "heartbeat timeout",
+      },
+      None => ws_rx.next().await,
+    };
+    
+    match next {
+      Some(Ok(message)) if message.is_close() => break "closed",
+      Some(Ok(message)) => {
+        // 텍스트가 아닌 메시지도 heartbeat로 인정
+        let text = match message.to_str() {
+          Ok(text) => text,
+          Err(_) => continue,
+        };
+        
+        // {"type": "ping"}에는 pong으로 응답
+        let message_type = serde_json::from_str::<serde_json::Value>(text).ok().and_then(|value| value["type"].as_str().map(String::from));
+        if message_type.as_deref() == Some("ping") {
+          let _ = tx.send(Message::text(json!({ "type": "pong" }).to_string())).await;
+        } else if session.is_none() && message_type.as_deref() == Some("login") {
+          let login = serde_json::from_str::<LoginRequest>(text).ok();
+          session = login.and_then(|login| manager.sessions.open(&login.api_key, ip, login.cancel_on_disconnect, login.heartbeat_secs));
+          match &session {
+            Some(session) => start_session(&manager, session, &tx).await,
+            None => {
+              let error = json!({ "type": "error", "error": "UNAUTHORIZED", "message": "a valid api_key is required" });
+              let _ = tx.send(Message::text(error.to_string())).await;
+            }
+          }
+        } else if let Some(session) = &session {
+          manager.order_entry.handle(session, &tx, text).await;
+        }
       }
+      Some(Err(_)) => break "error",
+      None => break "closed",
     }
-  }
+  };
   
   // 연결 종료 시 정리
   manager_clone.remove_connection(&tx);
+  if let Some(session) = &session {
+    let open_orders = manager_clone.order_entry.close_session(&session.session_id);
+    manager_clone.sessions.close(session, reason, open_orders).await;
+  }
 }
 
 // 체결 수신 및 WebSocket 브로드캐스트 작업
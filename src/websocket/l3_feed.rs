@@ -0,0 +1,243 @@
+/*
+* filename : l3_feed
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 개별 주문 단위(L3) 시장 데이터 피드
+**/
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use futures::{FutureExt, StreamExt};
+use tokio::sync::mpsc::Sender;
+use tokio_stream::wrappers::ReceiverStream;
+use warp::ws::{Message, WebSocket};
+use warp::Filter;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::instrument::{Instrument, InstrumentRegistry};
+use crate::matching_engine::{BookUpdate, OrderEventKind};
+use crate::models::{OrderBook, PriceLevel, Side};
+
+// Resting order in an L3 snapshot, in price then time priority.
+// Prices and quantities are decimal strings at the instrument's scale.
+#[derive(Clone, Debug, Serialize)]
+pub struct L3Order {
+  pub order_id: u64,
+  pub price: String,
+  pub quantity: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct L3Snapshot {
+  pub symbol: String,
+  pub sequence: u64, // Sequence of the last event included
+  pub bids: Vec<L3Order>,
+  pub asks: Vec<L3Order>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct L3EventDto {
+  sequence: u64,
+  event: OrderEventKind,
+  order_id: u64,
+  side: Side,
+  price: String,
+  quantity: String,
+}
+
+// Order ids are replaced by numbers that reveal neither the account nor the
+// original id, and a replaced order gets a new number
+#[derive(Default)]
+struct PublicIds {
+  next: u64,
+  ids: HashMap<String, u64>,
+}
+
+impl PublicIds {
+  fn get(&mut self, order_id: &str) -> u64 {
+    if let Some(&id) = self.ids.get(order_id) {
+      return id;
+    }
+    self.next += 1;
+    self.ids.insert(order_id.to_string(), self.next);
+    self.next
+  }
+}
+
+#[derive(Default)]
+struct L3Book {
+  sequence: u64,
+  order_ids: PublicIds,
+  book: OrderBook,
+}
+
+impl L3Book {
+  fn snapshot(&mut self, instrument: &Instrument) -> L3Snapshot {
+    let order_ids = &mut self.order_ids;
+    let mut l3_orders = |level: &PriceLevel| -> Vec<L3Order> {
+      level.orders.iter()
+        .map(|order| L3Order {
+          order_id: order_ids.get(&order.order_id),
+          price: instrument.format_price(order.price),
+          quantity: instrument.format_quantity(order.remain_quantity),
+        })
+        .collect()
+    };
+    let bids = self.book.buy_book.limits.values().rev().flat_map(&mut l3_orders).collect();
+    let asks = self.book.sell_book.limits.values().flat_map(&mut l3_orders).collect();
+    L3Snapshot { symbol: instrument.symbol.clone(), sequence: self.sequence, bids, asks }
+  }
+}
+
+// Publishes add, modify, execute and delete events for every resting order,
+// numbered per symbol so that clients can detect gaps and resynchronize
+pub struct L3FeedManager {
+  connections: Mutex<HashMap<String, Vec<Sender<Message>>>>,
+  books: Mutex<HashMap<String, L3Book>>,
+  instruments: Option<Arc<InstrumentRegistry>>,
+}
+
+impl L3FeedManager {
+  pub fn new() -> Self {
+    L3FeedManager {
+      connections: Mutex::new(HashMap::new()),
+      books: Mutex::new(HashMap::new()),
+      instruments: None,
+    }
+  }
+  
+  // Prices and quantities formatted at the scales of the registered instruments
+  pub fn with_instruments(instruments: Arc<InstrumentRegistry>) -> Self {
+    L3FeedManager {
+      instruments: Some(instruments),
+      ..L3FeedManager::new()
+    }
+  }
+  
+  fn instrument(&self, symbol: &str) -> Instrument {
+    self.instruments.as_ref()
+      .and_then(|instruments| instruments.get(symbol))
+      .unwrap_or_else(|| Instrument::new(symbol))
+  }
+  
+  pub fn update(&self, update: &BookUpdate) {
+    let instrument = self.instrument(&update.symbol);
+    // Events are sent while the lock is held so that they go out in sequence order
+    let mut books = self.books.lock().unwrap();
+    let state = books.entry(update.symbol.clone()).or_default();
+    
+    let mut events = Vec::new();
+    for event in &update.events {
+      state.sequence += 1;
+      let order_id = state.order_ids.get(&event.order_id);
+      if event.kind == OrderEventKind::Delete {
+        state.order_ids.ids.remove(&event.order_id);
+      }
+      event.apply(&mut state.book);
+      events.push(L3EventDto {
+        sequence: state.sequence,
+        event: event.kind,
+        order_id,
+        side: event.side.clone(),
+        price: instrument.format_price(event.price),
+        quantity: instrument.format_quantity(event.quantity),
+      });
+    }
+    // Fully executed orders have left the book
+    let book = &state.book;
+    state.order_ids.ids.retain(|order_id, _| book.order_map.contains_key(order_id));
+    
+    if events.is_empty() {
+      return;
+    }
+    let message = Message::text(json!({
+      "type": "l3",
+      "symbol": update.symbol,
+      "events": events
+    }).to_string());
+    let connections = self.connections.lock().unwrap();
+    for tx in connections.get(&update.symbol).into_iter().flatten() {
+      let _ = tx.try_send(message.clone());
+    }
+  }
+  
+  // Every resting order of the symbol, empty with sequence 0 before the first event
+  pub fn snapshot(&self, symbol: &str) -> L3Snapshot {
+    let instrument = self.instrument(symbol);
+    let mut books = self.books.lock().unwrap();
+    match books.get_mut(symbol) {
+      Some(state) => state.snapshot(&instrument),
+      None => L3Snapshot { symbol: symbol.to_string(), sequence: 0, bids: Vec::new(), asks: Vec::new() },
+    }
+  }
+  
+  pub fn add_connection(&self, symbol: &str, tx: Sender<Message>) {
+    self.connections.lock().unwrap().entry(symbol.to_string()).or_default().push(tx);
+  }
+  
+  pub fn remove_connection(&self, symbol: &str, tx: &Sender<Message>) {
+    if let Some(connections) = self.connections.lock().unwrap().get_mut(symbol) {
+      connections.retain(|x| !x.same_channel(tx));
+    }
+  }
+  
+  fn snapshot_message(&self, symbol: &str) -> Message {
+    Message::text(json!({ "type": "l3_snapshot", "data": self.snapshot(symbol) }).to_string())
+  }
+}
+
+impl Default for L3FeedManager {
+  fn default() -> Self {
+    L3FeedManager::new()
+  }
+}
+
+// GET /api/v1/l3/{symbol}
+pub fn routes(manager: Arc<L3FeedManager>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+  warp::path!("api" / "v1" / "l3" / String)
+    .and(warp::get())
+    .map(move |symbol: String| warp::reply::json(&manager.snapshot(&symbol)))
+}
+
+// /ws/l3/{symbol}: a snapshot, then the events that follow it
+pub fn ws_l3_route(
+  manager: Arc<L3FeedManager>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+  warp::path!("ws" / "l3" / String)
+    .and(warp::ws())
+    .map(move |symbol: String, ws: warp::ws::Ws| {
+      let manager = manager.clone();
+      ws.on_upgrade(move |socket| handle_l3_connection(socket, symbol, manager))
+    })
+}
+
+async fn handle_l3_connection(ws: WebSocket, symbol: String, manager: Arc<L3FeedManager>) {
+  let (ws_tx, mut ws_rx) = ws.split();
+  let (tx, rx) = tokio::sync::mpsc::channel::<Message>(1000);
+  
+  tokio::task::spawn(ReceiverStream::new(rx).map(Ok).forward(ws_tx).map(|result| {
+    if let Err(e) = result {
+      eprintln!("WebSocket send error: {}", e);
+    }
+  }));
+  manager.add_connection(&symbol, tx.clone());
+  let _ = tx.send(manager.snapshot_message(&symbol)).await;
+  
+  // {"type": "snapshot"} requests a new snapshot after a gap in the sequence
+  while let Some(result) = ws_rx.next().await {
+    match result {
+      Ok(message) if message.is_close() => break,
+      Ok(message) => {
+        let is_snapshot_request = message.to_str().ok()
+          .and_then(|text| serde_json::from_str::<serde_json::Value>(text).ok())
+          .is_some_and(|value| value["type"] == "snapshot");
+        if is_snapshot_request {
+          let _ = tx.send(manager.snapshot_message(&symbol)).await;
+        }
+      }
+      Err(_) => break,
+    }
+  }
+  manager.remove_connection(&symbol, &tx);
+}
\ No newline at end of file
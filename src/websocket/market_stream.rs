@@ -0,0 +1,403 @@
+/*
+* filename : market_stream
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 구독 기반 다중화 시장 데이터 WebSocket (/ws)
+**/
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use futures::{FutureExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use thiserror::Error;
+use tokio::sync::mpsc::Sender;
+use tokio::time::{self, Duration};
+use tokio_stream::wrappers::ReceiverStream;
+use warp::ws::{Message, WebSocket};
+use warp::Filter;
+
+use crate::instrument::InstrumentRegistry;
+use crate::market_data_publisher::models::{BestBidOfferData, BookDepth, CandleInterval};
+use crate::market_data_publisher::publisher::MarketDataPublisher;
+
+pub const DEFAULT_MAX_SUBSCRIPTIONS: usize = 50;
+
+// A subscribable feed, named e.g. "trades:BTC-KRW", "book:BTC-KRW:10" or "kline:BTC-KRW:1m".
+// Book channels may group levels into buckets of N ticks, e.g. "book:BTC-KRW:full:10".
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Channel {
+  Trades(String),
+  Book { symbol: String, depth: BookDepth, group: u64 },
+  Ticker(String),
+  Kline { symbol: String, interval: CandleInterval },
+  Bbo(String),
+}
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum ChannelError {
+  #[error("unknown channel {0}")]
+  UnknownChannel(String),
+  #[error("unknown symbol {0}")]
+  UnknownSymbol(String),
+  #[error("book depth must be one of 5, 10, 20, 50, 100 or full")]
+  InvalidDepth(String),
+  #[error("book group must be a positive number of ticks")]
+  InvalidGroup(String),
+  #[error("invalid kline interval {0}")]
+  InvalidInterval(String),
+  #[error("at most {max} subscriptions per connection")]
+  SubscriptionLimit { max: usize },
+  #[error("not subscribed to {0}")]
+  NotSubscribed(String),
+}
+
+impl ChannelError {
+  pub fn code(&self) -> &'static str {
+    match self {
+      ChannelError::UnknownChannel(_) => "UNKNOWN_CHANNEL",
+      ChannelError::UnknownSymbol(_) => "UNKNOWN_SYMBOL",
+      ChannelError::InvalidDepth(_) => "INVALID_DEPTH",
+      ChannelError::InvalidGroup(_) => "INVALID_GROUP",
+      ChannelError::InvalidInterval(_) => "INVALID_INTERVAL",
+      ChannelError::SubscriptionLimit { .. } => "SUBSCRIPTION_LIMIT",
+      ChannelError::NotSubscribed(_) => "NOT_SUBSCRIBED",
+    }
+  }
+}
+
+impl Channel {
+  pub fn parse(name: &str) -> Result<Channel, ChannelError> {
+    let parts = name.split(':').collect::<Vec<_>>();
+    let channel = match parts.as_slice() {
+      ["trades", symbol] => Channel::Trades(symbol.to_string()),
+      ["ticker", symbol] => Channel::Ticker(symbol.to_string()),
+      ["bbo", symbol] => Channel::Bbo(symbol.to_string()),
+      ["book", symbol, depth, group @ ..] if group.len() <= 1 => {
+        let depth = BookDepth::parse(depth).ok_or_else(|| ChannelError::InvalidDepth(depth.to_string()))?;
+        let group = match group {
+          [group] => group.parse::<u64>().ok().filter(|group| *group > 0)
+            .ok_or_else(|| ChannelError::InvalidGroup(group.to_string()))?,
+          _ => 1,
+        };
+        Channel::Book { symbol: symbol.to_string(), depth, group }
+      }
+      ["kline", symbol, interval] => {
+        let interval = CandleInterval::from_string(interval)
+          .ok_or_else(|| ChannelError::InvalidInterval(interval.to_string()))?;
+        Channel::Kline { symbol: symbol.to_string(), interval }
+      }
+      _ => return Err(ChannelError::UnknownChannel(name.to_string())),
+    };
+    if channel.symbol().is_empty() {
+      return Err(ChannelError::UnknownChannel(name.to_string()));
+    }
+    Ok(channel)
+  }
+  
+  pub fn symbol(&self) -> &str {
+    match self {
+      Channel::Trades(symbol) | Channel::Ticker(symbol) | Channel::Bbo(symbol) => symbol,
+      Channel::Book { symbol, .. } | Channel::Kline { symbol, .. } => symbol,
+    }
+  }
+}
+
+impl fmt::Display for Channel {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Channel::Trades(symbol) => write!(f, "trades:{}", symbol),
+      Channel::Book { symbol, depth, group: 1 } => write!(f, "book:{}:{}", symbol, depth),
+      Channel::Book { symbol, depth, group } => write!(f, "book:{}:{}:{}", symbol, depth, group),
+      Channel::Ticker(symbol) => write!(f, "ticker:{}", symbol),
+      Channel::Kline { symbol, interval } => write!(f, "kline:{}:{}", symbol, interval),
+      Channel::Bbo(symbol) => write!(f, "bbo:{}", symbol),
+    }
+  }
+}
+
+struct Subscriber {
+  tx: Sender<Message>,
+  channels: HashSet<Channel>,
+}
+
+// Outcome of a subscribe or unsubscribe request
+#[derive(Debug, Default)]
+pub struct SubscriptionResult {
+  pub channels: Vec<Channel>,
+  pub errors: Vec<(String, ChannelError)>,
+}
+
+// Connections of /ws and the channels each one subscribed to
+pub struct MarketStreamHub {
+  subscribers: Mutex<HashMap<u64, Subscriber>>,
+  snapshots: Mutex<HashMap<Channel, Value>>, // Last data sent on snapshot channels, for new subscribers
+  next_id: AtomicU64,
+  instruments: Arc<InstrumentRegistry>,
+  max_subscriptions: usize,
+}
+
+impl MarketStreamHub {
+  pub fn new(instruments: Arc<InstrumentRegistry>) -> Self {
+    MarketStreamHub::with_limit(instruments, DEFAULT_MAX_SUBSCRIPTIONS)
+  }
+  
+  pub fn with_limit(instruments: Arc<InstrumentRegistry>, max_subscriptions: usize) -> Self {
+    MarketStreamHub {
+      subscribers: Mutex::new(HashMap::new()),
+      snapshots: Mutex::new(HashMap::new()),
+      next_id: AtomicU64::new(1),
+      instruments,
+      max_subscriptions,
+    }
+  }
+  
+  fn connect(&self, tx: Sender<Message>) -> u64 {
+    let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+    self.subscribers.lock().unwrap().insert(id, Subscriber { tx, channels: HashSet::new() });
+    id
+  }
+  
+  fn disconnect(&self, id: u64) {
+    self.subscribers.lock().unwrap().remove(&id);
+    self.forget_unsubscribed();
+  }
+  
+  // Channels are added one by one until the connection reaches its limit;
+  // subscribing again to a channel is acknowledged without counting twice
+  pub fn subscribe(&self, id: u64, names: &[String]) -> SubscriptionResult {
+    let mut result = SubscriptionResult::default();
+    let mut subscribers = self.subscribers.lock().unwrap();
+    let subscriber = match subscribers.get_mut(&id) {
+      Some(subscriber) => subscriber,
+      None => return result,
+    };
+    
+    for name in names {
+      let channel = Channel::parse(name).and_then(|channel| {
+        if self.instruments.get(channel.symbol()).is_none() {
+          return Err(ChannelError::UnknownSymbol(channel.symbol().to_string()));
+        }
+        if !subscriber.channels.contains(&channel) && subscriber.channels.len() >= self.max_subscriptions {
+          return Err(ChannelError::SubscriptionLimit { max: self.max_subscriptions });
+        }
+        Ok(channel)
+      });
+      match channel {
+        Ok(channel) => {
+          subscriber.channels.insert(channel.clone());
+          result.channels.push(channel);
+        }
+        Err(e) => result.errors.push((name.clone(), e)),
+      }
+    }
+    result
+  }
+  
+  pub fn unsubscribe(&self, id: u64, names: &[String]) -> SubscriptionResult {
+    let mut result = SubscriptionResult::default();
+    {
+      let mut subscribers = self.subscribers.lock().unwrap();
+      let subscriber = match subscribers.get_mut(&id) {
+        Some(subscriber) => subscriber,
+        None => return result,
+      };
+      for name in names {
+        match Channel::parse(name) {
+          Ok(channel) if subscriber.channels.remove(&channel) => result.channels.push(channel),
+          Ok(_) => result.errors.push((name.clone(), ChannelError::NotSubscribed(name.clone()))),
+          Err(e) => result.errors.push((name.clone(), e)),
+        }
+      }
+    }
+    self.forget_unsubscribed();
+    result
+  }
+  
+  // Every channel with at least one subscriber
+  pub fn subscribed_channels(&self) -> HashSet<Channel> {
+    let subscribers = self.subscribers.lock().unwrap();
+    subscribers.values().flat_map(|subscriber| subscriber.channels.iter().cloned()).collect()
+  }
+  
+  // The last data sent on a snapshot channel
+  pub fn snapshot(&self, channel: &Channel) -> Option<Value> {
+    self.snapshots.lock().unwrap().get(channel).cloned()
+  }
+  
+  // Send to the channel's subscribers. Slow clients miss updates instead of holding up the others.
+  pub fn publish(&self, channel: &Channel, data: Value) {
+    let message = Message::text(update_message(channel, &data).to_string());
+    let subscribers = self.subscribers.lock().unwrap();
+    for subscriber in subscribers.values().filter(|subscriber| subscriber.channels.contains(channel)) {
+      let _ = subscriber.tx.try_send(message.clone());
+    }
+  }
+  
+  // Publish snapshot data if it differs from what was last sent, ignoring its timestamp
+  pub fn update(&self, channel: &Channel, data: Value) {
+    {
+      let mut snapshots = self.snapshots.lock().unwrap();
+      if snapshots.get(channel).is_some_and(|last| without_timestamp(last) == without_timestamp(&data)) {
+        return;
+      }
+      snapshots.insert(channel.clone(), data.clone());
+    }
+    self.publish(channel, data);
+  }
+  
+  fn forget_unsubscribed(&self) {
+    let subscribed = self.subscribed_channels();
+    self.snapshots.lock().unwrap().retain(|channel, _| subscribed.contains(channel));
+  }
+}
+
+fn update_message(channel: &Channel, data: &Value) -> Value {
+  json!({ "type": "update", "channel": channel.to_string(), "data": data })
+}
+
+fn without_timestamp(data: &Value) -> Value {
+  let mut data = data.clone();
+  if let Some(object) = data.as_object_mut() {
+    object.remove("timestamp");
+  }
+  data
+}
+
+// {"type": "subscribe" | "unsubscribe" | "ping", "id": ..., "channels": [...]}
+#[derive(Debug, Deserialize)]
+struct StreamRequest {
+  #[serde(rename = "type")]
+  kind: String,
+  #[serde(default)]
+  id: Value,
+  #[serde(default)]
+  channels: Vec<String>,
+}
+
+pub fn ws_stream_route(
+  hub: Arc<MarketStreamHub>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+  warp::path("ws")
+    .and(warp::path::end())
+    .and(warp::ws())
+    .map(move |ws: warp::ws::Ws| {
+      let hub = hub.clone();
+      ws.on_upgrade(move |socket| handle_stream_connection(socket, hub))
+    })
+}
+
+async fn handle_stream_connection(ws: WebSocket, hub: Arc<MarketStreamHub>) {
+  let (ws_tx, mut ws_rx) = ws.split();
+  let (tx, rx) = tokio::sync::mpsc::channel::<Message>(1000);
+  
+  tokio::task::spawn(ReceiverStream::new(rx).map(Ok).forward(ws_tx).map(|result| {
+    if let Err(e) = result {
+      eprintln!("WebSocket send error: {}", e);
+    }
+  }));
+  let id = hub.connect(tx.clone());
+  
+  while let Some(result) = ws_rx.next().await {
+    let message = match result {
+      Ok(message) if message.is_close() => break,
+      Ok(message) => message,
+      Err(_) => break,
+    };
+    let text = match message.to_str() {
+      Ok(text) => text,
+      Err(_) => continue,
+    };
+    for reply in handle_request(&hub, id, text) {
+      if tx.send(Message::text(reply.to_string())).await.is_err() {
+        break;
+      }
+    }
+  }
+  hub.disconnect(id);
+}
+
+// Replies to a client message: the acknowledgement, one error per rejected channel
+// and the current snapshot of each newly subscribed channel
+fn handle_request(hub: &MarketStreamHub, id: u64, text: &str) -> Vec<Value> {
+  let request = match serde_json::from_str::<StreamRequest>(text) {
+    Ok(request) => request,
+    Err(e) => return vec![json!({ "type": "error", "id": null, "error": "INVALID_REQUEST", "message": e.to_string() })],
+  };
+  
+  let (result, acknowledgement) = match request.kind.as_str() {
+    "ping" => return vec![json!({ "type": "pong", "id": request.id })],
+    "subscribe" => (hub.subscribe(id, &request.channels), "subscribed"),
+    "unsubscribe" => (hub.unsubscribe(id, &request.channels), "unsubscribed"),
+    other => {
+      let message = format!("unknown request type {}", other);
+      return vec![json!({ "type": "error", "id": request.id, "error": "INVALID_REQUEST", "message": message })];
+    }
+  };
+  
+  let names = result.channels.iter().map(|channel| channel.to_string()).collect::<Vec<_>>();
+  let mut replies = vec![json!({ "type": acknowledgement, "id": request.id, "channels": names })];
+  for (channel, error) in &result.errors {
+    replies.push(json!({ "type": "error", "id": request.id, "channel": channel, "error": error.code(), "message": error.to_string() }));
+  }
+  if acknowledgement == "subscribed" {
+    for channel in &result.channels {
+      if let Some(data) = hub.snapshot(channel) {
+        replies.push(update_message(channel, &data));
+      }
+    }
+  }
+  replies
+}
+
+// Top of book changes from the matching engine, sent without waiting for the broadcaster
+pub fn publish_bbo(hub: &MarketStreamHub, bbo: &BestBidOfferData) {
+  if let Ok(data) = serde_json::to_value(bbo) {
+    hub.update(&Channel::Bbo(bbo.symbol.clone()), data);
+  }
+}
+
+// Refresh the subscribed book, ticker, kline and bbo channels from the market data publisher.
+// Trades are pushed as they happen by the public trades channel. Bbo changes are also pushed
+// by publish_bbo; refreshing it here gives new subscribers the current top of book.
+pub async fn run_market_stream_broadcaster(hub: Arc<MarketStreamHub>, mdp: Arc<MarketDataPublisher>) {
+  let mut interval = time::interval(Duration::from_millis(100));
+  
+  loop {
+    interval.tick().await;
+    
+    for channel in hub.subscribed_channels() {
+      let data = match &channel {
+        Channel::Trades(_) => continue,
+        Channel::Book { symbol, depth, group } => mdp.get_orderbook(symbol, *depth, *group).and_then(|book| serde_json::to_value(book).ok()),
+        Channel::Ticker(symbol) => mdp.get_statistics(symbol).and_then(|stats| serde_json::to_value(stats).ok()),
+        Channel::Kline { symbol, interval } => mdp.get_current_candle(symbol, *interval).and_then(|candle| serde_json::to_value(candle).ok()),
+        Channel::Bbo(symbol) => mdp.get_bbo(symbol).and_then(|bbo| serde_json::to_value(bbo).ok()),
+      };
+      if let Some(data) = data {
+        hub.update(&channel, data);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  
+  #[test]
+  fn test_channel_names() {
+    for name in ["trades:SYM", "book:SYM:10", "book:SYM:full:5", "ticker:SYM", "kline:SYM:1m", "bbo:SYM"] {
+      assert_eq!(Channel::parse(name).unwrap().to_string(), name);
+    }
+    assert_eq!(Channel::parse("book:SYM:20:1").unwrap().to_string(), "book:SYM:20");
+    assert_eq!(Channel::parse("book:SYM:15"), Err(ChannelError::InvalidDepth("15".into())));
+    assert_eq!(Channel::parse("book:SYM:10:0"), Err(ChannelError::InvalidGroup("0".into())));
+    assert_eq!(Channel::parse("book:SYM:10:1:1"), Err(ChannelError::UnknownChannel("book:SYM:10:1:1".into())));
+    assert_eq!(Channel::parse("kline:SYM:2m"), Err(ChannelError::InvalidInterval("2m".into())));
+    assert_eq!(Channel::parse("trades:"), Err(ChannelError::UnknownChannel("trades:".into())));
+    assert_eq!(Channel::parse("orders:SYM"), Err(ChannelError::UnknownChannel("orders:SYM".into())));
+  }
+}
\ No newline at end of file
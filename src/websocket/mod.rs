@@ -1,4 +1,4 @@
-/**
+/*
 * filename : mod
 * author : HAMA
 * date: 2025. 4. 30.
@@ -6,4 +6,9 @@
 **/
 
 pub mod execution_push;
-pub mod orderbook_relay;
\ No newline at end of file
+pub mod orderbook_relay;
+pub mod session;
+pub mod order_entry;
+pub mod public_trades;
+pub mod market_stream;
+pub mod l3_feed;
\ No newline at end of file
@@ -0,0 +1,510 @@
+/*
+* filename : order_entry
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 거래 세션 WebSocket 주문 입력 (신규, 취소, 정정, 일괄) 및 응답/체결 전달
+**/
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::mpsc::Sender;
+use warp::ws::Message;
+
+use crate::models::{Amend, Execution, Order, OrderMessage, OrderStatus, OrderType, Price, Qty, Side};
+use crate::instrument::{Instrument, InstrumentRegistry, OrderValidationError};
+use crate::matching_engine::{CancelReason, OrderOutcome};
+use crate::order_manager::{prepare_order, NewOrder};
+use crate::error::ApiError;
+use crate::risk::{RiskManager, RiskRejection};
+use crate::throttle::Throttle;
+use crate::util::decimal::DecimalInput;
+use crate::util::serializer;
+use super::session::TradingSession;
+
+pub const MAX_BATCH_SIZE: usize = 100;
+
+// Requests of a trading session, tagged by "type". Batches are handled separately since
+// their items are parsed one by one.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum EntryRequest {
+  New(NewRequest),
+  Cancel(CancelRequest),
+  Amend(AmendRequest),
+}
+
+#[derive(Deserialize)]
+struct NewRequest {
+  request_id: Option<String>,
+  client_order_id: String,
+  #[serde(flatten)]
+  order: NewOrder, // The account is always the session's
+}
+
+#[derive(Deserialize)]
+struct CancelRequest {
+  request_id: Option<String>,
+  client_order_id: String,
+}
+
+// Omitted fields keep their current value. The quantity includes what has already been filled.
+#[derive(Deserialize)]
+struct AmendRequest {
+  request_id: Option<String>,
+  client_order_id: String,
+  price: Option<DecimalInput>,
+  quantity: Option<DecimalInput>,
+}
+
+#[derive(Deserialize)]
+struct BatchRequest {
+  request_id: Option<String>,
+  requests: Vec<Value>,
+}
+
+// An order entered through a session, as far as the engine has confirmed it. Fills and
+// outcomes are forwarded to the session's connection until the order is completely filled,
+// leaves the book or the session ends.
+struct RoutedOrder {
+  session_id: String,
+  client_order_id: String,
+  request_id: Option<String>,
+  symbol: String,
+  side: Side,
+  order_type: OrderType,
+  price: Price,
+  quantity: Qty,
+  filled: Qty,
+  tx: Sender<Message>,
+  // Fills and outcomes are held back until the acknowledgement has been sent, so that they
+  // never overtake it
+  acknowledged: bool,
+  pending: Vec<Message>,
+  // The engine ended the order, it is forgotten once acknowledged
+  closed: bool,
+  // request_ids of the cancels and amends sequenced and not answered yet, in sequence order
+  changes: VecDeque<Option<String>>,
+}
+
+impl RoutedOrder {
+  fn done(&self) -> bool {
+    self.closed || self.filled >= self.quantity
+  }
+  
+  // The request_id an outcome answers: that of the oldest open cancel or amend when the
+  // outcome answers one, otherwise that of the new order
+  fn answering(&mut self, answers_change: bool) -> Option<String> {
+    let change = if answers_change { self.changes.pop_front() } else { None };
+    change.unwrap_or_else(|| self.request_id.clone())
+  }
+}
+
+#[derive(Default)]
+struct Routes {
+  orders: HashMap<String, RoutedOrder>,          // order id -> order
+  client_ids: HashMap<(String, String), String>, // (session id, client order id) -> order id
+}
+
+// Order entry for authenticated sessions. Requests are rate limited and validated like REST
+// orders, new orders and amends pass the shared risk stage, and every request is answered
+// with an ack or a reject carrying its request_id.
+// An ack means the request has been sequenced; fills follow as separate messages.
+pub struct OrderEntry {
+  instruments: Arc<InstrumentRegistry>,
+  order_tx: Sender<OrderMessage>,
+  risk: Arc<RiskManager>,
+  throttle: Arc<Throttle>,
+  routes: Mutex<Routes>,
+}
+
+fn reject(request_id: &Option<String>, client_order_id: Option<&str>, code: &str, message: &str) -> Value {
+  json!({ "type": "reject", "request_id": request_id, "client_order_id": client_order_id, "error": code, "message": message })
+}
+
+fn reject_invalid(request_id: &Option<String>, client_order_id: &str, e: &OrderValidationError) -> Value {
+  reject(request_id, Some(client_order_id), e.code(), &e.to_string())
+}
+
+fn reject_risk(request_id: &Option<String>, client_order_id: &str, e: &RiskRejection) -> Value {
+  reject(request_id, Some(client_order_id), e.code(), &e.to_string())
+}
+
+fn reject_throttled(request_id: &Option<String>, client_order_id: Option<&str>, e: &ApiError) -> Value {
+  reject(request_id, client_order_id, e.code(), &e.to_string())
+}
+
+fn ack(request_id: &Option<String>, client_order_id: &str, order_id: &str, action: &str) -> Value {
+  json!({ "type": "ack", "action": action, "request_id": request_id, "client_order_id": client_order_id, "order_id": order_id })
+}
+
+impl OrderEntry {
+  pub fn new(instruments: Arc<InstrumentRegistry>, order_tx: Sender<OrderMessage>, risk: Arc<RiskManager>, throttle: Arc<Throttle>) -> Self {
+    OrderEntry {
+      instruments,
+      order_tx,
+      risk,
+      throttle,
+      routes: Mutex::new(Routes::default()),
+    }
+  }
+  
+  // Handle one text message of the session and send the reply, followed by any fills
+  // that arrived for the new orders in the meantime
+  pub async fn handle(&self, session: &TradingSession, tx: &Sender<Message>, text: &str) {
+    let mut created = Vec::new();
+    let reply = match serde_json::from_str::<Value>(text) {
+      Ok(request) if request["type"] == "batch" => self.batch(session, tx, request, &mut created).await,
+      Ok(request) => self.request(session, tx, request, &mut created).await,
+      Err(e) => reject(&None, None, "INVALID_REQUEST", &e.to_string()),
+    };
+    
+    let _ = tx.send(Message::text(reply.to_string())).await;
+    for order_id in created {
+      self.release(&order_id).await;
+    }
+  }
+  
+  async fn batch(&self, session: &TradingSession, tx: &Sender<Message>, request: Value, created: &mut Vec<String>) -> Value {
+    let batch = match serde_json::from_value::<BatchRequest>(request) {
+      Ok(batch) => batch,
+      Err(e) => return reject(&None, None, "INVALID_REQUEST", &e.to_string()),
+    };
+    if batch.requests.len() > MAX_BATCH_SIZE {
+      let message = format!("a batch holds at most {} requests", MAX_BATCH_SIZE);
+      return reject(&batch.request_id, None, "BATCH_TOO_LARGE", &message);
+    }
+    
+    // Items are sequenced in order; a rejected item does not affect the others
+    let mut results = Vec::with_capacity(batch.requests.len());
+    for item in batch.requests {
+      let result = if item["type"] == "batch" {
+        let request_id = item["request_id"].as_str().map(str::to_string);
+        reject(&request_id, None, "INVALID_REQUEST", "batches cannot be nested")
+      } else {
+        self.request(session, tx, item, created).await
+      };
+      results.push(result);
+    }
+    json!({ "type": "batch", "request_id": batch.request_id, "results": results })
+  }
+  
+  async fn request(&self, session: &TradingSession, tx: &Sender<Message>, request: Value, created: &mut Vec<String>) -> Value {
+    let request_id = request["request_id"].as_str().map(str::to_string);
+    // Every request that would be sequenced counts, whether or not it is valid
+    if let Err(e) = self.throttle.admit(Some(&session.api_key), session.ip, &self.order_tx) {
+      return reject_throttled(&request_id, request["client_order_id"].as_str(), &e);
+    }
+    match serde_json::from_value::<EntryRequest>(request) {
+      Ok(EntryRequest::New(new)) => self.new_order(session, tx, new, created).await,
+      Ok(EntryRequest::Cancel(cancel)) => self.cancel(session, cancel).await,
+      Ok(EntryRequest::Amend(amend)) => self.amend(session, amend).await,
+      Err(e) => reject(&request_id, None, "INVALID_REQUEST", &e.to_string()),
+    }
+  }
+  
+  async fn new_order(&self, session: &TradingSession, tx: &Sender<Message>, request: NewRequest, created: &mut Vec<String>) -> Value {
+    let NewRequest { request_id, client_order_id, mut order } = request;
+    if client_order_id.is_empty() {
+      return reject(&request_id, None, "INVALID_REQUEST", "client_order_id is required");
+    }
+    let key = (session.session_id.clone(), client_order_id.clone());
+    if self.routes.lock().unwrap().client_ids.contains_key(&key) {
+      return reject(&request_id, Some(&client_order_id), "DUPLICATE_CLIENT_ORDER_ID", "client_order_id is already in use in this session");
+    }
+    
+    order.account = session.account.clone();
+    let order = match prepare_order(order, &self.instruments) {
+      Ok((order, instrument)) => match self.risk.admit(&order, &instrument) {
+        Ok(()) => order,
+        Err(e) => return reject_risk(&request_id, &client_order_id, &e),
+      },
+      Err(e) => return reject_invalid(&request_id, &client_order_id, &e),
+    };
+    let order_id = order.order_id.clone();
+    
+    // Registered before sequencing so that no fill can miss the route
+    {
+      let mut routes = self.routes.lock().unwrap();
+      routes.client_ids.insert(key.clone(), order_id.clone());
+      routes.orders.insert(order_id.clone(), RoutedOrder {
+        session_id: session.session_id.clone(),
+        client_order_id: client_order_id.clone(),
+        request_id: request_id.clone(),
+        symbol: order.symbol.clone(),
+        side: order.side.clone(),
+        order_type: order.order_type.clone(),
+        price: order.price,
+        quantity: order.quantity,
+        filled: Qty::ZERO,
+        tx: tx.clone(),
+        acknowledged: false,
+        pending: Vec::new(),
+        closed: false,
+        changes: VecDeque::new(),
+      });
+    }
+    
+    if let Err(e) = self.throttle.try_send(&self.order_tx, OrderMessage::Order(order)) {
+      let mut routes = self.routes.lock().unwrap();
+      routes.client_ids.remove(&key);
+      routes.orders.remove(&order_id);
+      self.risk.release(&order_id);
+      return reject_throttled(&request_id, Some(&client_order_id), &e);
+    }
+    created.push(order_id.clone());
+    ack(&request_id, &client_order_id, &order_id, "new")
+  }
+  
+  async fn cancel(&self, session: &TradingSession, request: CancelRequest) -> Value {
+    let CancelRequest { request_id, client_order_id } = request;
+    let order_id = match self.lookup(session, &client_order_id) {
+      Some(order_id) => order_id,
+      None => return reject(&request_id, Some(&client_order_id), "UNKNOWN_ORDER", "no open order with this client_order_id"),
+    };
+    
+    let cancel = Order {
+      order_id: order_id.clone(),
+      symbol: String::new(),
+      account: session.account.clone(),
+      price: Price::ZERO,
+      quantity: Qty::ZERO,
+      side: Side::Buy,
+      order_type: OrderType::Limit,
+      status: OrderStatus::Cancelled,
+      filled_quantity: Qty::ZERO,
+      remain_quantity: Qty::ZERO,
+      entry_time: Utc::now()
+    };
+    // Registered before sequencing so that the engine's answer finds it
+    self.expect_change(&order_id, &request_id);
+    if let Err(e) = self.throttle.try_send(&self.order_tx, OrderMessage::Order(cancel)) {
+      self.forget_change(&order_id);
+      return reject_throttled(&request_id, Some(&client_order_id), &e);
+    }
+    ack(&request_id, &client_order_id, &order_id, "cancel")
+  }
+  
+  async fn amend(&self, session: &TradingSession, request: AmendRequest) -> Value {
+    let AmendRequest { request_id, client_order_id, price, quantity } = request;
+    let current = self.lookup(session, &client_order_id).and_then(|order_id| {
+      let routes = self.routes.lock().unwrap();
+      routes.orders.get(&order_id).map(|order| (order_id.clone(), order.symbol.clone(), order.side.clone(), order.order_type.clone(), order.price, order.quantity))
+    });
+    let (order_id, symbol, side, order_type, current_price, current_quantity) = match current {
+      Some(current) => current,
+      None => return reject(&request_id, Some(&client_order_id), "UNKNOWN_ORDER", "no open order with this client_order_id"),
+    };
+    if order_type != OrderType::Limit {
+      return reject(&request_id, Some(&client_order_id), "NOT_AMENDABLE", "only limit orders can be amended");
+    }
+    
+    let instrument = match self.instruments.get(&symbol) {
+      Some(instrument) => instrument,
+      None => return reject_invalid(&request_id, &client_order_id, &OrderValidationError::UnknownSymbol(symbol)),
+    };
+    let amended = parse_amend(&instrument, price, quantity, current_price, current_quantity).and_then(|(price, quantity)| {
+      // The amended order has to satisfy the same constraints as a new one
+      let probe = Order {
+        order_id: order_id.clone(),
+        symbol: symbol.clone(),
+        account: session.account.clone(),
+        price,
+        quantity,
+        side,
+        order_type: OrderType::Limit,
+        status: OrderStatus::New,
+        filled_quantity: Qty::ZERO,
+        remain_quantity: quantity,
+        entry_time: Utc::now()
+      };
+      instrument.validate_order(&probe)?;
+      Ok(probe)
+    });
+    let amended = match amended {
+      Ok(amended) => amended,
+      Err(e) => return reject_invalid(&request_id, &client_order_id, &e),
+    };
+    if let Err(e) = self.risk.admit_amend(&amended, &instrument) {
+      return reject_risk(&request_id, &client_order_id, &e);
+    }
+    let (price, quantity) = (amended.price, amended.quantity);
+    
+    let message = OrderMessage::Amend(Amend { order_id: order_id.clone(), account: amended.account.clone(), price, quantity });
+    self.expect_change(&order_id, &request_id);
+    if let Err(e) = self.throttle.try_send(&self.order_tx, message) {
+      self.forget_change(&order_id);
+      self.risk.release_amend(&order_id);
+      return reject_throttled(&request_id, Some(&client_order_id), &e);
+    }
+    // The price and quantity change once the engine reports the amend
+    ack(&request_id, &client_order_id, &order_id, "amend")
+  }
+  
+  fn expect_change(&self, order_id: &str, request_id: &Option<String>) {
+    if let Some(order) = self.routes.lock().unwrap().orders.get_mut(order_id) {
+      order.changes.push_back(request_id.clone());
+    }
+  }
+  
+  // Undo expect_change for a request that was not sequenced
+  fn forget_change(&self, order_id: &str) {
+    if let Some(order) = self.routes.lock().unwrap().orders.get_mut(order_id) {
+      order.changes.pop_back();
+    }
+  }
+  
+  // The order id of an order the session is still following
+  fn lookup(&self, session: &TradingSession, client_order_id: &str) -> Option<String> {
+    let routes = self.routes.lock().unwrap();
+    let key = (session.session_id.clone(), client_order_id.to_string());
+    routes.client_ids.get(&key).filter(|order_id| routes.orders.contains_key(*order_id)).cloned()
+  }
+  
+  // Send the fills held back while the order was unacknowledged. New fills keep being queued
+  // until the queue is found empty, so they are delivered in order.
+  async fn release(&self, order_id: &str) {
+    loop {
+      let (tx, pending) = {
+        let mut routes = self.routes.lock().unwrap();
+        let order = match routes.orders.get_mut(order_id) {
+          Some(order) => order,
+          None => return,
+        };
+        if order.pending.is_empty() {
+          order.acknowledged = true;
+          if order.done() {
+            routes.orders.remove(order_id);
+          }
+          return;
+        }
+        (order.tx.clone(), std::mem::take(&mut order.pending))
+      };
+      for message in pending {
+        let _ = tx.send(message).await;
+      }
+    }
+  }
+  
+  // The fill message for an execution of a session's order and the connection to send it to.
+  // None when the order was not entered through a session or the fill is held back.
+  pub fn route_execution(&self, execution: &Execution, instrument: &Instrument) -> Option<(Sender<Message>, Message)> {
+    let mut routes = self.routes.lock().unwrap();
+    let order = routes.orders.get_mut(&execution.order_id)?;
+    order.filled += execution.quantity;
+    let fill = json!({
+      "type": "fill",
+      "request_id": order.request_id,
+      "client_order_id": order.client_order_id,
+      "execution": serializer::execution_to_dto(execution, instrument),
+    });
+    Self::route(&mut routes, &execution.order_id, fill)
+  }
+  
+  // The message for an engine outcome of a session's order and the connection to send it to.
+  // Answers to cancels and amends carry the request_id of that cancel or amend, anything else
+  // the request_id of the order's new request like fills.
+  pub fn route_outcome(&self, outcome: &OrderOutcome) -> Option<(Sender<Message>, Message)> {
+    let mut routes = self.routes.lock().unwrap();
+    let order = routes.orders.get_mut(outcome.order_id())?;
+    let instrument = self.instruments.get(&order.symbol).unwrap_or_else(|| Instrument::new(&order.symbol));
+    let message = match outcome {
+      // A reject that follows the ack; after an amend the original order is gone as well
+      OrderOutcome::Rejected { order_id, reason, replacement, .. } => {
+        order.closed = true;
+        json!({
+          "type": "reject",
+          "request_id": order.answering(*replacement),
+          "client_order_id": order.client_order_id,
+          "order_id": order_id,
+          "error": reason.code(),
+          "message": reason.to_string(),
+        })
+      }
+      // Cancelled on request, by a mass cancel, or the unfilled rest of a market order
+      OrderOutcome::Cancelled { order_id, quantity, reason, .. } => {
+        order.closed = true;
+        json!({
+          "type": "cancelled",
+          "request_id": order.answering(*reason == CancelReason::Requested),
+          "client_order_id": order.client_order_id,
+          "order_id": order_id,
+          "quantity": instrument.format_quantity(*quantity),
+          "reason": reason,
+        })
+      }
+      OrderOutcome::Amended { order_id, price, quantity, remaining, .. } => {
+        order.price = *price;
+        order.quantity = *quantity;
+        json!({
+          "type": "amended",
+          "request_id": order.answering(true),
+          "client_order_id": order.client_order_id,
+          "order_id": order_id,
+          "price": instrument.format_price(*price),
+          "quantity": instrument.format_quantity(*quantity),
+          "remaining": instrument.format_quantity(*remaining),
+        })
+      }
+      // A cancel or amend the engine did not apply, the order stays as it was
+      OrderOutcome::CancelRejected { order_id, reason, .. } => json!({
+        "type": "reject",
+        "request_id": order.answering(true),
+        "client_order_id": order.client_order_id,
+        "order_id": order_id,
+        "error": reason.code(),
+        "message": reason.to_string(),
+      }),
+    };
+    Self::route(&mut routes, outcome.order_id(), message)
+  }
+  
+  // Send now or hold back until the order is acknowledged. Completed orders are no longer
+  // followed, but their client order id stays in use.
+  fn route(routes: &mut Routes, order_id: &str, message: Value) -> Option<(Sender<Message>, Message)> {
+    let order = routes.orders.get_mut(order_id)?;
+    let message = Message::text(message.to_string());
+    if !order.acknowledged {
+      order.pending.push(message);
+      return None;
+    }
+    let route = (order.tx.clone(), message);
+    if order.done() {
+      routes.orders.remove(order_id);
+    }
+    Some(route)
+  }
+  
+  // Forget the session's orders and return the ids of those still open. Their fills are
+  // still broadcast publicly.
+  pub fn close_session(&self, session_id: &str) -> Vec<String> {
+    let mut routes = self.routes.lock().unwrap();
+    let open = routes.orders.iter()
+      .filter(|(_, order)| order.session_id == session_id && !order.done())
+      .map(|(order_id, _)| order_id.clone())
+      .collect();
+    routes.orders.retain(|_, order| order.session_id != session_id);
+    routes.client_ids.retain(|(session, _), _| session != session_id);
+    open
+  }
+}
+
+fn parse_amend(
+  instrument: &Instrument,
+  price: Option<DecimalInput>,
+  quantity: Option<DecimalInput>,
+  current_price: Price,
+  current_quantity: Qty
+) -> Result<(Price, Qty), OrderValidationError> {
+  let price = match price {
+    Some(price) => instrument.parse_price(&price.0)?,
+    None => current_price,
+  };
+  let quantity = match quantity {
+    Some(quantity) => instrument.parse_quantity(&quantity.0)?,
+    None => current_quantity,
+  };
+  Ok((price, quantity))
+}
\ No newline at end of file
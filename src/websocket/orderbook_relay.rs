@@ -1,4 +1,4 @@
-/**
+/*
 * filename : orderbook_relay
 * author : HAMA
 * date: 2025. 4. 30.
@@ -8,20 +8,38 @@
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use futures::{FutureExt, StreamExt};
-use tokio::sync::mpsc::{channel, Receiver, Sender};
-use tokio::sync::watch;
-use tokio::time::{self, Duration};
+use tokio::sync::mpsc::Sender;
+use tokio_stream::wrappers::ReceiverStream;
 use warp::ws::{Message, WebSocket};
 use warp::Filter;
 use serde_json::json;
 
-use crate::models::{OrderBook, Book, Side, PriceLevel};
-use crate::util::serializer::{orderbook_to_dto, PriceLevelDto};
+use crate::models::{OrderBook, Price, Side};
+use crate::instrument::{Instrument, InstrumentRegistry};
+use crate::matching_engine::BookUpdate;
+use crate::util::serializer::{calculate_orderbook_delta, orderbook_to_dto, price_level_to_dto, OrderBookDto};
+
+// 심볼별 오더북 사본과 마지막으로 전송한 델타의 update_id, 시각
+#[derive(Default)]
+struct RelayBook {
+  book: OrderBook,
+  update_id: u64,
+  timestamp: i64,
+}
 
 // 오더북 중계 관리자
+// 호가 단계가 바뀔 때마다 심볼별로 1씩 증가하는 update_id와 함께 델타를 전송합니다.
+// 클라이언트는 스냅샷의 update_id 이후 델타만 적용하고, 번호가 건너뛰면 스냅샷을 다시 받습니다.
 pub struct OrderBookRelayManager {
   connections: Arc<Mutex<HashMap<String, Vec<Sender<Message>>>>>,
-  orderbooks: Arc<Mutex<HashMap<String, OrderBook>>>,
+  orderbooks: Arc<Mutex<HashMap<String, RelayBook>>>, // 매칭 엔진의 주문 이벤트로 유지하는 오더북 사본
+  instruments: Option<Arc<InstrumentRegistry>>, // 가격, 수량 소수 자릿수 (없으면 정수 단위)
+}
+
+impl Default for OrderBookRelayManager {
+  fn default() -> Self {
+    OrderBookRelayManager::new()
+  }
 }
 
 impl OrderBookRelayManager {
@@ -29,34 +47,97 @@ impl OrderBookRelayManager {
     OrderBookRelayManager {
       connections: Arc::new(Mutex::new(HashMap::new())),
       orderbooks: Arc::new(Mutex::new(HashMap::new())),
+      instruments: None,
+    }
+  }
+  
+  // 종목 소수 자릿수를 적용하는 오더북 중계 관리자 생성
+  pub fn with_instruments(instruments: Arc<InstrumentRegistry>) -> Self {
+    OrderBookRelayManager {
+      instruments: Some(instruments),
+      ..OrderBookRelayManager::new()
+    }
+  }
+  
+  fn instrument(&self, symbol: &str) -> Instrument {
+    self.instruments.as_ref()
+      .and_then(|instruments| instruments.get(symbol))
+      .unwrap_or_else(|| Instrument::new(symbol))
+  }
+  
+  // 매칭 엔진의 주문 이벤트를 오더북 사본에 적용하고, 이벤트가 바꾼 호가 단계만 비교해 델타 전송
+  pub fn apply_book_update(&self, update: &BookUpdate) {
+    let instrument = self.instrument(&update.symbol);
+    let mut books = self.orderbooks.lock().unwrap();
+    let state = books.entry(update.symbol.clone()).or_default();
+    
+    // 이벤트가 바꾸는 호가 단계는 처음 바뀌기 전의 상태를 기억
+    let mut levels: Vec<(Side, Price)> = Vec::new();
+    let mut old_levels = empty_dto(&update.symbol);
+    for event in &update.events {
+      let level = event.level(&state.book);
+      if !levels.contains(&level) {
+        push_level(&mut old_levels, &state.book, &level, &instrument);
+        levels.push(level);
+      }
+      event.apply(&mut state.book);
     }
+    let mut new_levels = empty_dto(&update.symbol);
+    for level in &levels {
+      push_level(&mut new_levels, &state.book, level, &instrument);
+    }
+    self.send_delta(&update.symbol, state, &old_levels, &new_levels);
   }
   
-  // 오더북 업데이트
+  // 오더북 전체를 교체하고 이전 상태와 달라진 호가 단계를 델타로 전송
   pub fn update_orderbook(&self, symbol: &str, orderbook: OrderBook) {
+    let instrument = self.instrument(symbol);
     let mut books = self.orderbooks.lock().unwrap();
-    books.insert(symbol.to_string(), orderbook);
+    let state = books.entry(symbol.to_string()).or_default();
+    let old_book = orderbook_to_dto(&state.book, symbol, &instrument);
+    let new_book = orderbook_to_dto(&orderbook, symbol, &instrument);
+    state.book = orderbook;
+    self.send_delta(symbol, state, &old_book, &new_book);
   }
   
-  // 오더북 스냅샷 생성
-  fn create_snapshot(&self, symbol: &str) -> Option<serde_json::Value> {
-    let books = self.orderbooks.lock().unwrap();
-    let orderbook = books.get(symbol)?;
+  // 달라진 호가 단계가 있으면 update_id를 올리고 구독자에게 델타 전송
+  // 델타가 update_id 순서대로 전송되도록 호출하는 쪽이 오더북 잠금을 유지합니다
+  fn send_delta(&self, symbol: &str, state: &mut RelayBook, old_levels: &OrderBookDto, new_levels: &OrderBookDto) {
+    let mut delta = calculate_orderbook_delta(old_levels, new_levels);
+    if !has_changes(&delta) {
+      return;
+    }
+    delta["prev_update_id"] = json!(state.update_id);
+    state.update_id += 1;
+    state.timestamp = new_levels.timestamp;
+    delta["update_id"] = json!(state.update_id);
     
-    // 오더북 DTO로 변환
-    let dto = orderbook_to_dto(orderbook, symbol);
+    // 느린 구독자는 기다리지 않고 델타를 건너뛰며, update_id로 누락을 감지해 다시 동기화합니다
+    let message = Message::text(delta.to_string());
+    let connections = self.connections.lock().unwrap();
+    if let Some(symbol_connections) = connections.get(symbol) {
+      for tx in symbol_connections {
+        let _ = tx.try_send(message.clone());
+      }
+    }
+  }
+  
+  // 오더북 스냅샷 생성 (update_id 포함)
+  pub fn create_snapshot(&self, symbol: &str) -> Option<serde_json::Value> {
+    let books = self.orderbooks.lock().unwrap();
+    let state = books.get(symbol)?;
+    let mut dto = orderbook_to_dto(&state.book, symbol, &self.instrument(symbol));
+    dto.update_id = state.update_id;
+    dto.timestamp = state.timestamp;
     
     // JSON으로 변환
-    match serde_json::to_value(dto) {
-      Ok(json) => Some(json),
-      Err(_) => None,
-    }
+    serde_json::to_value(dto).ok()
   }
   
   // 심볼에 새 연결 추가
   pub fn add_connection(&self, symbol: &str, tx: Sender<Message>) {
     let mut connections = self.connections.lock().unwrap();
-    let symbol_connections = connections.entry(symbol.to_string()).or_insert_with(Vec::new);
+    let symbol_connections = connections.entry(symbol.to_string()).or_default();
     symbol_connections.push(tx);
   }
   
@@ -70,32 +151,58 @@ impl OrderBookRelayManager {
     }
   }
   
-  // 특정 심볼 오더북 브로드캐스트
-  pub async fn broadcast_orderbook(&self, symbol: &str) {
-    let snapshot = match self.create_snapshot(symbol) {
-      Some(s) => s,
-      None => return, // 오더북이 없으면 중단
-    };
-    
-    let message = json!({
-            "type": "orderbook",
-            "data": snapshot
-        });
-    
-    let message_json = match serde_json::to_string(&message) {
-      Ok(j) => j,
-      Err(_) => return, // 직렬화 에러
-    };
-    
-    let connections = self.connections.lock().unwrap();
-    if let Some(symbol_connections) = connections.get(symbol) {
-      for tx in symbol_connections {
-        let _ = tx.send(Message::text(message_json.clone())).await;
-      }
-    }
+  // 스냅샷 메시지 (아직 호가가 없으면 update_id 0의 빈 오더북)
+  fn snapshot_message(&self, symbol: &str) -> serde_json::Value {
+    let snapshot = self.create_snapshot(symbol).unwrap_or_else(|| json!({
+      "symbol": symbol,
+      "timestamp": chrono::Utc::now().timestamp_millis(),
+      "update_id": 0,
+      "bids": [],
+      "asks": []
+    }));
+    json!({
+      "type": "orderbook_snapshot",
+      "data": snapshot
+    })
   }
 }
 
+// 호가 단계를 담을 빈 오더북
+fn empty_dto(symbol: &str) -> OrderBookDto {
+  OrderBookDto {
+    symbol: symbol.to_string(),
+    timestamp: chrono::Utc::now().timestamp_millis(),
+    update_id: 0,
+    bids: Vec::new(),
+    asks: Vec::new(),
+  }
+}
+
+// 오더북에 남아 있는 호가 단계를 해당 방향에 추가 (비어 있는 단계는 제외)
+fn push_level(dto: &mut OrderBookDto, book: &OrderBook, (side, price): &(Side, Price), instrument: &Instrument) {
+  let (book_side, levels) = match side {
+    Side::Buy => (&book.buy_book, &mut dto.bids),
+    Side::Sell => (&book.sell_book, &mut dto.asks),
+  };
+  if let Some(level) = book_side.limits.get(price) {
+    levels.push(price_level_to_dto(level, instrument));
+  }
+}
+
+// 델타에 바뀐 호가 단계가 있는지 확인
+fn has_changes(delta: &serde_json::Value) -> bool {
+  ["bids", "asks"].iter().any(|side| {
+    ["added", "updated", "removed"].iter().any(|kind| delta[side][kind].as_array().is_some_and(|levels| !levels.is_empty()))
+  })
+}
+
+// REST 스냅샷 API: 델타 스트림과 같은 update_id를 포함
+pub fn routes(manager: Arc<OrderBookRelayManager>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+  warp::path!("api" / "v1" / "orderbook" / String / "snapshot")
+    .and(warp::get())
+    .map(move |symbol: String| warp::reply::json(&manager.snapshot_message(&symbol)["data"]))
+}
+
 // 오더북 WebSocket 라우트
 pub fn ws_orderbook_route(
   manager: Arc<OrderBookRelayManager>,
@@ -122,7 +229,7 @@ async fn handle_orderbook_connection(
   let symbol_clone = symbol.clone();
   
   // 메시지 전송 작업
-  tokio::task::spawn(rx.forward(ws_tx).map(|result| {
+  tokio::task::spawn(ReceiverStream::new(rx).map(Ok).forward(ws_tx).map(|result| {
     if let Err(e) = result {
       eprintln!("WebSocket send error: {}", e);
     }
@@ -132,25 +239,21 @@ async fn handle_orderbook_connection(
   manager.add_connection(&symbol, tx.clone());
   
   // 초기 스냅샷 전송
-  if let Some(snapshot) = manager.create_snapshot(&symbol) {
-    let initial_message = json!({
-            "type": "orderbook_snapshot",
-            "data": snapshot
-        });
-    
-    if let Ok(json) = serde_json::to_string(&initial_message) {
-      let _ = tx.send(Message::text(json)).await;
-    }
-  }
+  let _ = tx.send(Message::text(manager.snapshot_message(&symbol).to_string())).await;
   
-  // 클라이언트 메시지 처리
+  // 클라이언트 메시지 처리: {"type": "snapshot"}에는 스냅샷을 다시 전송 (누락된 델타 재동기화)
   while let Some(result) = ws_rx.next().await {
     match result {
       Ok(msg) => {
         if msg.is_close() {
           break;
         }
-        // 필요시 클라이언트 메시지 처리
+        let is_snapshot_request = msg.to_str().ok()
+          .and_then(|text| serde_json::from_str::<serde_json::Value>(text).ok())
+          .is_some_and(|value| value["type"] == "snapshot");
+        if is_snapshot_request {
+          let _ = tx.send(Message::text(manager.snapshot_message(&symbol).to_string())).await;
+        }
       }
       Err(_) => {
         break;
@@ -160,23 +263,4 @@ async fn handle_orderbook_connection(
   
   // 연결 종료 정리
   manager_clone.remove_connection(&symbol_clone, &tx);
-}
-
-// 정기적 오더북 업데이트 작업
-pub async fn run_orderbook_broadcaster(manager: Arc<OrderBookRelayManager>) {
-  let mut interval = time::interval(Duration::from_millis(100)); // 100ms마다 업데이트
-  
-  loop {
-    interval.tick().await;
-    
-    // 각 심볼에 대한 오더북 브로드캐스트
-    let symbols = {
-      let connections = manager.connections.lock().unwrap();
-      connections.keys().cloned().collect::<Vec<String>>()
-    };
-    
-    for symbol in symbols {
-      manager.broadcast_orderbook(&symbol).await;
-    }
-  }
 }
\ No newline at end of file
@@ -0,0 +1,124 @@
+/*
+* filename : public_trades
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 익명 공개 체결 WebSocket 채널
+**/
+
+use std::sync::{Arc, Mutex};
+use futures::{FutureExt, StreamExt};
+use tokio::sync::mpsc::Sender;
+use tokio_stream::wrappers::ReceiverStream;
+use warp::ws::{Message, WebSocket};
+use warp::Filter;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::models::Execution;
+use crate::instrument::{Instrument, InstrumentRegistry};
+use crate::util::serializer;
+use super::market_stream::{Channel, MarketStreamHub};
+
+// Trades without order ids, accounts or fees, one message per match
+pub struct PublicTradeManager {
+  connections: Mutex<Vec<(Option<String>, Sender<Message>)>>, // Symbol filter, connection
+  instruments: Arc<InstrumentRegistry>,
+  last_trade_id: Mutex<String>,
+  stream: Option<Arc<MarketStreamHub>>, // Also published on the trades:{symbol} channels of /ws
+}
+
+impl PublicTradeManager {
+  pub fn new(instruments: Arc<InstrumentRegistry>) -> Self {
+    PublicTradeManager {
+      connections: Mutex::new(Vec::new()),
+      instruments,
+      last_trade_id: Mutex::new(String::new()),
+      stream: None,
+    }
+  }
+  
+  pub fn with_stream(instruments: Arc<InstrumentRegistry>, stream: Arc<MarketStreamHub>) -> Self {
+    PublicTradeManager {
+      stream: Some(stream),
+      ..PublicTradeManager::new(instruments)
+    }
+  }
+  
+  pub fn add_connection(&self, symbol: Option<String>, tx: Sender<Message>) {
+    self.connections.lock().unwrap().push((symbol, tx));
+  }
+  
+  pub fn remove_connection(&self, tx: &Sender<Message>) {
+    self.connections.lock().unwrap().retain(|(_, x)| !x.same_channel(tx));
+  }
+  
+  // Both executions of a match arrive one after the other. The first one that is not the
+  // maker's is published: the taker's in continuous trading, the buyer's in an auction.
+  pub fn publish(&self, execution: &Execution) {
+    if execution.is_maker {
+      return;
+    }
+    {
+      let mut last_trade_id = self.last_trade_id.lock().unwrap();
+      if !execution.trade_id.is_empty() && *last_trade_id == execution.trade_id {
+        return;
+      }
+      last_trade_id.clone_from(&execution.trade_id);
+    }
+    
+    let instrument = self.instruments.get(&execution.symbol)
+      .unwrap_or_else(|| Instrument::new(&execution.symbol));
+    let trade = serializer::trade_to_dto(execution, &instrument);
+    if let (Some(stream), Ok(data)) = (&self.stream, serde_json::to_value(&trade)) {
+      stream.publish(&Channel::Trades(execution.symbol.clone()), data);
+    }
+    let message = Message::text(json!({ "type": "trade", "data": trade }).to_string());
+    
+    // Slow clients miss trades instead of holding up the others
+    let connections = self.connections.lock().unwrap();
+    for (symbol, tx) in connections.iter() {
+      if symbol.as_ref().is_none_or(|symbol| symbol == &execution.symbol) {
+        let _ = tx.try_send(message.clone());
+      }
+    }
+  }
+}
+
+// /ws/trades?symbol=BTC-KRW, every symbol without the filter
+#[derive(Debug, Default, Deserialize)]
+struct TradeParams {
+  symbol: Option<String>,
+}
+
+pub fn ws_trades_route(
+  manager: Arc<PublicTradeManager>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+  warp::path!("ws" / "trades")
+    .and(warp::ws())
+    .and(warp::query::<TradeParams>())
+    .map(move |ws: warp::ws::Ws, params: TradeParams| {
+      let manager = manager.clone();
+      ws.on_upgrade(move |socket| handle_trades_connection(socket, params.symbol, manager))
+    })
+}
+
+async fn handle_trades_connection(ws: WebSocket, symbol: Option<String>, manager: Arc<PublicTradeManager>) {
+  let (ws_tx, mut ws_rx) = ws.split();
+  let (tx, rx) = tokio::sync::mpsc::channel::<Message>(1000);
+  
+  tokio::task::spawn(ReceiverStream::new(rx).map(Ok).forward(ws_tx).map(|result| {
+    if let Err(e) = result {
+      eprintln!("WebSocket send error: {}", e);
+    }
+  }));
+  manager.add_connection(symbol, tx.clone());
+  
+  while let Some(result) = ws_rx.next().await {
+    match result {
+      Ok(message) if message.is_close() => break,
+      Ok(_) => {}
+      Err(_) => break,
+    }
+  }
+  manager.remove_connection(&tx);
+}
\ No newline at end of file
@@ -0,0 +1,104 @@
+/*
+* filename : session
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 인증된 WebSocket 거래 세션 및 연결 종료 시 주문 자동 취소
+**/
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use chrono::Utc;
+use tokio::sync::mpsc::Sender;
+
+use crate::auth::AccountRegistry;
+use crate::models::{Order, OrderMessage, OrderStatus, OrderType, Price, Qty, Side};
+
+pub const DEFAULT_HEARTBEAT_SECS: u64 = 30;
+pub const MAX_HEARTBEAT_SECS: u64 = 300;
+
+// An authenticated connection. With cancel_on_disconnect the orders entered through the
+// session are cancelled when the connection closes or no message arrives within the
+// heartbeat interval. Orders of the account entered elsewhere are left alone.
+// Requests of the session count against the rate limits of its API key and address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TradingSession {
+  pub session_id: String,
+  pub account: String,
+  pub api_key: String,
+  pub ip: Option<IpAddr>,
+  pub cancel_on_disconnect: bool,
+  pub heartbeat: Duration,
+}
+
+pub struct SessionManager {
+  accounts: Arc<AccountRegistry>,
+  order_tx: Sender<OrderMessage>,
+  sessions: Mutex<HashMap<String, TradingSession>>,
+}
+
+impl SessionManager {
+  pub fn new(accounts: Arc<AccountRegistry>, order_tx: Sender<OrderMessage>) -> Self {
+    SessionManager {
+      accounts,
+      order_tx,
+      sessions: Mutex::new(HashMap::new()),
+    }
+  }
+  
+  // Authenticate an API key and register a session, None if the key is unknown
+  pub fn open(&self, api_key: &str, ip: Option<IpAddr>, cancel_on_disconnect: bool, heartbeat_secs: Option<u64>) -> Option<TradingSession> {
+    let account = self.accounts.authenticate(api_key)?;
+    let heartbeat_secs = heartbeat_secs.unwrap_or(DEFAULT_HEARTBEAT_SECS).clamp(1, MAX_HEARTBEAT_SECS);
+    
+    let session = TradingSession {
+      session_id: uuid::Uuid::new_v4().to_string(),
+      account,
+      api_key: api_key.to_string(),
+      ip,
+      cancel_on_disconnect,
+      heartbeat: Duration::from_secs(heartbeat_secs),
+    };
+    self.sessions.lock().unwrap().insert(session.session_id.clone(), session.clone());
+    Some(session)
+  }
+  
+  // Unregister the session and, if it opted in, sequence the cancels of the orders it left
+  // open as one batch
+  pub async fn close(&self, session: &TradingSession, reason: &str, open_orders: Vec<String>) {
+    self.sessions.lock().unwrap().remove(&session.session_id);
+    if !session.cancel_on_disconnect || open_orders.is_empty() {
+      return;
+    }
+    
+    println!("Session {} of {} ended ({}), cancelling {} open orders", session.session_id, session.account, reason, open_orders.len());
+    let cancels = open_orders.into_iter()
+      .map(|order_id| OrderMessage::Order(Order {
+        order_id,
+        symbol: String::new(),
+        account: session.account.clone(),
+        price: Price::ZERO,
+        quantity: Qty::ZERO,
+        side: Side::Buy,
+        order_type: OrderType::Limit,
+        status: OrderStatus::Cancelled,
+        filled_quantity: Qty::ZERO,
+        remain_quantity: Qty::ZERO,
+        entry_time: Utc::now()
+      }))
+      .collect();
+    if self.order_tx.send(OrderMessage::Batch(cancels)).await.is_err() {
+      eprintln!("Cancel-on-disconnect for {} could not be sequenced: engine unavailable", session.account);
+    }
+  }
+  
+  // Channel to the input sequencer, shared with the sessions' order entry
+  pub fn order_tx(&self) -> Sender<OrderMessage> {
+    self.order_tx.clone()
+  }
+  
+  pub fn active_sessions(&self) -> Vec<TradingSession> {
+    self.sessions.lock().unwrap().values().cloned().collect()
+  }
+}
\ No newline at end of file
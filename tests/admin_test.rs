@@ -0,0 +1,253 @@
+/*
+* filename : admin_test
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 거래 정지/재개, 일괄 취소 관리자 API 및 감사 로그 테스트
+**/
+
+mod common;
+
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use warp::test::request;
+use warp::http::StatusCode;
+use serde_json::Value;
+
+use xTraderz::admin::{self, AdminState, AuditLog};
+use xTraderz::matching_engine::{CancelRejectReason, MatchingEngine, OrderOutcome};
+use xTraderz::models::{Amend, MassCancel, OrderMessage, OrderType, Price, Qty, Side};
+use xTraderz::instrument::TradingStatus;
+use xTraderz::risk::RiskManager;
+use common::{new_order, cancel_order, registry};
+
+fn limit(order_id: &str, symbol: &str, account: &str, side: Side, price: u64) -> OrderMessage {
+  let mut order = new_order(order_id.into(), side, OrderType::Limit, price, 1);
+  order.symbol = symbol.into();
+  order.account = account.into();
+  OrderMessage::Order(order)
+}
+
+#[tokio::test]
+async fn admin_commands_require_a_token_and_are_audited() {
+  let (order_tx, mut order_rx) = mpsc::channel(10);
+  let audit = Arc::new(AuditLog::new());
+  let state = Arc::new(AdminState::new(Some("secret".into()), order_tx, registry(&["SYM", "OTHER"]), Arc::new(RiskManager::new()), audit.clone()));
+  let routes = admin::routes(state);
+  
+  let resp = request().method("POST").path("/admin/v1/halt/SYM").reply(&routes).await;
+  assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+  let resp = request()
+    .method("POST")
+    .path("/admin/v1/halt")
+    .header("authorization", "Bearer wrong")
+    .header("x-admin-user", "mallory")
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+  assert!(order_rx.try_recv().is_err());
+  
+  let resp = request()
+    .method("POST")
+    .path("/admin/v1/halt/SYM")
+    .header("authorization", "Bearer secret")
+    .header("x-admin-user", "ops")
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::ACCEPTED);
+  assert!(matches!(
+    order_rx.try_recv().unwrap(),
+    OrderMessage::SetTradingStatus { symbol, status: TradingStatus::Halted } if symbol == "SYM"
+  ));
+  
+  let resp = request()
+    .method("POST")
+    .path("/admin/v1/mass-cancel")
+    .header("authorization", "Bearer secret")
+    .header("x-admin-user", "ops")
+    .json(&serde_json::json!({ "account": "mm1", "side": "Buy" }))
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::ACCEPTED);
+  match order_rx.try_recv().unwrap() {
+    OrderMessage::MassCancel(request) => assert_eq!(request, MassCancel {
+      symbol: None,
+      account: Some("mm1".into()),
+      side: Some(Side::Buy),
+    }),
+    other => panic!("unexpected message {:?}", other),
+  }
+  
+  let resp = request()
+    .method("POST")
+    .path("/admin/v1/resume/NOPE")
+    .header("authorization", "Bearer secret")
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+  
+  let outcomes: Vec<(String, String, String)> = audit.entries().into_iter()
+    .map(|e| (e.actor, e.action, e.outcome))
+    .collect();
+  assert_eq!(outcomes, vec![
+    ("unknown".into(), "halt".into(), "unauthorized".into()),
+    ("mallory".into(), "halt_all".into(), "unauthorized".into()),
+    ("ops".into(), "halt".into(), "accepted".into()),
+    ("ops".into(), "mass_cancel".into(), "accepted".into()),
+    ("unknown".into(), "resume".into(), "unknown symbol".into()),
+  ]);
+  
+  let resp = request()
+    .method("GET")
+    .path("/admin/v1/audit")
+    .header("authorization", "Bearer secret")
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  let json: Value = serde_json::from_slice(resp.body()).unwrap();
+  assert_eq!(json.as_array().unwrap().len(), 5);
+  assert_eq!(json[3]["details"]["account"], "mm1");
+}
+
+#[tokio::test]
+async fn refused_requests_are_audited_without_arguments_and_at_a_bounded_rate() {
+  let (order_tx, _order_rx) = mpsc::channel(10);
+  let audit = Arc::new(AuditLog::new());
+  let state = Arc::new(AdminState::new(Some("secret".into()), order_tx, registry(&["SYM"]), Arc::new(RiskManager::new()), audit.clone()));
+  let routes = admin::routes(state);
+  
+  for _ in 0..AuditLog::MAX_REFUSED_PER_SEC + 5 {
+    let resp = request()
+      .method("POST")
+      .path("/admin/v1/mass-cancel")
+      .header("x-admin-user", "m".repeat(1000))
+      .json(&serde_json::json!({ "account": "x".repeat(1000) }))
+      .reply(&routes)
+      .await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+  }
+  
+  let entries = audit.entries();
+  assert_eq!(entries.len(), AuditLog::MAX_REFUSED_PER_SEC as usize);
+  for entry in &entries {
+    assert_eq!((entry.actor.len(), entry.outcome.as_str()), (AuditLog::MAX_ACTOR_LEN, "unauthorized"));
+    assert_eq!(entry.details, serde_json::json!({ "suppressed": 0 }));
+  }
+  
+  // Only the latest entries are kept in memory
+  for _ in 0..AuditLog::MAX_ENTRIES {
+    audit.record("ops", "halt", Value::Null, "accepted");
+  }
+  let entries = audit.entries();
+  assert_eq!(entries.len(), AuditLog::MAX_ENTRIES);
+  assert!(entries.iter().all(|entry| entry.action == "halt"));
+}
+
+#[tokio::test]
+async fn admin_api_is_disabled_without_a_token() {
+  let (order_tx, order_rx) = mpsc::channel(10);
+  let state = Arc::new(AdminState::new(None, order_tx.clone(), registry(&["SYM", "OTHER"]), Arc::new(RiskManager::new()), Arc::new(AuditLog::new())));
+  let routes = admin::routes(state);
+  
+  let resp = request()
+    .method("POST")
+    .path("/admin/v1/halt")
+    .header("authorization", "Bearer ")
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+  
+  let resp = request()
+    .method("PUT")
+    .path("/admin/v1/risk/limits")
+    .json(&serde_json::json!({ "max_order_notional": 1 }))
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+  
+  // A stopped engine is reported instead of failing silently
+  drop(order_rx);
+  let state = Arc::new(AdminState::new(Some("secret".into()), order_tx, registry(&["SYM", "OTHER"]), Arc::new(RiskManager::new()), Arc::new(AuditLog::new())));
+  let resp = request()
+    .method("POST")
+    .path("/admin/v1/halt")
+    .header("authorization", "Bearer secret")
+    .reply(&admin::routes(state))
+    .await;
+  assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[test]
+fn halted_symbols_are_cancel_only() {
+  let instruments = registry(&["SYM", "OTHER"]);
+  let mut engine = MatchingEngine::new(instruments.clone());
+  engine.handle(limit("b1", "SYM", "mm1", Side::Buy, 100));
+  engine.handle(limit("b2", "SYM", "mm1", Side::Buy, 99));
+  let mut partial = new_order("o1".into(), Side::Buy, OrderType::Limit, 100, 3);
+  partial.symbol = "OTHER".into();
+  partial.account = "mm1".into();
+  engine.handle(OrderMessage::Order(partial));
+  engine.handle(limit("s0", "OTHER", "mm2", Side::Sell, 100));
+  
+  engine.handle(OrderMessage::HaltAll);
+  assert_eq!(instruments.get("SYM").unwrap().status, TradingStatus::Halted);
+  assert_eq!(instruments.get("OTHER").unwrap().status, TradingStatus::Halted);
+  
+  engine.handle(limit("s1", "SYM", "mm2", Side::Sell, 100));
+  engine.handle(OrderMessage::Order(cancel_order("b2".into())));
+  let book = engine.book("SYM").unwrap();
+  assert!(!book.order_map.contains_key("s1"));
+  assert!(!book.order_map.contains_key("b2"));
+  assert!(book.order_map.contains_key("b1"));
+  
+  // An amend to at most the filled quantity is a cancel, any other amend is refused
+  let amend = |order_id: &str, price: u64, quantity: u64| {
+    OrderMessage::Amend(Amend { order_id: order_id.into(), account: "mm1".into(), price: Price(price), quantity: Qty(quantity) })
+  };
+  let refused = engine.handle(amend("b1", 101, 1));
+  assert!(matches!(
+    &refused.outcomes[..],
+    [OrderOutcome::CancelRejected { reason: CancelRejectReason::NotTrading(TradingStatus::Halted), .. }]
+  ));
+  let cancelled = engine.handle(amend("o1", 99, 1));
+  assert!(matches!(&cancelled.outcomes[..], [OrderOutcome::Cancelled { order_id, .. }] if order_id == "o1"));
+  assert!(!engine.book("OTHER").unwrap().order_map.contains_key("o1"));
+  assert!(engine.book("SYM").unwrap().order_map.contains_key("b1"));
+  
+  // Closed symbols are not reopened by a global resume
+  engine.handle(OrderMessage::SetTradingStatus { symbol: "OTHER".into(), status: TradingStatus::Closed });
+  engine.handle(OrderMessage::ResumeAll);
+  assert_eq!(instruments.get("SYM").unwrap().status, TradingStatus::Trading);
+  assert_eq!(instruments.get("OTHER").unwrap().status, TradingStatus::Closed);
+  assert_eq!(engine.handle(limit("s1", "SYM", "mm2", Side::Sell, 100)).executions.len(), 2);
+}
+
+#[test]
+fn mass_cancel_by_symbol_account_and_side() {
+  let mut engine = MatchingEngine::new(registry(&["SYM", "OTHER"]));
+  for message in [
+    limit("a1", "SYM", "mm1", Side::Buy, 90),
+    limit("a2", "SYM", "mm1", Side::Sell, 110),
+    limit("a3", "OTHER", "mm1", Side::Buy, 90),
+    limit("b1", "SYM", "mm2", Side::Buy, 91),
+    limit("b2", "OTHER", "mm2", Side::Sell, 110),
+  ] {
+    engine.handle(message);
+  }
+  let resting = |engine: &MatchingEngine| {
+    let mut ids: Vec<String> = ["SYM", "OTHER"].iter()
+      .flat_map(|symbol| engine.book(symbol).unwrap().order_map.keys().cloned().collect::<Vec<_>>())
+      .collect();
+    ids.sort();
+    ids
+  };
+  
+  engine.handle(OrderMessage::MassCancel(MassCancel { account: Some("mm1".into()), side: Some(Side::Buy), ..MassCancel::default() }));
+  assert_eq!(resting(&engine), vec!["a2", "b1", "b2"]);
+  
+  engine.handle(OrderMessage::MassCancel(MassCancel { symbol: Some("SYM".into()), ..MassCancel::default() }));
+  assert_eq!(resting(&engine), vec!["b2"]);
+  assert_eq!(engine.book("SYM").unwrap().validate(), Ok(()));
+  
+  engine.handle(OrderMessage::MassCancel(MassCancel::default()));
+  assert!(resting(&engine).is_empty());
+}
\ No newline at end of file
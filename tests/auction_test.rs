@@ -0,0 +1,196 @@
+/*
+* filename : auction_test
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 개장/폐장 동시호가 및 단일가 체결 테스트
+**/
+
+mod common;
+
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use warp::test::request;
+use warp::http::StatusCode;
+use serde_json::Value;
+
+use xTraderz::matching_engine::MatchingEngine;
+use xTraderz::models::{OrderMessage, OrderType, Side, Price, Qty};
+use xTraderz::admin::{self, AdminState, AuditLog};
+use xTraderz::instrument::{FeeSchedule, Instrument, InstrumentRegistry, TradingStatus};
+use xTraderz::market_data_publisher::models::{IndicativeAuction, MarketDataEvent};
+use xTraderz::risk::RiskManager;
+use xTraderz::MarketDataPublisher;
+use common::{new_order, registry_of};
+
+fn registry() -> Arc<InstrumentRegistry> {
+  registry_of([Instrument {
+    fees: FeeSchedule { maker_fee_bps: 0, taker_fee_bps: 10 },
+    ..Instrument::new("SYM")
+  }])
+}
+
+fn set_status(status: TradingStatus) -> OrderMessage {
+  OrderMessage::SetTradingStatus { symbol: "SYM".into(), status }
+}
+
+fn limit(order_id: &str, side: Side, price: u64, quantity: u64) -> OrderMessage {
+  OrderMessage::Order(new_order(order_id.into(), side, OrderType::Limit, price, quantity))
+}
+
+fn indicative(events: &[MarketDataEvent]) -> IndicativeAuction {
+  match events.last() {
+    Some(MarketDataEvent::AuctionIndicative(indicative)) => indicative.clone(),
+    other => panic!("expected an indicative update, got {:?}", other),
+  }
+}
+
+#[test]
+fn opening_auction_uncrosses_at_equilibrium_price() {
+  let instruments = registry();
+  let mut engine = MatchingEngine::new(instruments.clone());
+  
+  let output = engine.handle(set_status(TradingStatus::PreOpen));
+  assert_eq!(indicative(&output.market_data).price, None);
+  
+  // Crossing orders rest without matching during the call
+  for message in [
+    limit("b1", Side::Buy, 102, 10),
+    limit("b2", Side::Buy, 101, 5),
+    limit("s1", Side::Sell, 98, 4),
+    limit("s2", Side::Sell, 100, 8),
+  ] {
+    assert!(engine.handle(message).executions.is_empty());
+  }
+  let output = engine.handle(limit("s3", Side::Sell, 103, 10));
+  let current = indicative(&output.market_data);
+  assert_eq!(current.price, Some(100));
+  assert_eq!(current.volume, 12);
+  assert_eq!(current.imbalance, 3);
+  assert_eq!(current.imbalance_side.as_deref(), Some("Buy"));
+  
+  // Market orders cannot take part in the call
+  let output = engine.handle(OrderMessage::Order(new_order("m1".into(), Side::Buy, OrderType::Market, 0, 1)));
+  assert!(output.executions.is_empty() && output.market_data.is_empty());
+  
+  // Opening the market executes every crossing order at the single auction price
+  let output = engine.handle(set_status(TradingStatus::Trading));
+  assert_eq!(output.market_data, vec![MarketDataEvent::AuctionEnded { symbol: "SYM".into() }]);
+  assert!(output.executions.iter().all(|e| e.price == Price(100) && !e.is_maker));
+  let bought: Qty = output.executions.iter().filter(|e| e.side == Side::Buy).map(|e| e.quantity).sum();
+  assert_eq!(bought, Qty(12));
+  // 10bp of 4 * 100 = 0.4, truncated
+  assert_eq!(output.executions[0].fee, 0);
+  
+  let book = engine.book("SYM").unwrap();
+  assert_eq!(book.validate(), Ok(()));
+  assert_eq!(book.buy_book.best_level, Some(Price(101)));
+  assert_eq!(book.sell_book.best_level, Some(Price(103)));
+  assert_eq!(instruments.get("SYM").unwrap().status, TradingStatus::Trading);
+  
+  // Continuous trading resumes
+  let output = engine.handle(limit("s4", Side::Sell, 101, 3));
+  assert_eq!(output.executions.len(), 2);
+  assert_eq!(output.executions[0].price, Price(101));
+}
+
+#[test]
+fn closing_auction_uses_last_price_as_reference() {
+  let mut engine = MatchingEngine::new(registry());
+  
+  // Last trade at 105
+  engine.handle(limit("s0", Side::Sell, 105, 1));
+  engine.handle(limit("b0", Side::Buy, 105, 1));
+  
+  engine.handle(set_status(TradingStatus::PreClose));
+  engine.handle(limit("b1", Side::Buy, 102, 10));
+  engine.handle(limit("s1", Side::Sell, 100, 10));
+  
+  // Volume and imbalance are equal from 100 to 102, 102 is closest to the last trade
+  let output = engine.handle(set_status(TradingStatus::Closed));
+  assert_eq!(output.executions.len(), 2);
+  assert!(output.executions.iter().all(|e| e.price == Price(102) && e.quantity == Qty(10)));
+  
+  // Closed markets reject new orders
+  assert!(engine.handle(limit("b2", Side::Buy, 102, 1)).market_data.is_empty());
+  assert!(!engine.book("SYM").unwrap().order_map.contains_key("b2"));
+}
+
+#[tokio::test]
+async fn session_status_changes_are_sequenced() {
+  let instruments = registry();
+  let (order_tx, mut order_rx) = mpsc::channel(10);
+  let audit = Arc::new(AuditLog::new());
+  let state = AdminState::new(Some("secret".to_string()), order_tx, instruments.clone(), Arc::new(RiskManager::new()), audit.clone());
+  let routes = admin::routes(Arc::new(state));
+  
+  // Phase changes are admin commands
+  let resp = request()
+    .method("PUT")
+    .path("/admin/v1/instruments/SYM/status")
+    .json(&serde_json::json!({ "status": "PreOpen" }))
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+  assert!(order_rx.try_recv().is_err());
+  
+  let resp = request()
+    .method("PUT")
+    .path("/admin/v1/instruments/SYM/status")
+    .header("authorization", "Bearer secret")
+    .header("x-admin-user", "ops")
+    .json(&serde_json::json!({ "status": "PreOpen" }))
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::ACCEPTED);
+  match order_rx.try_recv().unwrap() {
+    OrderMessage::SetTradingStatus { symbol, status } => {
+      assert_eq!(symbol, "SYM");
+      assert_eq!(status, TradingStatus::PreOpen);
+    }
+    other => panic!("unexpected message {:?}", other),
+  }
+  // The registry is updated by the engine, not by the API
+  assert_eq!(instruments.get("SYM").unwrap().status, TradingStatus::Trading);
+  
+  let entry = audit.entries().pop().unwrap();
+  assert_eq!((entry.actor.as_str(), entry.action.as_str()), ("ops", "set_status"));
+  
+  let resp = request()
+    .method("PUT")
+    .path("/admin/v1/instruments/OTHER/status")
+    .header("authorization", "Bearer secret")
+    .json(&serde_json::json!({ "status": "PreOpen" }))
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn indicative_auction_is_published() {
+  let mdp = MarketDataPublisher::new();
+  let routes = mdp.routes();
+  
+  let mut engine = MatchingEngine::new(registry());
+  let mut events = engine.handle(set_status(TradingStatus::PreOpen)).market_data;
+  events.extend(engine.handle(limit("b1", Side::Buy, 101, 5)).market_data);
+  events.extend(engine.handle(limit("s1", Side::Sell, 99, 3)).market_data);
+  for event in &events {
+    mdp.process_market_data_event(event);
+  }
+  
+  let resp = request().method("GET").path("/api/v1/auction/SYM").reply(&routes).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  let json: Value = serde_json::from_slice(resp.body()).unwrap();
+  assert_eq!(json["price"], "99");
+  assert_eq!(json["volume"], "3");
+  assert_eq!(json["imbalance"], "2");
+  assert_eq!(mdp.get_indicative_auction("SYM").unwrap().imbalance_side.as_deref(), Some("Buy"));
+  
+  for event in engine.handle(set_status(TradingStatus::Trading)).market_data {
+    mdp.process_market_data_event(&event);
+  }
+  let resp = request().method("GET").path("/api/v1/auction/SYM").reply(&routes).await;
+  assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+  let json: Value = serde_json::from_slice(resp.body()).unwrap();
+  assert_eq!(json["error"], "NOT_FOUND");
+}
\ No newline at end of file
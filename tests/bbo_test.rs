@@ -0,0 +1,74 @@
+/*
+* filename : bbo_test
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 최우선 호가(BBO) 추적과 조회 테스트
+**/
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use serde_json::{json, Value};
+
+use xTraderz::instrument::Instrument;
+use xTraderz::market_data_publisher::models::{BestBidOffer, MarketDataEvent};
+use xTraderz::market_data_publisher::publisher::MarketDataPublisher;
+use xTraderz::matching_engine::MatchingEngine;
+use xTraderz::models::{OrderMessage, OrderType, Side};
+use xTraderz::websocket::market_stream::{self, MarketStreamHub};
+use common::{new_order, registry, registry_of};
+
+fn order(order_id: &str, side: Side, price: u64, quantity: u64) -> OrderMessage {
+  OrderMessage::Order(new_order(order_id.into(), side, OrderType::Limit, price, quantity))
+}
+
+fn top(bbo: &[BestBidOffer]) -> Vec<(Option<u64>, u64, Option<u64>, u64)> {
+  bbo.iter().map(|b| (b.bid_price, b.bid_qty, b.ask_price, b.ask_qty)).collect()
+}
+
+#[test]
+fn engine_reports_top_of_book_changes_only() {
+  let mut engine = MatchingEngine::new(registry(&["SYM"]));
+  assert_eq!(top(&engine.handle(order("b1", Side::Buy, 100, 5)).bbo), [(Some(100), 5, None, 0)]);
+  assert_eq!(top(&engine.handle(order("s1", Side::Sell, 110, 3)).bbo), [(Some(100), 5, Some(110), 3)]);
+  
+  // Orders behind the best level leave the top unchanged
+  assert!(engine.handle(order("b2", Side::Buy, 90, 1)).bbo.is_empty());
+  
+  // Quantity at the best level counts, and a filled level gives way to the next one
+  assert_eq!(top(&engine.handle(order("b3", Side::Buy, 100, 2)).bbo), [(Some(100), 7, Some(110), 3)]);
+  assert_eq!(top(&engine.handle(order("s2", Side::Sell, 100, 7)).bbo), [(Some(90), 1, Some(110), 3)]);
+}
+
+#[tokio::test]
+async fn bbo_is_served_over_rest_and_websocket() {
+  // Prices and quantities are served with the instrument's decimal places
+  let scaled = registry_of([Instrument { price_scale: 1, quantity_scale: 1, ..Instrument::new("SYM") }]);
+  let mdp = Arc::new(MarketDataPublisher::with_instruments(scaled));
+  let hub = Arc::new(MarketStreamHub::new(registry(&["SYM"])));
+  let routes = mdp.routes();
+  
+  let response = warp::test::request().path("/api/v1/ticker/bookTicker/SYM").reply(&routes).await;
+  let empty: Value = serde_json::from_slice(response.body()).unwrap();
+  assert_eq!((&empty["bid_price"], &empty["ask_price"]), (&Value::Null, &Value::Null));
+  
+  let mut client = warp::test::ws().path("/ws").handshake(market_stream::ws_stream_route(hub.clone())).await.unwrap();
+  client.send_text(json!({ "type": "subscribe", "channels": ["bbo:SYM"] }).to_string()).await;
+  client.recv().await.unwrap();
+  
+  let mut engine = MatchingEngine::new(registry(&["SYM"]));
+  engine.handle(order("b1", Side::Buy, 100, 5));
+  let bbo = engine.handle(order("s1", Side::Sell, 110, 3)).bbo.remove(0);
+  mdp.process_market_data_event(&MarketDataEvent::Bbo(bbo.clone()));
+  market_stream::publish_bbo(&hub, &mdp.get_bbo("SYM").unwrap());
+  
+  let message = tokio::time::timeout(Duration::from_millis(500), client.recv()).await.unwrap().unwrap();
+  let update: Value = serde_json::from_str(message.to_str().unwrap()).unwrap();
+  assert_eq!(update["channel"], "bbo:SYM");
+  assert_eq!((&update["data"]["bid_price"], &update["data"]["ask_qty"]), (&json!("10"), &json!("0.3")));
+  
+  let response = warp::test::request().path("/api/v1/ticker/bookTicker/SYM").reply(&routes).await;
+  let ticker: Value = serde_json::from_slice(response.body()).unwrap();
+  assert_eq!(ticker, update["data"]);
+}
\ No newline at end of file
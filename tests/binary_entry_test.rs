@@ -0,0 +1,218 @@
+/*
+* filename : binary_entry_test
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 바이너리 주문 입력 게이트웨이와 클라이언트 라이브러리 통합 테스트
+**/
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+use xTraderz::auth::AccountRegistry;
+use xTraderz::binary::client::{BinaryClient, ClientError};
+use xTraderz::binary::gateway::BinaryGateway;
+use xTraderz::instrument::{Instrument, InstrumentRegistry};
+use xTraderz::models::{OrderType, Price, Qty, Side};
+use xTraderz::risk::RiskManager;
+use xTraderz::throttle::Throttle;
+use xTraderz::sequencer::{self, SequencerOutputs};
+use xTraderz::util::binary_codec::{BinaryMessage, ExecType, ExecutionReport, RejectReason};
+
+// Gateway in front of a running sequencer and matching engine
+async fn start() -> SocketAddr {
+  let accounts = Arc::new(AccountRegistry::new());
+  accounts.insert("fund1", "key-1");
+  accounts.insert("fund2", "key-2");
+  let instruments = Arc::new(InstrumentRegistry::new());
+  let mut instrument = Instrument::new("SYM");
+  instrument.tick_size = Price(5);
+  // Orders priced more than 10% through the last trade are rejected by the engine
+  instrument.price_band.band_bps = 1_000;
+  instruments.insert(instrument);
+  
+  let (order_tx, order_rx) = mpsc::channel(100);
+  let (exec_tx, mut exec_rx) = mpsc::channel(100);
+  let (md_tx, mut md_rx) = mpsc::channel(100);
+  let (outcome_tx, mut outcome_rx) = mpsc::channel(100);
+  tokio::spawn(sequencer::run(order_rx, SequencerOutputs { outcome_tx, ..SequencerOutputs::new(exec_tx, md_tx) }, instruments.clone()));
+  tokio::spawn(async move { while md_rx.recv().await.is_some() {} });
+  
+  let gateway = Arc::new(BinaryGateway::new(accounts, instruments, order_tx, Arc::new(RiskManager::new()), Arc::new(Throttle::default())));
+  let gateway_for_exec = gateway.clone();
+  tokio::spawn(async move {
+    loop {
+      tokio::select! {
+        biased;
+        execution = exec_rx.recv() => match execution {
+          Some(execution) => gateway_for_exec.on_execution(&execution).await,
+          None => break,
+        },
+        Some(outcome) = outcome_rx.recv() => gateway_for_exec.on_outcome(&outcome).await,
+      }
+    }
+  });
+  
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let addr = listener.local_addr().unwrap();
+  tokio::spawn(gateway.serve(listener));
+  addr
+}
+
+async fn recv(client: &mut BinaryClient) -> BinaryMessage {
+  tokio::time::timeout(Duration::from_secs(3), client.recv()).await.expect("no message received").unwrap()
+}
+
+async fn report(client: &mut BinaryClient) -> ExecutionReport {
+  match recv(client).await {
+    BinaryMessage::ExecutionReport(report) => report,
+    other => panic!("expected an execution report, received {:?}", other),
+  }
+}
+
+async fn reject(client: &mut BinaryClient) -> (String, RejectReason) {
+  match recv(client).await {
+    BinaryMessage::Reject { client_order_id, reason } => (client_order_id, reason),
+    other => panic!("expected a reject, received {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn logon_is_required() {
+  let addr = start().await;
+  
+  let refused = BinaryClient::connect(addr, "wrong").await;
+  assert!(matches!(refused, Err(ClientError::LogonRejected(RejectReason::AuthenticationFailed))));
+  
+  // Orders sent before logging on are rejected
+  let mut stream = TcpStream::connect(addr).await.unwrap();
+  let cancel = BinaryMessage::Cancel { client_order_id: "c1".to_string() };
+  stream.write_all(&cancel.encode().unwrap()).await.unwrap();
+  let mut buffer = Vec::new();
+  let reply = loop {
+    if let Some(message) = BinaryMessage::decode(&mut buffer).unwrap() {
+      break message;
+    }
+    let mut chunk = [0u8; 64];
+    let n = stream.read(&mut chunk).await.unwrap();
+    buffer.extend_from_slice(&chunk[..n]);
+  };
+  assert_eq!(reply, BinaryMessage::Reject { client_order_id: String::new(), reason: RejectReason::NotLoggedOn });
+  
+  let client = BinaryClient::connect(addr, "key-1").await.unwrap();
+  assert_eq!(client.account(), "fund1");
+}
+
+#[tokio::test]
+async fn orders_are_acknowledged_and_filled() {
+  let addr = start().await;
+  let mut buyer = BinaryClient::connect(addr, "key-1").await.unwrap();
+  let mut seller = BinaryClient::connect(addr, "key-2").await.unwrap();
+  
+  buyer.new_order("c1", "SYM", Side::Buy, OrderType::Limit, Price(100), Qty(10)).await.unwrap();
+  let new = report(&mut buyer).await;
+  assert_eq!((new.client_order_id.as_str(), new.exec_type, new.leaves_quantity), ("c1", ExecType::New, Qty(10)));
+  
+  buyer.new_order("c1", "SYM", Side::Buy, OrderType::Limit, Price(100), Qty(10)).await.unwrap();
+  assert_eq!(reject(&mut buyer).await, ("c1".to_string(), RejectReason::DuplicateClientOrderId));
+  buyer.new_order("c2", "SYM", Side::Buy, OrderType::Limit, Price(101), Qty(10)).await.unwrap();
+  assert_eq!(reject(&mut buyer).await, ("c2".to_string(), RejectReason::InvalidOrder));
+  buyer.new_order("c3", "NOPE", Side::Buy, OrderType::Limit, Price(100), Qty(10)).await.unwrap();
+  assert_eq!(reject(&mut buyer).await, ("c3".to_string(), RejectReason::UnknownSymbol));
+  
+  buyer.amend("c1", Price(100), Qty(8)).await.unwrap();
+  let amended = report(&mut buyer).await;
+  assert_eq!((amended.exec_type, amended.order_id, amended.quantity), (ExecType::Amended, new.order_id, Qty(8)));
+  
+  seller.new_order("s1", "SYM", Side::Sell, OrderType::Limit, Price(100), Qty(5)).await.unwrap();
+  assert_eq!(report(&mut seller).await.exec_type, ExecType::New);
+  let fill = report(&mut seller).await;
+  assert_eq!((fill.exec_type, fill.price, fill.quantity, fill.leaves_quantity), (ExecType::Trade, Price(100), Qty(5), Qty(0)));
+  let fill = report(&mut buyer).await;
+  assert_eq!((fill.client_order_id.as_str(), fill.exec_type, fill.quantity, fill.leaves_quantity), ("c1", ExecType::Trade, Qty(5), Qty(3)));
+  
+  buyer.cancel("c1").await.unwrap();
+  let cancelled = report(&mut buyer).await;
+  assert_eq!((cancelled.exec_type, cancelled.leaves_quantity), (ExecType::Cancelled, Qty(0)));
+  buyer.cancel("c1").await.unwrap();
+  assert_eq!(reject(&mut buyer).await, ("c1".to_string(), RejectReason::UnknownOrder));
+  buyer.amend("s1", Price(100), Qty(8)).await.unwrap();
+  assert_eq!(reject(&mut buyer).await, ("s1".to_string(), RejectReason::UnknownOrder));
+}
+
+#[tokio::test]
+async fn engine_rejects_are_reported() {
+  let addr = start().await;
+  let mut buyer = BinaryClient::connect(addr, "key-1").await.unwrap();
+  let mut seller = BinaryClient::connect(addr, "key-2").await.unwrap();
+  
+  // A trade at 100 puts the band at 90 to 110
+  buyer.new_order("b1", "SYM", Side::Buy, OrderType::Limit, Price(100), Qty(5)).await.unwrap();
+  assert_eq!(report(&mut buyer).await.exec_type, ExecType::New);
+  seller.new_order("s1", "SYM", Side::Sell, OrderType::Limit, Price(100), Qty(5)).await.unwrap();
+  assert_eq!(report(&mut seller).await.exec_type, ExecType::New);
+  assert_eq!(report(&mut seller).await.exec_type, ExecType::Trade);
+  assert_eq!(report(&mut buyer).await.exec_type, ExecType::Trade);
+  
+  // Accepted by the gateway, rejected by the engine
+  buyer.new_order("b2", "SYM", Side::Buy, OrderType::Limit, Price(200), Qty(5)).await.unwrap();
+  assert_eq!(report(&mut buyer).await.exec_type, ExecType::New);
+  assert_eq!(reject(&mut buyer).await, ("b2".to_string(), RejectReason::InvalidOrder));
+  
+  // The original order is gone when its replacement is rejected
+  buyer.new_order("b3", "SYM", Side::Buy, OrderType::Limit, Price(100), Qty(5)).await.unwrap();
+  assert_eq!(report(&mut buyer).await.exec_type, ExecType::New);
+  buyer.amend("b3", Price(200), Qty(5)).await.unwrap();
+  assert_eq!(reject(&mut buyer).await, ("b3".to_string(), RejectReason::InvalidOrder));
+  buyer.cancel("b3").await.unwrap();
+  assert_eq!(reject(&mut buyer).await, ("b3".to_string(), RejectReason::UnknownOrder));
+}
+
+#[tokio::test]
+async fn cancels_follow_the_engine() {
+  let addr = start().await;
+  let mut buyer = BinaryClient::connect(addr, "key-1").await.unwrap();
+  let mut seller = BinaryClient::connect(addr, "key-2").await.unwrap();
+  
+  // The cancel reaches the engine after the order has filled
+  seller.new_order("s1", "SYM", Side::Sell, OrderType::Limit, Price(100), Qty(5)).await.unwrap();
+  assert_eq!(report(&mut seller).await.exec_type, ExecType::New);
+  buyer.new_order("b1", "SYM", Side::Buy, OrderType::Limit, Price(100), Qty(5)).await.unwrap();
+  assert_eq!(report(&mut buyer).await.exec_type, ExecType::New);
+  seller.cancel("s1").await.unwrap();
+  assert_eq!(report(&mut buyer).await.exec_type, ExecType::Trade);
+  let fill = report(&mut seller).await;
+  assert_eq!((fill.exec_type, fill.leaves_quantity), (ExecType::Trade, Qty(0)));
+  assert_eq!(reject(&mut seller).await, ("s1".to_string(), RejectReason::UnknownOrder));
+  
+  // The unfilled rest of a market order is cancelled
+  seller.new_order("s2", "SYM", Side::Sell, OrderType::Limit, Price(100), Qty(4)).await.unwrap();
+  assert_eq!(report(&mut seller).await.exec_type, ExecType::New);
+  buyer.new_order("b2", "SYM", Side::Buy, OrderType::Market, Price(0), Qty(10)).await.unwrap();
+  assert_eq!(report(&mut buyer).await.exec_type, ExecType::New);
+  let fill = report(&mut buyer).await;
+  assert_eq!((fill.exec_type, fill.quantity, fill.leaves_quantity), (ExecType::Trade, Qty(4), Qty(6)));
+  let cancelled = report(&mut buyer).await;
+  assert_eq!((cancelled.client_order_id.as_str(), cancelled.exec_type, cancelled.leaves_quantity), ("b2", ExecType::Cancelled, Qty(0)));
+}
+
+#[tokio::test]
+async fn open_orders_are_cancelled_on_disconnect() {
+  let addr = start().await;
+  let mut buyer = BinaryClient::connect(addr, "key-1").await.unwrap();
+  let mut seller = BinaryClient::connect(addr, "key-2").await.unwrap();
+  seller.new_order("s1", "SYM", Side::Sell, OrderType::Limit, Price(100), Qty(5)).await.unwrap();
+  assert_eq!(report(&mut seller).await.exec_type, ExecType::New);
+  drop(seller);
+  tokio::time::sleep(Duration::from_millis(200)).await;
+  
+  // The seller's order no longer trades with nobody left to report it to
+  buyer.new_order("b1", "SYM", Side::Buy, OrderType::Limit, Price(100), Qty(5)).await.unwrap();
+  assert_eq!(report(&mut buyer).await.exec_type, ExecType::New);
+  buyer.cancel("b1").await.unwrap();
+  let cancelled = report(&mut buyer).await;
+  assert_eq!((cancelled.client_order_id.as_str(), cancelled.exec_type), ("b1", ExecType::Cancelled));
+}
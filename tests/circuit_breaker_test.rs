@@ -0,0 +1,122 @@
+/*
+* filename : circuit_breaker_test
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 가격 제한폭 및 변동성 서킷 브레이커 테스트
+**/
+
+mod common;
+
+use std::sync::Arc;
+use chrono::{Duration, Utc};
+
+use xTraderz::matching_engine::MatchingEngine;
+use xTraderz::models::{OrderMessage, OrderType, Side, Price, Qty};
+use xTraderz::instrument::{BandAction, BandReference, CircuitBreaker, Instrument, InstrumentRegistry, PriceBand, TradingStatus};
+use xTraderz::market_data_publisher::models::MarketDataEvent;
+use common::{new_order, registry_of};
+
+fn registry(action: BandAction, circuit_breaker: CircuitBreaker) -> Arc<InstrumentRegistry> {
+  registry_of([Instrument {
+    price_band: PriceBand { band_bps: 1000, reference: BandReference::LastTrade, vwap_window_secs: 0, action },
+    circuit_breaker,
+    ..Instrument::new("SYM")
+  }])
+}
+
+fn limit(order_id: &str, side: Side, price: u64, quantity: u64) -> OrderMessage {
+  OrderMessage::Order(new_order(order_id.into(), side, OrderType::Limit, price, quantity))
+}
+
+fn market(order_id: &str, side: Side, quantity: u64) -> OrderMessage {
+  OrderMessage::Order(new_order(order_id.into(), side, OrderType::Market, 0, quantity))
+}
+
+// Trade once at the price to set the band reference
+fn trade_at(engine: &mut MatchingEngine, price: u64) {
+  engine.handle(limit(&format!("s@{}", price), Side::Sell, price, 1));
+  assert_eq!(engine.handle(limit(&format!("b@{}", price), Side::Buy, price, 1)).executions.len(), 2);
+}
+
+#[test]
+fn orders_through_the_band_are_rejected() {
+  let mut engine = MatchingEngine::new(registry(BandAction::Reject, CircuitBreaker::default()));
+  
+  // No reference price yet, so no band
+  engine.handle(limit("s1", Side::Sell, 200, 5));
+  trade_at(&mut engine, 100);
+  
+  // Band is 90..=110 around the last trade
+  assert!(engine.handle(limit("b1", Side::Buy, 250, 5)).executions.is_empty());
+  assert!(!engine.book("SYM").unwrap().order_map.contains_key("b1"));
+  
+  // Resting orders away from the reference are fine
+  engine.handle(limit("b2", Side::Buy, 50, 1));
+  assert!(engine.book("SYM").unwrap().order_map.contains_key("b2"));
+  
+  // Market orders sweep no further than the band
+  engine.handle(limit("s2", Side::Sell, 104, 2));
+  let output = engine.handle(market("m1", Side::Buy, 10));
+  assert_eq!(output.executions.len(), 2);
+  assert!(output.executions.iter().all(|e| e.price == Price(104) && e.quantity == Qty(2)));
+  assert_eq!(engine.book("SYM").unwrap().sell_book.best_level, Some(Price(200)));
+}
+
+#[test]
+fn orders_through_the_band_are_clipped() {
+  let mut engine = MatchingEngine::new(registry(BandAction::Clip, CircuitBreaker::default()));
+  trade_at(&mut engine, 100);
+  
+  engine.handle(limit("s1", Side::Sell, 112, 1));
+  engine.handle(limit("s2", Side::Sell, 108, 1));
+  let output = engine.handle(limit("b1", Side::Buy, 120, 2));
+  assert_eq!(output.executions.len(), 2);
+  assert_eq!(output.executions[0].price, Price(108));
+  
+  // The unfilled remainder rests at the band edge
+  let book = engine.book("SYM").unwrap();
+  assert_eq!(book.buy_book.best_level, Some(Price(110)));
+  assert_eq!(book.order_map.get("b1").unwrap().price, Price(110));
+}
+
+#[test]
+fn circuit_breaker_halts_and_reopens_through_an_auction() {
+  let breaker = CircuitBreaker { threshold_bps: 500, window_secs: 60, halt_secs: 30, reopen_auction_secs: 10 };
+  let instruments = registry(BandAction::Reject, breaker);
+  let mut engine = MatchingEngine::new(instruments.clone());
+  let status = || instruments.get("SYM").unwrap().status;
+  
+  trade_at(&mut engine, 100);
+  trade_at(&mut engine, 104);
+  assert_eq!(status(), TradingStatus::Trading);
+  
+  // 100 -> 106 within the window is a 6% move
+  trade_at(&mut engine, 106);
+  assert_eq!(status(), TradingStatus::Halted);
+  assert!(engine.handle(limit("b1", Side::Buy, 106, 1)).market_data.is_empty());
+  assert!(!engine.book("SYM").unwrap().order_map.contains_key("b1"));
+  
+  let start = Utc::now();
+  assert!(engine.poll_timers(start).market_data.is_empty());
+  
+  // After the halt orders are collected in a re-opening auction
+  let output = engine.poll_timers(start + Duration::seconds(31));
+  assert!(matches!(output.market_data.as_slice(), [MarketDataEvent::AuctionIndicative(_)]));
+  assert_eq!(status(), TradingStatus::PreOpen);
+  assert!(engine.handle(limit("b2", Side::Buy, 108, 2)).executions.is_empty());
+  assert!(engine.handle(limit("s1", Side::Sell, 107, 1)).executions.is_empty());
+  
+  // The auction uncrosses closest to the last trade, bands do not apply during the call
+  let output = engine.poll_timers(start + Duration::seconds(42));
+  assert_eq!(status(), TradingStatus::Trading);
+  assert_eq!(output.executions.len(), 2);
+  assert!(output.executions.iter().all(|e| e.price == Price(107)));
+  assert_eq!(output.market_data, vec![MarketDataEvent::AuctionEnded { symbol: "SYM".into() }]);
+  
+  // Manual status changes cancel a pending re-opening
+  trade_at(&mut engine, 113);
+  assert_eq!(status(), TradingStatus::Halted);
+  engine.handle(OrderMessage::SetTradingStatus { symbol: "SYM".into(), status: TradingStatus::Closed });
+  engine.poll_timers(Utc::now() + Duration::seconds(3600));
+  assert_eq!(status(), TradingStatus::Closed);
+}
\ No newline at end of file
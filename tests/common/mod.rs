@@ -0,0 +1,104 @@
+#![allow(dead_code)]
+
+/*
+* filename : mod
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 통합 테스트 공용 주문 생성 및 종목 레지스트리 헬퍼
+**/
+
+use std::sync::Arc;
+use chrono::Utc;
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use xTraderz::instrument::{Instrument, InstrumentRegistry};
+use xTraderz::models::{Amend, Order, OrderMessage, Side, OrderType, OrderStatus, Price, Qty};
+
+pub fn registry_of(instruments: impl IntoIterator<Item = Instrument>) -> Arc<InstrumentRegistry> {
+  let registry = Arc::new(InstrumentRegistry::new());
+  for instrument in instruments {
+    registry.insert(instrument);
+  }
+  registry
+}
+
+// Instruments with the permissive defaults of Instrument::new
+pub fn registry(symbols: &[&str]) -> Arc<InstrumentRegistry> {
+  registry_of(symbols.iter().map(|symbol| Instrument::new(symbol)))
+}
+
+pub fn new_order(order_id: String, side: Side, order_type: OrderType, price: u64, quantity: u64) -> Order {
+  Order {
+    order_id,
+    symbol: "SYM".into(),
+    account: String::new(),
+    price: Price(price),
+    quantity: Qty(quantity),
+    side,
+    order_type,
+    status: OrderStatus::New,
+    filled_quantity: Qty::ZERO,
+    remain_quantity: Qty(quantity),
+    entry_time: Utc::now()
+  }
+}
+
+pub fn cancel_order(order_id: String) -> Order {
+  let mut order = new_order(order_id, Side::Buy, OrderType::Limit, 0, 0);
+  order.status = OrderStatus::Cancelled;
+  order
+}
+
+// Random command generator: new limit orders, cancels, amends (cancel/replace) and market orders.
+// Cancels target any previously issued id so that a stream stays valid when commands are removed from it.
+pub fn random_commands(rng: &mut StdRng, next_id: &mut u64, issued: &[String]) -> Vec<Order> {
+  let side = if rng.gen_bool(0.5) { Side::Buy } else { Side::Sell };
+  let price = rng.gen_range(90..=110);
+  let quantity = rng.gen_range(1..=20);
+  
+  let mut fresh_id = || {
+    *next_id += 1;
+    format!("o{}", next_id)
+  };
+  
+  match rng.gen_range(0..10) {
+    0..=5 => vec![new_order(fresh_id(), side, OrderType::Limit, price, quantity)],
+    6 | 7 if !issued.is_empty() => {
+      vec![cancel_order(issued[rng.gen_range(0..issued.len())].clone())]
+    }
+    8 if !issued.is_empty() => {
+      let target = issued[rng.gen_range(0..issued.len())].clone();
+      vec![cancel_order(target), new_order(fresh_id(), side, OrderType::Limit, price, quantity)]
+    }
+    _ => vec![new_order(fresh_id(), side, OrderType::Market, 0, quantity)],
+  }
+}
+
+// A random engine message with the same mix as random_commands, except that amends are real
+// amends of a resting order: a lower quantity at the same price, which keeps the order's
+// priority, or a new price and quantity.
+pub fn random_message(rng: &mut StdRng, next_id: &mut u64, resting: &[Order]) -> OrderMessage {
+  let side = if rng.gen_bool(0.5) { Side::Buy } else { Side::Sell };
+  let price = rng.gen_range(90..=110);
+  let quantity = rng.gen_range(1..=20);
+  *next_id += 1;
+  let fresh_id = format!("o{}", next_id);
+  
+  match rng.gen_range(0..10) {
+    0..=5 => OrderMessage::Order(new_order(fresh_id, side, OrderType::Limit, price, quantity)),
+    6 | 7 if !resting.is_empty() => {
+      OrderMessage::Order(cancel_order(resting[rng.gen_range(0..resting.len())].order_id.clone()))
+    }
+    8 if !resting.is_empty() => {
+      let target = &resting[rng.gen_range(0..resting.len())];
+      let (price, quantity) = if rng.gen_bool(0.5) {
+        (target.price, Qty(rng.gen_range(1..=target.quantity.0)))
+      } else {
+        (Price(price), Qty(quantity))
+      };
+      OrderMessage::Amend(Amend { order_id: target.order_id.clone(), account: target.account.clone(), price, quantity })
+    }
+    _ => OrderMessage::Order(new_order(fresh_id, side, OrderType::Market, 0, quantity)),
+  }
+}
@@ -0,0 +1,165 @@
+/*
+* filename : differential_test
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 단순 참조 매칭기와 매칭 엔진의 체결 결과 비교
+**/
+
+mod common;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use xTraderz::matching_engine::process_order;
+use xTraderz::models::{Order, OrderBook, Side, OrderType, OrderStatus, Price, Qty};
+use common::random_commands;
+
+const SEEDS: u64 = 200;
+const COMMANDS: usize = 300;
+
+// (order_id, side, price, quantity) - the parts of an execution both matchers must agree on
+type Fill = (String, Side, Price, Qty);
+
+// Deliberately simple price-time priority matcher: resting orders are kept in arrival order
+// and every match scans the whole list for the best price, earliest arrival.
+#[derive(Default)]
+struct ReferenceMatcher {
+  resting: Vec<Order>,
+}
+
+impl ReferenceMatcher {
+  fn process(&mut self, mut order: Order) -> Vec<Fill> {
+    let mut fills = Vec::new();
+    
+    if order.status == OrderStatus::Cancelled {
+      self.resting.retain(|o| o.order_id != order.order_id);
+      return fills;
+    }
+    
+    while !order.remain_quantity.is_zero() {
+      let crosses = |o: &Order| {
+        o.side != order.side && match (order.order_type.clone(), order.side.clone()) {
+          (OrderType::Market, _) => true,
+          (OrderType::Limit, Side::Buy) => o.price <= order.price,
+          (OrderType::Limit, Side::Sell) => o.price >= order.price,
+        }
+      };
+      
+      let mut best: Option<usize> = None;
+      for (i, o) in self.resting.iter().enumerate() {
+        if !crosses(o) { continue; }
+        let better = match best {
+          None => true,
+          Some(b) => match order.side {
+            Side::Buy => o.price < self.resting[b].price,
+            Side::Sell => o.price > self.resting[b].price,
+          },
+        };
+        if better { best = Some(i); }
+      }
+      
+      let index = match best {
+        Some(i) => i,
+        None => break,
+      };
+      
+      let maker = &mut self.resting[index];
+      let qty = order.remain_quantity.min(maker.remain_quantity);
+      fills.push((order.order_id.clone(), order.side.clone(), maker.price, qty));
+      fills.push((maker.order_id.clone(), maker.side.clone(), maker.price, qty));
+      
+      order.remain_quantity -= qty;
+      maker.remain_quantity -= qty;
+      if maker.remain_quantity.is_zero() {
+        self.resting.remove(index);
+      }
+    }
+    
+    if !order.remain_quantity.is_zero() && order.order_type == OrderType::Limit {
+      self.resting.push(order);
+    }
+    
+    fills
+  }
+}
+
+fn generate(seed: u64) -> Vec<Order> {
+  let mut rng = StdRng::seed_from_u64(seed);
+  let mut next_id = 0;
+  let mut commands: Vec<Order> = Vec::new();
+  
+  while commands.len() < COMMANDS {
+    let issued: Vec<String> = commands.iter()
+      .filter(|o| o.status != OrderStatus::Cancelled)
+      .map(|o| o.order_id.clone())
+      .collect();
+    commands.extend(random_commands(&mut rng, &mut next_id, &issued));
+  }
+  
+  commands
+}
+
+// Feed a command stream to both matchers and describe the first divergence, if any
+fn first_divergence(commands: &[Order]) -> Option<String> {
+  let mut book = OrderBook::new();
+  let mut reference = ReferenceMatcher::default();
+  
+  for (i, command) in commands.iter().enumerate() {
+    let actual: Vec<Fill> = process_order(&mut book, command.clone())
+      .into_iter()
+      .map(|e| (e.order_id, e.side, e.price, e.quantity))
+      .collect();
+    let expected = reference.process(command.clone());
+    
+    if actual != expected {
+      return Some(format!("command {}: engine {:?}, reference {:?}", i, actual, expected));
+    }
+  }
+  
+  None
+}
+
+// Greedy delta debugging: drop chunks of commands, then single commands, while the divergence persists
+fn minimize(mut commands: Vec<Order>) -> Vec<Order> {
+  let mut chunk = commands.len() / 2;
+  
+  while chunk > 0 {
+    let mut start = 0;
+    while start < commands.len() {
+      let end = (start + chunk).min(commands.len());
+      let candidate: Vec<Order> = commands[..start].iter().chain(commands[end..].iter()).cloned().collect();
+      
+      if first_divergence(&candidate).is_some() {
+        commands = candidate;
+      } else {
+        start += chunk;
+      }
+    }
+    chunk /= 2;
+  }
+  
+  commands
+}
+
+fn describe(commands: &[Order]) -> String {
+  commands.iter().map(|o| {
+    if o.status == OrderStatus::Cancelled {
+      format!("  cancel {}", o.order_id)
+    } else {
+      format!("  {} {:?} {:?} {} @ {}", o.order_id, o.side, o.order_type, o.quantity, o.price)
+    }
+  }).collect::<Vec<_>>().join("\n")
+}
+
+#[test]
+fn engine_matches_reference_implementation() {
+  for seed in 0..SEEDS {
+    let commands = generate(seed);
+    
+    if first_divergence(&commands).is_some() {
+      let minimized = minimize(commands);
+      panic!("seed {}: engine diverged from reference after {} commands\n{}\n{}",
+             seed, minimized.len(), describe(&minimized), first_divergence(&minimized).unwrap());
+    }
+  }
+}
\ No newline at end of file
@@ -0,0 +1,449 @@
+/*
+* filename : fix_test
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 스크립트 FIX 클라이언트를 이용한 FIX 4.4 게이트웨이 통합 테스트
+**/
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+use xTraderz::auth::AccountRegistry;
+use xTraderz::fix::gateway::FixGateway;
+use xTraderz::fix::message::{format_timestamp, msg_type, tags, FixMessage};
+use xTraderz::fix::store::{SessionStore, MAX_STORED_MESSAGES};
+use xTraderz::instrument::{Instrument, InstrumentRegistry};
+use xTraderz::risk::RiskManager;
+use xTraderz::throttle::Throttle;
+use xTraderz::sequencer::{self, SequencerOutputs};
+
+const GATEWAY: &str = "XTRADERZ";
+
+// Gateway in front of a running sequencer and matching engine
+async fn start(store_dir: &Path) -> SocketAddr {
+  let accounts = Arc::new(AccountRegistry::new());
+  accounts.insert("fund1", "key-1");
+  accounts.insert("fund2", "key-2");
+  accounts.bind_comp_id("fund1", "FUND1");
+  accounts.bind_comp_id("fund2", "FUND2");
+  let instruments = Arc::new(InstrumentRegistry::new());
+  // Orders priced more than 10% through the last trade are rejected by the engine
+  let mut instrument = Instrument::new("SYM");
+  instrument.price_band.band_bps = 1_000;
+  instruments.insert(instrument);
+  
+  let (order_tx, order_rx) = mpsc::channel(100);
+  let (exec_tx, mut exec_rx) = mpsc::channel(100);
+  let (md_tx, mut md_rx) = mpsc::channel(100);
+  let (outcome_tx, mut outcome_rx) = mpsc::channel(100);
+  tokio::spawn(sequencer::run(order_rx, SequencerOutputs { outcome_tx, ..SequencerOutputs::new(exec_tx, md_tx) }, instruments.clone()));
+  tokio::spawn(async move { while md_rx.recv().await.is_some() {} });
+  
+  let gateway = Arc::new(FixGateway::new(GATEWAY, store_dir, accounts, instruments, order_tx, Arc::new(RiskManager::new()), Arc::new(Throttle::default())));
+  let gateway_for_exec = gateway.clone();
+  tokio::spawn(async move {
+    loop {
+      tokio::select! {
+        biased;
+        execution = exec_rx.recv() => match execution {
+          Some(execution) => gateway_for_exec.on_execution(&execution).await,
+          None => break,
+        },
+        Some(outcome) = outcome_rx.recv() => gateway_for_exec.on_outcome(&outcome).await,
+      }
+    }
+  });
+  
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let addr = listener.local_addr().unwrap();
+  tokio::spawn(gateway.serve(listener));
+  addr
+}
+
+fn store_dir() -> PathBuf {
+  std::env::temp_dir().join(format!("fix_test_{}", uuid::Uuid::new_v4()))
+}
+
+// Scripted FIX initiator
+struct FixClient {
+  stream: TcpStream,
+  buffer: Vec<u8>,
+  comp_id: String,
+  seq: u64,
+}
+
+impl FixClient {
+  async fn connect(addr: SocketAddr, comp_id: &str, seq: u64) -> Self {
+    FixClient {
+      stream: TcpStream::connect(addr).await.unwrap(),
+      buffer: Vec::new(),
+      comp_id: comp_id.to_string(),
+      seq,
+    }
+  }
+  
+  async fn send(&mut self, message: FixMessage) {
+    let seq = self.seq;
+    self.seq += 1;
+    self.send_as(message, seq).await;
+  }
+  
+  async fn send_as(&mut self, message: FixMessage, seq: u64) {
+    let mut framed = FixMessage::new(message.msg_type())
+      .with(tags::SENDER_COMP_ID, &self.comp_id)
+      .with(tags::TARGET_COMP_ID, GATEWAY)
+      .with(tags::MSG_SEQ_NUM, seq)
+      .with(tags::SENDING_TIME, format_timestamp(Utc::now()));
+    for (tag, value) in message.body() {
+      framed.set(*tag, value);
+    }
+    self.stream.write_all(&framed.encode()).await.unwrap();
+  }
+  
+  async fn recv(&mut self) -> FixMessage {
+    tokio::time::timeout(Duration::from_secs(5), async {
+      loop {
+        if let Some(message) = FixMessage::decode(&mut self.buffer).unwrap() {
+          return message;
+        }
+        let mut chunk = [0u8; 4096];
+        let n = self.stream.read(&mut chunk).await.unwrap();
+        assert!(n > 0, "connection closed");
+        self.buffer.extend_from_slice(&chunk[..n]);
+      }
+    }).await.expect("no message received")
+  }
+  
+  async fn is_closed(&mut self) -> bool {
+    let mut chunk = [0u8; 4096];
+    matches!(tokio::time::timeout(Duration::from_secs(5), self.stream.read(&mut chunk)).await, Ok(Ok(0)) | Ok(Err(_)))
+  }
+  
+  async fn logon(&mut self, api_key: &str) -> FixMessage {
+    let logon = FixMessage::new(msg_type::LOGON)
+      .with(tags::ENCRYPT_METHOD, 0)
+      .with(tags::HEART_BT_INT, 30)
+      .with(tags::PASSWORD, api_key);
+    self.send(logon).await;
+    self.recv().await
+  }
+}
+
+fn new_order(cl_ord_id: &str, side: &str, price: &str, quantity: &str) -> FixMessage {
+  FixMessage::new(msg_type::NEW_ORDER_SINGLE)
+    .with(tags::CL_ORD_ID, cl_ord_id)
+    .with(tags::SYMBOL, "SYM")
+    .with(tags::SIDE, side)
+    .with(tags::ORD_TYPE, 2)
+    .with(tags::PRICE, price)
+    .with(tags::ORDER_QTY, quantity)
+    .with(tags::TRANSACT_TIME, format_timestamp(Utc::now()))
+}
+
+fn cancel(orig_cl_ord_id: &str, cl_ord_id: &str) -> FixMessage {
+  FixMessage::new(msg_type::ORDER_CANCEL_REQUEST)
+    .with(tags::ORIG_CL_ORD_ID, orig_cl_ord_id)
+    .with(tags::CL_ORD_ID, cl_ord_id)
+    .with(tags::SYMBOL, "SYM")
+    .with(tags::SIDE, 1)
+}
+
+fn fields(message: &FixMessage, wanted: &[u32]) -> Vec<String> {
+  wanted.iter().map(|&tag| message.get(tag).unwrap_or("").to_string()).collect()
+}
+
+#[tokio::test]
+async fn logon_heartbeats_and_logout() {
+  let addr = start(&store_dir()).await;
+  
+  let mut intruder = FixClient::connect(addr, "FUND1", 1).await;
+  let refused = intruder.logon("wrong").await;
+  assert_eq!(refused.msg_type(), msg_type::LOGOUT);
+  assert!(intruder.is_closed().await);
+  
+  let mut client = FixClient::connect(addr, "FUND1", 1).await;
+  let logon = FixMessage::new(msg_type::LOGON).with(tags::HEART_BT_INT, 1).with(tags::PASSWORD, "key-1");
+  client.send(logon).await;
+  let reply = client.recv().await;
+  assert_eq!(fields(&reply, &[tags::MSG_TYPE, tags::MSG_SEQ_NUM, tags::HEART_BT_INT]), ["A", "1", "1"]);
+  
+  // A second connection for the same session is refused
+  let mut duplicate = FixClient::connect(addr, "FUND1", 1).await;
+  assert_eq!(duplicate.logon("key-1").await.msg_type(), msg_type::LOGOUT);
+  
+  // Silence is answered by heartbeats and then a TestRequest
+  let test_request = loop {
+    let message = client.recv().await;
+    if message.msg_type() == msg_type::TEST_REQUEST {
+      break message;
+    }
+    assert_eq!(message.msg_type(), msg_type::HEARTBEAT);
+  };
+  let id = test_request.get(tags::TEST_REQ_ID).unwrap().to_string();
+  client.send(FixMessage::new(msg_type::HEARTBEAT).with(tags::TEST_REQ_ID, &id)).await;
+  
+  client.send(FixMessage::new(msg_type::TEST_REQUEST).with(tags::TEST_REQ_ID, "ping")).await;
+  let heartbeat = loop {
+    let message = client.recv().await;
+    if message.get(tags::TEST_REQ_ID) == Some("ping") {
+      break message;
+    }
+  };
+  assert_eq!(heartbeat.msg_type(), msg_type::HEARTBEAT);
+  
+  client.send(FixMessage::new(msg_type::LOGOUT)).await;
+  loop {
+    if client.recv().await.msg_type() == msg_type::LOGOUT {
+      break;
+    }
+  }
+  assert!(client.is_closed().await);
+}
+
+#[tokio::test]
+async fn orders_are_reported_with_execution_reports() {
+  let addr = start(&store_dir()).await;
+  let mut buyer = FixClient::connect(addr, "FUND1", 1).await;
+  let mut seller = FixClient::connect(addr, "FUND2", 1).await;
+  buyer.logon("key-1").await;
+  seller.logon("key-2").await;
+  
+  buyer.send(new_order("c1", "1", "100", "10")).await;
+  let report = buyer.recv().await;
+  assert_eq!(fields(&report, &[tags::MSG_TYPE, tags::CL_ORD_ID, tags::EXEC_TYPE, tags::ORD_STATUS, tags::LEAVES_QTY]), ["8", "c1", "0", "0", "10"]);
+  let order_id = report.get(tags::ORDER_ID).unwrap().to_string();
+  
+  // Rejections: duplicate ClOrdID, unknown symbol, missing tag
+  buyer.send(new_order("c1", "1", "100", "10")).await;
+  assert_eq!(fields(&buyer.recv().await, &[tags::EXEC_TYPE, tags::ORD_REJ_REASON]), ["8", "6"]);
+  buyer.send(new_order("c2", "1", "100", "10").with(tags::SYMBOL, "NOPE")).await;
+  assert_eq!(fields(&buyer.recv().await, &[tags::EXEC_TYPE, tags::ORD_REJ_REASON]), ["8", "1"]);
+  buyer.send(FixMessage::new(msg_type::NEW_ORDER_SINGLE).with(tags::CL_ORD_ID, "c2")).await;
+  assert_eq!(fields(&buyer.recv().await, &[tags::MSG_TYPE, tags::SESSION_REJECT_REASON]), ["3", "1"]);
+  
+  // Reducing the quantity keeps the order's priority
+  let replace = FixMessage::new(msg_type::ORDER_CANCEL_REPLACE_REQUEST)
+    .with(tags::ORIG_CL_ORD_ID, "c1")
+    .with(tags::CL_ORD_ID, "c3")
+    .with(tags::PRICE, "100")
+    .with(tags::ORDER_QTY, "8");
+  buyer.send(replace).await;
+  let pending = buyer.recv().await;
+  assert_eq!(fields(&pending, &[tags::EXEC_TYPE, tags::ORD_STATUS, tags::CL_ORD_ID, tags::ORIG_CL_ORD_ID, tags::ORDER_QTY]), ["E", "E", "c3", "c1", "10"]);
+  let report = buyer.recv().await;
+  assert_eq!(fields(&report, &[tags::EXEC_TYPE, tags::CL_ORD_ID, tags::ORIG_CL_ORD_ID, tags::ORDER_QTY, tags::LEAVES_QTY]), ["5", "c3", "c1", "8", "8"]);
+  assert_eq!(report.get(tags::ORDER_ID), Some(order_id.as_str()));
+  
+  seller.send(new_order("s1", "2", "100", "5")).await;
+  assert_eq!(seller.recv().await.get(tags::EXEC_TYPE), Some("0"));
+  let fill = seller.recv().await;
+  assert_eq!(fields(&fill, &[tags::EXEC_TYPE, tags::ORD_STATUS, tags::LAST_QTY, tags::LAST_PX, tags::CUM_QTY, tags::LEAVES_QTY]), ["F", "2", "5", "100", "5", "0"]);
+  let fill = buyer.recv().await;
+  assert_eq!(fields(&fill, &[tags::EXEC_TYPE, tags::ORD_STATUS, tags::CL_ORD_ID, tags::CUM_QTY, tags::LEAVES_QTY, tags::AVG_PX]), ["F", "1", "c3", "5", "3", "100"]);
+  
+  buyer.send(cancel("c3", "c4")).await;
+  let pending = buyer.recv().await;
+  assert_eq!(fields(&pending, &[tags::EXEC_TYPE, tags::ORD_STATUS, tags::CL_ORD_ID, tags::ORIG_CL_ORD_ID, tags::LEAVES_QTY]), ["6", "6", "c4", "c3", "3"]);
+  let report = buyer.recv().await;
+  assert_eq!(fields(&report, &[tags::EXEC_TYPE, tags::ORD_STATUS, tags::CL_ORD_ID, tags::ORIG_CL_ORD_ID, tags::CUM_QTY, tags::LEAVES_QTY]), ["4", "4", "c4", "c3", "5", "0"]);
+  
+  buyer.send(cancel("c4", "c5")).await;
+  assert_eq!(fields(&buyer.recv().await, &[tags::MSG_TYPE, tags::CXL_REJ_RESPONSE_TO, tags::CXL_REJ_REASON]), ["9", "1", "0"]);
+  buyer.send(cancel("unknown", "c6")).await;
+  assert_eq!(fields(&buyer.recv().await, &[tags::MSG_TYPE, tags::CXL_REJ_REASON]), ["9", "1"]);
+  
+  // The cancelled order no longer trades
+  seller.send(new_order("s2", "2", "100", "3")).await;
+  assert_eq!(seller.recv().await.get(tags::EXEC_TYPE), Some("0"));
+  buyer.send(FixMessage::new(msg_type::TEST_REQUEST).with(tags::TEST_REQ_ID, "t")).await;
+  assert_eq!(buyer.recv().await.msg_type(), msg_type::HEARTBEAT);
+}
+
+#[tokio::test]
+async fn sequence_numbers_persist_and_gaps_are_resent() {
+  let dir = store_dir();
+  let addr = start(&dir).await;
+  
+  let mut client = FixClient::connect(addr, "FUND1", 1).await;
+  client.logon("key-1").await;
+  client.send(new_order("c1", "1", "100", "10")).await;
+  assert_eq!(client.recv().await.get(tags::MSG_SEQ_NUM), Some("2"));
+  client.send(FixMessage::new(msg_type::LOGOUT)).await;
+  assert_eq!(client.recv().await.msg_type(), msg_type::LOGOUT);
+  assert!(client.is_closed().await);
+  
+  // Both sides continue their sequences after reconnecting
+  let mut client = FixClient::connect(addr, "FUND1", 4).await;
+  assert_eq!(client.logon("key-1").await.get(tags::MSG_SEQ_NUM), Some("4"));
+  
+  // Session messages are gap filled, the execution report is resent as a possible duplicate
+  client.send(FixMessage::new(msg_type::RESEND_REQUEST).with(tags::BEGIN_SEQ_NO, 1).with(tags::END_SEQ_NO, 0)).await;
+  let wanted = [tags::MSG_TYPE, tags::MSG_SEQ_NUM, tags::POSS_DUP_FLAG, tags::NEW_SEQ_NO];
+  assert_eq!(fields(&client.recv().await, &wanted), ["4", "1", "Y", "2"]);
+  let resent = client.recv().await;
+  assert_eq!(fields(&resent, &wanted), ["8", "2", "Y", ""]);
+  assert_eq!(resent.get(tags::CL_ORD_ID), Some("c1"));
+  assert!(resent.get(tags::ORIG_SENDING_TIME).is_some());
+  assert_eq!(fields(&client.recv().await, &wanted), ["4", "3", "Y", "5"]);
+  
+  // A gap in the client's messages is requested and filled
+  client.send_as(FixMessage::new(msg_type::HEARTBEAT), 8).await;
+  let request = client.recv().await;
+  assert_eq!(fields(&request, &[tags::MSG_TYPE, tags::BEGIN_SEQ_NO, tags::END_SEQ_NO]), ["2", "6", "0"]);
+  let gap_fill = FixMessage::new(msg_type::SEQUENCE_RESET).with(tags::GAP_FILL_FLAG, "Y").with(tags::NEW_SEQ_NO, 9);
+  client.send_as(gap_fill, 6).await;
+  client.seq = 9;
+  client.send(FixMessage::new(msg_type::TEST_REQUEST).with(tags::TEST_REQ_ID, "after-gap")).await;
+  let heartbeat = client.recv().await;
+  assert_eq!(fields(&heartbeat, &[tags::MSG_TYPE, tags::TEST_REQ_ID]), ["0", "after-gap"]);
+  
+  // A sequence number below the expected one ends the session
+  client.send_as(FixMessage::new(msg_type::HEARTBEAT), 3).await;
+  assert_eq!(client.recv().await.msg_type(), msg_type::LOGOUT);
+  assert!(client.is_closed().await);
+  
+  // A new gateway resumes from the stored sequence numbers
+  let addr = start(&dir).await;
+  let mut client = FixClient::connect(addr, "FUND1", 10).await;
+  let logon = client.logon("key-1").await;
+  assert_eq!(logon.get(tags::MSG_SEQ_NUM), Some("8"));
+  
+  let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn session_store_is_bounded_and_refuses_corrupt_sequence_numbers() {
+  let dir = store_dir();
+  let mut store = SessionStore::open(&dir, "FUND1").unwrap();
+  for seq in 1..=MAX_STORED_MESSAGES as u64 + 1 {
+    store.store_outgoing(seq, b"8=FIX.4.4").unwrap();
+  }
+  
+  // The older half is dropped, in memory and on disk
+  let last = MAX_STORED_MESSAGES as u64 + 1;
+  let kept = store.outgoing(1, last);
+  assert_eq!(kept.len(), MAX_STORED_MESSAGES / 2);
+  assert_eq!(kept.last().unwrap().0, last);
+  let store = SessionStore::open(&dir, "FUND1").unwrap();
+  assert_eq!(store.outgoing(1, last).len(), MAX_STORED_MESSAGES / 2);
+  assert_eq!(store.next_out(), last + 1);
+  
+  // A damaged sequence number file is an error, not a silent reset to 1
+  std::fs::write(dir.join("FUND1.seqnums"), "100").unwrap();
+  assert!(SessionStore::open(&dir, "FUND1").is_err());
+  
+  let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn engine_rejects_are_reported() {
+  let addr = start(&store_dir()).await;
+  let mut buyer = FixClient::connect(addr, "FUND1", 1).await;
+  let mut seller = FixClient::connect(addr, "FUND2", 1).await;
+  buyer.logon("key-1").await;
+  seller.logon("key-2").await;
+  
+  // A trade at 100 puts the band at 90 to 110
+  buyer.send(new_order("b1", "1", "100", "5")).await;
+  assert_eq!(buyer.recv().await.get(tags::EXEC_TYPE), Some("0"));
+  seller.send(new_order("s1", "2", "100", "5")).await;
+  assert_eq!(seller.recv().await.get(tags::EXEC_TYPE), Some("0"));
+  assert_eq!(seller.recv().await.get(tags::EXEC_TYPE), Some("F"));
+  assert_eq!(buyer.recv().await.get(tags::EXEC_TYPE), Some("F"));
+  
+  // Accepted by the gateway, rejected by the engine
+  buyer.send(new_order("b2", "1", "200", "5")).await;
+  assert_eq!(buyer.recv().await.get(tags::EXEC_TYPE), Some("0"));
+  let report = buyer.recv().await;
+  assert_eq!(fields(&report, &[tags::EXEC_TYPE, tags::ORD_STATUS, tags::CL_ORD_ID, tags::LEAVES_QTY, tags::ORD_REJ_REASON]), ["8", "8", "b2", "0", "99"]);
+  assert!(report.get(tags::TEXT).unwrap().starts_with("PRICE_OUTSIDE_BAND"));
+  
+  // The original order is gone when its replacement is rejected
+  buyer.send(new_order("b3", "1", "100", "5")).await;
+  assert_eq!(buyer.recv().await.get(tags::EXEC_TYPE), Some("0"));
+  let replace = FixMessage::new(msg_type::ORDER_CANCEL_REPLACE_REQUEST)
+    .with(tags::ORIG_CL_ORD_ID, "b3")
+    .with(tags::CL_ORD_ID, "b4")
+    .with(tags::PRICE, "200")
+    .with(tags::ORDER_QTY, "5");
+  buyer.send(replace).await;
+  assert_eq!(buyer.recv().await.get(tags::EXEC_TYPE), Some("E"));
+  assert_eq!(fields(&buyer.recv().await, &[tags::EXEC_TYPE, tags::ORD_STATUS, tags::CL_ORD_ID]), ["8", "8", "b4"]);
+  buyer.send(cancel("b4", "b5")).await;
+  assert_eq!(fields(&buyer.recv().await, &[tags::MSG_TYPE, tags::CXL_REJ_REASON]), ["9", "0"]);
+}
+
+#[tokio::test]
+async fn sessions_are_bound_to_their_account() {
+  let addr = start(&store_dir()).await;
+  
+  // Another account's key, and a SenderCompID that is no file name
+  let mut client = FixClient::connect(addr, "FUND2", 1).await;
+  assert_eq!(client.logon("key-1").await.msg_type(), msg_type::LOGOUT);
+  assert!(client.is_closed().await);
+  let mut client = FixClient::connect(addr, "../FUND1", 1).await;
+  assert_eq!(client.logon("key-1").await.msg_type(), msg_type::LOGOUT);
+  assert!(client.is_closed().await);
+  let mut client = FixClient::connect(addr, "FUND3", 1).await;
+  assert_eq!(client.logon("key-1").await.msg_type(), msg_type::LOGOUT);
+  assert!(client.is_closed().await);
+  
+  let mut client = FixClient::connect(addr, "FUND1", 1).await;
+  assert_eq!(client.logon("key-1").await.msg_type(), msg_type::LOGON);
+}
+
+#[tokio::test]
+async fn reports_while_logged_out_are_resent() {
+  let dir = store_dir();
+  let addr = start(&dir).await;
+  let mut buyer = FixClient::connect(addr, "FUND1", 1).await;
+  let mut seller = FixClient::connect(addr, "FUND2", 1).await;
+  buyer.logon("key-1").await;
+  seller.logon("key-2").await;
+  
+  buyer.send(new_order("b1", "1", "100", "10")).await;
+  assert_eq!(buyer.recv().await.get(tags::MSG_SEQ_NUM), Some("2"));
+  buyer.send(FixMessage::new(msg_type::LOGOUT)).await;
+  assert_eq!(buyer.recv().await.msg_type(), msg_type::LOGOUT);
+  assert!(buyer.is_closed().await);
+  
+  // Filled while logged out
+  seller.send(new_order("s1", "2", "100", "4")).await;
+  assert_eq!(seller.recv().await.get(tags::EXEC_TYPE), Some("0"));
+  assert_eq!(seller.recv().await.get(tags::EXEC_TYPE), Some("F"));
+  tokio::time::sleep(Duration::from_millis(100)).await;
+  
+  // The fill took sequence number 4, after the logout
+  let mut buyer = FixClient::connect(addr, "FUND1", 4).await;
+  assert_eq!(buyer.logon("key-1").await.get(tags::MSG_SEQ_NUM), Some("5"));
+  buyer.send(FixMessage::new(msg_type::RESEND_REQUEST).with(tags::BEGIN_SEQ_NO, 4).with(tags::END_SEQ_NO, 4)).await;
+  let resent = buyer.recv().await;
+  assert_eq!(fields(&resent, &[tags::MSG_TYPE, tags::MSG_SEQ_NUM, tags::POSS_DUP_FLAG, tags::EXEC_TYPE, tags::CL_ORD_ID, tags::LAST_QTY]), ["8", "4", "Y", "F", "b1", "4"]);
+  
+  let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn unfilled_market_orders_are_cancelled() {
+  let addr = start(&store_dir()).await;
+  let mut buyer = FixClient::connect(addr, "FUND1", 1).await;
+  let mut seller = FixClient::connect(addr, "FUND2", 1).await;
+  buyer.logon("key-1").await;
+  seller.logon("key-2").await;
+  
+  seller.send(new_order("s1", "2", "100", "4")).await;
+  assert_eq!(seller.recv().await.get(tags::EXEC_TYPE), Some("0"));
+  let market = new_order("b1", "1", "0", "10").with(tags::ORD_TYPE, "1");
+  buyer.send(market).await;
+  assert_eq!(buyer.recv().await.get(tags::EXEC_TYPE), Some("0"));
+  let fill = buyer.recv().await;
+  assert_eq!(fields(&fill, &[tags::EXEC_TYPE, tags::ORD_STATUS, tags::CUM_QTY]), ["F", "1", "4"]);
+  let cancelled = buyer.recv().await;
+  assert_eq!(fields(&cancelled, &[tags::EXEC_TYPE, tags::ORD_STATUS, tags::CL_ORD_ID, tags::CUM_QTY, tags::LEAVES_QTY]), ["4", "4", "b1", "4", "0"]);
+}
@@ -0,0 +1,208 @@
+/*
+* filename : instrument_test
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 종목 레지스트리 및 참조 데이터 API 테스트
+**/
+
+use std::sync::Arc;
+use warp::Filter;
+use warp::test::request;
+use warp::http::StatusCode;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use xTraderz::admin::{self, AdminState, AuditLog};
+use xTraderz::instrument::{self, Instrument, InstrumentRegistry, TradingStatus};
+use xTraderz::models::{OrderMessage, Price, Qty};
+use xTraderz::risk::RiskManager;
+
+#[tokio::test]
+async fn test_instrument_reference_api() {
+  let registry = Arc::new(InstrumentRegistry::new());
+  registry.insert(Instrument {
+    tick_size: Price(1000),
+    ..Instrument::new("BTC-KRW")
+  });
+  registry.insert(Instrument::new("ETH-KRW"));
+  
+  let routes = instrument::routes(registry.clone());
+  
+  // 전체 종목 조회
+  let resp = request()
+    .method("GET")
+    .path("/api/v1/instruments")
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  let json: Value = serde_json::from_slice(resp.body()).unwrap();
+  let instruments = json.as_array().unwrap();
+  assert_eq!(instruments.len(), 2);
+  assert_eq!(instruments[0]["symbol"], "BTC-KRW");
+  assert_eq!(instruments[1]["symbol"], "ETH-KRW");
+  
+  // 단일 종목 조회
+  let resp = request()
+    .method("GET")
+    .path("/api/v1/instruments/BTC-KRW")
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  let json: Value = serde_json::from_slice(resp.body()).unwrap();
+  assert_eq!(json["base_asset"], "BTC");
+  assert_eq!(json["quote_asset"], "KRW");
+  assert_eq!(json["tick_size"], 1000);
+  assert_eq!(json["status"], "Trading");
+  
+  // 존재하지 않는 종목
+  let resp = request()
+    .method("GET")
+    .path("/api/v1/instruments/XRP-KRW")
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+  let json: Value = serde_json::from_slice(resp.body()).unwrap();
+  assert_eq!(json["error"], "UNKNOWN_SYMBOL");
+}
+
+#[tokio::test]
+async fn test_instrument_admin_api() {
+  let registry = Arc::new(InstrumentRegistry::new());
+  let (order_tx, mut order_rx) = mpsc::channel(10);
+  let audit = Arc::new(AuditLog::new());
+  let state = AdminState::new(Some("secret".to_string()), order_tx, registry.clone(), Arc::new(RiskManager::new()), audit.clone());
+  let routes = instrument::routes(registry.clone()).or(admin::routes(Arc::new(state)));
+  
+  // 관리자 토큰 없이는 변경 불가
+  let resp = request()
+    .method("PUT")
+    .path("/admin/v1/instruments/XRP-KRW")
+    .json(&serde_json::json!({ "tick_size": 10 }))
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+  assert!(registry.get("XRP-KRW").is_none());
+  
+  // 종목 등록 (생략된 필드는 기본값)
+  let resp = request()
+    .method("PUT")
+    .path("/admin/v1/instruments/XRP-KRW")
+    .header("authorization", "Bearer secret")
+    .json(&serde_json::json!({
+      "base_asset": "XRP",
+      "quote_asset": "KRW",
+      "tick_size": 10,
+      "fees": { "maker_fee_bps": -1, "taker_fee_bps": 7 }
+    }))
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  
+  let instrument = registry.get("XRP-KRW").unwrap();
+  assert_eq!(instrument.symbol, "XRP-KRW");
+  assert_eq!(instrument.tick_size, Price(10));
+  assert_eq!(instrument.lot_size, Qty(1));
+  assert_eq!(instrument.status, TradingStatus::Trading);
+  assert_eq!(instrument.fees.maker_fee_bps, -1);
+  
+  // 거래 상태는 정의 변경으로 바꿀 수 없음
+  let resp = request()
+    .method("PUT")
+    .path("/admin/v1/instruments/XRP-KRW")
+    .header("authorization", "Bearer secret")
+    .json(&serde_json::json!({ "tick_size": 5, "status": "Halted" }))
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+  assert_eq!(registry.get("XRP-KRW").unwrap().tick_size, Price(10));
+  
+  // 호가/수량 단위가 0이거나 범위가 비었거나 소수 자릿수가 너무 큰 정의는 거부
+  for definition in [
+    serde_json::json!({ "tick_size": 0 }),
+    serde_json::json!({ "lot_size": 0 }),
+    serde_json::json!({ "min_price": 100, "max_price": 10 }),
+    serde_json::json!({ "min_quantity": 100, "max_quantity": 10 }),
+    serde_json::json!({ "price_scale": 40 }),
+  ] {
+    let resp = request()
+      .method("PUT")
+      .path("/admin/v1/instruments/XRP-KRW")
+      .header("authorization", "Bearer secret")
+      .json(&definition)
+      .reply(&routes)
+      .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{}", definition);
+  }
+  assert_eq!(registry.get("XRP-KRW").unwrap().tick_size, Price(10));
+  
+  // 서킷 브레이커와 가격 제한폭의 시간 설정은 상한을 넘을 수 없음
+  let resp = request()
+    .method("PUT")
+    .path("/admin/v1/instruments/XRP-KRW")
+    .header("authorization", "Bearer secret")
+    .json(&serde_json::json!({ "tick_size": 5, "circuit_breaker": { "threshold_bps": 500, "halt_secs": u64::MAX } }))
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+  let json: Value = serde_json::from_slice(resp.body()).unwrap();
+  assert_eq!(json["error"], "INVALID_REQUEST");
+  assert_eq!(registry.get("XRP-KRW").unwrap().tick_size, Price(10));
+  
+  // 정의를 교체해도 현재 거래 상태는 유지
+  registry.set_status("XRP-KRW", TradingStatus::Halted);
+  let resp = request()
+    .method("PUT")
+    .path("/admin/v1/instruments/XRP-KRW")
+    .header("authorization", "Bearer secret")
+    .json(&serde_json::json!({ "tick_size": 5 }))
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  let instrument = registry.get("XRP-KRW").unwrap();
+  assert_eq!(instrument.tick_size, Price(5));
+  assert_eq!(instrument.status, TradingStatus::Halted);
+  
+  // 종목 삭제 (남은 주문은 매칭 엔진에서 일괄 취소)
+  let resp = request()
+    .method("DELETE")
+    .path("/admin/v1/instruments/XRP-KRW")
+    .header("authorization", "Bearer secret")
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert!(registry.get("XRP-KRW").is_none());
+  match order_rx.try_recv().unwrap() {
+    OrderMessage::MassCancel(cancel) => assert_eq!(cancel.symbol.as_deref(), Some("XRP-KRW")),
+    other => panic!("unexpected message {:?}", other),
+  }
+  
+  // 매칭 엔진이 멈춰 있으면 삭제하지 않음
+  registry.define(Instrument::new("XRP-KRW")).unwrap();
+  drop(order_rx);
+  let resp = request()
+    .method("DELETE")
+    .path("/admin/v1/instruments/XRP-KRW")
+    .header("authorization", "Bearer secret")
+    .reply(&routes)
+    .await;
+  assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+  assert!(registry.get("XRP-KRW").is_some());
+  
+  let actions: Vec<(String, String)> = audit.entries().into_iter().map(|e| (e.action, e.outcome)).collect();
+  assert_eq!(actions[0], ("define_instrument".to_string(), "unauthorized".to_string()));
+  assert!(actions.contains(&("delete_instrument".to_string(), "applied".to_string())));
+  assert_eq!(actions.last().unwrap(), &("delete_instrument".to_string(), "engine unavailable".to_string()));
+}
+
+#[test]
+fn test_load_instruments_from_file() {
+  let registry = InstrumentRegistry::load_from_file(
+    concat!(env!("CARGO_MANIFEST_DIR"), "/config/instruments.json")
+  ).expect("설정 파일 로드 실패");
+  
+  let btc = registry.get("BTC-KRW").expect("BTC-KRW 없음");
+  assert_eq!(btc.base_asset, "BTC");
+  assert_eq!(btc.fees.taker_fee_bps, 10);
+  
+  assert!(InstrumentRegistry::load_from_file("does/not/exist.json").is_err());
+}
\ No newline at end of file
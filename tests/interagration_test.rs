@@ -1,4 +1,4 @@
-/**
+/*
 * filename : interagration_test
 * author : HAMA
 * date: 2025. 4. 10.
@@ -11,22 +11,39 @@ mod integration_tests {
   use tokio::sync::mpsc;
   use std::sync::Arc;
   use std::time::Duration;
-  use xTraderz::models::{Order, OrderMessage, Side, OrderType, OrderStatus, Execution};
-  use xTraderz::sequencer;
-  use xTraderz::order_manager;
+  use xTraderz::auth::AccountRegistry;
+  use xTraderz::models::{Order, OrderMessage, Side, OrderType, OrderStatus, Price, Qty};
+  use xTraderz::sequencer::{self, SequencerOutputs};
+  use xTraderz::instrument::{Instrument, InstrumentRegistry};
+  use xTraderz::order_manager::{self, OrderApi};
+  use xTraderz::risk::RiskManager;
+  use xTraderz::throttle::{RateLimit, Throttle, ThrottleConfig};
   use chrono::Utc;
   use warp::test::request;
   
+  // Accounts acc1 to acc5 with the API keys key-1 to key-5
+  fn accounts() -> Arc<AccountRegistry> {
+    let accounts = Arc::new(AccountRegistry::new());
+    for i in 1..=5 {
+      accounts.insert(&format!("acc{}", i), &format!("key-{}", i));
+    }
+    accounts
+  }
+  
   #[tokio::test]
   async fn integration_order_execution_flow() {
     // Setup channels and store
     let (order_tx, order_rx) = mpsc::channel(100);
     let (exec_tx, mut exec_rx) = mpsc::channel(100);
+    let (md_tx, _md_rx) = mpsc::channel(100);
     let exec_store = Arc::new(tokio::sync::Mutex::new(Vec::new()));
     let store_clone = exec_store.clone();
+    let instruments = Arc::new(InstrumentRegistry::new());
+    instruments.insert(Instrument::new("TST"));
+    let instruments_for_engine = instruments.clone();
     
     // Spawn sequencer and persistence
-    tokio::spawn(async move { sequencer::run(order_rx, exec_tx).await; });
+    tokio::spawn(async move { sequencer::run(order_rx, SequencerOutputs::new(exec_tx, md_tx), instruments_for_engine).await; });
     tokio::spawn(async move {
       while let Some(exec) = exec_rx.recv().await {
         store_clone.lock().await.push(exec);
@@ -37,19 +54,20 @@ mod integration_tests {
     let sell = Order {
       order_id: "sell1".into(),
       symbol: "TST".into(),
-      price: 100,
-      quantity: 10,
+      account: String::new(),
+      price: Price(100),
+      quantity: Qty(10),
       side: Side::Sell,
       order_type: OrderType::Limit,
       status: OrderStatus::New,
-      filled_quantity: 0,
-      remain_quantity: 10,
+      filled_quantity: Qty(0),
+      remain_quantity: Qty(10),
       entry_time: Utc::now()
     };
-    order_tx.send(OrderMessage(sell)).await.unwrap();
+    order_tx.send(OrderMessage::Order(sell)).await.unwrap();
     
     // Build API
-    let api = order_manager::routes(order_tx.clone(), exec_store.clone());
+    let api = order_manager::routes(OrderApi::new(order_tx.clone(), exec_store.clone(), instruments.clone(), accounts()));
     
     // Send buy order via HTTP POST
     let buy_req = serde_json::json!({
@@ -62,6 +80,7 @@ mod integration_tests {
     let resp = request()
       .method("POST")
       .path("/v1/order")
+      .header("x-api-key", "key-1")
       .json(&buy_req)
       .reply(&api)
       .await;
@@ -71,15 +90,654 @@ mod integration_tests {
     tokio::time::sleep(Duration::from_millis(100)).await;
     
     // Retrieve executions via HTTP GET
+    let executions = |path: &str, api_key: &str| request().method("GET").path(path).header("x-api-key", api_key);
+    let resp = executions("/v1/execution?symbol=TST", "key-1").reply(&api).await;
+    let buyer: Vec<serde_json::Value> = serde_json::from_slice(resp.body()).unwrap();
+    
+    // Only the account's own side of the trade is returned
+    assert_eq!(exec_store.lock().await.len(), 2);
+    assert_eq!(buyer.len(), 1);
+    assert_eq!((buyer[0]["quantity"].as_str(), buyer[0]["side"].as_str()), (Some("5"), Some("Buy")));
+    let resp = executions("/v1/execution?symbol=TST", "key-2").reply(&api).await;
+    assert_eq!(serde_json::from_slice::<Vec<serde_json::Value>>(resp.body()).unwrap().len(), 0);
+    let resp = request().method("GET").path("/v1/execution?symbol=TST").reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::UNAUTHORIZED);
+    
+    // Time range filters
+    let resp = executions("/v1/execution?start_time=2000-01-01T00:00:00Z&end_time=2100-01-01T00:00:00Z", "key-1").reply(&api).await;
+    assert_eq!(serde_json::from_slice::<Vec<serde_json::Value>>(resp.body()).unwrap().len(), 1);
+    let resp = executions("/v1/execution?start_time=2100-01-01T00:00:00Z", "key-1").reply(&api).await;
+    assert_eq!(serde_json::from_slice::<Vec<serde_json::Value>>(resp.body()).unwrap().len(), 0);
+    let resp = executions("/v1/execution?start_time=yesterday", "key-1").reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::BAD_REQUEST);
+  }
+  
+  #[tokio::test]
+  async fn integration_rejects_invalid_order() {
+    let (order_tx, mut order_rx) = mpsc::channel(100);
+    let exec_store = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+    let instruments = Arc::new(InstrumentRegistry::new());
+    instruments.insert(Instrument {
+      tick_size: Price(100),
+      ..Instrument::new("TST")
+    });
+    
+    let api = order_manager::routes(OrderApi::new(order_tx, exec_store, instruments, accounts()));
+    
+    // Zero quantity orders are rejected
+    let resp = request()
+      .method("POST")
+      .path("/v1/order")
+      .header("x-api-key", "key-1")
+      .json(&serde_json::json!({
+        "symbol": "TST",
+        "side": "Buy",
+        "price": 100,
+        "order_type": "Limit",
+        "quantity": 0
+      }))
+      .reply(&api)
+      .await;
+    assert_eq!(resp.status(), warp::http::StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(body["error"], "ZERO_QUANTITY");
+    
+    // Symbols must be registered
+    let resp = request()
+      .method("POST")
+      .path("/v1/order")
+      .header("x-api-key", "key-1")
+      .json(&serde_json::json!({
+        "symbol": "OTHER",
+        "side": "Buy",
+        "price": 100,
+        "order_type": "Limit",
+        "quantity": 1
+      }))
+      .reply(&api)
+      .await;
+    assert_eq!(resp.status(), warp::http::StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(body["error"], "UNKNOWN_SYMBOL");
+    
+    // Prices off the instrument's tick grid are rejected
+    let resp = request()
+      .method("POST")
+      .path("/v1/order")
+      .header("x-api-key", "key-1")
+      .json(&serde_json::json!({
+        "symbol": "TST",
+        "side": "Buy",
+        "price": 150,
+        "order_type": "Limit",
+        "quantity": 1
+      }))
+      .reply(&api)
+      .await;
+    assert_eq!(resp.status(), warp::http::StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(body["error"], "INVALID_TICK_SIZE");
+    
+    // Nothing reaches the sequencer
+    assert!(order_rx.try_recv().is_err());
+  }
+  
+  #[tokio::test]
+  async fn integration_decimal_prices_and_quantities() {
+    let (order_tx, mut order_rx) = mpsc::channel(100);
+    let exec_store = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+    let instruments = Arc::new(InstrumentRegistry::new());
+    instruments.insert(Instrument {
+      price_scale: 2,
+      quantity_scale: 8,
+      ..Instrument::new("BTC-KRW")
+    });
+    
+    let api = order_manager::routes(OrderApi::new(order_tx, exec_store, instruments, accounts()));
+    
+    // Decimal strings and JSON numbers are both converted with the instrument's scales
+    let resp = request()
+      .method("POST")
+      .path("/v1/order")
+      .header("x-api-key", "key-1")
+      .json(&serde_json::json!({
+        "symbol": "BTC-KRW",
+        "side": "Buy",
+        "price": "12345.67",
+        "order_type": "Limit",
+        "quantity": 0.001
+      }))
+      .reply(&api)
+      .await;
+    assert_eq!(resp.status(), warp::http::StatusCode::CREATED);
+    let body: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(body["price"], "12345.67");
+    assert_eq!(body["quantity"], "0.001");
+    
+    let order = match order_rx.try_recv().unwrap() {
+      OrderMessage::Order(order) => order,
+      other => panic!("unexpected message {:?}", other),
+    };
+    assert_eq!(order.price, Price(1234567));
+    assert_eq!(order.quantity, Qty(100000));
+    
+    // More decimal places than the instrument supports
+    let resp = request()
+      .method("POST")
+      .path("/v1/order")
+      .header("x-api-key", "key-1")
+      .json(&serde_json::json!({
+        "symbol": "BTC-KRW",
+        "side": "Buy",
+        "price": "12345.678",
+        "order_type": "Limit",
+        "quantity": "1"
+      }))
+      .reply(&api)
+      .await;
+    assert_eq!(resp.status(), warp::http::StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(body["error"], "INVALID_PRICE");
+  }
+  #[tokio::test]
+  async fn integration_client_order_ids() {
+    let (order_tx, mut order_rx) = mpsc::channel(100);
+    let exec_store = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+    let instruments = Arc::new(InstrumentRegistry::new());
+    instruments.insert(Instrument::new("TST"));
+    let orders = Arc::new(std::sync::Mutex::new(order_manager::OrderStore::new()));
+    let api = order_manager::routes(OrderApi { orders: orders.clone(), ..OrderApi::new(order_tx, exec_store, instruments, accounts()) });
+    let new_order = serde_json::json!({
+      "symbol": "TST",
+      "side": "Buy",
+      "price": 100,
+      "order_type": "Limit",
+      "quantity": 10,
+      "client_order_id": "c1"
+    });
+    
+    // A resubmission returns the original order without entering a second one
+    let post = |api_key: &str| request().method("POST").path("/v1/order").header("x-api-key", api_key).json(&new_order);
+    let resp = post("key-1").reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::CREATED);
+    let original: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(original["client_order_id"], "c1");
+    let resp = post("key-1").reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::OK);
+    let duplicate: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(duplicate["order_id"], original["order_id"]);
+    assert!(matches!(order_rx.try_recv(), Ok(OrderMessage::Order(_))));
+    assert!(order_rx.try_recv().is_err());
+    
+    // Client order ids are unique per account, and the account is the API key's
+    let unauthenticated = request().method("POST").path("/v1/order").json(&new_order).reply(&api).await;
+    assert_eq!(unauthenticated.status(), warp::http::StatusCode::UNAUTHORIZED);
+    let resp = post("key-2").reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::CREATED);
+    order_rx.try_recv().unwrap();
+    
+    // Queries reflect executions
+    let order_id = original["order_id"].as_str().unwrap().to_string();
+    orders.lock().unwrap().on_execution(&xTraderz::models::Execution {
+      exec_id: "e1".into(),
+      order_id: order_id.clone(),
+      trade_id: "t1".into(),
+      account: "acc1".into(),
+      symbol: "TST".into(),
+      side: Side::Buy,
+      price: Price(100),
+      quantity: Qty(4),
+      is_maker: true,
+      fee: 0,
+      transaction_time: Utc::now(),
+    });
+    let get = |path: &str, api_key: &str| request().method("GET").path(path).header("x-api-key", api_key);
+    let resp = get("/v1/order?client_order_id=c1", "key-1").reply(&api).await;
+    let order: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!((order["order_id"].as_str(), order["status"].as_str()), (Some(order_id.as_str()), Some("PartiallyFilled")));
+    assert_eq!((order["filled_quantity"].as_str(), order["remain_quantity"].as_str()), (Some("4"), Some("6")));
+    
+    // Other accounts can neither see nor cancel the order
+    let resp = get(&format!("/v1/order?order_id={}", order_id), "key-2").reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::NOT_FOUND);
+    let cancel = |body: serde_json::Value, api_key: &str| request().method("POST").path("/v1/order/cancel").header("x-api-key", api_key).json(&body);
+    let resp = cancel(serde_json::json!({ "order_id": order_id }), "key-2").reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::NOT_FOUND);
+    assert!(order_rx.try_recv().is_err());
+    
+    // Cancel by client order id
+    let resp = cancel(serde_json::json!({ "client_order_id": "c1" }), "key-1").reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::OK);
+    let cancelled: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!((cancelled["order_id"].as_str(), cancelled["price"].as_str()), (Some(order_id.as_str()), Some("100")));
+    assert_eq!((cancelled["status"].as_str(), cancelled["filled_quantity"].as_str()), (Some("Cancelled"), Some("4")));
+    match order_rx.try_recv().unwrap() {
+      OrderMessage::Order(cancel) => assert_eq!((cancel.order_id, cancel.status, cancel.account), (order_id.clone(), OrderStatus::Cancelled, "acc1".to_string())),
+      other => panic!("unexpected message {:?}", other),
+    }
+    let resp = get(&format!("/v1/order?order_id={}", order_id), "key-1").reply(&api).await;
+    let order: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!((order["status"].as_str(), order["remain_quantity"].as_str()), (Some("Cancelled"), Some("0")));
+    
+    let resp = get("/v1/order?client_order_id=c1", "key-3").reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::NOT_FOUND);
+    let resp = cancel(serde_json::json!({ "client_order_id": "c1" }), "key-3").reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::NOT_FOUND);
+    assert!(order_rx.try_recv().is_err());
+  }
+  #[tokio::test]
+  async fn integration_order_batches() {
+    let (order_tx, mut order_rx) = mpsc::channel(100);
+    let exec_store = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+    let instruments = Arc::new(InstrumentRegistry::new());
+    instruments.insert(Instrument::new("TST"));
+    let api = order_manager::routes(OrderApi::new(order_tx, exec_store, instruments.clone(), accounts()));
+    let order = |side: &str, price: serde_json::Value, client_order_id: &str| serde_json::json!({
+      "symbol": "TST",
+      "side": side,
+      "price": price,
+      "order_type": "Limit",
+      "quantity": 10,
+      "client_order_id": client_order_id
+    });
+    
+    // With all_or_nothing, one invalid order rejects the batch and nothing is sequenced
+    let batch = serde_json::json!({ "orders": [order("Buy", 100.into(), "b1"), order("Buy", "1.5".into(), "b2")], "all_or_nothing": true });
+    let resp = request().method("POST").path("/v1/orders/batch").header("x-api-key", "key-1").json(&batch).reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(body["error"], "BATCH_REJECTED");
+    assert_eq!((body["results"][0]["error"].as_str(), body["results"][1]["status"].as_str()), (Some("BATCH_REJECTED"), Some("rejected")));
+    assert!(order_rx.try_recv().is_err());
+    
+    // Otherwise every item is answered and the accepted orders are sequenced as one message
+    let batch = serde_json::json!({ "orders": [order("Buy", 100.into(), "b1"), order("Buy", "1.5".into(), "b2"), order("Sell", 100.into(), "s1"), order("Buy", 100.into(), "b1")] });
+    let resp = request().method("POST").path("/v1/orders/batch").header("x-api-key", "key-1").json(&batch).reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::OK);
+    let body: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    let statuses: Vec<&str> = body["results"].as_array().unwrap().iter().map(|r| r["status"].as_str().unwrap()).collect();
+    assert_eq!(statuses, ["accepted", "rejected", "accepted", "duplicate"]);
+    assert_eq!(body["results"][3]["order"]["order_id"], body["results"][0]["order"]["order_id"]);
+    let message = match order_rx.try_recv().unwrap() {
+      message @ OrderMessage::Batch(_) => message,
+      other => panic!("unexpected message {:?}", other),
+    };
+    assert!(order_rx.try_recv().is_err());
+    
+    // The engine handles the batch as a unit
+    let mut engine = xTraderz::matching_engine::MatchingEngine::new(instruments);
+    let output = engine.handle(message);
+    assert_eq!(output.executions.len(), 2);
+    
+    let cancels = serde_json::json!({ "cancels": [{ "client_order_id": "b1" }, { "client_order_id": "zz" }] });
+    let resp = request().method("POST").path("/v1/orders/cancel-batch").header("x-api-key", "key-1").json(&cancels).reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::OK);
+    let body: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!((body["results"][0]["status"].as_str(), body["results"][1]["error"].as_str()), (Some("accepted"), Some("UNKNOWN_ORDER")));
+    assert_eq!(body["results"][0]["order"]["price"], "100");
+    match order_rx.try_recv().unwrap() {
+      OrderMessage::Batch(messages) => assert_eq!(messages.len(), 1),
+      other => panic!("unexpected message {:?}", other),
+    }
+  }
+  #[tokio::test]
+  async fn integration_pre_trade_risk_limits() {
+    let (order_tx, mut order_rx) = mpsc::channel(100);
+    let exec_store = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+    let instruments = Arc::new(InstrumentRegistry::new());
+    instruments.insert(Instrument::new("TST"));
+    let risk = Arc::new(RiskManager::new());
+    let (admin_tx, _admin_rx) = mpsc::channel(10);
+    let admin_state = xTraderz::admin::AdminState::new(
+      Some("secret".to_string()),
+      admin_tx,
+      instruments.clone(),
+      risk.clone(),
+      Arc::new(xTraderz::admin::AuditLog::new())
+    );
+    let api = order_manager::routes(OrderApi { risk: risk.clone(), ..OrderApi::new(order_tx, exec_store, instruments, accounts()) })
+      .or(xTraderz::admin::routes(Arc::new(admin_state)));
+    let new_order = |quantity: u64| serde_json::json!({
+      "symbol": "TST",
+      "side": "Buy",
+      "price": 100,
+      "order_type": "Limit",
+      "quantity": quantity
+    });
+    
+    // Limits are changed at runtime
+    let resp = request()
+      .method("PUT")
+      .path("/admin/v1/risk/limits/acc1")
+      .header("authorization", "Bearer secret")
+      .json(&serde_json::json!({ "max_order_notional": 1000, "max_open_orders_per_symbol": 1 }))
+      .reply(&api)
+      .await;
+    assert_eq!(resp.status(), warp::http::StatusCode::OK);
+    
+    let resp = request().method("POST").path("/v1/order").header("x-api-key", "key-1").json(&new_order(11)).reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(body["error"], "RISK_MAX_ORDER_NOTIONAL");
+    
+    let resp = request().method("POST").path("/v1/order").header("x-api-key", "key-1").json(&new_order(10)).reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::CREATED);
+    let order: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    order_rx.try_recv().unwrap();
+    let resp = request().method("POST").path("/v1/order").header("x-api-key", "key-1").json(&new_order(10)).reply(&api).await;
+    let body: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(body["error"], "RISK_MAX_OPEN_ORDERS");
+    
+    // A cancelled order counts as open until the engine has removed it
+    let resp = request()
+      .method("POST")
+      .path("/v1/order/cancel")
+      .header("x-api-key", "key-1")
+      .json(&serde_json::json!({ "order_id": order["order_id"] }))
+      .reply(&api)
+      .await;
+    assert_eq!(resp.status(), warp::http::StatusCode::OK);
+    let resp = request().method("POST").path("/v1/order").header("x-api-key", "key-1").json(&new_order(10)).reply(&api).await;
+    let body: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(body["error"], "RISK_MAX_OPEN_ORDERS");
+    risk.on_outcome(&xTraderz::matching_engine::OrderOutcome::Cancelled {
+      order_id: order["order_id"].as_str().unwrap().to_string(),
+      account: "acc1".into(),
+      symbol: "TST".into(),
+      quantity: Qty(10),
+      reason: xTraderz::matching_engine::CancelReason::Requested,
+    });
+    let resp = request().method("POST").path("/v1/order").header("x-api-key", "key-1").json(&new_order(10)).reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::CREATED);
+    
+    // Other accounts use the default limits
     let resp = request()
       .method("GET")
-      .path("/v1/execution?symbol=TST")
+      .path("/admin/v1/risk/limits/acc2")
+      .header("authorization", "Bearer secret")
       .reply(&api)
       .await;
-    let executions: Vec<Execution> = serde_json::from_slice(resp.body()).unwrap();
+    let body: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(body["limits"]["max_order_notional"], 0);
+  }
+  #[tokio::test]
+  async fn integration_rate_limits_and_overload() {
+    let (order_tx, order_rx) = mpsc::channel(8);
+    let exec_store = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+    let instruments = Arc::new(InstrumentRegistry::new());
+    instruments.insert(Instrument::new("TST"));
+    let throttle = Arc::new(Throttle::new(ThrottleConfig {
+      per_api_key: RateLimit { per_second: 1, burst: 2 },
+      per_ip: RateLimit { per_second: 1, burst: 3 },
+      overload_queue_pct: 50,
+      overload_retry_after_secs: 2,
+    }));
+    let api = order_manager::routes(OrderApi { throttle, ..OrderApi::new(order_tx, exec_store, instruments, accounts()) });
+    let body = serde_json::json!({ "symbol": "TST", "side": "Buy", "price": 100, "order_type": "Limit", "quantity": 1 });
+    let post = |api_key: &str| request()
+      .method("POST")
+      .path("/v1/order")
+      .header("x-api-key", api_key)
+      .remote_addr("10.0.0.1:5000".parse().unwrap())
+      .json(&body);
+    
+    // Each API key and each IP has its own bucket
+    assert_eq!(post("key-1").reply(&api).await.status(), warp::http::StatusCode::CREATED);
+    assert_eq!(post("key-1").reply(&api).await.status(), warp::http::StatusCode::CREATED);
+    let resp = post("key-1").reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(resp.headers()["retry-after"], "1");
+    assert_eq!(post("key-2").reply(&api).await.status(), warp::http::StatusCode::CREATED);
+    assert_eq!(post("key-3").reply(&api).await.status(), warp::http::StatusCode::TOO_MANY_REQUESTS);
+    
+    // Four queued messages reach the overload threshold
+    let post = |api_key: &str| request().method("POST").path("/v1/order").header("x-api-key", api_key).json(&body);
+    let resp = post("key-4").reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::CREATED);
+    let resp = post("key-4").reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(resp.headers()["retry-after"], "2");
+    let error: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(error["error"], "OVERLOADED");
+    
+    let resp = request().method("GET").path("/v1/metrics").reply(&api).await;
+    let metrics: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(metrics, serde_json::json!({
+      "sequencer_queue_depth": 4,
+      "sequencer_queue_capacity": 8,
+      "rate_limited_requests": 2,
+      "overloaded_requests": 1
+    }));
     
-    // We expect 2 executions - one for each side of the trade
-    assert_eq!(executions.len(), 2);
-    assert_eq!(executions[0].quantity, 5);
+    // A stopped sequencer is reported as such
+    drop(order_rx);
+    let resp = post("key-5").reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::SERVICE_UNAVAILABLE);
+    let error: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(error["error"], "ENGINE_UNAVAILABLE");
+  }
+  #[tokio::test]
+  async fn integration_unsent_cancels_keep_the_order() {
+    let (order_tx, order_rx) = mpsc::channel(8);
+    let exec_store = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+    let instruments = Arc::new(InstrumentRegistry::new());
+    instruments.insert(Instrument::new("TST"));
+    let api = order_manager::routes(OrderApi::new(order_tx, exec_store, instruments, accounts()));
+    let body = serde_json::json!({ "symbol": "TST", "side": "Buy", "price": 100, "order_type": "Limit", "quantity": 1 });
+    let resp = request().method("POST").path("/v1/order").header("x-api-key", "key-1").json(&body).reply(&api).await;
+    let order_id = serde_json::from_slice::<serde_json::Value>(resp.body()).unwrap()["order_id"].as_str().unwrap().to_string();
+    
+    drop(order_rx);
+    let resp = request()
+      .method("POST")
+      .path("/v1/order/cancel")
+      .header("x-api-key", "key-1")
+      .json(&serde_json::json!({ "order_id": order_id }))
+      .reply(&api)
+      .await;
+    assert_eq!(resp.status(), warp::http::StatusCode::SERVICE_UNAVAILABLE);
+    let resp = request()
+      .method("POST")
+      .path("/v1/orders/cancel-batch")
+      .header("x-api-key", "key-1")
+      .json(&serde_json::json!({ "cancels": [{ "order_id": order_id }] }))
+      .reply(&api)
+      .await;
+    assert_eq!(resp.status(), warp::http::StatusCode::SERVICE_UNAVAILABLE);
+    
+    let resp = request().method("GET").path(&format!("/v1/order?order_id={}", order_id)).header("x-api-key", "key-1").reply(&api).await;
+    let order: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!((order["status"].as_str(), order["remain_quantity"].as_str()), (Some("New"), Some("1")));
+  }
+  
+  #[tokio::test]
+  async fn integration_error_responses() {
+    let (order_tx, _order_rx) = mpsc::channel(8);
+    let exec_store = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+    let instruments = Arc::new(InstrumentRegistry::new());
+    instruments.insert(Instrument::new("TST"));
+    let api = order_manager::routes(OrderApi::new(order_tx, exec_store, instruments, accounts()));
+    let error = |resp: warp::http::Response<warp::hyper::body::Bytes>| {
+      let body: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+      (resp.status(), body["error"].as_str().map(str::to_string), body["message"].is_string())
+    };
+    
+    // Malformed bodies and wrong methods get JSON errors instead of warp's plain text
+    let resp = request().method("POST").path("/v1/order").header("x-api-key", "key-1").header("content-type", "application/json").body("{").reply(&api).await;
+    assert_eq!(error(resp), (warp::http::StatusCode::BAD_REQUEST, Some("INVALID_REQUEST".to_string()), true));
+    let resp = request().method("PUT").path("/v1/order").reply(&api).await;
+    assert_eq!(error(resp), (warp::http::StatusCode::METHOD_NOT_ALLOWED, Some("METHOD_NOT_ALLOWED".to_string()), true));
+    
+    let resp = request().method("GET").path("/v1/order?order_id=missing").reply(&api).await;
+    assert_eq!(error(resp), (warp::http::StatusCode::UNAUTHORIZED, Some("UNAUTHORIZED".to_string()), true));
+    let resp = request().method("GET").path("/v1/order?order_id=missing").header("x-api-key", "key-1").reply(&api).await;
+    assert_eq!(error(resp), (warp::http::StatusCode::NOT_FOUND, Some("UNKNOWN_ORDER".to_string()), true));
+    let batch = serde_json::json!({ "orders": [] });
+    let resp = request().method("POST").path("/v1/orders/batch").header("x-api-key", "key-1").json(&batch).reply(&api).await;
+    assert_eq!(error(resp), (warp::http::StatusCode::BAD_REQUEST, Some("BATCH_TOO_LARGE".to_string()), true));
+    
+    // Unknown paths still fall through to the other route groups
+    let resp = request().method("GET").path("/v1/unknown").reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::NOT_FOUND);
+    let fallback = api.recover(xTraderz::error::handle_unmatched);
+    let resp = request().method("GET").path("/v1/unknown").reply(&fallback).await;
+    assert_eq!(error(resp), (warp::http::StatusCode::NOT_FOUND, Some("NOT_FOUND".to_string()), true));
+  }  
+  #[tokio::test]
+  async fn integration_engine_outcomes() {
+    use xTraderz::instrument::OrderValidationError;
+    use xTraderz::matching_engine::{CancelReason, OrderOutcome};
+    
+    let (order_tx, order_rx) = mpsc::channel(100);
+    let (exec_tx, _exec_rx) = mpsc::channel(100);
+    let (md_tx, _md_rx) = mpsc::channel(100);
+    let (outcome_tx, mut outcome_rx) = mpsc::channel(100);
+    let instruments = Arc::new(InstrumentRegistry::new());
+    instruments.insert(Instrument {
+      tick_size: Price(5),
+      ..Instrument::new("TST")
+    });
+    tokio::spawn(sequencer::run(order_rx, SequencerOutputs { outcome_tx, ..SequencerOutputs::new(exec_tx, md_tx) }, instruments));
+    
+    let order = |order_id: &str, symbol: &str, order_type: OrderType, price: u64| Order {
+      order_id: order_id.into(),
+      symbol: symbol.into(),
+      account: "acct".into(),
+      price: Price(price),
+      quantity: Qty(5),
+      side: Side::Buy,
+      order_type,
+      status: OrderStatus::New,
+      filled_quantity: Qty(0),
+      remain_quantity: Qty(5),
+      entry_time: Utc::now()
+    };
+    async fn next(outcome_rx: &mut mpsc::Receiver<OrderOutcome>) -> OrderOutcome {
+      tokio::time::timeout(Duration::from_secs(1), outcome_rx.recv()).await.unwrap().unwrap()
+    }
+    
+    // Orders the engine refuses are reported, not only logged
+    order_tx.send(OrderMessage::Order(order("o1", "OTHER", OrderType::Limit, 100))).await.unwrap();
+    match next(&mut outcome_rx).await {
+      OrderOutcome::Rejected { order_id, account, reason, .. } => {
+        assert_eq!((order_id.as_str(), account.as_str()), ("o1", "acct"));
+        assert_eq!(reason, OrderValidationError::UnknownSymbol("OTHER".into()));
+      }
+      outcome => panic!("unexpected outcome {:?}", outcome),
+    }
+    
+    // The unfilled remainder of a market order is cancelled
+    order_tx.send(OrderMessage::Order(order("o2", "TST", OrderType::Market, 0))).await.unwrap();
+    match next(&mut outcome_rx).await {
+      OrderOutcome::Cancelled { order_id, quantity, reason, .. } => {
+        assert_eq!((order_id.as_str(), quantity, reason), ("o2", Qty(5), CancelReason::Unfilled));
+      }
+      outcome => panic!("unexpected outcome {:?}", outcome),
+    }
+    
+    // A rejected replacement is reported under the order id, the original is gone
+    order_tx.send(OrderMessage::Order(order("o3", "TST", OrderType::Limit, 100))).await.unwrap();
+    order_tx.send(OrderMessage::Amend(xTraderz::models::Amend { order_id: "o3".into(), account: "acct".into(), price: Price(103), quantity: Qty(5) })).await.unwrap();
+    match next(&mut outcome_rx).await {
+      OrderOutcome::Rejected { order_id, reason, replacement, .. } => {
+        assert_eq!((order_id.as_str(), replacement), ("o3", true));
+        assert_eq!(reason.code(), "INVALID_TICK_SIZE");
+      }
+      outcome => panic!("unexpected outcome {:?}", outcome),
+    }
+    let mut cancel = order("o3", "TST", OrderType::Limit, 0);
+    cancel.status = OrderStatus::Cancelled;
+    order_tx.send(OrderMessage::Order(cancel)).await.unwrap();
+    assert!(matches!(next(&mut outcome_rx).await, OrderOutcome::CancelRejected { order_id, .. } if order_id == "o3"));
+    
+    // Only the owning account can cancel a resting order
+    order_tx.send(OrderMessage::Order(order("o4", "TST", OrderType::Limit, 100))).await.unwrap();
+    let mut cancel = order("o4", "TST", OrderType::Limit, 0);
+    cancel.status = OrderStatus::Cancelled;
+    cancel.account = "other".into();
+    order_tx.send(OrderMessage::Order(cancel.clone())).await.unwrap();
+    assert!(matches!(next(&mut outcome_rx).await, OrderOutcome::CancelRejected { order_id, .. } if order_id == "o4"));
+    cancel.account = "acct".into();
+    order_tx.send(OrderMessage::Order(cancel)).await.unwrap();
+    assert!(matches!(next(&mut outcome_rx).await, OrderOutcome::Cancelled { order_id, .. } if order_id == "o4"));
+  }
+  
+  #[tokio::test]
+  async fn integration_engine_reject_reaches_rest() {
+    let (order_tx, order_rx) = mpsc::channel(100);
+    let (exec_tx, _exec_rx) = mpsc::channel(100);
+    let (md_tx, _md_rx) = mpsc::channel(100);
+    let (outcome_tx, mut outcome_rx) = mpsc::channel(100);
+    let exec_store = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+    let instruments = Arc::new(InstrumentRegistry::new());
+    instruments.insert(Instrument::new("TST"));
+    let orders = Arc::new(std::sync::Mutex::new(order_manager::OrderStore::new()));
+    let api = order_manager::routes(OrderApi { orders: orders.clone(), ..OrderApi::new(order_tx, exec_store, instruments.clone(), accounts()) });
+    
+    // Accepted by the REST validation, then halted before the engine sees it
+    let resp = request()
+      .method("POST")
+      .path("/v1/order")
+      .header("x-api-key", "key-1")
+      .json(&serde_json::json!({ "symbol": "TST", "side": "Buy", "price": 100, "order_type": "Limit", "quantity": 1 }))
+      .reply(&api)
+      .await;
+    assert_eq!(resp.status(), warp::http::StatusCode::CREATED);
+    let order_id = serde_json::from_slice::<serde_json::Value>(resp.body()).unwrap()["order_id"].as_str().unwrap().to_string();
+    instruments.set_status("TST", xTraderz::instrument::TradingStatus::Halted);
+    
+    tokio::spawn(sequencer::run(order_rx, SequencerOutputs { outcome_tx, ..SequencerOutputs::new(exec_tx, md_tx) }, instruments));
+    let outcome = tokio::time::timeout(Duration::from_secs(1), outcome_rx.recv()).await.unwrap().unwrap();
+    orders.lock().unwrap().on_outcome(&outcome);
+    
+    let resp = request()
+      .method("GET")
+      .path(&format!("/v1/order?order_id={}", order_id))
+      .header("x-api-key", "key-1")
+      .reply(&api)
+      .await;
+    let body: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(body["status"], "Rejected");
+    assert_eq!(body["reject_reason"], "NOT_TRADING");
+    assert_eq!(body["remain_quantity"], "0");
+  }
+  
+  #[tokio::test]
+  async fn integration_market_order_rest_reads_cancelled() {
+    let (order_tx, order_rx) = mpsc::channel(100);
+    let (exec_tx, mut exec_rx) = mpsc::channel(100);
+    let (md_tx, _md_rx) = mpsc::channel(100);
+    let (outcome_tx, mut outcome_rx) = mpsc::channel(100);
+    let exec_store = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+    let instruments = Arc::new(InstrumentRegistry::new());
+    instruments.insert(Instrument::new("TST"));
+    let orders = Arc::new(std::sync::Mutex::new(order_manager::OrderStore::new()));
+    let api = order_manager::routes(OrderApi { orders: orders.clone(), ..OrderApi::new(order_tx, exec_store, instruments.clone(), accounts()) });
+    tokio::spawn(sequencer::run(order_rx, SequencerOutputs { outcome_tx, ..SequencerOutputs::new(exec_tx, md_tx) }, instruments));
+    
+    // Only 2 of the 5 bought are offered, the rest of the market order is cancelled
+    let post = |api_key: &str, body: serde_json::Value| request().method("POST").path("/v1/order").header("x-api-key", api_key).json(&body);
+    let resp = post("key-2", serde_json::json!({ "symbol": "TST", "side": "Sell", "price": 100, "order_type": "Limit", "quantity": 2 })).reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::CREATED);
+    let resp = post("key-1", serde_json::json!({ "symbol": "TST", "side": "Buy", "price": 0, "order_type": "Market", "quantity": 5 })).reply(&api).await;
+    assert_eq!(resp.status(), warp::http::StatusCode::CREATED);
+    let order_id = serde_json::from_slice::<serde_json::Value>(resp.body()).unwrap()["order_id"].as_str().unwrap().to_string();
+    
+    let outcome = tokio::time::timeout(Duration::from_secs(1), outcome_rx.recv()).await.unwrap().unwrap();
+    assert_eq!(outcome.order_id(), order_id);
+    orders.lock().unwrap().on_outcome(&outcome);
+    while let Ok(execution) = exec_rx.try_recv() {
+      orders.lock().unwrap().on_execution(&execution);
+    }
+    
+    let resp = request()
+      .method("GET")
+      .path(&format!("/v1/order?order_id={}", order_id))
+      .header("x-api-key", "key-1")
+      .reply(&api)
+      .await;
+    let body: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(body["status"], "Cancelled");
+    assert_eq!((body["filled_quantity"].as_str(), body["remain_quantity"].as_str()), (Some("2"), Some("0")));
   }
 }
\ No newline at end of file
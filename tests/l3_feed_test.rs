@@ -0,0 +1,104 @@
+/*
+* filename : l3_feed_test
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 개별 주문 단위(L3) 이벤트와 피드 테스트
+**/
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use serde_json::{json, Value};
+use warp::test::WsClient;
+
+use xTraderz::instrument::Instrument;
+use xTraderz::matching_engine::{BookUpdate, EngineOutput, MatchingEngine, OrderEventKind};
+use xTraderz::models::{Amend, MassCancel, OrderMessage, OrderType, Price, Qty, Side};
+use xTraderz::websocket::l3_feed::{self, ws_l3_route, L3FeedManager};
+use common::{cancel_order, new_order, registry, registry_of};
+
+fn order(order_id: &str, side: Side, price: u64, quantity: u64) -> OrderMessage {
+  let mut order = new_order(order_id.into(), side, OrderType::Limit, price, quantity);
+  order.account = "acc1".into();
+  OrderMessage::Order(order)
+}
+
+fn amend(order_id: &str, price: u64, quantity: u64) -> OrderMessage {
+  OrderMessage::Amend(Amend { order_id: order_id.into(), account: "acc1".into(), price: Price(price), quantity: Qty(quantity) })
+}
+
+fn events(output: &EngineOutput) -> Vec<(OrderEventKind, &str, u64)> {
+  output.order_events.iter().map(|e| (e.kind, e.order_id.as_str(), e.quantity.0)).collect()
+}
+
+// Applies a message and hands its events to the feed, like the engine's book channel
+fn apply(engine: &mut MatchingEngine, feed: &L3FeedManager, message: OrderMessage) {
+  let output = engine.handle(message);
+  feed.update(&BookUpdate { symbol: "SYM".into(), events: output.order_events });
+}
+
+async fn receive(client: &mut WsClient) -> Option<Value> {
+  let message = tokio::time::timeout(Duration::from_millis(500), client.recv()).await.ok()?.ok()?;
+  serde_json::from_str(message.to_str().unwrap()).ok()
+}
+
+#[test]
+fn engine_reports_every_resting_order_change() {
+  use OrderEventKind::*;
+  let mut engine = MatchingEngine::new(registry(&["SYM"]));
+  assert_eq!(events(&engine.handle(order("s1", Side::Sell, 110, 3))), [(Add, "s1", 3)]);
+  assert_eq!(events(&engine.handle(order("s2", Side::Sell, 110, 2))), [(Add, "s2", 2)]);
+  
+  // Makers trade in queue order, the taker's remainder rests afterwards
+  let output = engine.handle(order("b1", Side::Buy, 110, 6));
+  assert_eq!(events(&output), [(Execute, "s1", 3), (Execute, "s2", 2), (Add, "b1", 1)]);
+  assert_eq!(output.order_events[2].side, Side::Buy);
+  
+  engine.handle(order("b2", Side::Buy, 100, 5));
+  assert_eq!(events(&engine.handle(amend("b2", 100, 4))), [(Modify, "b2", 4)]);
+  assert_eq!(events(&engine.handle(amend("b2", 101, 4))), [(Delete, "b2", 4), (Add, "b2", 4)]);
+  
+  assert_eq!(events(&engine.handle(OrderMessage::Order(cancel_order("b1".into())))), [(Delete, "b1", 1)]);
+  
+  let mass_cancel = MassCancel { symbol: Some("SYM".into()), account: None, side: None };
+  assert_eq!(events(&engine.handle(OrderMessage::MassCancel(mass_cancel))), [(Delete, "b2", 4)]);
+}
+
+#[tokio::test]
+async fn feed_streams_anonymized_events_after_a_snapshot() {
+  // Prices and quantities are published at the instrument's scales
+  let instruments = registry_of([Instrument { price_scale: 1, quantity_scale: 2, ..Instrument::new("SYM") }]);
+  let mut engine = MatchingEngine::new(instruments.clone());
+  let feed = Arc::new(L3FeedManager::with_instruments(instruments));
+  apply(&mut engine, &feed, order("s1", Side::Sell, 110, 3));
+  
+  let mut client = warp::test::ws().path("/ws/l3/SYM").handshake(ws_l3_route(feed.clone())).await.unwrap();
+  let snapshot = receive(&mut client).await.unwrap();
+  assert_eq!(snapshot["type"], "l3_snapshot");
+  assert_eq!(snapshot["data"], json!({ "symbol": "SYM", "sequence": 1, "bids": [], "asks": [{ "order_id": 1, "price": "11", "quantity": "0.03" }] }));
+  
+  apply(&mut engine, &feed, order("b1", Side::Buy, 110, 5));
+  let message = receive(&mut client).await.unwrap();
+  assert_eq!(message["type"], "l3");
+  assert_eq!(message["events"], json!([
+    { "sequence": 2, "event": "execute", "order_id": 1, "side": "Sell", "price": "11", "quantity": "0.03" },
+    { "sequence": 3, "event": "add", "order_id": 2, "side": "Buy", "price": "11", "quantity": "0.02" }
+  ]));
+  
+  // A replaced order loses its queue position and gets a new id
+  apply(&mut engine, &feed, order("b2", Side::Buy, 110, 1));
+  apply(&mut engine, &feed, amend("b1", 110, 6));
+  receive(&mut client).await.unwrap();
+  let events = receive(&mut client).await.unwrap()["events"].clone();
+  assert_eq!((events[0]["event"].as_str(), events[0]["order_id"].as_u64()), (Some("delete"), Some(2)));
+  assert_eq!((events[1]["event"].as_str(), events[1]["order_id"].as_u64()), (Some("add"), Some(4)));
+  
+  let response = warp::test::request().path("/api/v1/l3/SYM").reply(&l3_feed::routes(feed)).await;
+  let snapshot: Value = serde_json::from_slice(response.body()).unwrap();
+  assert_eq!(snapshot["sequence"], 6);
+  assert_eq!(snapshot["bids"], json!([
+    { "order_id": 3, "price": "11", "quantity": "0.01" },
+    { "order_id": 4, "price": "11", "quantity": "0.03" }
+  ]));
+}
\ No newline at end of file
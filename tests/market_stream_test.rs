@@ -0,0 +1,120 @@
+/*
+* filename : market_stream_test
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 구독 기반 다중화 시장 데이터 WebSocket 테스트
+**/
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use serde_json::{json, Value};
+use warp::test::WsClient;
+
+use xTraderz::market_data_publisher::publisher::MarketDataPublisher;
+use xTraderz::models::{Execution, Price, Qty, Side};
+use xTraderz::websocket::market_stream::{self, Channel, MarketStreamHub};
+use xTraderz::websocket::public_trades::PublicTradeManager;
+use common::registry;
+
+async fn connect(hub: Arc<MarketStreamHub>) -> WsClient {
+  warp::test::ws().path("/ws").handshake(market_stream::ws_stream_route(hub)).await.unwrap()
+}
+
+async fn receive(client: &mut WsClient) -> Option<Value> {
+  let message = tokio::time::timeout(Duration::from_millis(500), client.recv()).await.ok()?.ok()?;
+  serde_json::from_str(message.to_str().unwrap()).ok()
+}
+
+async fn request(client: &mut WsClient, request: Value) -> Value {
+  client.send_text(request.to_string()).await;
+  receive(client).await.unwrap()
+}
+
+fn execution(symbol: &str, price: u64) -> Execution {
+  Execution {
+    exec_id: uuid::Uuid::new_v4().to_string(),
+    order_id: "o1".into(),
+    trade_id: uuid::Uuid::new_v4().to_string(),
+    account: "acc1".into(),
+    symbol: symbol.into(),
+    side: Side::Buy,
+    price: Price(price),
+    quantity: Qty(2),
+    is_maker: false,
+    fee: 0,
+    transaction_time: Utc::now(),
+  }
+}
+
+#[tokio::test]
+async fn subscriptions_are_acknowledged_and_validated() {
+  let hub = Arc::new(MarketStreamHub::with_limit(registry(&["SYM", "ABC"]), 2));
+  let mut client = connect(hub.clone()).await;
+  
+  let ack = request(&mut client, json!({ "type": "subscribe", "id": 1, "channels": ["trades:SYM", "book:SYM:10", "quotes:SYM"] })).await;
+  assert_eq!(ack, json!({ "type": "subscribed", "id": 1, "channels": ["trades:SYM", "book:SYM:10"] }));
+  let error = receive(&mut client).await.unwrap();
+  assert_eq!((error["id"].as_i64(), error["channel"].as_str(), error["error"].as_str()), (Some(1), Some("quotes:SYM"), Some("UNKNOWN_CHANNEL")));
+  
+  // The limit counts distinct channels per connection
+  let ack = request(&mut client, json!({ "type": "subscribe", "id": 2, "channels": ["trades:SYM", "ticker:ABC", "bbo:NOPE"] })).await;
+  assert_eq!(ack["channels"], json!(["trades:SYM"]));
+  assert_eq!(receive(&mut client).await.unwrap()["error"], "SUBSCRIPTION_LIMIT");
+  assert_eq!(receive(&mut client).await.unwrap()["error"], "UNKNOWN_SYMBOL");
+  
+  let ack = request(&mut client, json!({ "type": "unsubscribe", "id": 3, "channels": ["book:SYM:10", "bbo:SYM"] })).await;
+  assert_eq!(ack, json!({ "type": "unsubscribed", "id": 3, "channels": ["book:SYM:10"] }));
+  assert_eq!(receive(&mut client).await.unwrap()["error"], "NOT_SUBSCRIBED");
+  assert_eq!(hub.subscribed_channels().len(), 1);
+  
+  assert_eq!(request(&mut client, json!({ "type": "ping", "id": 4 })).await, json!({ "type": "pong", "id": 4 }));
+  assert_eq!(request(&mut client, json!({ "channels": [] })).await["error"], "INVALID_REQUEST");
+}
+
+#[tokio::test]
+async fn updates_reach_only_subscribers() {
+  let hub = Arc::new(MarketStreamHub::new(registry(&["SYM", "ABC"])));
+  let trades = PublicTradeManager::with_stream(registry(&["SYM", "ABC"]), hub.clone());
+  let mut sym = connect(hub.clone()).await;
+  let mut abc = connect(hub.clone()).await;
+  request(&mut sym, json!({ "type": "subscribe", "channels": ["trades:SYM", "ticker:SYM"] })).await;
+  request(&mut abc, json!({ "type": "subscribe", "channels": ["trades:ABC"] })).await;
+  
+  trades.publish(&execution("SYM", 100));
+  let update = receive(&mut sym).await.unwrap();
+  assert_eq!((update["type"].as_str(), update["channel"].as_str()), (Some("update"), Some("trades:SYM")));
+  assert_eq!((update["data"]["price"].as_str(), update["data"]["order_id"].as_str()), (Some("100"), None));
+  assert!(receive(&mut abc).await.is_none());
+  
+  // Snapshot channels only send changes, and new subscribers start with the latest one
+  let ticker = Channel::parse("ticker:SYM").unwrap();
+  hub.update(&ticker, json!({ "last_price": 100, "timestamp": 1 }));
+  hub.update(&ticker, json!({ "last_price": 100, "timestamp": 2 }));
+  assert_eq!(receive(&mut sym).await.unwrap()["data"]["last_price"], 100);
+  assert!(receive(&mut sym).await.is_none());
+  
+  let mut late = connect(hub.clone()).await;
+  request(&mut late, json!({ "type": "subscribe", "channels": ["ticker:SYM"] })).await;
+  assert_eq!(receive(&mut late).await.unwrap()["channel"], "ticker:SYM");
+}
+
+#[tokio::test]
+async fn broadcaster_publishes_market_data() {
+  let hub = Arc::new(MarketStreamHub::new(registry(&["SYM", "ABC"])));
+  let mdp = Arc::new(MarketDataPublisher::new());
+  tokio::spawn(market_stream::run_market_stream_broadcaster(hub.clone(), mdp.clone()));
+  let mut client = connect(hub).await;
+  request(&mut client, json!({ "type": "subscribe", "channels": ["ticker:SYM", "kline:SYM:1m"] })).await;
+  
+  mdp.process_execution(&execution("SYM", 105));
+  let mut channels = Vec::new();
+  for _ in 0..2 {
+    let update = receive(&mut client).await.unwrap();
+    channels.push(update["channel"].as_str().unwrap().to_string());
+  }
+  channels.sort();
+  assert_eq!(channels, ["kline:SYM:1m", "ticker:SYM"]);
+}
\ No newline at end of file
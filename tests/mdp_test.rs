@@ -1,10 +1,11 @@
-/**
+/*
 * filename : mdp_test
 * author : HAMA
 * date: 2025. 5. 1.
 * description: 
 **/
 
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::sleep;
 use chrono::Utc;
@@ -12,9 +13,9 @@ use warp::test::request;
 use warp::http::StatusCode;
 use serde_json::Value;
 
-use order_matching_engine::models::{Order, Execution, Side, OrderType, OrderStatus, OrderBook};
-use order_matching_engine::market_data_publisher::publisher::MarketDataPublisher;
-use order_matching_engine::market_data_publisher::models::CandleInterval;
+use xTraderz::models::{Order, Execution, Side, OrderType, OrderStatus, OrderBook, Price, Qty};
+use xTraderz::market_data_publisher::publisher::MarketDataPublisher;
+use xTraderz::instrument::{Instrument, InstrumentRegistry};
 
 #[tokio::test]
 async fn test_mdp_orderbook_api() {
@@ -28,13 +29,14 @@ async fn test_mdp_orderbook_api() {
   let buy_order = Order {
     order_id: "buy_1".to_string(),
     symbol: "BTC-KRW".to_string(),
-    price: 50000000,
-    quantity: 5,
+    account: String::new(),
+    price: Price(50000000),
+    quantity: Qty(5),
     side: Side::Buy,
     order_type: OrderType::Limit,
     status: OrderStatus::New,
-    filled_quantity: 0,
-    remain_quantity: 5,
+    filled_quantity: Qty(0),
+    remain_quantity: Qty(5),
     entry_time: Utc::now(),
   };
   orderbook.insert_order(buy_order);
@@ -43,13 +45,14 @@ async fn test_mdp_orderbook_api() {
   let sell_order = Order {
     order_id: "sell_1".to_string(),
     symbol: "BTC-KRW".to_string(),
-    price: 51000000,
-    quantity: 3,
+    account: String::new(),
+    price: Price(51000000),
+    quantity: Qty(3),
     side: Side::Sell,
     order_type: OrderType::Limit,
     status: OrderStatus::New,
-    filled_quantity: 0,
-    remain_quantity: 3,
+    filled_quantity: Qty(0),
+    remain_quantity: Qty(3),
     entry_time: Utc::now(),
   };
   orderbook.insert_order(sell_order);
@@ -84,11 +87,72 @@ async fn test_mdp_orderbook_api() {
   assert_eq!(bids.len(), 1);
   assert_eq!(asks.len(), 1);
   
-  assert_eq!(bids[0]["price"], 50000000);
-  assert_eq!(bids[0]["volume"], 5);
+  assert_eq!(bids[0]["price"], "50000000");
+  assert_eq!(bids[0]["volume"], "5");
   
-  assert_eq!(asks[0]["price"], 51000000);
-  assert_eq!(asks[0]["volume"], 3);
+  assert_eq!(asks[0]["price"], "51000000");
+  assert_eq!(asks[0]["volume"], "3");
+}
+
+#[tokio::test]
+async fn test_mdp_orderbook_depth_and_group() {
+  // 호가 단위 10인 종목
+  let instruments = Arc::new(InstrumentRegistry::new());
+  let mut instrument = Instrument::new("BTC-KRW");
+  instrument.tick_size = Price(10);
+  instruments.insert(instrument);
+  let mdp = MarketDataPublisher::with_instruments(instruments);
+  
+  // 매수 1000~950 각 1개, 매도 1010 1개, 1020 2개
+  let mut orderbook = OrderBook::new();
+  let mut add = |order_id: &str, side: Side, price: u64, quantity: u64| {
+    orderbook.insert_order(Order {
+      order_id: order_id.to_string(),
+      symbol: "BTC-KRW".to_string(),
+      account: String::new(),
+      price: Price(price),
+      quantity: Qty(quantity),
+      side,
+      order_type: OrderType::Limit,
+      status: OrderStatus::New,
+      filled_quantity: Qty(0),
+      remain_quantity: Qty(quantity),
+      entry_time: Utc::now(),
+    });
+  };
+  for (i, price) in [1000, 990, 980, 970, 960, 950].into_iter().enumerate() {
+    add(&format!("buy_{}", i), Side::Buy, price, 1);
+  }
+  add("sell_1", Side::Sell, 1010, 1);
+  add("sell_2", Side::Sell, 1020, 2);
+  mdp.update_orderbook("BTC-KRW", orderbook);
+  let routes = mdp.routes();
+  
+  let get = |query: &'static str| request().method("GET").path(query).reply(&routes);
+  
+  // 깊이 제한과 누적 수량
+  let resp = get("/api/v1/orderbook/BTC-KRW?depth=5").await;
+  let json: Value = serde_json::from_slice(resp.body()).unwrap();
+  let cumulative: Vec<&str> = json["bids"].as_array().unwrap().iter().map(|level| level["cumulative_volume"].as_str().unwrap()).collect();
+  assert_eq!(cumulative, ["1", "2", "3", "4", "5"]);
+  
+  // 3 호가 단위(30)로 묶기: 매수는 내림, 매도는 올림
+  let resp = get("/api/v1/orderbook/BTC-KRW?depth=full&group=3").await;
+  let json: Value = serde_json::from_slice(resp.body()).unwrap();
+  assert_eq!(json["bids"], serde_json::json!([
+    { "price": "990", "volume": "2", "order_count": 2, "cumulative_volume": "2" },
+    { "price": "960", "volume": "3", "order_count": 3, "cumulative_volume": "5" },
+    { "price": "930", "volume": "1", "order_count": 1, "cumulative_volume": "6" }
+  ]));
+  assert_eq!(json["asks"], serde_json::json!([{ "price": "1020", "volume": "3", "order_count": 2, "cumulative_volume": "3" }]));
+  
+  // 잘못된 파라미터
+  for query in ["/api/v1/orderbook/BTC-KRW?depth=7", "/api/v1/orderbook/BTC-KRW?group=0"] {
+    let resp = get(query).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let json: Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(json["error"], "INVALID_REQUEST");
+  }
 }
 
 #[tokio::test]
@@ -100,16 +164,21 @@ async fn test_mdp_execution_processing() {
   let execution = Execution {
     exec_id: "exec_1".to_string(),
     order_id: "order_1".to_string(),
+    trade_id: "trade_1".to_string(),
+    account: String::new(),
     symbol: "ETH-KRW".to_string(),
     side: Side::Buy,
-    price: 2000000,
-    quantity: 2,
-    fee: 0.05,
+    price: Price(2000000),
+    quantity: Qty(2),
+    is_maker: false,
+    fee: 50,
     transaction_time: Utc::now(),
   };
   
-  // 체결 처리
+  // 체결 처리 (같은 매칭의 메이커 체결과 같은 trade_id의 체결은 한 번만 반영)
   mdp.process_execution(&execution);
+  mdp.process_execution(&Execution { exec_id: "exec_2".to_string(), side: Side::Sell, is_maker: true, ..execution.clone() });
+  mdp.process_execution(&Execution { exec_id: "exec_3".to_string(), side: Side::Sell, ..execution.clone() });
   
   // API 라우트 생성
   let routes = mdp.routes();
@@ -135,8 +204,8 @@ async fn test_mdp_execution_processing() {
   
   let exec = &executions[0];
   assert_eq!(exec["symbol"], "ETH-KRW");
-  assert_eq!(exec["price"], 2000000);
-  assert_eq!(exec["volume"], 2);
+  assert_eq!(exec["price"], "2000000");
+  assert_eq!(exec["volume"], "2");
   
   // 시장 통계 API 요청
   let resp = request()
@@ -154,11 +223,58 @@ async fn test_mdp_execution_processing() {
   
   // 시장 통계 검증
   assert_eq!(json["symbol"], "ETH-KRW");
-  assert_eq!(json["open_price_24h"], 2000000);
-  assert_eq!(json["high_price_24h"], 2000000);
-  assert_eq!(json["low_price_24h"], 2000000);
-  assert_eq!(json["last_price"], 2000000);
-  assert_eq!(json["volume_24h"], 2);
+  assert_eq!(json["open_price_24h"], "2000000");
+  assert_eq!(json["high_price_24h"], "2000000");
+  assert_eq!(json["low_price_24h"], "2000000");
+  assert_eq!(json["last_price"], "2000000");
+  assert_eq!(json["volume_24h"], "2");
+}
+
+#[tokio::test]
+async fn test_mdp_rolling_statistics() {
+  let mdp = MarketDataPublisher::new();
+  let now = Utc::now();
+  let trade = |trade_id: &str, minutes_ago: i64, price: u64, quantity: u64, is_maker: bool| Execution {
+    exec_id: format!("exec_{}", trade_id),
+    order_id: "order_1".to_string(),
+    trade_id: trade_id.to_string(),
+    account: String::new(),
+    symbol: "ETH-KRW".to_string(),
+    side: Side::Buy,
+    price: Price(price),
+    quantity: Qty(quantity),
+    is_maker,
+    fee: 0,
+    transaction_time: now - chrono::Duration::minutes(minutes_ago),
+  };
+  
+  // 25시간 전 체결은 기간 밖이지만 24시간 전 가격이 됨, 메이커 체결은 중복 집계하지 않음
+  mdp.process_execution(&trade("t1", 25 * 60, 100, 5, false));
+  mdp.process_execution(&trade("t2", 120, 110, 2, false));
+  mdp.process_execution(&trade("t2", 120, 110, 2, true));
+  mdp.process_execution(&trade("t3", 60, 90, 1, false));
+  mdp.process_execution(&trade("t4", 0, 120, 1, false));
+  
+  let stats = mdp.get_statistics("ETH-KRW").unwrap();
+  assert_eq!([&stats.open_price_24h, &stats.high_price_24h, &stats.low_price_24h, &stats.last_price], ["100", "120", "90", "120"]);
+  assert_eq!([&stats.volume_24h, &stats.quote_volume_24h], ["4", "430"]);
+  assert_eq!(stats.trade_count_24h, 3);
+  assert_eq!([&stats.vwap_24h, &stats.price_change_24h], ["107", "20"]);
+  assert_eq!(stats.price_change_bps_24h, 2000);
+  
+  // 체결 없이 시간이 지나면 오래된 버킷이 빠짐
+  mdp.refresh_statistics(now + chrono::Duration::minutes(22 * 60 + 30));
+  let stats = mdp.get_statistics("ETH-KRW").unwrap();
+  assert_eq!([&stats.open_price_24h, &stats.high_price_24h, &stats.low_price_24h], ["110", "120", "90"]);
+  assert_eq!([&stats.volume_24h, &stats.vwap_24h], ["2", "105"]);
+  assert_eq!(stats.trade_count_24h, 2);
+  assert_eq!((stats.price_change_24h.as_str(), stats.price_change_bps_24h), ("10", 909));
+  
+  mdp.refresh_statistics(now + chrono::Duration::days(2));
+  let stats = mdp.get_statistics("ETH-KRW").unwrap();
+  assert_eq!([&stats.open_price_24h, &stats.high_price_24h, &stats.low_price_24h, &stats.last_price], ["120", "120", "120", "120"]);
+  assert_eq!([&stats.volume_24h, &stats.vwap_24h, &stats.price_change_24h], ["0", "0", "0"]);
+  assert_eq!(stats.trade_count_24h, 0);
 }
 
 #[tokio::test]
@@ -170,11 +286,14 @@ async fn test_mdp_candlestick() {
   let execution1 = Execution {
     exec_id: "exec_1".to_string(),
     order_id: "order_1".to_string(),
+    trade_id: "trade_1".to_string(),
+    account: String::new(),
     symbol: "BTC-KRW".to_string(),
     side: Side::Buy,
-    price: 50000000,
-    quantity: 1,
-    fee: 0.05,
+    price: Price(50000000),
+    quantity: Qty(1),
+    is_maker: false,
+    fee: 50,
     transaction_time: Utc::now(),
   };
   
@@ -186,15 +305,19 @@ async fn test_mdp_candlestick() {
   let execution2 = Execution {
     exec_id: "exec_2".to_string(),
     order_id: "order_2".to_string(),
+    trade_id: "trade_2".to_string(),
+    account: String::new(),
     symbol: "BTC-KRW".to_string(),
     side: Side::Sell,
-    price: 50100000,
-    quantity: 2,
-    fee: 0.05,
+    price: Price(50100000),
+    quantity: Qty(2),
+    is_maker: false,
+    fee: 50,
     transaction_time: Utc::now(),
   };
   
   mdp.process_execution(&execution2);
+  mdp.process_execution(&Execution { exec_id: "exec_3".to_string(), side: Side::Buy, is_maker: true, ..execution2.clone() });
   
   // API 라우트 생성
   let routes = mdp.routes();
@@ -223,11 +346,11 @@ async fn test_mdp_candlestick() {
   assert_eq!(candle["interval"], "1m");
   
   // OHLCV 데이터 검증
-  assert_eq!(candle["open"], 50000000);
-  assert_eq!(candle["high"], 50100000);
-  assert_eq!(candle["low"], 50000000);
-  assert_eq!(candle["close"], 50100000);
-  assert_eq!(candle["volume"], 3);
+  assert_eq!(candle["open"], "50000000");
+  assert_eq!(candle["high"], "50100000");
+  assert_eq!(candle["low"], "50000000");
+  assert_eq!(candle["close"], "50100000");
+  assert_eq!(candle["volume"], "3");
   assert_eq!(candle["trade_count"], 2);
   
   // 잘못된 간격 요청
@@ -0,0 +1,399 @@
+/*
+* filename : order_entry_test
+* author : HAMA
+* date: 2026. 10. 18.
+* description: WebSocket 거래 세션 주문 입력 (신규, 취소, 정정, 일괄) 및 체결 전달 테스트
+**/
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use tokio::sync::mpsc;
+use serde_json::{json, Value};
+use warp::test::WsClient;
+
+use xTraderz::auth::AccountRegistry;
+use xTraderz::instrument::{Instrument, InstrumentRegistry, OrderValidationError, TradingStatus};
+use xTraderz::matching_engine::{CancelReason, CancelRejectReason, MatchingEngine, OrderOutcome};
+use xTraderz::models::{Amend, Execution, Order, OrderMessage, OrderStatus, OrderType, Price, Qty, Side};
+use xTraderz::risk::{RiskLimits, RiskManager};
+use xTraderz::throttle::{RateLimit, Throttle, ThrottleConfig};
+use xTraderz::websocket::execution_push::{ws_execution_route, ExecutionPushManager};
+use xTraderz::websocket::session::SessionManager;
+use common::new_order;
+
+fn setup() -> (Arc<ExecutionPushManager>, mpsc::Receiver<OrderMessage>) {
+  setup_with_risk(Arc::new(RiskManager::new()))
+}
+
+fn setup_with_risk(risk: Arc<RiskManager>) -> (Arc<ExecutionPushManager>, mpsc::Receiver<OrderMessage>) {
+  setup_with(risk, Arc::new(Throttle::default()))
+}
+
+fn setup_with(risk: Arc<RiskManager>, throttle: Arc<Throttle>) -> (Arc<ExecutionPushManager>, mpsc::Receiver<OrderMessage>) {
+  let accounts = Arc::new(AccountRegistry::new());
+  accounts.insert("mm1", "key-1");
+  let instruments = Arc::new(InstrumentRegistry::new());
+  let mut instrument = Instrument::new("SYM");
+  instrument.tick_size = Price(5);
+  instruments.insert(instrument);
+  
+  let (order_tx, order_rx) = mpsc::channel(10);
+  let sessions = Arc::new(SessionManager::new(accounts, order_tx));
+  (Arc::new(ExecutionPushManager::new(instruments, sessions, risk, throttle)), order_rx)
+}
+
+async fn connect(manager: Arc<ExecutionPushManager>) -> WsClient {
+  let mut client = warp::test::ws()
+    .path("/ws/executions?api_key=key-1")
+    .handshake(ws_execution_route(manager))
+    .await
+    .unwrap();
+  client.recv().await.unwrap(); // Session message
+  client
+}
+
+async fn request(client: &mut WsClient, request: Value) -> Value {
+  client.send_text(request.to_string()).await;
+  receive(client).await
+}
+
+async fn receive(client: &mut WsClient) -> Value {
+  let message = tokio::time::timeout(Duration::from_secs(3), client.recv()).await.unwrap().unwrap();
+  serde_json::from_str(message.to_str().unwrap()).unwrap()
+}
+
+fn new_request(request_id: &str, client_order_id: &str, price: &str) -> Value {
+  json!({ "type": "new", "request_id": request_id, "client_order_id": client_order_id,
+          "symbol": "SYM", "side": "Buy", "order_type": "Limit", "price": price, "quantity": "10" })
+}
+
+fn execution(order_id: &str, quantity: u64) -> Execution {
+  Execution {
+    exec_id: uuid::Uuid::new_v4().to_string(),
+    order_id: order_id.into(),
+    trade_id: uuid::Uuid::new_v4().to_string(),
+    account: "mm1".into(),
+    symbol: "SYM".into(),
+    side: Side::Buy,
+    price: Price(100),
+    quantity: Qty(quantity),
+    is_maker: true,
+    fee: 0,
+    transaction_time: Utc::now(),
+  }
+}
+
+#[tokio::test]
+async fn new_orders_are_acknowledged_or_rejected() {
+  let (manager, mut order_rx) = setup();
+  let mut client = connect(manager).await;
+  
+  let ack = request(&mut client, new_request("r1", "c1", "100")).await;
+  assert_eq!(ack["type"], "ack");
+  assert_eq!(ack["action"], "new");
+  assert_eq!(ack["request_id"], "r1");
+  assert_eq!(ack["client_order_id"], "c1");
+  match order_rx.try_recv().unwrap() {
+    OrderMessage::Order(order) => {
+      assert_eq!(order.order_id, ack["order_id"].as_str().unwrap());
+      assert_eq!(order.account, "mm1");
+      assert_eq!(order.price, Price(100));
+    }
+    other => panic!("unexpected message {:?}", other),
+  }
+  
+  let reject = request(&mut client, new_request("r2", "c1", "100")).await;
+  assert_eq!((reject["type"].as_str(), reject["error"].as_str()), (Some("reject"), Some("DUPLICATE_CLIENT_ORDER_ID")));
+  let reject = request(&mut client, new_request("r3", "c2", "101")).await;
+  assert_eq!((reject["request_id"].as_str(), reject["error"].as_str()), (Some("r3"), Some("INVALID_TICK_SIZE")));
+  let reject = request(&mut client, json!({ "type": "new", "request_id": "r4" })).await;
+  assert_eq!((reject["request_id"].as_str(), reject["error"].as_str()), (Some("r4"), Some("INVALID_REQUEST")));
+  assert!(order_rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn cancels_amends_and_fills_use_client_order_ids() {
+  let (manager, mut order_rx) = setup();
+  let mut client = connect(manager.clone()).await;
+  
+  let ack = request(&mut client, new_request("r1", "c1", "100")).await;
+  let order_id = ack["order_id"].as_str().unwrap().to_string();
+  order_rx.try_recv().unwrap();
+  
+  let ack = request(&mut client, json!({ "type": "amend", "request_id": "r2", "client_order_id": "c1", "price": "105" })).await;
+  assert_eq!((ack["type"].as_str(), ack["action"].as_str()), (Some("ack"), Some("amend")));
+  match order_rx.try_recv().unwrap() {
+    OrderMessage::Amend(amend) => assert_eq!(amend, Amend { order_id: order_id.clone(), account: "mm1".into(), price: Price(105), quantity: Qty(10) }),
+    other => panic!("unexpected message {:?}", other),
+  }
+  
+  // Fills reach the session with the client order id, besides the account's execution stream
+  manager.broadcast_execution(&execution(&order_id, 4)).await;
+  let private = receive(&mut client).await;
+  assert_eq!(private["order_id"], order_id.as_str());
+  let fill = receive(&mut client).await;
+  assert_eq!(fill["type"], "fill");
+  assert_eq!(fill["client_order_id"], "c1");
+  assert_eq!(fill["request_id"], "r1");
+  assert_eq!(fill["execution"]["quantity"], "4");
+  
+  let ack = request(&mut client, json!({ "type": "cancel", "request_id": "r3", "client_order_id": "c1" })).await;
+  assert_eq!((ack["action"].as_str(), ack["order_id"].as_str()), (Some("cancel"), Some(order_id.as_str())));
+  match order_rx.try_recv().unwrap() {
+    OrderMessage::Order(order) => assert_eq!((order.order_id, order.status), (order_id, OrderStatus::Cancelled)),
+    other => panic!("unexpected message {:?}", other),
+  }
+  
+  let reject = request(&mut client, json!({ "type": "cancel", "request_id": "r4", "client_order_id": "nope" })).await;
+  assert_eq!(reject["error"], "UNKNOWN_ORDER");
+}
+
+#[tokio::test]
+async fn engine_rejects_reach_the_session() {
+  let (manager, mut order_rx) = setup();
+  let mut client = connect(manager.clone()).await;
+  
+  let ack = request(&mut client, new_request("r1", "c1", "100")).await;
+  let order_id = ack["order_id"].as_str().unwrap().to_string();
+  order_rx.try_recv().unwrap();
+  
+  // Halted between the gateway's validation and the engine
+  manager.broadcast_outcome(&OrderOutcome::Rejected {
+    order_id: order_id.clone(),
+    account: "mm1".into(),
+    symbol: "SYM".into(),
+    reason: OrderValidationError::NotTrading { symbol: "SYM".into(), status: TradingStatus::Halted },
+    replacement: false,
+  }).await;
+  let reject = receive(&mut client).await;
+  assert_eq!(reject["type"], "reject");
+  assert_eq!((reject["request_id"].as_str(), reject["client_order_id"].as_str()), (Some("r1"), Some("c1")));
+  assert_eq!((reject["order_id"].as_str(), reject["error"].as_str()), (Some(order_id.as_str()), Some("NOT_TRADING")));
+  
+  // The rejected order is no longer followed
+  let reject = request(&mut client, json!({ "type": "cancel", "request_id": "r2", "client_order_id": "c1" })).await;
+  assert_eq!(reject["error"], "UNKNOWN_ORDER");
+}
+
+#[tokio::test]
+async fn session_orders_follow_engine_outcomes() {
+  let (manager, mut order_rx) = setup();
+  let mut client = connect(manager.clone()).await;
+  
+  let ack = request(&mut client, new_request("r1", "c1", "100")).await;
+  let order_id = ack["order_id"].as_str().unwrap().to_string();
+  order_rx.try_recv().unwrap();
+  
+  // An amend the engine refuses leaves the order as it was
+  request(&mut client, json!({ "type": "amend", "request_id": "r2", "client_order_id": "c1", "price": "105" })).await;
+  order_rx.try_recv().unwrap();
+  manager.broadcast_outcome(&OrderOutcome::CancelRejected {
+    order_id: order_id.clone(),
+    account: "mm1".into(),
+    reason: CancelRejectReason::NotTrading(TradingStatus::Halted),
+  }).await;
+  let reject = receive(&mut client).await;
+  assert_eq!((reject["type"].as_str(), reject["error"].as_str()), (Some("reject"), Some("NOT_TRADING")));
+  assert_eq!(reject["request_id"], "r2");
+  request(&mut client, json!({ "type": "amend", "request_id": "r3", "client_order_id": "c1", "quantity": "8" })).await;
+  match order_rx.try_recv().unwrap() {
+    OrderMessage::Amend(amend) => assert_eq!(amend, Amend { order_id: order_id.clone(), account: "mm1".into(), price: Price(100), quantity: Qty(8) }),
+    other => panic!("unexpected message {:?}", other),
+  }
+  
+  // Applied amends are reported and become the order's state
+  manager.broadcast_outcome(&OrderOutcome::Amended {
+    order_id: order_id.clone(),
+    account: "mm1".into(),
+    symbol: "SYM".into(),
+    price: Price(100),
+    quantity: Qty(8),
+    remaining: Qty(8),
+  }).await;
+  let amended = receive(&mut client).await;
+  assert_eq!((amended["type"].as_str(), amended["quantity"].as_str(), amended["remaining"].as_str()), (Some("amended"), Some("8"), Some("8")));
+  assert_eq!(amended["request_id"], "r3");
+  request(&mut client, json!({ "type": "amend", "request_id": "r4", "client_order_id": "c1", "price": "110" })).await;
+  match order_rx.try_recv().unwrap() {
+    OrderMessage::Amend(amend) => assert_eq!(amend, Amend { order_id: order_id.clone(), account: "mm1".into(), price: Price(110), quantity: Qty(8) }),
+    other => panic!("unexpected message {:?}", other),
+  }
+  
+  // Cancelled orders are no longer followed. A mass cancel answers no request of the session.
+  manager.broadcast_outcome(&OrderOutcome::Cancelled {
+    order_id: order_id.clone(),
+    account: "mm1".into(),
+    symbol: "SYM".into(),
+    quantity: Qty(8),
+    reason: CancelReason::MassCancel,
+  }).await;
+  let cancelled = receive(&mut client).await;
+  assert_eq!((cancelled["type"].as_str(), cancelled["reason"].as_str(), cancelled["quantity"].as_str()), (Some("cancelled"), Some("mass_cancel"), Some("8")));
+  assert_eq!(cancelled["request_id"], "r1");
+  let reject = request(&mut client, json!({ "type": "cancel", "request_id": "r5", "client_order_id": "c1" })).await;
+  assert_eq!(reject["error"], "UNKNOWN_ORDER");
+  
+  // The unfilled rest of a market order is cancelled
+  let market = json!({ "type": "new", "request_id": "r6", "client_order_id": "c2", "symbol": "SYM", "side": "Buy", "order_type": "Market", "price": "0", "quantity": "10" });
+  let ack = request(&mut client, market).await;
+  let order_id = ack["order_id"].as_str().unwrap().to_string();
+  order_rx.try_recv().unwrap();
+  manager.broadcast_execution(&execution(&order_id, 4)).await;
+  receive(&mut client).await; // Account execution stream
+  receive(&mut client).await; // Fill
+  manager.broadcast_outcome(&OrderOutcome::Cancelled {
+    order_id,
+    account: "mm1".into(),
+    symbol: "SYM".into(),
+    quantity: Qty(6),
+    reason: CancelReason::Unfilled,
+  }).await;
+  let cancelled = receive(&mut client).await;
+  assert_eq!((cancelled["client_order_id"].as_str(), cancelled["reason"].as_str(), cancelled["quantity"].as_str()), (Some("c2"), Some("unfilled"), Some("6")));
+  let reject = request(&mut client, json!({ "type": "cancel", "request_id": "r7", "client_order_id": "c2" })).await;
+  assert_eq!(reject["error"], "UNKNOWN_ORDER");
+}
+
+#[tokio::test]
+async fn risk_limits_apply_to_new_orders_and_amends() {
+  let risk = Arc::new(RiskManager::with_limits(RiskLimits {
+    max_order_notional: 1000,
+    max_open_orders_per_symbol: 1,
+    ..RiskLimits::default()
+  }));
+  let (manager, mut order_rx) = setup_with_risk(risk.clone());
+  let mut client = connect(manager).await;
+  
+  let ack = request(&mut client, new_request("r1", "c1", "100")).await;
+  assert_eq!(ack["type"], "ack");
+  let order_id = ack["order_id"].as_str().unwrap().to_string();
+  order_rx.try_recv().unwrap();
+  
+  let reject = request(&mut client, new_request("r2", "c2", "100")).await;
+  assert_eq!((reject["type"].as_str(), reject["error"].as_str()), (Some("reject"), Some("RISK_MAX_OPEN_ORDERS")));
+  let reject = request(&mut client, json!({ "type": "amend", "request_id": "r3", "client_order_id": "c1", "quantity": "20" })).await;
+  assert_eq!((reject["request_id"].as_str(), reject["error"].as_str()), (Some("r3"), Some("RISK_MAX_ORDER_NOTIONAL")));
+  assert!(order_rx.try_recv().is_err());
+  
+  // The order stops counting once the engine reports it cancelled
+  risk.on_outcome(&OrderOutcome::Cancelled {
+    order_id,
+    account: "mm1".into(),
+    symbol: "SYM".into(),
+    quantity: Qty(10),
+    reason: CancelReason::Requested,
+  });
+  let ack = request(&mut client, new_request("r4", "c2", "100")).await;
+  assert_eq!(ack["type"], "ack");
+}
+
+#[tokio::test]
+async fn requests_are_rate_limited_and_refused_when_the_queue_is_full() {
+  let throttle = Arc::new(Throttle::new(ThrottleConfig {
+    per_api_key: RateLimit { per_second: 1, burst: 1 },
+    ..ThrottleConfig::default()
+  }));
+  let (manager, mut order_rx) = setup_with(Arc::new(RiskManager::new()), throttle);
+  let mut client = connect(manager).await;
+  assert_eq!(request(&mut client, new_request("r1", "c1", "100")).await["type"], "ack");
+  let reject = request(&mut client, json!({ "type": "cancel", "request_id": "r2", "client_order_id": "c1" })).await;
+  assert_eq!((reject["request_id"].as_str(), reject["error"].as_str()), (Some("r2"), Some("RATE_LIMITED")));
+  order_rx.try_recv().unwrap();
+  assert!(order_rx.try_recv().is_err());
+  
+  // Without queue space a request is refused instead of waiting for it
+  let throttle = Arc::new(Throttle::new(ThrottleConfig { overload_queue_pct: 0, ..ThrottleConfig::default() }));
+  let (manager, _order_rx) = setup_with(Arc::new(RiskManager::new()), throttle);
+  let mut client = connect(manager).await;
+  for i in 0..10 {
+    assert_eq!(request(&mut client, new_request(&format!("r{}", i), &format!("c{}", i), "100")).await["type"], "ack");
+  }
+  let reject = request(&mut client, new_request("r10", "c10", "100")).await;
+  assert_eq!((reject["client_order_id"].as_str(), reject["error"].as_str()), (Some("c10"), Some("OVERLOADED")));
+}
+
+#[tokio::test]
+async fn batches_answer_every_request() {
+  let (manager, mut order_rx) = setup();
+  let mut client = connect(manager).await;
+  
+  let reply = request(&mut client, json!({ "type": "batch", "request_id": "b1", "requests": [
+    new_request("r1", "c1", "100"),
+    new_request("r2", "c2", "101"),
+    { "type": "cancel", "request_id": "r3", "client_order_id": "c1" },
+    { "type": "batch", "request_id": "r4", "requests": [] },
+  ]})).await;
+  assert_eq!(reply["type"], "batch");
+  assert_eq!(reply["request_id"], "b1");
+  let results: Vec<(&str, &str)> = reply["results"].as_array().unwrap().iter()
+    .map(|result| (result["request_id"].as_str().unwrap(), result["type"].as_str().unwrap()))
+    .collect();
+  assert_eq!(results, vec![("r1", "ack"), ("r2", "reject"), ("r3", "ack"), ("r4", "reject")]);
+  
+  assert!(matches!(order_rx.try_recv().unwrap(), OrderMessage::Order(order) if order.status == OrderStatus::New));
+  assert!(matches!(order_rx.try_recv().unwrap(), OrderMessage::Order(order) if order.status == OrderStatus::Cancelled));
+  assert!(order_rx.try_recv().is_err());
+}
+
+#[test]
+fn amends_keep_priority_only_when_reducing_quantity() {
+  let instruments = Arc::new(InstrumentRegistry::new());
+  instruments.insert(Instrument::new("SYM"));
+  let mut engine = MatchingEngine::new(instruments);
+  for order_id in ["b1", "b2"] {
+    engine.handle(OrderMessage::Order(new_order(order_id.into(), Side::Buy, OrderType::Limit, 100, 10)));
+  }
+  
+  // b1 is reduced in place and still matches first
+  engine.handle(OrderMessage::Amend(Amend { order_id: "b1".into(), account: String::new(), price: Price(100), quantity: Qty(4) }));
+  let output = engine.handle(OrderMessage::Order(new_order("s1".into(), Side::Sell, OrderType::Limit, 100, 5)));
+  let makers: Vec<(&str, Qty)> = output.executions.iter().filter(|e| e.is_maker).map(|e| (e.order_id.as_str(), e.quantity)).collect();
+  assert_eq!(makers, vec![("b1", Qty(4)), ("b2", Qty(1))]);
+  
+  // A price change re-queues the order and may match immediately
+  engine.handle(OrderMessage::Order(new_order("s2".into(), Side::Sell, OrderType::Limit, 102, 3)));
+  let output = engine.handle(OrderMessage::Amend(Amend { order_id: "b2".into(), account: String::new(), price: Price(102), quantity: Qty(10) }));
+  assert_eq!(output.executions.iter().map(|e| e.quantity).sum::<Qty>(), Qty(6));
+  let book = engine.book("SYM").unwrap();
+  assert_eq!(book.buy_book.limits[&Price(102)].total_volume, Qty(6));
+  assert_eq!(book.validate(), Ok(()));
+  
+  // Amending to the filled quantity leaves nothing open
+  engine.handle(OrderMessage::Amend(Amend { order_id: "b2".into(), account: String::new(), price: Price(102), quantity: Qty(4) }));
+  assert!(engine.book("SYM").unwrap().order_map.is_empty());
+}
+#[test]
+fn amends_only_apply_to_the_accounts_own_orders() {
+  let instruments = Arc::new(InstrumentRegistry::new());
+  instruments.insert(Instrument::new("SYM"));
+  let mut engine = MatchingEngine::new(instruments.clone());
+  let mut order = new_order("b1".into(), Side::Buy, OrderType::Limit, 100, 10);
+  order.account = "acc1".into();
+  engine.handle(OrderMessage::Order(order.clone()));
+  
+  // Another account's amend is answered as for an unknown order, under that account
+  let output = engine.handle(OrderMessage::Amend(Amend { order_id: "b1".into(), account: "acc2".into(), price: Price(100), quantity: Qty(4) }));
+  assert_eq!(output.outcomes, vec![OrderOutcome::CancelRejected {
+    order_id: "b1".into(),
+    account: "acc2".into(),
+    reason: CancelRejectReason::UnknownOrder,
+  }]);
+  assert_eq!(engine.book("SYM").unwrap().get_order("b1").unwrap().quantity, Qty(10));
+  
+  // The owner's rejected amend gives back its risk reservation
+  let risk = RiskManager::new();
+  let instrument = instruments.get("SYM").unwrap();
+  let mut gone = order.clone();
+  gone.order_id = "b2".into();
+  risk.admit(&gone, &instrument).unwrap();
+  risk.admit_amend(&Order { quantity: Qty(20), ..gone.clone() }, &instrument).unwrap();
+  assert_eq!(risk.exposure("acc1", "SYM").open_buy, Qty(20));
+  let output = engine.handle(OrderMessage::Amend(Amend { order_id: "b2".into(), account: "acc1".into(), price: Price(100), quantity: Qty(20) }));
+  for outcome in &output.outcomes {
+    risk.on_outcome(outcome);
+  }
+  assert_eq!(risk.exposure("acc1", "SYM").open_buy, Qty(10));
+}
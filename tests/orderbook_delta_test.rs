@@ -0,0 +1,171 @@
+/*
+* filename : orderbook_delta_test
+* author : HAMA
+* date: 2026. 10. 18.
+* description: update_id 기반 오더북 델타 스트림 테스트
+**/
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use warp::test::WsClient;
+
+use xTraderz::instrument::Instrument;
+use xTraderz::market_data_publisher::models::BookDepth;
+use xTraderz::market_data_publisher::publisher::MarketDataPublisher;
+use xTraderz::matching_engine::{BookUpdate, MatchingEngine};
+use xTraderz::models::{Amend, Order, OrderBook, OrderMessage, OrderType, Price, Qty, Side};
+use xTraderz::sequencer::{self, SequencerOutputs};
+use xTraderz::util::serializer::orderbook_to_dto;
+use xTraderz::websocket::orderbook_relay::{self, ws_orderbook_route, OrderBookRelayManager};
+use common::{cancel_order, new_order, registry, registry_of};
+
+fn order(order_id: &str, side: Side, price: u64, quantity: u64) -> Order {
+  new_order(order_id.into(), side, OrderType::Limit, price, quantity)
+}
+
+async fn connect(manager: Arc<OrderBookRelayManager>) -> WsClient {
+  warp::test::ws().path("/ws/orderbook/SYM").handshake(ws_orderbook_route(manager)).await.unwrap()
+}
+
+async fn receive(client: &mut WsClient) -> Option<Value> {
+  let message = tokio::time::timeout(Duration::from_millis(500), client.recv()).await.ok()?.ok()?;
+  serde_json::from_str(message.to_str().unwrap()).ok()
+}
+
+#[tokio::test]
+async fn deltas_follow_the_snapshot_update_id() {
+  let manager = Arc::new(OrderBookRelayManager::new());
+  let mut client = connect(manager.clone()).await;
+  let snapshot = receive(&mut client).await.unwrap();
+  assert_eq!((snapshot["type"].as_str(), snapshot["data"]["update_id"].as_u64()), (Some("orderbook_snapshot"), Some(0)));
+  
+  let mut book = OrderBook::new();
+  book.insert_order(order("b1", Side::Buy, 100, 5));
+  book.insert_order(order("s1", Side::Sell, 110, 3));
+  manager.update_orderbook("SYM", book.clone());
+  let delta = receive(&mut client).await.unwrap();
+  assert_eq!(delta["type"], "orderbook_delta");
+  assert_eq!((delta["prev_update_id"].as_u64(), delta["update_id"].as_u64()), (Some(0), Some(1)));
+  assert_eq!(delta["bids"]["added"][0]["price"], "100");
+  assert_eq!(delta["asks"]["added"][0]["volume"], "3");
+  
+  // An unchanged book does not consume an update id
+  manager.update_orderbook("SYM", book.clone());
+  assert!(receive(&mut client).await.is_none());
+  
+  book.insert_order(order("b2", Side::Buy, 100, 2));
+  book.cancel_order("s1");
+  manager.update_orderbook("SYM", book);
+  let delta = receive(&mut client).await.unwrap();
+  assert_eq!((delta["prev_update_id"].as_u64(), delta["update_id"].as_u64()), (Some(1), Some(2)));
+  assert_eq!(delta["bids"]["updated"][0], json!({ "price": "100", "volume": "7", "order_count": 2 }));
+  assert_eq!(delta["asks"]["removed"], json!(["110"]));
+}
+
+#[tokio::test]
+async fn snapshots_can_be_requested_again() {
+  let manager = Arc::new(OrderBookRelayManager::new());
+  let mut book = OrderBook::new();
+  book.insert_order(order("b1", Side::Buy, 100, 5));
+  manager.update_orderbook("SYM", book.clone());
+  book.insert_order(order("s1", Side::Sell, 110, 3));
+  manager.update_orderbook("SYM", book);
+  
+  let mut client = connect(manager.clone()).await;
+  let snapshot = receive(&mut client).await.unwrap();
+  assert_eq!(snapshot["data"]["update_id"], 2);
+  assert_eq!(snapshot["data"]["asks"][0]["price"], "110");
+  
+  client.send_text(json!({ "type": "snapshot" }).to_string()).await;
+  assert_eq!(receive(&mut client).await.unwrap(), snapshot);
+  
+  let response = warp::test::request()
+    .path("/api/v1/orderbook/SYM/snapshot")
+    .reply(&orderbook_relay::routes(manager))
+    .await;
+  let rest: Value = serde_json::from_slice(response.body()).unwrap();
+  assert_eq!(rest, snapshot["data"]);
+}
+
+#[test]
+fn engine_reports_changed_books() {
+  let mut engine = MatchingEngine::new(registry(&["SYM"]));
+  assert_eq!(engine.handle(OrderMessage::Order(order("b1", Side::Buy, 100, 5))).books, ["SYM"]);
+  
+  assert!(engine.handle(OrderMessage::Order(cancel_order("missing".into()))).books.is_empty());
+  
+  let batch = OrderMessage::Batch(vec![
+    OrderMessage::Order(order("s1", Side::Sell, 110, 3)),
+    OrderMessage::Order(order("s2", Side::Sell, 120, 3)),
+  ]);
+  assert_eq!(engine.handle(batch).books, ["SYM"]);
+}
+
+#[tokio::test]
+async fn engine_books_feed_the_relay_and_publisher() {
+  let (order_tx, order_rx) = mpsc::channel(100);
+  let (exec_tx, _exec_rx) = mpsc::channel(100);
+  let (md_tx, _md_rx) = mpsc::channel(100);
+  let (order_event_tx, mut order_event_rx) = mpsc::channel(100);
+  tokio::spawn(sequencer::run(order_rx, SequencerOutputs { order_event_tx, ..SequencerOutputs::new(exec_tx, md_tx) }, registry(&["SYM"])));
+  
+  order_tx.send(OrderMessage::Order(order("s1", Side::Sell, 110, 3))).await.unwrap();
+  order_tx.send(OrderMessage::Order(order("b1", Side::Buy, 100, 5))).await.unwrap();
+  order_tx.send(OrderMessage::Order(order("b2", Side::Buy, 110, 1))).await.unwrap();
+  
+  // Both views format the engine's books with the instrument's decimal places
+  let scaled = registry_of([Instrument { price_scale: 1, quantity_scale: 1, ..Instrument::new("SYM") }]);
+  let relay = OrderBookRelayManager::with_instruments(scaled.clone());
+  let mdp = MarketDataPublisher::with_instruments(scaled);
+  for _ in 0..3 {
+    let update = tokio::time::timeout(Duration::from_secs(1), order_event_rx.recv()).await.unwrap().unwrap();
+    assert_eq!(update.symbol, "SYM");
+    relay.apply_book_update(&update);
+    mdp.apply_book_update(&update);
+  }
+  
+  let snapshot = relay.create_snapshot("SYM").unwrap();
+  assert_eq!(snapshot["update_id"], 3);
+  assert_eq!(snapshot["asks"], json!([{ "price": "11", "volume": "0.2", "order_count": 1 }]));
+  assert_eq!(snapshot["bids"], json!([{ "price": "10", "volume": "0.5", "order_count": 1 }]));
+  let book = mdp.get_orderbook("SYM", BookDepth::Levels(10), 1).unwrap();
+  assert_eq!([&book.asks[0].volume, &book.bids[0].volume], ["0.2", "0.5"]);
+}
+#[test]
+fn book_copies_follow_the_engine() {
+  let mut engine = MatchingEngine::new(registry(&["SYM"]));
+  let relay = OrderBookRelayManager::with_instruments(registry(&["SYM"]));
+  let mdp = MarketDataPublisher::with_instruments(registry(&["SYM"]));
+  let cancel = cancel_order("b2".into());
+  let messages = vec![
+    OrderMessage::Order(order("s1", Side::Sell, 110, 3)),
+    OrderMessage::Order(order("s2", Side::Sell, 120, 4)),
+    OrderMessage::Order(order("b1", Side::Buy, 100, 5)),
+    OrderMessage::Order(order("b2", Side::Buy, 100, 2)),
+    OrderMessage::Order(order("b3", Side::Buy, 115, 4)),
+    OrderMessage::Amend(Amend { order_id: "b1".into(), account: String::new(), price: Price(100), quantity: Qty(3) }),
+    OrderMessage::Amend(Amend { order_id: "s2".into(), account: String::new(), price: Price(130), quantity: Qty(4) }),
+    OrderMessage::Order(cancel),
+  ];
+  for message in messages {
+    let output = engine.handle(message);
+    for symbol in output.books {
+      let events = output.order_events.iter().filter(|e| e.symbol == symbol).cloned().collect();
+      let update = BookUpdate { symbol, events };
+      relay.apply_book_update(&update);
+      mdp.apply_book_update(&update);
+    }
+    
+    let expected = orderbook_to_dto(engine.book("SYM").unwrap(), "SYM", &Instrument::new("SYM"));
+    let snapshot = relay.create_snapshot("SYM").unwrap();
+    assert_eq!(snapshot["bids"], json!(expected.bids));
+    assert_eq!(snapshot["asks"], json!(expected.asks));
+    let book = mdp.get_orderbook("SYM", BookDepth::Levels(10), 1).unwrap();
+    assert_eq!(book.bids.len(), expected.bids.len());
+    assert_eq!(book.asks.len(), expected.asks.len());
+  }
+}
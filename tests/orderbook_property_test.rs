@@ -0,0 +1,201 @@
+/*
+* filename : orderbook_property_test
+* author : HAMA
+* date: 2026. 10. 18.
+* description: 무작위 명령 시퀀스로 주문책 불변 조건 검증
+**/
+
+mod common;
+
+use std::collections::HashMap;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use xTraderz::matching_engine::{process_order, MatchingEngine, OrderOutcome};
+use xTraderz::models::{Order, OrderBook, OrderMessage, Execution, Side, OrderType, OrderStatus, Price, Qty};
+use common::{new_order, cancel_order, random_commands, random_message, registry};
+
+const SEEDS: u64 = 200;
+const STEPS: usize = 500;
+
+// Executions are emitted in (taker, maker) pairs at the same price and quantity
+fn check_executions(order: &Order, executions: &[Execution], filled: &mut HashMap<String, Qty>) -> Result<(), String> {
+  if !executions.len().is_multiple_of(2) {
+    return Err(format!("odd number of executions: {}", executions.len()));
+  }
+  
+  let mut taker_filled = Qty::ZERO;
+  for pair in executions.chunks(2) {
+    let (taker, maker) = (&pair[0], &pair[1]);
+    if taker.order_id != order.order_id || taker.side != order.side {
+      return Err(format!("taker execution {} does not belong to {}", taker.exec_id, order.order_id));
+    }
+    if maker.side == taker.side || maker.price != taker.price || maker.quantity != taker.quantity {
+      return Err(format!("maker execution {} does not mirror the taker", maker.exec_id));
+    }
+    if taker.is_maker || !maker.is_maker {
+      return Err(format!("executions {} and {} have the wrong liquidity flags", taker.exec_id, maker.exec_id));
+    }
+    if taker.quantity.is_zero() {
+      return Err("zero quantity execution".into());
+    }
+    if order.order_type == OrderType::Limit {
+      let through = match order.side {
+        Side::Buy => taker.price > order.price,
+        Side::Sell => taker.price < order.price,
+      };
+      if through {
+        return Err(format!("execution at {} trades through limit {}", taker.price, order.price));
+      }
+    }
+    taker_filled += taker.quantity;
+    *filled.entry(maker.order_id.clone()).or_insert(Qty::ZERO) += maker.quantity;
+  }
+  
+  if taker_filled > order.quantity {
+    return Err(format!("order {} filled {} of {}", order.order_id, taker_filled, order.quantity));
+  }
+  filled.insert(order.order_id.clone(), taker_filled);
+  Ok(())
+}
+
+// Resting orders must carry the fill quantity implied by the execution stream
+fn check_conservation(book: &OrderBook, filled: &HashMap<String, Qty>) -> Result<(), String> {
+  for level in book.buy_book.limits.values().chain(book.sell_book.limits.values()) {
+    for order in &level.orders {
+      let expected = filled.get(&order.order_id).cloned().unwrap_or(Qty::ZERO);
+      if order.filled_quantity != expected {
+        return Err(format!("order {} has filled_quantity {} but executions total {}",
+                           order.order_id, order.filled_quantity, expected));
+      }
+    }
+  }
+  Ok(())
+}
+
+fn run_sequence(seed: u64) -> Result<(), String> {
+  let mut rng = StdRng::seed_from_u64(seed);
+  let mut book = OrderBook::new();
+  let mut filled = HashMap::new();
+  let mut next_id = 0;
+  
+  for step in 0..STEPS {
+    // Sorted so that a seed reproduces the same sequence
+    let mut live: Vec<String> = book.order_map.keys().cloned().collect();
+    live.sort();
+    for order in random_commands(&mut rng, &mut next_id, &live) {
+      let is_cancel = order.status == OrderStatus::Cancelled;
+      let executions = process_order(&mut book, order.clone());
+      
+      let result = if is_cancel {
+        if executions.is_empty() { Ok(()) } else { Err("cancel produced executions".into()) }
+      } else {
+        check_executions(&order, &executions, &mut filled)
+      };
+      
+      result
+        .and_then(|_| book.validate())
+        .and_then(|_| check_conservation(&book, &filled))
+        .map_err(|e| format!("seed {} step {}: {}", seed, step, e))?;
+    }
+  }
+  
+  Ok(())
+}
+
+// Executions of one engine message. Fills are totalled per order id, which an order keeps
+// when it is amended.
+fn check_engine_executions(executions: &[Execution], filled: &mut HashMap<String, Qty>) -> Result<(), String> {
+  if !executions.len().is_multiple_of(2) {
+    return Err(format!("odd number of executions: {}", executions.len()));
+  }
+  for pair in executions.chunks(2) {
+    let (taker, maker) = (&pair[0], &pair[1]);
+    if maker.side == taker.side || maker.price != taker.price || maker.quantity != taker.quantity {
+      return Err(format!("maker execution {} does not mirror the taker", maker.exec_id));
+    }
+    if taker.is_maker || !maker.is_maker || taker.quantity.is_zero() {
+      return Err(format!("executions {} and {} are not a taker and maker fill", taker.exec_id, maker.exec_id));
+    }
+    for execution in pair {
+      *filled.entry(execution.order_id.clone()).or_insert(Qty::ZERO) += execution.quantity;
+    }
+  }
+  Ok(())
+}
+
+// An applied amend leaves the order resting, if at all, at the amended price and quantity
+fn check_amend(message: &OrderMessage, outcomes: &[OrderOutcome], book: &OrderBook) -> Result<(), String> {
+  let amend = match message {
+    OrderMessage::Amend(amend) => amend,
+    _ => return Ok(()),
+  };
+  let applied = outcomes.iter().any(|outcome| matches!(outcome, OrderOutcome::Amended { order_id, .. } if *order_id == amend.order_id));
+  match book.get_order(&amend.order_id) {
+    Some(order) if applied && (order.price != amend.price || order.quantity != amend.quantity) => {
+      Err(format!("amended order {} rests at {} x {}", order.order_id, order.price, order.quantity))
+    }
+    Some(_) if !applied => Err(format!("amend of {} was not applied", amend.order_id)),
+    _ => Ok(()),
+  }
+}
+
+fn run_engine_sequence(seed: u64) -> Result<(), String> {
+  let mut rng = StdRng::seed_from_u64(seed);
+  let mut engine = MatchingEngine::new(registry(&["SYM"]));
+  let mut filled = HashMap::new();
+  let mut next_id = 0;
+  
+  for step in 0..STEPS {
+    let empty = OrderBook::new();
+    let book = engine.book("SYM").unwrap_or(&empty);
+    let mut resting: Vec<Order> = book.order_map.keys().filter_map(|order_id| book.get_order(order_id).cloned()).collect();
+    resting.sort_by(|a, b| a.order_id.cmp(&b.order_id));
+    
+    let message = random_message(&mut rng, &mut next_id, &resting);
+    let output = engine.handle(message.clone());
+    let book = engine.book("SYM").unwrap_or(&empty);
+    check_engine_executions(&output.executions, &mut filled)
+      .and_then(|_| check_amend(&message, &output.outcomes, book))
+      .and_then(|_| book.validate())
+      .and_then(|_| check_conservation(book, &filled))
+      .map_err(|e| format!("seed {} step {}: {}", seed, step, e))?;
+  }
+  
+  Ok(())
+}
+
+#[test]
+fn random_command_sequences_preserve_book_invariants() {
+  for seed in 0..SEEDS {
+    if let Err(e) = run_sequence(seed) {
+      panic!("{}", e);
+    }
+  }
+}
+
+#[test]
+fn random_engine_sequences_with_amends_preserve_book_invariants() {
+  for seed in 0..SEEDS {
+    if let Err(e) = run_engine_sequence(seed) {
+      panic!("{}", e);
+    }
+  }
+}
+
+#[test]
+fn cancel_after_partial_match_removes_the_right_order() {
+  let mut book = OrderBook::new();
+  process_order(&mut book, new_order("s1".into(), Side::Sell, OrderType::Limit, 100, 5));
+  process_order(&mut book, new_order("s2".into(), Side::Sell, OrderType::Limit, 100, 5));
+  process_order(&mut book, new_order("s3".into(), Side::Sell, OrderType::Limit, 100, 5));
+  
+  // s1 leaves the front of the queue, shifting s2 and s3 forward
+  process_order(&mut book, new_order("b1".into(), Side::Buy, OrderType::Limit, 100, 5));
+  process_order(&mut book, cancel_order("s3".into()));
+  
+  let level = book.sell_book.limits.get(&Price(100)).unwrap();
+  assert_eq!(level.orders.len(), 1);
+  assert_eq!(level.orders[0].order_id, "s2");
+  assert_eq!(book.validate(), Ok(()));
+}
\ No newline at end of file
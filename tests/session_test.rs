@@ -0,0 +1,219 @@
+/*
+* filename : session_test
+* author : HAMA
+* date: 2026. 10. 18.
+* description: WebSocket 거래 세션 인증 및 cancel-on-disconnect 테스트
+**/
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use serde_json::Value;
+
+use xTraderz::auth::AccountRegistry;
+use xTraderz::instrument::{Instrument, InstrumentRegistry};
+use xTraderz::models::{Execution, OrderMessage, OrderStatus, Price, Qty, Side};
+use xTraderz::risk::RiskManager;
+use xTraderz::throttle::Throttle;
+use xTraderz::websocket::execution_push::{ws_execution_route, ExecutionPushManager};
+use xTraderz::websocket::public_trades::{ws_trades_route, PublicTradeManager};
+use xTraderz::websocket::session::SessionManager;
+
+fn setup() -> (Arc<ExecutionPushManager>, Arc<SessionManager>, mpsc::Receiver<OrderMessage>) {
+  let accounts = Arc::new(AccountRegistry::new());
+  accounts.insert("mm1", "key-1");
+  accounts.insert("mm2", "key-2");
+  let (order_tx, order_rx) = mpsc::channel(10);
+  let sessions = Arc::new(SessionManager::new(accounts, order_tx));
+  let instruments = Arc::new(InstrumentRegistry::new());
+  instruments.insert(Instrument::new("SYM"));
+  let manager = Arc::new(ExecutionPushManager::new(instruments, sessions.clone(), Arc::new(RiskManager::new()), Arc::new(Throttle::default())));
+  (manager, sessions, order_rx)
+}
+
+async fn next_message(order_rx: &mut mpsc::Receiver<OrderMessage>) -> Option<OrderMessage> {
+  tokio::time::timeout(Duration::from_secs(3), order_rx.recv()).await.ok().flatten()
+}
+
+// Order ids of a batch of cancels, sorted
+fn cancelled_order_ids(message: Option<OrderMessage>) -> Vec<String> {
+  let mut order_ids: Vec<String> = match message {
+    Some(OrderMessage::Batch(messages)) => messages.into_iter()
+      .map(|message| match message {
+        OrderMessage::Order(order) if order.status == OrderStatus::Cancelled => order.order_id,
+        other => panic!("not a cancel: {:?}", other),
+      })
+      .collect(),
+    other => panic!("not a batch: {:?}", other),
+  };
+  order_ids.sort();
+  order_ids
+}
+
+// Enter an order through the session and return its order id
+async fn enter_order(client: &mut warp::test::WsClient, order_rx: &mut mpsc::Receiver<OrderMessage>, client_order_id: &str) -> String {
+  let request = serde_json::json!({ "type": "new", "client_order_id": client_order_id, "symbol": "SYM",
+                                    "side": "Buy", "order_type": "Limit", "price": "100", "quantity": "10" });
+  client.send_text(request.to_string()).await;
+  let ack: Value = serde_json::from_str(client.recv().await.unwrap().to_str().unwrap()).unwrap();
+  assert_eq!(ack["type"], "ack");
+  assert!(matches!(next_message(order_rx).await, Some(OrderMessage::Order(_))));
+  ack["order_id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn trading_sessions_require_a_valid_api_key() {
+  let (manager, _, _order_rx) = setup();
+  let route = ws_execution_route(manager);
+  
+  assert!(warp::test::ws().path("/ws/executions?cancel_on_disconnect=true").handshake(route.clone()).await.is_err());
+  assert!(warp::test::ws().path("/ws/executions?api_key=wrong").handshake(route.clone()).await.is_err());
+  
+  // Connections without a token are accepted and may log in later
+  assert!(warp::test::ws().path("/ws/executions").handshake(route).await.is_ok());
+}
+
+#[tokio::test]
+async fn closing_a_session_cancels_its_orders() {
+  let (manager, sessions, mut order_rx) = setup();
+  let route = ws_execution_route(manager);
+  
+  let mut client = warp::test::ws()
+    .path("/ws/executions?api_key=key-1&cancel_on_disconnect=true")
+    .handshake(route.clone())
+    .await
+    .unwrap();
+  let opened: Value = serde_json::from_str(client.recv().await.unwrap().to_str().unwrap()).unwrap();
+  assert_eq!(opened["type"], "session");
+  assert_eq!(opened["account"], "mm1");
+  assert_eq!(opened["cancel_on_disconnect"], true);
+  assert_eq!(sessions.active_sessions().len(), 1);
+  let mut order_ids = vec![
+    enter_order(&mut client, &mut order_rx, "c1").await,
+    enter_order(&mut client, &mut order_rx, "c2").await,
+  ];
+  order_ids.sort();
+  
+  // Another session of the same account keeps its orders
+  let mut other = warp::test::ws().path("/ws/executions?api_key=key-1").handshake(route).await.unwrap();
+  other.recv().await.unwrap();
+  enter_order(&mut other, &mut order_rx, "c1").await;
+  
+  // All cancels are sequenced together
+  drop(client);
+  assert_eq!(cancelled_order_ids(next_message(&mut order_rx).await), order_ids);
+  tokio::time::sleep(Duration::from_millis(200)).await;
+  assert!(order_rx.try_recv().is_err());
+  assert_eq!(sessions.active_sessions().len(), 1);
+  drop(other);
+}
+
+#[tokio::test]
+async fn heartbeat_timeout_cancels_orders() {
+  let (manager, _, mut order_rx) = setup();
+  let route = ws_execution_route(manager);
+  
+  let mut client = warp::test::ws()
+    .path("/ws/executions?api_key=key-1&cancel_on_disconnect=true&heartbeat_secs=1")
+    .handshake(route)
+    .await
+    .unwrap();
+  client.recv().await.unwrap();
+  let order_id = enter_order(&mut client, &mut order_rx, "c1").await;
+  
+  // Heartbeats keep the session alive
+  for _ in 0..3 {
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    client.send_text(r#"{"type":"ping"}"#).await;
+    let pong: Value = serde_json::from_str(client.recv().await.unwrap().to_str().unwrap()).unwrap();
+    assert_eq!(pong["type"], "pong");
+  }
+  assert!(order_rx.try_recv().is_err());
+  
+  // Silence for longer than the heartbeat interval ends the session while the socket is still open
+  assert_eq!(cancelled_order_ids(next_message(&mut order_rx).await), vec![order_id]);
+  drop(client);
+}
+
+#[tokio::test]
+async fn sessions_without_the_flag_leave_orders_alone() {
+  let (manager, sessions, mut order_rx) = setup();
+  let route = ws_execution_route(manager);
+  
+  let mut client = warp::test::ws().path("/ws/executions?api_key=key-1").handshake(route).await.unwrap();
+  client.recv().await.unwrap();
+  assert_eq!(sessions.active_sessions()[0].account, "mm1");
+  enter_order(&mut client, &mut order_rx, "c1").await;
+  
+  drop(client);
+  tokio::time::sleep(Duration::from_millis(200)).await;
+  assert!(sessions.active_sessions().is_empty());
+  assert!(order_rx.try_recv().is_err());
+}
+
+fn execution(order_id: &str, trade_id: &str, account: &str, is_maker: bool) -> Execution {
+  Execution {
+    exec_id: format!("{}-exec", order_id),
+    order_id: order_id.into(),
+    trade_id: trade_id.into(),
+    account: account.into(),
+    symbol: "SYM".into(),
+    side: if is_maker { Side::Sell } else { Side::Buy },
+    price: Price(100),
+    quantity: Qty(3),
+    is_maker,
+    fee: 1,
+    transaction_time: chrono::Utc::now(),
+  }
+}
+
+async fn receive(client: &mut warp::test::WsClient) -> Option<Value> {
+  let message = tokio::time::timeout(Duration::from_millis(300), client.recv()).await.ok()?.ok()?;
+  serde_json::from_str(message.to_str().unwrap()).ok()
+}
+
+#[tokio::test]
+async fn executions_only_reach_the_owning_account() {
+  let (manager, _, _order_rx) = setup();
+  let route = ws_execution_route(manager.clone());
+  
+  let mut mm1 = warp::test::ws().path("/ws/executions?api_key=key-1").handshake(route.clone()).await.unwrap();
+  receive(&mut mm1).await.unwrap();
+  
+  // Connections without a token authenticate with a login message
+  let mut mm2 = warp::test::ws().path("/ws/executions").handshake(route).await.unwrap();
+  mm2.send_text(r#"{"type":"login","api_key":"wrong"}"#).await;
+  assert_eq!(receive(&mut mm2).await.unwrap()["error"], "UNAUTHORIZED");
+  mm2.send_text(r#"{"type":"login","api_key":"key-2"}"#).await;
+  let opened = receive(&mut mm2).await.unwrap();
+  assert_eq!((opened["type"].as_str(), opened["account"].as_str()), (Some("session"), Some("mm2")));
+  
+  manager.broadcast_execution(&execution("o1", "t1", "mm1", false)).await;
+  manager.broadcast_execution(&execution("o2", "t1", "mm2", true)).await;
+  let fill = receive(&mut mm1).await.unwrap();
+  assert_eq!((fill["order_id"].as_str(), fill["trade_id"].as_str()), (Some("o1"), Some("t1")));
+  let fill = receive(&mut mm2).await.unwrap();
+  assert_eq!(fill["order_id"], "o2");
+  assert!(receive(&mut mm1).await.is_none());
+  assert!(receive(&mut mm2).await.is_none());
+}
+
+#[tokio::test]
+async fn public_trades_are_anonymous_and_published_once() {
+  let manager = Arc::new(PublicTradeManager::new(Arc::new(InstrumentRegistry::new())));
+  let route = ws_trades_route(manager.clone());
+  let mut all = warp::test::ws().path("/ws/trades").handshake(route.clone()).await.unwrap();
+  let mut other = warp::test::ws().path("/ws/trades?symbol=OTHER").handshake(route).await.unwrap();
+  tokio::time::sleep(Duration::from_millis(50)).await;
+  
+  manager.publish(&execution("o1", "t1", "mm1", false));
+  manager.publish(&execution("o2", "t1", "mm2", true));
+  let trade = receive(&mut all).await.unwrap();
+  assert_eq!(trade["type"], "trade");
+  assert_eq!(trade["data"], serde_json::json!({
+    "trade_id": "t1", "symbol": "SYM", "side": "Buy", "price": "100", "quantity": "3",
+    "transaction_time": trade["data"]["transaction_time"]
+  }));
+  assert!(receive(&mut all).await.is_none());
+  assert!(receive(&mut other).await.is_none());
+}
\ No newline at end of file